@@ -53,7 +53,7 @@ fn pk_lo_hi() -> Result<String> {
 }
 fn sv() -> Result<String> {
     let sv_spec = SubValueSpec {
-        member_idxs: vec![],
+        member_path: vec![],
         datum_type: DatumType::I64,
     }
     .ser_solo()?;
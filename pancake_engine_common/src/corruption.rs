@@ -0,0 +1,34 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+
+/// The content of an on-disk file is not what had been written, e.g. due to a flipped bit.
+///
+/// It is distinct from other errors, so that callers may tell data loss apart from e.g. IO errors.
+#[derive(Debug)]
+pub struct CorruptionError {
+    pub file_path: PathBuf,
+    pub offset: u64,
+    pub reason: String,
+}
+
+impl CorruptionError {
+    pub fn new<P: Into<PathBuf>, S: Into<String>>(file_path: P, offset: u64, reason: S) -> Self {
+        Self {
+            file_path: file_path.into(),
+            offset,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl Display for CorruptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Corrupt file {:?} at offset {}. {}",
+            self.file_path, self.offset, self.reason
+        )
+    }
+}
+impl Error for CorruptionError {}
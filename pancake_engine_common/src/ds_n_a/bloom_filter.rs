@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+use std::io::{Read, Write};
+use std::mem;
+
+/// About 1% false positive rate, when paired with [`HASH_COUNT`].
+const BITS_PER_ITEM: usize = 10;
+const HASH_COUNT: u32 = 7;
+
+/// A bloom filter over byte strings.
+///
+/// The hash is stable across processes and platforms, so that a persisted filter remains valid.
+#[derive(PartialEq, Eq, Debug)]
+pub struct BloomFilter {
+    bits: Vec<u8>,
+    hash_count: u32,
+}
+
+impl BloomFilter {
+    /// @arg `item_hashes`: Each item's [`Self::hash()`].
+    pub fn from_hashes(item_hashes: &[u64]) -> Self {
+        let bit_count = (item_hashes.len() * BITS_PER_ITEM).max(64);
+        let mut moi = Self {
+            bits: vec![0u8; bit_count.div_ceil(8)],
+            hash_count: HASH_COUNT,
+        };
+        for item_hash in item_hashes {
+            for bit_i in moi.bit_idxs(*item_hash) {
+                moi.bits[bit_i / 8] |= 1 << (bit_i % 8);
+            }
+        }
+        moi
+    }
+
+    /// 64-bit FNV-1a.
+    pub fn hash(item: &[u8]) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in item {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+
+    /// @return Whether the item may have been inserted. False positives are possible; false negatives are not.
+    pub fn may_contain(&self, item: &[u8]) -> bool {
+        let item_hash = Self::hash(item);
+        self.bit_idxs(item_hash)
+            .all(|bit_i| self.bits[bit_i / 8] & (1 << (bit_i % 8)) != 0)
+    }
+
+    /// Derives `hash_count` bit indexes from one hash, by double hashing.
+    fn bit_idxs(&self, item_hash: u64) -> impl Iterator<Item = usize> {
+        let bit_count = (self.bits.len() * 8) as u64;
+        let h1 = mix(item_hash);
+        let h2 = mix(h1) | 1;
+        (0..self.hash_count as u64)
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bit_count) as usize)
+    }
+}
+
+/// The finalizer of splitmix64. It spreads FNV's weakly mixed high bits.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/* De/Serialization. */
+impl BloomFilter {
+    /// ```text
+    /// struct BloomFilter {
+    ///     hash_count:     u32,
+    ///     bits:           [u8],   // The length is implied by the container.
+    /// }
+    /// ```
+    pub fn ser<W: Write>(&self, w: &mut W) -> Result<usize> {
+        let hash_count = self.hash_count.to_le_bytes();
+        w.write_all(&hash_count)?;
+        w.write_all(&self.bits)?;
+        Ok(hash_count.len() + self.bits.len())
+    }
+
+    pub fn deser<R: Read>(r: &mut R, ser_len: usize) -> Result<Self> {
+        let mut hash_count_buf = [0u8; mem::size_of::<u32>()];
+        r.read_exact(&mut hash_count_buf)?;
+        let hash_count = u32::from_le_bytes(hash_count_buf);
+
+        let bits_len = ser_len
+            .checked_sub(hash_count_buf.len())
+            .filter(|bits_len| *bits_len > 0)
+            .ok_or_else(|| anyhow!("Invalid bloom filter length {ser_len}"))?;
+        let mut bits = vec![0u8; bits_len];
+        r.read_exact(&mut bits)?;
+
+        Ok(Self { bits, hash_count })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gen_items(range: std::ops::Range<u32>) -> Vec<Vec<u8>> {
+        range.map(|i| format!("item_{i}").into_bytes()).collect()
+    }
+
+    #[test]
+    fn membership() -> Result<()> {
+        let items = gen_items(0..1000);
+        let hashes = items
+            .iter()
+            .map(|item| BloomFilter::hash(item))
+            .collect::<Vec<_>>();
+        let filter = BloomFilter::from_hashes(&hashes);
+
+        assert!(items.iter().all(|item| filter.may_contain(item)));
+
+        let false_pos_ct = gen_items(1000..11000)
+            .iter()
+            .filter(|item| filter.may_contain(item))
+            .count();
+        assert!(false_pos_ct < 300, "{false_pos_ct}");
+
+        let mut buf = vec![];
+        let ser_len = filter.ser(&mut buf)?;
+        assert_eq!(ser_len, buf.len());
+        assert_eq!(BloomFilter::deser(&mut &buf[..], ser_len)?, filter);
+
+        Ok(())
+    }
+
+    #[test]
+    fn empty() {
+        let filter = BloomFilter::from_hashes(&[]);
+        assert!(filter.may_contain(b"anything") == false);
+    }
+}
@@ -1,4 +1,5 @@
 //! Data structures and algorithm.
 
 pub mod bisect;
+pub mod bloom_filter;
 pub mod cmp;
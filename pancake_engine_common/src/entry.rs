@@ -79,20 +79,44 @@ impl<'a, K, V> Entry<'a, K, V> {
     }
 }
 impl<'a, K, V> Entry<'a, K, OptDatum<V>> {
-    pub fn to_option_entry(self) -> Option<Entry<'a, K, V>> {
+    /// @arg `now`: Entries that have expired as of this time, in microseconds since the unix epoch, are hidden like tombstones.
+    pub fn to_option_entry(self, now: i64) -> Option<Entry<'a, K, V>> {
         match self {
-            Self::Ref((k, optdat_v)) => match optdat_v {
-                OptDatum::Tombstone => None,
-                OptDatum::Some(v) => Some(Entry::Ref((k, v))),
-            },
+            Self::Ref((k, optdat_v)) => optdat_v.as_live(now).map(|v| Entry::Ref((k, v))),
             Self::Own(res) => match res {
                 Err(e) => Some(Entry::Own(Err(e))),
-                Ok((_k, OptDatum::Tombstone)) => None,
-                Ok((k, OptDatum::Some(v))) => Some(Entry::Own(Ok((k, v)))),
+                Ok((k, optdat_v)) => optdat_v.into_live(now).map(|v| Entry::Own(Ok((k, v)))),
             },
         }
     }
 }
+impl<'a, K, V> Entry<'a, K, OptDatum<V>>
+where
+    K: Clone,
+{
+    /// Prepares an entry to be written by flushing or compaction.
+    ///
+    /// An entry that has expired as of `now` is replaced by a tombstone, so that it keeps shadowing older entries.
+    ///
+    /// @arg `skip_tombstones`: Whether to drop tombstones, which is valid iff there is no older entry for them to shadow.
+    pub fn to_compacted_entry(self, now: i64, skip_tombstones: bool) -> Option<Self> {
+        let (is_tomb, is_expired) = match self.try_borrow() {
+            Err(_) => return Some(self),
+            Ok((_k, optdat_v)) => (
+                matches!(optdat_v, OptDatum::Tombstone),
+                optdat_v.is_expired_at(now),
+            ),
+        };
+        if (is_tomb || is_expired) && skip_tombstones {
+            return None;
+        }
+        if is_expired {
+            let k = self.into_owned_k().ok()?;
+            return Some(Entry::Own(Ok((k, OptDatum::Tombstone))));
+        }
+        return Some(self);
+    }
+}
 
 /* Comparing */
 impl<K, V, Rhs> TryPartialOrd<Rhs, anyhow::Error> for Entry<'_, K, V>
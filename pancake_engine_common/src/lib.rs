@@ -1,12 +1,19 @@
+mod corruption;
 pub mod ds_n_a;
 mod entry;
 pub mod fs_utils;
+mod memlog_file;
 mod memlog_r;
 mod memlog_w;
 pub mod merging;
+mod options;
+mod range_tombstones;
 mod sstable;
 
+pub use corruption::*;
 pub use entry::*;
 pub use memlog_r::*;
 pub use memlog_w::*;
+pub use options::*;
+pub use range_tombstones::*;
 pub use sstable::*;
@@ -0,0 +1,454 @@
+//! The write-ahead log file of a MemLog.
+//!
+//! ```text
+//! struct LogFile {
+//!     magic:              [u8; 8],
+//!     format_version:     u32,
+//!     records:            [LogRecord],
+//! }
+//!
+//! struct LogRecord {
+//!     body_len:           u32,
+//!     body_len_crc:       u32,    // CRC-32 of `body_len`.
+//!     crc:                u32,    // CRC-32 of `body`.
+//!     body:               {
+//!         kind:               u8,     // See `RecordKind`.
+//!         content:            (K, V) | (lo_incl: K, hi_incl: K),  // In the format described at `pancake_types::iters`.
+//!     },
+//! }
+//! ```
+//!
+//! Only an incomplete final record is a torn write. It is dropped during replay.
+//! That is, either the file ends within the record's header, or the verified `body_len` runs past the end of file.
+//! Any other mismatch is corruption.
+//!
+//! Files of format version 1 lack range tombstones. Their record bodies lack `kind`, and are all `(K, V)`s.
+//!
+//! Files of format versions 1 and 2 lack `body_len_crc`. Hence in them, any `body_len` that runs past the end of file
+//! is taken for a torn write.
+//!
+//! A file that does not begin with `magic` was written before records had checksums.
+//! It is a plain sequence of `(K, V)`s, which are replayed without verification.
+
+use crate::{fs_utils, CorruptionError};
+use anyhow::Result;
+use pancake_types::{
+    iters::KeyValueReader,
+    serde::ReadResult,
+    types::{Deser, Ser},
+};
+use std::fs::OpenOptions;
+use std::io::{BufReader, Cursor, ErrorKind, Read, Write};
+use std::mem;
+use std::path::Path;
+
+const MAGIC: [u8; 8] = *b"PNCK_LOG";
+const FORMAT_VERSION: u32 = 3;
+pub(crate) const HEADER_LEN: u64 = (MAGIC.len() + mem::size_of::<u32>()) as u64;
+const RECORD_HEADER_LEN: usize = 3 * mem::size_of::<u32>();
+
+fn record_header_len(version: u32) -> usize {
+    match version {
+        1 | 2 => RECORD_HEADER_LEN - mem::size_of::<u32>(),
+        _ => RECORD_HEADER_LEN,
+    }
+}
+
+#[repr(u8)]
+enum RecordKind {
+    Put = 0,
+    RangeTombstone = 1,
+}
+
+pub(crate) enum LogRecord<K, V> {
+    Put(K, V),
+    RangeTombstone(K, K),
+}
+
+pub(crate) fn ser_header<W: Write>(w: &mut W) -> Result<()> {
+    w.write_all(&MAGIC)?;
+    w.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    Ok(())
+}
+
+/// @arg `body_buf`: A reusable scratch buffer.
+/// @return The written length.
+pub(crate) fn ser_record<W, K, V>(w: &mut W, k: &K, v: &V, body_buf: &mut Vec<u8>) -> Result<u64>
+where
+    W: Write,
+    K: Ser,
+    V: Ser,
+{
+    body_buf.clear();
+    body_buf.push(RecordKind::Put as u8);
+    k.ser(body_buf)?;
+    v.ser(body_buf)?;
+
+    ser_body(w, body_buf)
+}
+
+/// @arg `body_buf`: A reusable scratch buffer.
+/// @return The written length.
+pub(crate) fn ser_range_tombstone_record<W, K>(
+    w: &mut W,
+    lo: &K,
+    hi: &K,
+    body_buf: &mut Vec<u8>,
+) -> Result<u64>
+where
+    W: Write,
+    K: Ser,
+{
+    body_buf.clear();
+    body_buf.push(RecordKind::RangeTombstone as u8);
+    lo.ser(body_buf)?;
+    hi.ser(body_buf)?;
+
+    ser_body(w, body_buf)
+}
+
+fn ser_body<W: Write>(w: &mut W, body_buf: &[u8]) -> Result<u64> {
+    let body_len = u32::try_from(body_buf.len())?.to_le_bytes();
+    let body_len_crc = crc32fast::hash(&body_len);
+    let crc = crc32fast::hash(body_buf);
+
+    w.write_all(&body_len)?;
+    w.write_all(&body_len_crc.to_le_bytes())?;
+    w.write_all(&crc.to_le_bytes())?;
+    w.write_all(body_buf)?;
+    Ok((RECORD_HEADER_LEN + body_buf.len()) as u64)
+}
+
+pub(crate) enum LogFormat {
+    /// The file is empty; not even the header has been written.
+    Empty,
+    Checksummed {
+        /// The length of the header and the complete records. Any torn record follows it.
+        valid_len: u64,
+    },
+    /// The file is either of an older format version or predates checksums.
+    /// It must be rewritten before any record is appended.
+    Outdated,
+}
+
+/// Calls `on_record` on each record in the log, in the order of writing.
+///
+/// @return Err of [`CorruptionError`] if any complete record fails verification.
+pub(crate) fn replay<K, V>(
+    log_path: &Path,
+    mut on_record: impl FnMut(LogRecord<K, V>),
+) -> Result<LogFormat>
+where
+    K: Deser,
+    V: Deser,
+{
+    let log_file = fs_utils::open_file(log_path, OpenOptions::new().read(true))?;
+    let file_len = log_file.metadata()?.len();
+    let mut r = BufReader::new(log_file);
+
+    let mut header = [0u8; HEADER_LEN as usize];
+    let header_len = read_until_full(&mut r, &mut header)?;
+    if header_len == 0 {
+        return Ok(LogFormat::Empty);
+    }
+    if header_len < header.len() || header.starts_with(&MAGIC) == false {
+        let log_file = fs_utils::open_file(log_path, OpenOptions::new().read(true))?;
+        let iter = KeyValueReader::<_, K, V>::from(log_file).into_iter_kv();
+        for res_kv in iter {
+            let (k, v) = res_kv?;
+            on_record(LogRecord::Put(k, v));
+        }
+        return Ok(LogFormat::Outdated);
+    }
+    let version = u32::from_le_bytes(header[MAGIC.len()..].try_into()?);
+    if (1..=FORMAT_VERSION).contains(&version) == false {
+        return Err(CorruptionError::new(
+            log_path,
+            MAGIC.len() as u64,
+            format!("Unsupported log format version {version}"),
+        )
+        .into());
+    }
+
+    let rec_header_len = record_header_len(version);
+    let mut offset = HEADER_LEN;
+    let mut body = vec![];
+    loop {
+        let mut rec_header = [0u8; RECORD_HEADER_LEN];
+        let rec_header = &mut rec_header[..rec_header_len];
+        if read_until_full(&mut r, rec_header)? < rec_header_len {
+            break; // Either EOF or a torn record.
+        }
+        let corruption = |reason: &str| CorruptionError::new(log_path, offset, reason);
+
+        let (body_len, rest) = rec_header.split_at(mem::size_of::<u32>());
+        let (body_len_crc, crc) = rest.split_at(rest.len() - mem::size_of::<u32>());
+        if body_len_crc.is_empty() == false
+            && crc32fast::hash(body_len) != u32::from_le_bytes(body_len_crc.try_into()?)
+        {
+            return Err(corruption("Log record length checksum mismatch").into());
+        }
+        let body_len = u32::from_le_bytes(body_len.try_into()?) as usize;
+        let crc = u32::from_le_bytes(crc.try_into()?);
+
+        if offset + (rec_header_len + body_len) as u64 > file_len {
+            break; // A torn record.
+        }
+        body.resize(body_len, 0);
+        r.read_exact(&mut body)?;
+
+        if crc32fast::hash(&body) != crc {
+            return Err(corruption("Log record checksum mismatch").into());
+        }
+        let mut body_r = Cursor::new(&body);
+        let kind = match version {
+            1 => RecordKind::Put as u8,
+            _ => {
+                let mut kind_buf = [0u8];
+                body_r
+                    .read_exact(&mut kind_buf)
+                    .map_err(|_| corruption("Log record lacks a kind"))?;
+                kind_buf[0]
+            }
+        };
+        let record = if kind == RecordKind::Put as u8 {
+            match (K::deser(&mut body_r), V::deser(&mut body_r)) {
+                (Ok(ReadResult::Some(_, k)), Ok(ReadResult::Some(_, v))) => LogRecord::Put(k, v),
+                _ => return Err(corruption("Invalid log record body").into()),
+            }
+        } else if kind == RecordKind::RangeTombstone as u8 {
+            match (K::deser(&mut body_r), K::deser(&mut body_r)) {
+                (Ok(ReadResult::Some(_, lo)), Ok(ReadResult::Some(_, hi))) => {
+                    LogRecord::RangeTombstone(lo, hi)
+                }
+                _ => return Err(corruption("Invalid log record body").into()),
+            }
+        } else {
+            return Err(corruption("Unknown log record kind").into());
+        };
+        if body_r.position() != body_len as u64 {
+            return Err(corruption("Log record has trailing bytes").into());
+        }
+        on_record(record);
+
+        offset += (rec_header_len + body_len) as u64;
+    }
+
+    if version != FORMAT_VERSION {
+        return Ok(LogFormat::Outdated);
+    }
+    Ok(LogFormat::Checksummed { valid_len: offset })
+}
+
+/// Unlike [`Read::read_exact()`], EOF is not an error.
+///
+/// @return The count of bytes read. It is less than `buf.len()` only at EOF.
+fn read_until_full<R: Read>(r: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match r.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ReadonlyMemLog, WritableMemLog};
+    use pancake_types::{
+        serde::{Datum, OptDatum},
+        types::{PKShared, PVShared, PrimaryKey, Value},
+    };
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    type TestMemLog = WritableMemLog<PKShared, OptDatum<PVShared>>;
+
+    fn gen_file_path(test_name: &str) -> PathBuf {
+        let dir_path = env::temp_dir().join("pancake_memlog_test").join(test_name);
+        if dir_path.exists() {
+            fs::remove_dir_all(&dir_path).unwrap();
+        }
+        fs::create_dir_all(&dir_path).unwrap();
+        dir_path.join("memlog.kv")
+    }
+
+    fn gen_entry(i: i64) -> (PKShared, OptDatum<PVShared>) {
+        let pk = Arc::new(PrimaryKey(Datum::I64(i)));
+        let pv = OptDatum::Some(Arc::new(Value(Datum::Str(format!("val_{i}")))));
+        (pk, pv)
+    }
+
+    fn write_entries(log_path: &Path, is: impl Iterator<Item = i64>) -> Result<()> {
+        let mut memlog = TestMemLog::load_or_new(log_path)?;
+        for i in is {
+            let (k, v) = gen_entry(i);
+            memlog.put(k, v)?;
+        }
+        memlog.flush()?;
+        assert_eq!(memlog.log_len(), fs::metadata(log_path)?.len());
+        Ok(())
+    }
+
+    fn verify_entries(log_path: &Path, is: impl Iterator<Item = i64>) -> Result<()> {
+        let memlog = ReadonlyMemLog::<PKShared, OptDatum<PVShared>>::load(log_path)?;
+        let act = memlog.memtable.into_iter().collect::<Vec<_>>();
+        let exp = is.map(gen_entry).collect::<Vec<_>>();
+        assert_eq!(act, exp);
+        Ok(())
+    }
+
+    #[test]
+    fn torn_tail() -> Result<()> {
+        let log_path = gen_file_path("torn_tail");
+
+        write_entries(&log_path, 0..5)?;
+        let intact_len = fs::metadata(&log_path)?.len();
+
+        write_entries(&log_path, 5..6)?;
+        let file = fs::OpenOptions::new().write(true).open(&log_path)?;
+        file.set_len(fs::metadata(&log_path)?.len() - 3)?;
+
+        verify_entries(&log_path, 0..5)?;
+
+        /* Reopening for writing truncates the torn record, so that subsequent records are not lost. */
+        write_entries(&log_path, 6..8)?;
+        assert!(fs::metadata(&log_path)?.len() > intact_len);
+        verify_entries(&log_path, (0..5).chain(6..8))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_record() -> Result<()> {
+        let log_path = gen_file_path("corrupt_record");
+
+        write_entries(&log_path, 0..1)?;
+        let second_record_offset = fs::metadata(&log_path)?.len();
+        write_entries(&log_path, 1..3)?;
+
+        let mut buf = fs::read(&log_path)?;
+        buf[second_record_offset as usize + RECORD_HEADER_LEN + 2] ^= 0x10;
+        fs::write(&log_path, buf)?;
+
+        let e = verify_entries(&log_path, 0..3).err().unwrap();
+        let e = e.downcast_ref::<CorruptionError>().unwrap();
+        assert_eq!(e.file_path, log_path);
+        assert_eq!(e.offset, second_record_offset);
+
+        assert!(TestMemLog::load_or_new(&log_path).is_err());
+
+        Ok(())
+    }
+
+    /// A flipped bit in a record's length is not taken for a torn tail, which would drop all subsequent records.
+    #[test]
+    fn corrupt_record_len() -> Result<()> {
+        let log_path = gen_file_path("corrupt_record_len");
+
+        write_entries(&log_path, 0..1)?;
+        let second_record_offset = fs::metadata(&log_path)?.len();
+        write_entries(&log_path, 1..5)?;
+
+        let mut buf = fs::read(&log_path)?;
+        buf[second_record_offset as usize + 1] ^= 0x10;
+        fs::write(&log_path, &buf)?;
+
+        let e = verify_entries(&log_path, 0..5).err().unwrap();
+        let e = e.downcast_ref::<CorruptionError>().unwrap();
+        assert_eq!(e.file_path, log_path);
+        assert_eq!(e.offset, second_record_offset);
+
+        assert!(TestMemLog::load_or_new(&log_path).is_err());
+        assert_eq!(fs::read(&log_path)?, buf);
+
+        Ok(())
+    }
+
+    /// A log that predates checksums is readable, and is rewritten with checksums upon reopening for writing.
+    #[test]
+    fn legacy() -> Result<()> {
+        let log_path = gen_file_path("legacy");
+
+        let mut buf = vec![];
+        for i in 0..3 {
+            let (k, v) = gen_entry(i);
+            k.ser(&mut buf)?;
+            v.ser(&mut buf)?;
+        }
+        fs::write(&log_path, buf)?;
+
+        verify_entries(&log_path, 0..3)?;
+
+        write_entries(&log_path, 3..4)?;
+        assert!(fs::read(&log_path)?.starts_with(&MAGIC));
+        verify_entries(&log_path, 0..4)?;
+
+        Ok(())
+    }
+
+    /// A range tombstone removes the earlier entries within it, but not the later ones.
+    #[test]
+    fn range_tombstones() -> Result<()> {
+        let log_path = gen_file_path("range_tombstones");
+
+        write_entries(&log_path, 0..10)?;
+        let mut memlog = TestMemLog::load_or_new(&log_path)?;
+        let (lo, _) = gen_entry(3);
+        let (hi, _) = gen_entry(6);
+        memlog.delete_range(lo.clone(), hi.clone())?;
+        memlog.delete_range(hi.clone(), lo.clone())?;
+        memlog.flush()?;
+        assert_eq!(memlog.log_len(), fs::metadata(&log_path)?.len());
+        write_entries(&log_path, 5..6)?;
+
+        verify_entries(&log_path, (0..3).chain(5..6).chain(7..10))?;
+        let memlog = ReadonlyMemLog::<PKShared, OptDatum<PVShared>>::load(&log_path)?;
+        let act = memlog.range_tombstones.iter().cloned().collect::<Vec<_>>();
+        assert_eq!(act, vec![(lo, hi)]);
+
+        Ok(())
+    }
+
+    /// A log of an older format version is readable, and is rewritten in the current version upon reopening for writing.
+    ///
+    /// Version 1 records lack a kind. Version 1 and 2 records lack a length checksum.
+    #[test]
+    fn older_format_versions() -> Result<()> {
+        for version in 1..FORMAT_VERSION {
+            let log_path = gen_file_path(&format!("format_version_{version}"));
+
+            let mut buf = vec![];
+            buf.extend_from_slice(&MAGIC);
+            buf.extend_from_slice(&version.to_le_bytes());
+            for i in 0..3 {
+                let (k, v) = gen_entry(i);
+                let mut body = vec![];
+                if version >= 2 {
+                    body.push(RecordKind::Put as u8);
+                }
+                k.ser(&mut body)?;
+                v.ser(&mut body)?;
+                buf.extend_from_slice(&(body.len() as u32).to_le_bytes());
+                buf.extend_from_slice(&crc32fast::hash(&body).to_le_bytes());
+                buf.extend_from_slice(&body);
+            }
+            fs::write(&log_path, buf)?;
+
+            verify_entries(&log_path, 0..3)?;
+
+            write_entries(&log_path, 3..4)?;
+            assert!(fs::read(&log_path)?[MAGIC.len()..].starts_with(&FORMAT_VERSION.to_le_bytes()));
+            verify_entries(&log_path, 0..4)?;
+        }
+
+        Ok(())
+    }
+}
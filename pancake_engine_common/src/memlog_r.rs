@@ -1,15 +1,20 @@
-use crate::fs_utils;
+use crate::{
+    memlog_file::{self, LogFormat, LogRecord},
+    RangeTombstones,
+};
 use anyhow::Result;
-use pancake_types::{iters::KeyValueReader, types::Deser};
+use pancake_types::types::Deser;
 use std::borrow::Borrow;
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
-use std::fs::OpenOptions;
 use std::path::{Path, PathBuf};
 
 /// A MemLog is a sorted dictionary (called Memtable), backed up by a write-ahead log file.
+///
+/// It may also contain [`RangeTombstones`], which shadow older entrysets only.
 pub struct ReadonlyMemLog<K, V> {
     pub memtable: BTreeMap<K, V>,
+    pub range_tombstones: RangeTombstones<K>,
     pub log_path: PathBuf,
 }
 
@@ -18,23 +23,46 @@ where
     K: Deser + Ord,
     V: Deser,
 {
+    /// A torn record at the end of the log is dropped.
+    ///
+    /// @return Err of [`CorruptionError`](crate::CorruptionError) if any other record fails verification.
     pub fn load<P: AsRef<Path>>(log_path: P) -> Result<Self> {
+        let (moi, _) = Self::load_with_format(log_path)?;
+        Ok(moi)
+    }
+
+    pub(crate) fn load_with_format<P: AsRef<Path>>(log_path: P) -> Result<(Self, LogFormat)> {
         let log_path = log_path.as_ref();
 
-        let mut memtable = BTreeMap::default();
+        let mut moi = Self {
+            memtable: BTreeMap::default(),
+            range_tombstones: RangeTombstones::default(),
+            log_path: log_path.into(),
+        };
+        let mut log_format = LogFormat::Empty;
         if log_path.exists() {
-            let log_file = fs_utils::open_file(log_path, OpenOptions::new().read(true))?;
-            let iter = KeyValueReader::<_, K, V>::from(log_file).into_iter_kv();
-            for res_kv in iter {
-                let (k, v) = res_kv?;
-                memtable.insert(k, v);
-            }
+            log_format = memlog_file::replay(log_path, |record| match record {
+                LogRecord::Put(k, v) => {
+                    moi.memtable.insert(k, v);
+                }
+                LogRecord::RangeTombstone(lo, hi) => moi.insert_range_tombstone(lo, hi),
+            })?;
         }
 
-        Ok(Self {
-            memtable,
-            log_path: log_path.into(),
-        })
+        Ok((moi, log_format))
+    }
+
+    /// Removes the memtable's entries within `[lo, hi]`, so that the range tombstone does not need to cover them.
+    pub(crate) fn insert_range_tombstone(&mut self, lo: K, hi: K) {
+        if lo > hi {
+            return;
+        }
+        let mut within_and_above = self.memtable.split_off(&lo);
+        let mut above = within_and_above.split_off(&hi);
+        above.remove(&hi);
+        self.memtable.append(&mut above);
+
+        self.range_tombstones.insert(lo, hi);
     }
 
     pub fn mem_len(&self) -> usize {
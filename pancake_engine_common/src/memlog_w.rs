@@ -1,11 +1,16 @@
-use crate::{fs_utils, ReadonlyMemLog};
+use crate::{
+    fs_utils,
+    memlog_file::{self, LogFormat},
+    ReadonlyMemLog,
+};
 use anyhow::Result;
 use pancake_types::types::Serializable;
 use shorthand::ShortHand;
+use std::ffi::OsString;
 use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
 use std::mem;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// A MemLog is a sorted dictionary (called Memtable), backed up by a write-ahead log file.
 #[derive(ShortHand)]
@@ -14,6 +19,8 @@ pub struct WritableMemLog<K, V> {
     #[shorthand(enable(get))]
     r_memlog: ReadonlyMemLog<K, V>,
     log_writer: BufWriter<File>,
+    log_len: u64,
+    record_buf: Vec<u8>,
 }
 
 impl<K, V> WritableMemLog<K, V>
@@ -21,31 +28,102 @@ where
     K: Serializable + Ord,
     V: Serializable,
 {
+    /// A torn record at the end of the log is dropped, and truncated from the file.
+    /// A log of an older format version is rewritten in the current one.
     pub fn load_or_new<P: AsRef<Path>>(log_path: P) -> Result<Self> {
-        let r_memlog = ReadonlyMemLog::load(&log_path)?;
+        let (r_memlog, log_format) = ReadonlyMemLog::load_with_format(&log_path)?;
+
+        let mut log_len = memlog_file::HEADER_LEN;
+        if let LogFormat::Outdated = log_format {
+            log_len = Self::rewrite_log(&r_memlog)?;
+        }
 
         let log_file = fs_utils::open_file(
             &log_path,
             OpenOptions::new().create(true).append(true), /* Append. *Not* write. */
         )?;
-        let log_writer = BufWriter::new(log_file);
+        if let LogFormat::Checksummed { valid_len } = log_format {
+            log_file.set_len(valid_len)?;
+            log_len = valid_len;
+        }
+        let mut log_writer = BufWriter::new(log_file);
+        if let LogFormat::Empty = log_format {
+            memlog_file::ser_header(&mut log_writer)?;
+            log_writer.flush()?;
+        }
 
         Ok(Self {
             r_memlog,
             log_writer,
+            log_len,
+            record_buf: vec![],
         })
     }
 
+    /// Atomically replaces the log file with one that contains the range tombstones, followed by the memtable's entries.
+    ///
+    /// @return The length of the new log file.
+    fn rewrite_log(r_memlog: &ReadonlyMemLog<K, V>) -> Result<u64> {
+        let mut tmp_path = OsString::from(&r_memlog.log_path);
+        tmp_path.push(".rewrite");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let tmp_file = fs_utils::open_file(
+            &tmp_path,
+            OpenOptions::new().create(true).write(true).truncate(true),
+        )?;
+        let mut w = BufWriter::new(tmp_file);
+        memlog_file::ser_header(&mut w)?;
+        let mut log_len = memlog_file::HEADER_LEN;
+        let mut record_buf = vec![];
+        for (lo, hi) in r_memlog.range_tombstones.iter() {
+            log_len += memlog_file::ser_range_tombstone_record(&mut w, lo, hi, &mut record_buf)?;
+        }
+        for (k, v) in r_memlog.memtable.iter() {
+            log_len += memlog_file::ser_record(&mut w, k, v, &mut record_buf)?;
+        }
+        w.flush()?;
+
+        fs_utils::rename_file(&tmp_path, &r_memlog.log_path)?;
+        Ok(log_len)
+    }
+
+    /// The length of the log file, including any bytes that have been written but not yet [`Self::flush()`]ed.
+    pub fn log_len(&self) -> u64 {
+        self.log_len
+    }
+
     /// The caller is responsible for [`Self::flush()`]ing subsequently.
     pub fn put(&mut self, k: K, v: V) -> Result<()> {
-        k.ser(&mut self.log_writer)?;
-        v.ser(&mut self.log_writer)?;
+        self.log_len +=
+            memlog_file::ser_record(&mut self.log_writer, &k, &v, &mut self.record_buf)?;
 
         self.r_memlog.memtable.insert(k, v);
 
         Ok(())
     }
 
+    /// Deletes all entries within `[lo, hi]`, in this MemLog and in all older entrysets.
+    /// If `lo > hi`, nothing is deleted, and nothing is written.
+    ///
+    /// The caller is responsible for [`Self::flush()`]ing subsequently.
+    pub fn delete_range(&mut self, lo: K, hi: K) -> Result<()> {
+        if lo > hi {
+            return Ok(());
+        }
+
+        self.log_len += memlog_file::ser_range_tombstone_record(
+            &mut self.log_writer,
+            &lo,
+            &hi,
+            &mut self.record_buf,
+        )?;
+
+        self.r_memlog.insert_range_tombstone(lo, hi);
+
+        Ok(())
+    }
+
     pub fn flush(&mut self) -> Result<()> {
         self.log_writer.flush()?;
         Ok(())
@@ -53,6 +131,7 @@ where
 
     pub fn clear(&mut self) -> Result<()> {
         self.r_memlog.memtable.clear();
+        self.r_memlog.range_tombstones.clear();
 
         let log_file =
             fs_utils::open_file(&self.r_memlog.log_path, OpenOptions::new().write(true))?;
@@ -63,6 +142,9 @@ where
 
         let (_old_file, _old_res_buf) = old_writer.into_parts(); // Drop these without flushing.
 
+        memlog_file::ser_header(&mut self.log_writer)?;
+        self.log_len = memlog_file::HEADER_LEN;
+
         Ok(())
     }
 }
@@ -1,4 +1,4 @@
-use crate::entry::Entry;
+use crate::{entry::Entry, RangeTombstones};
 use anyhow::anyhow;
 use itertools::Itertools;
 use std::cmp::Ordering;
@@ -82,6 +82,38 @@ where
     deduped_entry_iter
 }
 
+/// Drops each entry that is covered by the range tombstones of any newer entryset.
+/// It should precede [`merge_entry_iters()`], which would otherwise pick a range-deleted entry over older ones.
+///
+/// @arg newer_range_tombstones: Those of the entrysets that are newer than all of `entry_iters`.
+/// @arg entry_iters: An iterator of iterators of entrysets, each paired with the entryset's own range tombstones,
+///     from newer entryset to older entryset.
+/// @return The filtered iterators, in the same order.
+pub fn drop_range_deleted<'a, EntIter, Ent, K>(
+    newer_range_tombstones: Vec<&'a RangeTombstones<K>>,
+    entry_iters: impl 'a + Iterator<Item = (EntIter, &'a RangeTombstones<K>)>,
+) -> impl 'a + Iterator<Item = impl 'a + Iterator<Item = Ent>>
+where
+    EntIter: 'a + Iterator<Item = Ent>,
+    Ent: 'a + Mergeable<K>,
+    K: 'a + Ord,
+{
+    let mut newer_range_tombstones = newer_range_tombstones;
+    entry_iters.map(move |(entry_iter, range_tombstones)| {
+        let shadowing_range_tombstones = newer_range_tombstones
+            .iter()
+            .filter(|rts| rts.is_empty() == false)
+            .cloned()
+            .collect::<Vec<_>>();
+        newer_range_tombstones.push(range_tombstones);
+
+        entry_iter.filter(move |entry| match entry.try_borrow() {
+            Err(_) => true,
+            Ok(k) => shadowing_range_tombstones.iter().any(|rts| rts.covers(k)) == false,
+        })
+    })
+}
+
 pub fn merge_differently_typed_entry_iters<'a, K, V>(
     mut entry_iter_newer: Option<impl 'a + Iterator<Item = (&'a K, &'a V)>>,
     entry_iter_older: impl 'a + Iterator<Item = Entry<'a, K, V>>,
@@ -0,0 +1,197 @@
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+/// The tuning of a storage engine instance.
+///
+/// Each option can be set by its field name, as a string, so that it may be read from a config file or env vars.
+///
+/// Each threshold is reached inclusively, e.g. a memtable is flushed as soon as its entry count *equals* the threshold.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EngineOptions {
+    /// In the serial engine, a memtable is flushed into an SSTable once it has this many entries.
+    /// In the SSI engine, it bounds only the memtables that buffer a secondary index being created.
+    pub memtable_flush_entry_count: usize,
+
+    /// In the serial engine, a memtable is flushed into an SSTable once its write-ahead log reaches this many bytes.
+    pub memtable_flush_byte_size: u64,
+
+    /// In the serial engine, how SSTables are chosen to be compacted.
+    pub compaction_strategy: CompactionStrategy,
+
+    /// In the serial engine, the count of sorted runs of SSTables, at which compaction begins.
+    /// See [`CompactionStrategy`].
+    pub sstable_compact_count: usize,
+
+    /// Compaction writes SSTables of about this many bytes each, as a sorted run split by key range.
+    pub sstable_target_file_size: u64,
+
+    /// An SSTable data block is closed once its uncompressed length reaches this many bytes.
+    pub sstable_data_block_size: u64,
+
+    /// In the SSI engine, the capacity of the channel that notifies the flushing-and-compaction worker
+    /// of newly committed versions.
+    /// A notification that does not fit is dropped, and the commit is flushed and compacted in a later round.
+    pub fc_able_commit_vers_capacity: usize,
+
+    /// In the SSI engine, the count of commit versions older than the current one, which can still be read as of.
+    /// The flushing-and-compaction worker keeps these versions apart.
+    /// Only versions committed since the DB was loaded are retained. 0 disables reading as of an older version.
+    pub commit_ver_retention_count: u64,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            memtable_flush_entry_count: 100_000,
+            memtable_flush_byte_size: 4 * 1024 * 1024,
+            compaction_strategy: CompactionStrategy::SizeTiered,
+            sstable_compact_count: 4,
+            sstable_target_file_size: 64 * 1024 * 1024,
+            sstable_data_block_size: 4 * 1024,
+            fc_able_commit_vers_capacity: 1024,
+            commit_ver_retention_count: 0,
+        }
+    }
+}
+
+impl EngineOptions {
+    pub const NAMES: [&'static str; 8] = [
+        "memtable_flush_entry_count",
+        "memtable_flush_byte_size",
+        "compaction_strategy",
+        "sstable_compact_count",
+        "sstable_target_file_size",
+        "sstable_data_block_size",
+        "fc_able_commit_vers_capacity",
+        "commit_ver_retention_count",
+    ];
+
+    /// @arg `name`: One of [`Self::NAMES`].
+    pub fn set_by_name(&mut self, name: &str, val: &str) -> Result<()> {
+        match name {
+            "memtable_flush_entry_count" => {
+                self.memtable_flush_entry_count = Self::parse(name, val)?
+            }
+            "memtable_flush_byte_size" => self.memtable_flush_byte_size = Self::parse(name, val)?,
+            "compaction_strategy" => self.compaction_strategy = Self::parse(name, val)?,
+            "sstable_compact_count" => self.sstable_compact_count = Self::parse(name, val)?,
+            "sstable_target_file_size" => self.sstable_target_file_size = Self::parse(name, val)?,
+            "sstable_data_block_size" => self.sstable_data_block_size = Self::parse(name, val)?,
+            "fc_able_commit_vers_capacity" => {
+                self.fc_able_commit_vers_capacity = Self::parse(name, val)?
+            }
+            "commit_ver_retention_count" => {
+                self.commit_ver_retention_count = Self::parse(name, val)?
+            }
+            _ => return Err(anyhow!("Unknown engine option {name:?}.")),
+        }
+        Ok(())
+    }
+
+    fn parse<T: FromStr>(name: &str, val: &str) -> Result<T> {
+        val.trim()
+            .parse::<T>()
+            .map_err(|_| anyhow!("Invalid value {val:?} for engine option {name:?}."))
+    }
+
+    /// Called by each engine upon loading a DB.
+    pub fn validate(&self) -> Result<()> {
+        let positives = [
+            (
+                "memtable_flush_entry_count",
+                self.memtable_flush_entry_count as u64,
+            ),
+            ("memtable_flush_byte_size", self.memtable_flush_byte_size),
+            ("sstable_target_file_size", self.sstable_target_file_size),
+            ("sstable_data_block_size", self.sstable_data_block_size),
+            (
+                "fc_able_commit_vers_capacity",
+                self.fc_able_commit_vers_capacity as u64,
+            ),
+        ];
+        for (name, val) in positives {
+            if val == 0 {
+                return Err(anyhow!("Engine option {name:?} must be positive."));
+            }
+        }
+
+        if self.sstable_compact_count < 2 {
+            return Err(anyhow!(
+                "Engine option \"sstable_compact_count\" must be at least 2. Got {}.",
+                self.sstable_compact_count
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Compaction always merges the newest sorted runs of SSTables into one sorted run.
+/// A sorted run is a sequence of SSTables with disjoint key ranges, e.g. the output of one compaction.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CompactionStrategy {
+    /// Once there are [`EngineOptions::sstable_compact_count`] runs, all runs are merged.
+    /// Every compaction rewrites the whole dataset.
+    Full,
+    /// Runs are merged once [`EngineOptions::sstable_compact_count`] of the newest runs are of similar sizes,
+    /// where each run is no larger than all newer merged runs combined.
+    /// Hence each entry is rewritten about logarithmically many times in the dataset size.
+    SizeTiered,
+}
+
+impl FromStr for CompactionStrategy {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "full" => Ok(Self::Full),
+            "size_tiered" => Ok(Self::SizeTiered),
+            _ => Err(anyhow!("Unknown compaction strategy {s:?}.")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn set_and_validate() -> Result<()> {
+        let mut opts = EngineOptions::default();
+        opts.validate()?;
+
+        for name in EngineOptions::NAMES {
+            let val = match name {
+                "compaction_strategy" => " full ",
+                _ => " 7 ",
+            };
+            opts.set_by_name(name, val)?;
+        }
+        assert_eq!(opts.memtable_flush_entry_count, 7);
+        assert_eq!(opts.memtable_flush_byte_size, 7);
+        assert_eq!(opts.compaction_strategy, CompactionStrategy::Full);
+        assert_eq!(opts.sstable_compact_count, 7);
+        assert_eq!(opts.sstable_target_file_size, 7);
+        assert_eq!(opts.sstable_data_block_size, 7);
+        assert_eq!(opts.fc_able_commit_vers_capacity, 7);
+        assert_eq!(opts.commit_ver_retention_count, 7);
+        opts.validate()?;
+
+        assert!(opts.set_by_name("no_such_option", "7").is_err());
+        assert!(opts.set_by_name("sstable_compact_count", "-7").is_err());
+        assert!(opts.set_by_name("sstable_compact_count", "seven").is_err());
+        assert!(opts.set_by_name("compaction_strategy", "leveled").is_err());
+
+        for (name, val) in [
+            ("memtable_flush_byte_size", "0"),
+            ("sstable_target_file_size", "0"),
+            ("fc_able_commit_vers_capacity", "0"),
+            ("sstable_compact_count", "1"),
+        ] {
+            let mut opts = EngineOptions::default();
+            opts.set_by_name(name, val)?;
+            assert!(opts.validate().is_err(), "{name} {val}");
+        }
+
+        Ok(())
+    }
+}
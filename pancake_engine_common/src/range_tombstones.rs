@@ -0,0 +1,90 @@
+/// The range tombstones of one entryset (i.e. one MemLog or one sorted run of SSTables).
+///
+/// Each range tombstone is an inclusive interval of keys, which deletes all entries within it
+/// that are in *older* entrysets. It does not cover the entryset's own entries,
+/// because these are always written after the range tombstone. (Writing a range tombstone into a MemLog
+/// removes the MemLog's own entries within the range.)
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RangeTombstones<K> {
+    /// Neither interval contains another.
+    itvs: Vec<(K, K)>,
+}
+
+impl<K> Default for RangeTombstones<K> {
+    fn default() -> Self {
+        Self { itvs: vec![] }
+    }
+}
+
+impl<K> RangeTombstones<K> {
+    pub fn is_empty(&self) -> bool {
+        self.itvs.is_empty()
+    }
+
+    /// @return The `(lo_incl, hi_incl)` intervals, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &(K, K)> {
+        self.itvs.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.itvs.clear();
+    }
+
+    pub fn covers<Q>(&self, k: &Q) -> bool
+    where
+        K: PartialOrd<Q>,
+    {
+        self.itvs.iter().any(|(lo, hi)| lo <= k && hi >= k)
+    }
+
+    /// @return Whether any interval overlaps `[lo_incl, hi_incl]`, where `None` is unbounded.
+    pub fn overlaps_with_range<Q>(&self, lo_incl: Option<&Q>, hi_incl: Option<&Q>) -> bool
+    where
+        K: PartialOrd<Q>,
+    {
+        self.itvs.iter().any(|(lo, hi)| {
+            let is_lo_ok = match lo_incl {
+                None => true,
+                Some(lo_incl) => hi >= lo_incl,
+            };
+            let is_hi_ok = match hi_incl {
+                None => true,
+                Some(hi_incl) => lo <= hi_incl,
+            };
+            is_lo_ok && is_hi_ok
+        })
+    }
+}
+
+impl<K> RangeTombstones<K>
+where
+    K: Ord,
+{
+    /// An empty interval, i.e. `lo > hi`, is ignored.
+    /// An interval that is contained in an existing one is ignored, and vice versa, existing ones are replaced.
+    pub fn insert(&mut self, lo: K, hi: K) {
+        if lo > hi {
+            return;
+        }
+        if self
+            .itvs
+            .iter()
+            .any(|(ex_lo, ex_hi)| ex_lo <= &lo && &hi <= ex_hi)
+        {
+            return;
+        }
+        self.itvs
+            .retain(|(ex_lo, ex_hi)| (&lo <= ex_lo && ex_hi <= &hi) == false);
+        self.itvs.push((lo, hi));
+    }
+
+    /// Unions `other` into `self`.
+    pub fn extend_from(&mut self, other: &Self)
+    where
+        K: Clone,
+    {
+        for (lo, hi) in other.itvs.iter() {
+            self.insert(lo.clone(), hi.clone());
+        }
+    }
+}
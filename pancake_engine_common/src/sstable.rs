@@ -1,35 +1,99 @@
-use crate::{ds_n_a::bisect, entry::Entry, fs_utils};
+use crate::{
+    ds_n_a::{bisect, bloom_filter::BloomFilter},
+    entry::Entry,
+    fs_utils, CorruptionError, RangeTombstones,
+};
 use anyhow::{anyhow, Result};
 use derive_more::{Deref, DerefMut, From};
+use footer::{FileOffset, Trailer};
+use itertools::Either;
 use pancake_types::{
     iters::KeyValueReader,
     serde::ReadResult,
-    types::{Deser, Ser},
+    types::{Deser, PartialOrdSer, Ser},
 };
+use std::any;
 use std::cmp::{Ord, Ordering, PartialOrd};
-use std::fs::OpenOptions;
-use std::io::{BufWriter, SeekFrom, Write};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Cursor, Read, Seek, SeekFrom, Write};
 use std::iter;
 use std::marker::PhantomData;
+use std::mem;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
-/// The sparseness is exaggeratedly small, so as to be helpful with debugging.
-/// In the future, we'll allow setting it from an env var.
-const FILE_OFFSETS_SPARSENESS: usize = 3;
+mod codec;
+mod footer;
+#[cfg(test)]
+mod test;
 
-fn is_kv_sparsely_captured(entry_i: usize) -> bool {
-    entry_i % FILE_OFFSETS_SPARSENESS == FILE_OFFSETS_SPARSENESS - 1
-}
+pub use codec::BlockCodec;
+
+/// Values are verbose documents, so compression pays off.
+const DATA_BLOCK_CODEC: BlockCodec = BlockCodec::Lz4;
 
-#[derive(Clone, Copy)]
-struct FileOffset(u64);
+const BLOCK_CHECKSUM_LEN: usize = mem::size_of::<u32>();
+
+/// A file of the unversioned format has no index block.
+/// On load, its entries are grouped into data blocks of about this length, as if it had one.
+const UNVERSIONED_BLOCK_SIZE: u64 = 4 * 1024;
 
 /// An SSTable is an abstraction of a sorted dictionary.
 /// An SSTable has these components:
 /// - A file which stores `(key, val_or_tombstone)` pairs, sorted by key, containing distinct keys.
-/// - An in-memory sorted structure that maps `{key: file_offset}` on sparsely captured keys. The offsets point to locations within the above file.
+/// - An in-memory sorted structure that maps `{key: file_offset}` on the first key of each data block.
+/// - An in-memory bloom filter over all keys. It lets point lookups of most absent keys skip reading the file.
+/// - In-memory [`RangeTombstones`], which shadow older entrysets only.
+///
+/// #### File layout
+///
+/// ```text
+/// struct SSTableFile {
+///     data_blocks:        [DataBlock; block_count],
+///     index_block:        [(first_k: K, data_block_offset: u64); block_count],
+///     filter_block:       BloomFilter,    // Over the serialized keys.
+///     key_bounds:         Option<(min_k: K, max_k: K)>,   // Absent iff entry_count == 0.
+///     range_tombstones:   [(lo_incl: K, hi_incl: K)],
+///     trailer:            {
+///         index_offset:       u64,
+///         filter_offset:      u64,
+///         key_bounds_offset:  u64,
+///         entry_count:        u64,
+///         codec:              u8,     // See `BlockCodec`.
+///         checksum:           u32,
+///         format_version:     u32,
+///         magic:              [u8; 8],
+///     },
+/// }
+///
+/// struct DataBlock {
+///     entries:            [(K, V)],   // In the format described at `pancake_types::iters`. Then encoded by `codec`.
+///     checksum:           u32,        // CRC-32 of the encoded `entries`.
+/// }
+/// ```
+///
+/// The trailer's `checksum` covers the index block, filter block, key bounds, range tombstones, and the preceding trailer fields.
+/// Checksums are verified on every read. A mismatch is reported as a [`CorruptionError`].
+///
+/// Data block offsets and lengths are those of the encoded blocks, including their checksums.
+///
+/// Loading an SSTable reads only the trailer, the index block, the filter block, the key bounds, and the range tombstones.
+/// Reading a range of keys reads, in whole, only the data blocks that may contain the range.
+///
+/// A file of the unversioned format, which predates the trailer, consists of the entries only.
+/// Loading it scans all of its entries. See [`Self::load_unversioned()`].
+/// A file that is neither of the unversioned format nor has a valid trailer is reported as a [`CorruptionError`].
 pub struct SSTable<K, V> {
-    sparse_file_offsets: SparseFileOffsets<K>,
+    block_offsets: BlockOffsets<K>,
+    data_end: FileOffset,
+    /// None iff the file was written in a format version that predates the filter block.
+    filter: Option<BloomFilter>,
+    key_bounds: Option<(K, K)>,
+    range_tombstones: RangeTombstones<K>,
+    entry_count: u64,
+    codec: BlockCodec,
+    /// False iff the file was written in a format version that predates checksums.
+    is_checksummed: bool,
     kv_file_path: PathBuf,
     _phant: PhantomData<V>,
 }
@@ -39,9 +103,75 @@ where
     K: Ser + Ord,
     V: Ser,
 {
+    /// @arg `block_size`: A data block is closed as soon as its uncompressed length reaches this,
+    ///     so every block except the last one is at least this long.
+    ///     An entry is never split across blocks.
+    ///     See [`EngineOptions::sstable_data_block_size`](crate::EngineOptions::sstable_data_block_size).
     pub fn new<'a>(
         entries: impl Iterator<Item = Entry<'a, K, V>>,
+        range_tombstones: RangeTombstones<K>,
+        kv_file_path: PathBuf,
+        block_size: u64,
+    ) -> Result<Self>
+    where
+        K: 'a + Clone,
+        V: 'a,
+    {
+        Self::new_with_format(
+            entries,
+            range_tombstones,
+            kv_file_path,
+            block_size,
+            DATA_BLOCK_CODEC,
+            u64::MAX,
+        )
+    }
+
+    /// Writes the entries into as many SSTables as necessary, such that each SSTable is about `file_size` long.
+    ///
+    /// An SSTable is closed as soon as its data blocks' length reaches `file_size`.
+    /// Since the entries are sorted, the resulting SSTables have disjoint key ranges, and are ordered by key.
+    ///
+    /// The range tombstones are all written into the first SSTable.
+    ///
+    /// @arg `gen_kv_file_path`: Called once per resulting SSTable.
+    /// @arg `block_size`: See [`Self::new()`].
+    /// @return No SSTable if `entries` and `range_tombstones` are both empty.
+    pub fn new_multi<'a>(
+        entries: impl Iterator<Item = Entry<'a, K, V>>,
+        mut range_tombstones: RangeTombstones<K>,
+        mut gen_kv_file_path: impl FnMut() -> PathBuf,
+        block_size: u64,
+        file_size: u64,
+    ) -> Result<Vec<Self>>
+    where
+        K: 'a + Clone,
+        V: 'a,
+    {
+        let mut entries = entries.peekable();
+        let mut ssts = vec![];
+        while entries.peek().is_some() || range_tombstones.is_empty() == false {
+            let sst = Self::new_with_format(
+                &mut entries,
+                mem::take(&mut range_tombstones),
+                gen_kv_file_path(),
+                block_size,
+                DATA_BLOCK_CODEC,
+                file_size,
+            )?;
+            ssts.push(sst);
+        }
+        Ok(ssts)
+    }
+
+    /// @arg `data_len_limit`: Once the data blocks' length reaches this, no more entries are consumed.
+    fn new_with_format<'a>(
+        entries: impl Iterator<Item = Entry<'a, K, V>>,
+        range_tombstones: RangeTombstones<K>,
         kv_file_path: PathBuf,
+        block_size: u64,
+        codec: BlockCodec,
+        data_len_limit: u64,
     ) -> Result<Self>
     where
         K: 'a + Clone,
@@ -53,32 +183,127 @@ where
         )?;
         let mut w = BufWriter::new(kv_file);
 
-        let mut sparse_file_offsets = SparseFileOffsets::from(vec![]);
+        /* Data blocks. */
+
+        let mut block_offsets = BlockOffsets::from(vec![]);
         let mut file_offset = FileOffset(0);
+        let mut entry_count = 0u64;
+        let mut last_entry = None;
+        let mut block_buf = vec![];
+        let mut k_buf = vec![];
+        let mut k_hashes = vec![];
 
-        for (entry_i, entry) in entries.enumerate() {
+        for entry in entries {
             let (k_ref, v_ref) = entry.try_borrow()?;
 
-            let mut delta_offset = 0;
-            delta_offset += *(k_ref.ser(&mut w)?);
-            delta_offset += *(v_ref.ser(&mut w)?);
+            if block_buf.is_empty() {
+                block_offsets.push((k_ref.clone(), file_offset));
+            }
+
+            k_buf.clear();
+            k_ref.ser(&mut k_buf)?;
+            block_buf.extend_from_slice(&k_buf);
+            v_ref.ser(&mut block_buf)?;
+            k_hashes.push(BloomFilter::hash(&k_buf));
+
+            entry_count += 1;
+            last_entry = Some(entry);
 
-            if is_kv_sparsely_captured(entry_i) {
-                let k_own = entry.into_owned_k()?;
-                sparse_file_offsets.push((k_own, file_offset));
+            if block_buf.len() as u64 >= block_size {
+                file_offset.0 += Self::write_block(&mut w, &mut block_buf, codec)?;
+
+                if file_offset.0 >= data_len_limit {
+                    break;
+                }
             }
+        }
+        if block_buf.is_empty() == false {
+            file_offset.0 += Self::write_block(&mut w, &mut block_buf, codec)?;
+        }
+
+        let data_end = file_offset;
+
+        /* Index block. It's buffered along with the rest of the metadata, to be checksummed. */
+
+        let mut meta_buf = vec![];
 
-            file_offset.0 += delta_offset as u64;
+        for (k, block_offset) in block_offsets.iter() {
+            k.ser(&mut meta_buf)?;
+            block_offset.ser(&mut meta_buf)?;
         }
 
+        /* Filter block. */
+
+        let filter_offset = FileOffset(data_end.0 + meta_buf.len() as u64);
+
+        let filter = BloomFilter::from_hashes(&k_hashes);
+        filter.ser(&mut meta_buf)?;
+
+        /* Key bounds. */
+
+        let key_bounds_offset = FileOffset(data_end.0 + meta_buf.len() as u64);
+
+        let key_bounds = match last_entry {
+            None => None,
+            Some(last_entry) => {
+                let (min_k, _) = &block_offsets[0];
+                let min_k = min_k.clone();
+                let max_k = last_entry.into_owned_k()?;
+                min_k.ser(&mut meta_buf)?;
+                max_k.ser(&mut meta_buf)?;
+                Some((min_k, max_k))
+            }
+        };
+
+        /* Range tombstones. */
+
+        for (lo, hi) in range_tombstones.iter() {
+            lo.ser(&mut meta_buf)?;
+            hi.ser(&mut meta_buf)?;
+        }
+
+        w.write_all(&meta_buf)?;
+
+        /* Trailer. */
+
+        let mut trailer = Trailer {
+            index_offset: data_end,
+            filter_offset: Some(filter_offset),
+            key_bounds_offset,
+            entry_count,
+            codec,
+            checksum: None,
+        };
+        trailer.checksum = Some(trailer.compute_checksum(&meta_buf)?);
+        trailer.ser(&mut w)?;
+
         w.flush()?;
 
         Ok(Self {
-            sparse_file_offsets,
+            block_offsets,
+            data_end,
+            filter: Some(filter),
+            key_bounds,
+            range_tombstones,
+            entry_count,
+            codec,
+            is_checksummed: true,
             kv_file_path,
             _phant: PhantomData,
         })
     }
+
+    /// Encodes and writes the block followed by its checksum, then clears the block.
+    ///
+    /// @return The written length.
+    fn write_block<W: Write>(w: &mut W, block_buf: &mut Vec<u8>, codec: BlockCodec) -> Result<u64> {
+        let stored = codec.encode(block_buf);
+        w.write_all(&stored)?;
+        w.write_all(&crc32fast::hash(&stored).to_le_bytes())?;
+        let stored_len = (stored.len() + BLOCK_CHECKSUM_LEN) as u64;
+        block_buf.clear();
+        Ok(stored_len)
+    }
 }
 
 impl<K, V> SSTable<K, V>
@@ -89,103 +314,372 @@ where
     pub fn load<P: AsRef<Path>>(kv_file_path: P) -> Result<Self> {
         let kv_file_path = kv_file_path.as_ref();
 
-        let kv_file = fs_utils::open_file(kv_file_path, OpenOptions::new().read(true))?;
+        let mut kv_file = fs_utils::open_file(kv_file_path, OpenOptions::new().read(true))?;
+
+        /* Trailer. */
+
+        let file_len = fs_utils::seek(&mut kv_file, SeekFrom::End(0), kv_file_path)?;
+        let tail_len = file_len.min(Trailer::MAX_LEN as u64);
+        fs_utils::seek(
+            &mut kv_file,
+            SeekFrom::Start(file_len - tail_len),
+            kv_file_path,
+        )?;
+        let mut tail = vec![0u8; tail_len as usize];
+        kv_file.read_exact(&mut tail)?;
+        if Trailer::is_present(&tail) == false {
+            return Self::load_unversioned(kv_file, kv_file_path, file_len);
+        }
+        let (trailer, trailer_len) = Trailer::deser(&tail)
+            .map_err(|e| CorruptionError::new(kv_file_path, file_len - tail_len, e.to_string()))?;
+        let trailer_offset = file_len - trailer_len as u64;
+        if (trailer.key_bounds_offset.0 <= trailer_offset) == false {
+            return Err(CorruptionError::new(
+                kv_file_path,
+                trailer_offset,
+                "SSTable trailer points past itself.",
+            )
+            .into());
+        }
+
+        /* Index block, filter block, key bounds, and range tombstones. */
+
+        fs_utils::seek(
+            &mut kv_file,
+            SeekFrom::Start(trailer.index_offset.0),
+            kv_file_path,
+        )?;
+        let mut buf = vec![0u8; (trailer_offset - trailer.index_offset.0) as usize];
+        kv_file.read_exact(&mut buf)?;
+        if let Some(checksum) = trailer.checksum {
+            if trailer.compute_checksum(&buf)? != checksum {
+                return Err(CorruptionError::new(
+                    kv_file_path,
+                    trailer.index_offset.0,
+                    "SSTable metadata checksum mismatch.",
+                )
+                .into());
+            }
+        }
+        let meta_len = buf.len() as u64;
+        let mut r = Cursor::new(buf);
+
+        let index_end = trailer.filter_offset.unwrap_or(trailer.key_bounds_offset);
+        let index_len = index_end.0 - trailer.index_offset.0;
+        let mut block_offsets = BlockOffsets::from(vec![]);
+        while r.position() < index_len {
+            let k = Self::deser_k(&mut r)?;
+            let block_offset = FileOffset::deser(&mut r)?;
+            block_offsets.push((k, block_offset));
+        }
+
+        let filter = match trailer.filter_offset {
+            None => None,
+            Some(filter_offset) => {
+                let filter_len = trailer.key_bounds_offset.0 - filter_offset.0;
+                Some(BloomFilter::deser(&mut r, filter_len as usize)?)
+            }
+        };
+
+        let key_bounds = if trailer.entry_count == 0 {
+            None
+        } else {
+            let min_k = Self::deser_k(&mut r)?;
+            let max_k = Self::deser_k(&mut r)?;
+            Some((min_k, max_k))
+        };
+
+        let mut range_tombstones = RangeTombstones::default();
+        while r.position() < meta_len {
+            let lo = Self::deser_k(&mut r)?;
+            let hi = Self::deser_k(&mut r)?;
+            range_tombstones.insert(lo, hi);
+        }
+
+        Ok(Self {
+            block_offsets,
+            data_end: trailer.index_offset,
+            filter,
+            key_bounds,
+            range_tombstones,
+            entry_count: trailer.entry_count,
+            codec: trailer.codec,
+            is_checksummed: trailer.checksum.is_some(),
+            kv_file_path: kv_file_path.into(),
+            _phant: PhantomData,
+        })
+    }
+
+    /// The file is a plain sequence of entries, uncompressed and without checksums.
+    /// The entries are scanned, to recreate the block offsets, the bloom filter, and the key bounds.
+    ///
+    /// Since the file has no magic, it is accepted only if it scans in whole as entries sorted by distinct keys.
+    /// Hence e.g. a file of a later format version that lost its trailer is reported as a [`CorruptionError`].
+    fn load_unversioned(mut kv_file: File, kv_file_path: &Path, file_len: u64) -> Result<Self> {
+        fs_utils::seek(&mut kv_file, SeekFrom::Start(0), kv_file_path)?;
         let mut reader = KeyValueReader::<_, K, V>::from(kv_file);
 
-        let mut sparse_file_offsets = SparseFileOffsets::from(vec![]);
+        let mut block_offsets = BlockOffsets::from(vec![]);
         let mut file_offset = FileOffset(0);
+        let mut entry_count = 0u64;
+        let mut min_k_buf = vec![];
+        let mut k_buf = vec![];
+        let mut k_hashes = vec![];
+        let mut prev_k = None;
 
-        for entry_i in 0usize.. {
-            if is_kv_sparsely_captured(entry_i) {
-                match reader.deser_k_skip_v()? {
-                    ReadResult::EOF => break,
-                    ReadResult::Some(delta_r_len, k) => {
-                        sparse_file_offsets.push((k, file_offset));
-                        file_offset.0 += delta_r_len as u64;
-                    }
-                }
-            } else {
-                match reader.skip_kv()? {
-                    ReadResult::EOF => break,
-                    ReadResult::Some(delta_r_len, ()) => file_offset.0 += delta_r_len as u64,
+        let corruption = |offset: FileOffset, reason: String| {
+            CorruptionError::new(kv_file_path, offset.0, reason)
+        };
+
+        loop {
+            let (entry_len, k) = match Self::read_unversioned_entry(&mut reader, &mut k_buf)
+                .map_err(|e| corruption(file_offset, e.to_string()))?
+            {
+                ReadResult::EOF => break,
+                ReadResult::Some(entry_len, k) => (entry_len, k),
+            };
+            if let Some(prev_k) = prev_k.as_ref() {
+                if (prev_k < &k) == false {
+                    let reason = "Unversioned SSTable entries are not sorted by distinct keys.";
+                    return Err(corruption(file_offset, String::from(reason)).into());
                 }
             }
+
+            let is_block_start = match block_offsets.last() {
+                None => true,
+                Some((_, block_offset)) => file_offset.0 - block_offset.0 >= UNVERSIONED_BLOCK_SIZE,
+            };
+            if is_block_start {
+                let block_k = Self::deser_k(&mut Cursor::new(&k_buf))?;
+                block_offsets.push((block_k, file_offset));
+            }
+            k_hashes.push(BloomFilter::hash(&k_buf));
+            if entry_count == 0 {
+                min_k_buf = k_buf.clone();
+            }
+            prev_k = Some(k);
+
+            entry_count += 1;
+            file_offset.0 += entry_len as u64;
+        }
+        if file_offset.0 != file_len {
+            let reason = "Unversioned SSTable file ends mid-entry.";
+            return Err(corruption(file_offset, String::from(reason)).into());
         }
 
+        let key_bounds = match prev_k {
+            None => None,
+            Some(max_k) => {
+                let min_k = Self::deser_k(&mut Cursor::new(&min_k_buf))?;
+                Some((min_k, max_k))
+            }
+        };
+
         Ok(Self {
-            sparse_file_offsets,
+            block_offsets,
+            data_end: file_offset,
+            filter: Some(BloomFilter::from_hashes(&k_hashes)),
+            key_bounds,
+            range_tombstones: RangeTombstones::default(),
+            entry_count,
+            codec: BlockCodec::None,
+            is_checksummed: false,
             kv_file_path: kv_file_path.into(),
             _phant: PhantomData,
         })
     }
 
+    /// Reads one entry of an unversioned file, and copies its serialized key into `k_buf`.
+    ///
+    /// @return The entry's length, and its key.
+    fn read_unversioned_entry(
+        reader: &mut KeyValueReader<File, K, V>,
+        k_buf: &mut Vec<u8>,
+    ) -> Result<ReadResult<K>> {
+        k_buf.clear();
+        let k_len = match reader.read_ser_k(k_buf)? {
+            ReadResult::EOF => return Ok(ReadResult::EOF),
+            ReadResult::Some(k_len, ()) => k_len,
+        };
+        let v_len = reader.skip_v()?;
+        let k = Self::deser_k(&mut Cursor::new(&k_buf))?;
+        Ok(ReadResult::Some(k_len + v_len, k))
+    }
+
+    fn deser_k<R: Read + Seek>(r: &mut R) -> Result<K> {
+        match K::deser(r)? {
+            ReadResult::EOF => Err(anyhow!(
+                "EOF where a `K` typed {} is expected.",
+                any::type_name::<K>()
+            )),
+            ReadResult::Some(_, k) => Ok(k),
+        }
+    }
+
+    pub fn entry_count(&self) -> u64 {
+        self.entry_count
+    }
+
+    /// The length of the data blocks, as stored. It excludes the metadata, which is comparatively small.
+    pub fn data_len(&self) -> u64 {
+        self.data_end.0
+    }
+
+    /// @return The min and max keys. None iff the SSTable is empty.
+    pub fn key_bounds(&self) -> Option<&(K, K)> {
+        self.key_bounds.as_ref()
+    }
+
+    /// They do not cover this SSTable's own entries. See [`RangeTombstones`].
+    pub fn range_tombstones(&self) -> &RangeTombstones<K> {
+        &self.range_tombstones
+    }
+
+    /// Consults the bloom filter first, if the file has one. It requires that `Q` serialize identically to the equal `K`.
     pub fn get_one<Q>(&self, k: &Q) -> Option<Result<(K, V)>>
     where
-        K: PartialOrd<Q>,
+        K: PartialOrdSer<Q>,
+        Q: Ser,
     {
-        let mut iter = self.get_range(Some(k), None).take(1);
+        self.get_one_as(k)
+    }
+
+    /// The counterpart of [`Self::get_one()`], which reads the value as a `V2` instead of a `V`,
+    /// eg as its serialized bytes. `V2` must be deserializable from any serialized `V`.
+    pub fn get_one_as<Q, V2>(&self, k: &Q) -> Option<Result<(K, V2)>>
+    where
+        K: PartialOrdSer<Q>,
+        Q: Ser,
+        V2: Deser,
+    {
+        if let Some(filter) = self.filter.as_ref() {
+            match k.ser_solo() {
+                Err(e) => return Some(Err(e)),
+                Ok(k_buf) => {
+                    if filter.may_contain(&k_buf) == false {
+                        return None;
+                    }
+                }
+            }
+        }
+
+        let mut iter = self.get_range_as(Some(k), None).take(1);
         iter.next().filter(|res| match res {
             Err(_) => true,
             Ok((sample_k, _)) => sample_k.partial_cmp(k).unwrap_or(Ordering::Equal).is_eq(),
         })
     }
 
-    /// 1. Bisect in the in-memory sparse index, to find the lower-bound file offset.
-    /// 1. Seek the offset in the file. Then read linearlly in file until either EOF or the last-read key is greater than the sought key.
+    /// 1. Bisect in the in-memory block offsets, to find the data blocks that may contain the range.
+    /// 1. Read each such data block in whole. Then read linearly in the block until either the end of the block or the last-read key is greater than the sought key.
     pub fn get_range<'a, Q>(
         &'a self,
         k_lo: Option<&'a Q>,
         k_hi: Option<&'a Q>,
     ) -> impl 'a + Iterator<Item = Result<(K, V)>>
     where
-        K: PartialOrd<Q>,
+        K: PartialOrdSer<Q>,
     {
-        let file_offset = self.sparse_file_offsets.nearest_preceding_file_offset(k_lo);
-
-        let res_file = fs_utils::open_file(&self.kv_file_path, OpenOptions::new().read(true));
-        let mut res_file_iter = res_file.and_then(|mut file| {
-            fs_utils::seek(
-                &mut file,
-                SeekFrom::Start(file_offset.0),
-                &self.kv_file_path,
-            )?;
+        self.get_range_as(k_lo, k_hi)
+    }
 
-            let iter = KeyValueReader::from(file).into_iter_kv_range(k_lo, k_hi);
+    fn get_range_as<'a, Q, V2>(
+        &'a self,
+        k_lo: Option<&'a Q>,
+        k_hi: Option<&'a Q>,
+    ) -> impl 'a + Iterator<Item = Result<(K, V2)>>
+    where
+        K: PartialOrdSer<Q>,
+        V2: 'a + Deser,
+    {
+        let block_idxs = self.block_offsets.block_idxs(&self.key_bounds, k_lo, k_hi);
+        self.read_blocks(block_idxs)
+            .flat_map(move |res_reader| match res_reader {
+                Err(e) => Either::Left(iter::once(Err(e))),
+                Ok(reader) => Either::Right(reader.into_iter_kv_range(k_lo, k_hi)),
+            })
+    }
 
-            Ok(iter)
-        });
+    pub fn get_all_keys(&self) -> impl '_ + Iterator<Item = Result<K>> {
+        self.read_blocks::<V>(0..self.block_offsets.len())
+            .flat_map(|res_reader| match res_reader {
+                Err(e) => Either::Left(iter::once(Err(e))),
+                Ok(reader) => Either::Right(reader.into_iter_k()),
+            })
+    }
 
-        let ret_iter_fn = move || -> Option<Result<(K, V)>> {
-            match res_file_iter.as_mut() {
-                Err(e) => {
-                    // This error occurred during the construction of the iterator.
-                    // Return the err as an iterator item.
-                    Some(Err(anyhow!(e.to_string())))
-                }
-                Ok(file_iter) => file_iter.next(),
+    /// Unlike [`Self::get_range()`], the iterator owns the SSTable.
+    pub fn into_iter_kv(self) -> impl Iterator<Item = Result<(K, V)>> {
+        let block_idxs = 0..self.block_offsets.len();
+        let mut res_file = self.open_file();
+        block_idxs
+            .map(move |block_i| match res_file.as_mut() {
+                Err(e) => Err(anyhow!(e.to_string())),
+                Ok(file) => self.read_block(file, block_i),
+            })
+            .flat_map(|res_reader| match res_reader {
+                Err(e) => Either::Left(iter::once(Err(e))),
+                Ok(reader) => Either::Right(reader.into_iter_kv()),
+            })
+    }
+
+    fn read_blocks<V2>(
+        &self,
+        block_idxs: Range<usize>,
+    ) -> impl '_ + Iterator<Item = Result<KeyValueReader<Cursor<Vec<u8>>, K, V2>>> {
+        let mut res_file = self.open_file();
+        block_idxs.map(move |block_i| match res_file.as_mut() {
+            Err(e) => {
+                // This error occurred during the construction of the iterator.
+                // Return the err as an iterator item.
+                Err(anyhow!(e.to_string()))
             }
+            Ok(file) => self.read_block(file, block_i),
+        })
+    }
+
+    fn open_file(&self) -> Result<File> {
+        fs_utils::open_file(&self.kv_file_path, OpenOptions::new().read(true))
+    }
+
+    fn read_block<V2>(
+        &self,
+        file: &mut File,
+        block_i: usize,
+    ) -> Result<KeyValueReader<Cursor<Vec<u8>>, K, V2>> {
+        let (_, block_offset) = &self.block_offsets[block_i];
+        let block_end = match self.block_offsets.get(block_i + 1) {
+            None => self.data_end,
+            Some((_, next_block_offset)) => *next_block_offset,
         };
-        iter::from_fn(ret_iter_fn)
-    }
-
-    pub fn get_all_keys(&self) -> impl Iterator<Item = Result<K>> {
-        let res_file = fs_utils::open_file(&self.kv_file_path, OpenOptions::new().read(true));
-        let mut res_file_iter = res_file.map(|file| {
-            let iter = KeyValueReader::<_, K, V>::from(file).into_iter_k();
-            iter
-        });
-
-        let ret_iter_fn = move || -> Option<Result<K>> {
-            match res_file_iter.as_mut() {
-                Err(e) => {
-                    // This error occurred during the construction of the iterator.
-                    // Return the err as an iterator item.
-                    Some(Err(anyhow!(e.to_string())))
-                }
-                Ok(file_iter) => file_iter.next(),
+
+        fs_utils::seek(
+            &mut *file,
+            SeekFrom::Start(block_offset.0),
+            &self.kv_file_path,
+        )?;
+        let mut buf = vec![0u8; (block_end.0 - block_offset.0) as usize];
+        file.read_exact(&mut buf)?;
+
+        let corruption =
+            |reason: String| CorruptionError::new(&self.kv_file_path, block_offset.0, reason);
+        if self.is_checksummed {
+            let checksum_offset = buf
+                .len()
+                .checked_sub(BLOCK_CHECKSUM_LEN)
+                .ok_or_else(|| corruption(String::from("Data block is too short.")))?;
+            let checksum = u32::from_le_bytes(buf[checksum_offset..].try_into()?);
+            buf.truncate(checksum_offset);
+            if crc32fast::hash(&buf) != checksum {
+                return Err(corruption(String::from("Data block checksum mismatch.")).into());
             }
-        };
-        iter::from_fn(ret_iter_fn)
+        }
+        let buf = self
+            .codec
+            .decode(buf)
+            .map_err(|e| corruption(e.to_string()))?;
+
+        Ok(KeyValueReader::from(Cursor::new(buf)))
     }
 
     pub fn remove_file(&self) -> Result<()> {
@@ -195,28 +689,50 @@ where
 }
 
 #[derive(From, Deref, DerefMut)]
-struct SparseFileOffsets<K>(Vec<(K, FileOffset)>);
+struct BlockOffsets<K>(Vec<(K, FileOffset)>);
 
-impl<K> SparseFileOffsets<K> {
-    fn nearest_preceding_file_offset<Q>(&self, k_lo: Option<&Q>) -> FileOffset
+impl<K> BlockOffsets<K> {
+    /// @return The indexes of the data blocks that may contain keys within `[k_lo, k_hi]`.
+    fn block_idxs<Q>(
+        &self,
+        key_bounds: &Option<(K, K)>,
+        k_lo: Option<&Q>,
+        k_hi: Option<&Q>,
+    ) -> Range<usize>
     where
         K: PartialOrd<Q>,
     {
-        if k_lo.is_none() {
-            return FileOffset(0);
+        let (min_k, max_k) = match key_bounds {
+            None => return 0..0,
+            Some(key_bounds) => key_bounds,
+        };
+        if let Some(k_lo) = k_lo {
+            if max_k.partial_cmp(k_lo) == Some(Ordering::Less) {
+                return 0..0;
+            }
+        }
+        if let Some(k_hi) = k_hi {
+            if min_k.partial_cmp(k_hi) == Some(Ordering::Greater) {
+                return 0..0;
+            }
         }
-        let k_lo = k_lo.unwrap();
 
-        let mem_idx_right: usize =
-            bisect::bisect_right(&self.0, 0, self.0.len(), |(sample_k, _offset)| {
-                sample_k.partial_cmp(k_lo).unwrap_or(Ordering::Greater)
-            });
+        let block_i_lo = match k_lo {
+            None => 0,
+            Some(k_lo) => {
+                let idx_right = bisect::bisect_right(&self.0, 0, self.0.len(), |(sample_k, _)| {
+                    sample_k.partial_cmp(k_lo).unwrap_or(Ordering::Greater)
+                });
+                idx_right.saturating_sub(1)
+            }
+        };
+        let block_i_hi_excl = match k_hi {
+            None => self.0.len(),
+            Some(k_hi) => bisect::bisect_right(&self.0, 0, self.0.len(), |(sample_k, _)| {
+                sample_k.partial_cmp(k_hi).unwrap_or(Ordering::Less)
+            }),
+        };
 
-        if mem_idx_right == 0 {
-            FileOffset(0)
-        } else {
-            let (_k, offset) = &self[mem_idx_right - 1];
-            *offset
-        }
+        block_i_lo..block_i_hi_excl.max(block_i_lo)
     }
 }
@@ -0,0 +1,44 @@
+use anyhow::{anyhow, Result};
+use std::borrow::Cow;
+
+/// How each data block of an SSTable is encoded on disk.
+///
+/// The codec is recorded in each file, so that files written with any codec remain readable.
+/// Readers decode a whole data block before iterating it, hence iteration is agnostic of the codec.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BlockCodec {
+    None,
+    Lz4,
+}
+
+impl BlockCodec {
+    pub fn encode<'a>(&self, raw: &'a [u8]) -> Cow<'a, [u8]> {
+        match self {
+            Self::None => Cow::Borrowed(raw),
+            Self::Lz4 => Cow::Owned(lz4_flex::block::compress_prepend_size(raw)),
+        }
+    }
+
+    pub fn decode(&self, stored: Vec<u8>) -> Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(stored),
+            Self::Lz4 => lz4_flex::block::decompress_size_prepended(&stored)
+                .map_err(|e| anyhow!("Corrupt LZ4 data block. {e}")),
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Lz4 => 1,
+        }
+    }
+
+    pub fn try_from_u8(int: u8) -> Result<Self> {
+        match int {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Lz4),
+            _ => Err(anyhow!("Unknown SSTable block codec {int}")),
+        }
+    }
+}
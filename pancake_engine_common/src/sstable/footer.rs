@@ -0,0 +1,186 @@
+use super::codec::BlockCodec;
+use anyhow::{anyhow, Result};
+use std::io::{Read, Write};
+use std::mem;
+
+/// Identifies a file as an SSTable.
+const MAGIC: [u8; 8] = *b"PNCK_SST";
+
+/// Bump this whenever the layout changes.
+///
+/// - Unversioned: The entries only. There is no trailer, hence no version either.
+/// - Version 1: Uncompressed data blocks, the index block, and the key bounds. There is no filter block.
+/// - Version 2: The index block is followed by a filter block.
+/// - Version 3: The trailer records the [`BlockCodec`] of the data blocks.
+/// - Version 4: Each data block is followed by its checksum. The trailer records a checksum of the metadata.
+/// - Version 5: The key bounds are followed by range tombstones. The trailer is unchanged.
+const FORMAT_VERSION: u32 = 5;
+const MIN_SUPPORTED_FORMAT_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FileOffset(pub u64);
+
+impl FileOffset {
+    pub fn ser<W: Write>(&self, w: &mut W) -> Result<usize> {
+        let buf = self.0.to_le_bytes();
+        w.write_all(&buf)?;
+        Ok(buf.len())
+    }
+    pub fn deser<R: Read>(r: &mut R) -> Result<Self> {
+        let mut buf = [0u8; mem::size_of::<u64>()];
+        r.read_exact(&mut buf)?;
+        Ok(Self(u64::from_le_bytes(buf)))
+    }
+}
+
+/// The tail of an SSTable file.
+///
+/// It is read first, in order to locate the variable-length index block, filter block, and key bounds.
+/// The range tombstones span from the end of the key bounds to the trailer.
+/// Its length depends on the format version, which is found at a fixed distance from the end of file.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Trailer {
+    pub index_offset: FileOffset,
+    /// Absent iff the format version predates the filter block.
+    pub filter_offset: Option<FileOffset>,
+    pub key_bounds_offset: FileOffset,
+    pub entry_count: u64,
+    pub codec: BlockCodec,
+    /// The CRC-32 of the index block, filter block, key bounds, range tombstones, and the preceding trailer fields.
+    /// Present iff the format version is 4 or later, in which case data blocks have checksums too.
+    pub checksum: Option<u32>,
+}
+
+impl Trailer {
+    const VERSION_AND_MAGIC_LEN: usize = mem::size_of::<u32>() + MAGIC.len();
+
+    /// The length of the trailer of the current version. No supported version has a longer trailer.
+    pub const MAX_LEN: usize = 4 * mem::size_of::<u64>()
+        + mem::size_of::<u8>()
+        + mem::size_of::<u32>()
+        + Self::VERSION_AND_MAGIC_LEN;
+
+    fn len_of_version(version: u32) -> Result<usize> {
+        match version {
+            1 => Ok(Self::MAX_LEN
+                - mem::size_of::<u64>()
+                - mem::size_of::<u32>()
+                - mem::size_of::<u8>()),
+            2 => Ok(Self::MAX_LEN - mem::size_of::<u32>() - mem::size_of::<u8>()),
+            3 => Ok(Self::MAX_LEN - mem::size_of::<u32>()),
+            4 | FORMAT_VERSION => Ok(Self::MAX_LEN),
+            _ => Err(anyhow!(
+                "Unsupported SSTable format version {version}. Expected {MIN_SUPPORTED_FORMAT_VERSION} to {FORMAT_VERSION}."
+            )),
+        }
+    }
+
+    /// @arg `meta`: The index block, filter block, key bounds, and range tombstones, as serialized.
+    pub fn compute_checksum(&self, meta: &[u8]) -> Result<u32> {
+        let mut fields = vec![];
+        self.ser_fields(&mut fields)?;
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(meta);
+        hasher.update(&fields);
+        Ok(hasher.finalize())
+    }
+
+    /// Always serializes in the current version. Hence the checksum must be present.
+    pub fn ser<W: Write>(&self, w: &mut W) -> Result<()> {
+        let checksum = self
+            .checksum
+            .ok_or_else(|| anyhow!("SSTable trailer lacks a checksum."))?;
+
+        self.ser_fields(w)?;
+        w.write_all(&checksum.to_le_bytes())?;
+        w.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        w.write_all(&MAGIC)?;
+        Ok(())
+    }
+
+    fn ser_fields<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.index_offset.ser(w)?;
+        if let Some(filter_offset) = self.filter_offset {
+            filter_offset.ser(w)?;
+        }
+        self.key_bounds_offset.ser(w)?;
+        w.write_all(&self.entry_count.to_le_bytes())?;
+        w.write_all(&[self.codec.to_u8()])?;
+        Ok(())
+    }
+
+    /// @return Whether the file has a trailer at all. Files of the unversioned format, which predates the trailer, do not.
+    pub fn is_present(tail: &[u8]) -> bool {
+        tail.ends_with(&MAGIC)
+    }
+
+    /// @arg `tail`: The last bytes of the file, up to [`Self::MAX_LEN`] bytes long.
+    /// @return The trailer, and its serialized length.
+    pub fn deser(tail: &[u8]) -> Result<(Self, usize)> {
+        if tail.len() < Self::VERSION_AND_MAGIC_LEN || Self::is_present(tail) == false {
+            return Err(anyhow!("Not an SSTable file of a supported format."));
+        }
+
+        let version_offset = tail.len() - Self::VERSION_AND_MAGIC_LEN;
+        let mut version_buf = [0u8; mem::size_of::<u32>()];
+        (&tail[version_offset..]).read_exact(&mut version_buf)?;
+        let version = u32::from_le_bytes(version_buf);
+
+        let trailer_len = Self::len_of_version(version)?;
+        if tail.len() < trailer_len {
+            return Err(anyhow!("SSTable file is too short."));
+        }
+        let mut r = &tail[tail.len() - trailer_len..version_offset];
+
+        let index_offset = FileOffset::deser(&mut r)?;
+        let filter_offset = if version >= 2 {
+            Some(FileOffset::deser(&mut r)?)
+        } else {
+            None
+        };
+        let key_bounds_offset = FileOffset::deser(&mut r)?;
+
+        let mut u64_buf = [0u8; mem::size_of::<u64>()];
+        r.read_exact(&mut u64_buf)?;
+        let entry_count = u64::from_le_bytes(u64_buf);
+
+        let codec = if version >= 3 {
+            let mut u8_buf = [0u8];
+            r.read_exact(&mut u8_buf)?;
+            BlockCodec::try_from_u8(u8_buf[0])?
+        } else {
+            BlockCodec::None
+        };
+
+        let checksum = if version >= 4 {
+            let mut u32_buf = [0u8; mem::size_of::<u32>()];
+            r.read_exact(&mut u32_buf)?;
+            Some(u32::from_le_bytes(u32_buf))
+        } else {
+            None
+        };
+
+        let is_ordered = match filter_offset {
+            None => index_offset.0 <= key_bounds_offset.0,
+            Some(filter_offset) => {
+                index_offset.0 <= filter_offset.0 && filter_offset.0 < key_bounds_offset.0
+            }
+        };
+        if is_ordered == false {
+            return Err(anyhow!(
+                "Corrupt SSTable trailer {index_offset:?} {filter_offset:?} {key_bounds_offset:?}"
+            ));
+        }
+
+        let trailer = Self {
+            index_offset,
+            filter_offset,
+            key_bounds_offset,
+            entry_count,
+            codec,
+            checksum,
+        };
+        Ok((trailer, trailer_len))
+    }
+}
@@ -0,0 +1,591 @@
+use super::*;
+use itertools::Itertools;
+use pancake_types::{
+    serde::{Datum, OptDatum},
+    types::{OptPVBuf, PKShared, PVShared, PrimaryKey, Value},
+};
+use std::env;
+use std::fs;
+use std::sync::Arc;
+
+type TestSSTable = SSTable<PKShared, OptDatum<PVShared>>;
+
+const DATA_BLOCK_SIZE: u64 = 4 * 1024;
+
+fn gen_file_path(test_name: &str) -> PathBuf {
+    let dir_path = env::temp_dir().join("pancake_sstable_test").join(test_name);
+    if dir_path.exists() {
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+    fs::create_dir_all(&dir_path).unwrap();
+    dir_path.join("sst.kv")
+}
+
+fn gen_pk(i: i64) -> PrimaryKey {
+    PrimaryKey(Datum::I64(i))
+}
+
+/// Even keys only, so that odd keys may be sought without being found.
+fn gen_entries(count: i64) -> Vec<(PKShared, OptDatum<PVShared>)> {
+    (0..count)
+        .map(|i| {
+            let pk = Arc::new(gen_pk(i * 2));
+            let pv = match i % 5 {
+                0 => OptDatum::Tombstone,
+                _ => OptDatum::Some(Arc::new(Value(Datum::Str(format!("val_{i}"))))),
+            };
+            (pk, pv)
+        })
+        .collect()
+}
+
+fn build(
+    file_path: &Path,
+    entries: &[(PKShared, OptDatum<PVShared>)],
+    block_size: u64,
+    codec: BlockCodec,
+) -> Result<TestSSTable> {
+    let entries = entries.iter().map(|(k, v)| Entry::Ref((k, v)));
+    TestSSTable::new_with_format(
+        entries,
+        RangeTombstones::default(),
+        file_path.into(),
+        block_size,
+        codec,
+        u64::MAX,
+    )
+}
+
+fn verify_range(
+    sst: &TestSSTable,
+    entries: &[(PKShared, OptDatum<PVShared>)],
+    lo: Option<i64>,
+    hi: Option<i64>,
+) -> Result<()> {
+    let k_lo = lo.map(gen_pk);
+    let k_hi = hi.map(gen_pk);
+    let act = sst
+        .get_range(k_lo.as_ref(), k_hi.as_ref())
+        .collect::<Result<Vec<_>>>()?;
+    let exp = entries
+        .iter()
+        .filter(|(k, _)| {
+            let is_lo_ok = match lo {
+                None => true,
+                Some(lo) => k.as_ref() >= &gen_pk(lo),
+            };
+            let is_hi_ok = match hi {
+                None => true,
+                Some(hi) => k.as_ref() <= &gen_pk(hi),
+            };
+            is_lo_ok && is_hi_ok
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(act, exp, "{lo:?} {hi:?}");
+    Ok(())
+}
+
+#[test]
+fn new_load_read() -> Result<()> {
+    let entries = gen_entries(50);
+
+    for (block_size, codec) in [1, 40, DATA_BLOCK_SIZE]
+        .into_iter()
+        .cartesian_product([BlockCodec::None, BlockCodec::Lz4])
+    {
+        let file_path = gen_file_path(&format!("new_load_read_{block_size}_{codec:?}"));
+
+        let sst_new = build(&file_path, &entries, block_size, codec)?;
+        let sst_load = TestSSTable::load(&file_path)?;
+
+        assert_eq!(sst_load.codec, codec);
+
+        assert_eq!(sst_new.block_offsets.len(), sst_load.block_offsets.len());
+        if block_size == 1 {
+            assert_eq!(sst_load.block_offsets.len(), entries.len());
+        } else if block_size == DATA_BLOCK_SIZE {
+            assert_eq!(sst_load.block_offsets.len(), 1);
+        }
+        for ((k_new, offset_new), (k_load, offset_load)) in sst_new
+            .block_offsets
+            .iter()
+            .zip(sst_load.block_offsets.iter())
+        {
+            assert_eq!(k_new, k_load);
+            assert_eq!(offset_new, offset_load);
+        }
+        assert_eq!(sst_new.data_end, sst_load.data_end);
+        assert_eq!(sst_new.filter, sst_load.filter);
+        assert_eq!(sst_load.entry_count(), entries.len() as u64);
+        assert_eq!(
+            sst_load.key_bounds,
+            Some((entries[0].0.clone(), entries[49].0.clone()))
+        );
+
+        for sst in [&sst_new, &sst_load] {
+            for (lo, hi) in [
+                (None, None),
+                (Some(-1), None),
+                (Some(0), Some(0)),
+                (Some(1), Some(1)),
+                (Some(31), Some(63)),
+                (Some(32), Some(64)),
+                (None, Some(17)),
+                (Some(98), None),
+                (Some(99), None),
+                (None, Some(-1)),
+                (Some(50), Some(40)),
+            ] {
+                verify_range(sst, &entries, lo, hi)?;
+            }
+
+            for i in [-1, 0, 1, 40, 41, 98, 99] {
+                let act = sst.get_one(&gen_pk(i)).transpose()?;
+                let exp = entries
+                    .iter()
+                    .find(|(k, _)| k.as_ref() == &gen_pk(i))
+                    .cloned();
+                assert_eq!(act, exp);
+
+                let act = sst.get_one_as::<_, OptPVBuf>(&gen_pk(i)).transpose()?;
+                let act = act.map(|(k, v)| (k, v.0));
+                let exp = exp.map(|(k, v)| (k, v.ser_solo().unwrap()));
+                assert_eq!(act, exp);
+            }
+
+            let false_pos_ct = (0..50)
+                .filter(|i| {
+                    sst.filter
+                        .as_ref()
+                        .unwrap()
+                        .may_contain(&gen_pk(i * 2 + 1).ser_solo().unwrap())
+                })
+                .count();
+            assert!(false_pos_ct < 10, "{false_pos_ct}");
+
+            let act_keys = sst.get_all_keys().collect::<Result<Vec<_>>>()?;
+            let exp_keys = entries.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>();
+            assert_eq!(act_keys, exp_keys);
+        }
+
+        let act = sst_load.into_iter_kv().collect::<Result<Vec<_>>>()?;
+        assert_eq!(act, entries);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn empty() -> Result<()> {
+    let file_path = gen_file_path("empty");
+
+    build(&file_path, &[], DATA_BLOCK_SIZE, DATA_BLOCK_CODEC)?;
+    let sst = TestSSTable::load(&file_path)?;
+
+    assert_eq!(sst.entry_count(), 0);
+    assert!(sst.key_bounds.is_none());
+    assert_eq!(sst.get_range::<PrimaryKey>(None, None).count(), 0);
+    assert!(sst.get_one(&gen_pk(0)).is_none());
+    assert_eq!(sst.get_all_keys().count(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn new_multi() -> Result<()> {
+    let entries = gen_entries(50);
+    let dir_path = gen_file_path("new_multi").parent().unwrap().to_owned();
+
+    let mut file_i = 0;
+    let mut gen_path = || {
+        file_i += 1;
+        dir_path.join(format!("{file_i}.kv"))
+    };
+
+    let ssts = TestSSTable::new_multi(
+        entries.iter().map(|(k, v)| Entry::Ref((k, v))),
+        RangeTombstones::default(),
+        &mut gen_path,
+        40,
+        100,
+    )?;
+    assert!(ssts.len() >= 3, "{}", ssts.len());
+    for sst in ssts[..ssts.len() - 1].iter() {
+        assert!(sst.data_len() >= 100);
+    }
+    for (sst_older, sst_newer) in ssts.iter().tuple_windows() {
+        let (_, max_k) = sst_older.key_bounds().unwrap();
+        let (min_k, _) = sst_newer.key_bounds().unwrap();
+        assert!(max_k < min_k);
+    }
+    let act = ssts
+        .into_iter()
+        .flat_map(|sst| sst.into_iter_kv())
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(act, entries);
+
+    let ssts = TestSSTable::new_multi(
+        iter::empty(),
+        RangeTombstones::default(),
+        &mut gen_path,
+        40,
+        100,
+    )?;
+    assert!(ssts.is_empty());
+
+    Ok(())
+}
+
+/// Range tombstones are written into the first SSTable only. They are written even without any entry.
+#[test]
+fn range_tombstones() -> Result<()> {
+    let entries = gen_entries(50);
+    let dir_path = gen_file_path("range_tombstones")
+        .parent()
+        .unwrap()
+        .to_owned();
+
+    let mut file_i = 0;
+    let mut gen_path = || {
+        file_i += 1;
+        dir_path.join(format!("{file_i}.kv"))
+    };
+
+    let mut rts = RangeTombstones::default();
+    rts.insert(Arc::new(gen_pk(-10)), Arc::new(gen_pk(-5)));
+    rts.insert(Arc::new(gen_pk(200)), Arc::new(gen_pk(300)));
+
+    let ssts = TestSSTable::new_multi(
+        entries.iter().map(|(k, v)| Entry::Ref((k, v))),
+        rts.clone(),
+        &mut gen_path,
+        40,
+        100,
+    )?;
+    assert!(ssts.len() >= 2, "{}", ssts.len());
+    for (sst_i, sst) in ssts.iter().enumerate() {
+        let sst_load = TestSSTable::load(&sst.kv_file_path)?;
+        let exp_rts = match sst_i {
+            0 => rts.clone(),
+            _ => RangeTombstones::default(),
+        };
+        assert_eq!(sst.range_tombstones(), &exp_rts);
+        assert_eq!(sst_load.range_tombstones(), &exp_rts);
+    }
+    verify_range(
+        &TestSSTable::load(&ssts[0].kv_file_path)?,
+        &entries,
+        None,
+        Some(0),
+    )?;
+
+    let ssts = TestSSTable::new_multi(iter::empty(), rts.clone(), &mut gen_path, 40, 100)?;
+    assert_eq!(ssts.len(), 1);
+    let sst = TestSSTable::load(&ssts[0].kv_file_path)?;
+    assert_eq!(sst.entry_count(), 0);
+    assert_eq!(sst.range_tombstones(), &rts);
+    assert!(sst.range_tombstones().covers(&gen_pk(-7)));
+    assert!(sst.range_tombstones().covers(&gen_pk(0)) == false);
+
+    Ok(())
+}
+
+#[test]
+fn compression() -> Result<()> {
+    let entries = (0..50)
+        .map(|i| {
+            let pv = Value(Datum::Str(format!("{i} {}", "verbose ".repeat(20))));
+            (Arc::new(gen_pk(i)), OptDatum::Some(Arc::new(pv)))
+        })
+        .collect::<Vec<_>>();
+
+    let mut data_lens = vec![];
+    for codec in [BlockCodec::None, BlockCodec::Lz4] {
+        let file_path = gen_file_path(&format!("compression_{codec:?}"));
+        build(&file_path, &entries, DATA_BLOCK_SIZE, codec)?;
+        let sst = TestSSTable::load(&file_path)?;
+        data_lens.push(sst.data_end.0);
+        assert_eq!(sst.into_iter_kv().collect::<Result<Vec<_>>>()?, entries);
+    }
+    assert!(data_lens[1] * 4 < data_lens[0], "{data_lens:?}");
+
+    Ok(())
+}
+
+/// Rewrites a file of the current format version in an older version.
+/// The file must have no range tombstones, which no older version supports.
+fn downgrade(file_path: &Path, version: u32) -> Result<()> {
+    let sst = TestSSTable::load(file_path)?;
+    let cur = fs::read(file_path)?;
+    let cur_trailer_offset = cur.len() - Trailer::MAX_LEN;
+    let (cur_trailer, _) = Trailer::deser(&cur[cur_trailer_offset..])?;
+    let cur_filter_offset = cur_trailer.filter_offset.unwrap();
+
+    let mut old = vec![];
+
+    /* Data blocks. Checksummed since version 4. */
+
+    let mut block_offsets = vec![];
+    for (block_i, (k, block_offset)) in sst.block_offsets.iter().enumerate() {
+        let mut block_end = match sst.block_offsets.get(block_i + 1) {
+            None => sst.data_end.0,
+            Some((_, next_block_offset)) => next_block_offset.0,
+        };
+        if version < 4 {
+            block_end -= BLOCK_CHECKSUM_LEN as u64;
+        }
+        block_offsets.push((k, FileOffset(old.len() as u64)));
+        old.extend_from_slice(&cur[block_offset.0 as usize..block_end as usize]);
+    }
+
+    /* Index block. */
+
+    let index_offset = old.len() as u64;
+    for (k, block_offset) in block_offsets {
+        k.ser(&mut old)?;
+        block_offset.ser(&mut old)?;
+    }
+
+    /* Filter block. Since version 2. */
+
+    let filter_offset = old.len() as u64;
+    if version >= 2 {
+        old.extend_from_slice(
+            &cur[cur_filter_offset.0 as usize..cur_trailer.key_bounds_offset.0 as usize],
+        );
+    }
+
+    /* Key bounds. */
+
+    let key_bounds_offset = old.len() as u64;
+    old.extend_from_slice(&cur[cur_trailer.key_bounds_offset.0 as usize..cur_trailer_offset]);
+
+    /* Trailer. */
+
+    let mut fields = vec![index_offset];
+    if version >= 2 {
+        fields.push(filter_offset);
+    }
+    fields.extend([key_bounds_offset, sst.entry_count]);
+    for field in fields {
+        old.extend_from_slice(&field.to_le_bytes());
+    }
+    if version >= 3 {
+        old.push(sst.codec.to_u8());
+    }
+    if version >= 4 {
+        let checksum = crc32fast::hash(&old[index_offset as usize..]);
+        old.extend_from_slice(&checksum.to_le_bytes());
+    }
+    old.extend_from_slice(&version.to_le_bytes());
+    old.extend_from_slice(b"PNCK_SST");
+
+    fs::write(file_path, old)?;
+    Ok(())
+}
+
+/// - Files of format version 1 lack a filter block.
+/// - Files of format versions 1 and 2 have uncompressed data blocks, and their trailers lack a codec.
+/// - Files of format versions 1 to 3 lack checksums.
+#[test]
+fn older_format_versions() -> Result<()> {
+    let entries = gen_entries(50);
+
+    for version in 1..=4 {
+        let codec = if version >= 3 {
+            BlockCodec::Lz4
+        } else {
+            BlockCodec::None
+        };
+        let file_path = gen_file_path(&format!("format_version_{version}"));
+
+        build(&file_path, &entries, 40, codec)?;
+        downgrade(&file_path, version)?;
+
+        let sst = TestSSTable::load(&file_path)?;
+        assert_eq!(sst.codec, codec);
+        assert_eq!(sst.filter.is_some(), version >= 2);
+        assert_eq!(sst.is_checksummed, version >= 4);
+        assert_eq!(
+            sst.key_bounds(),
+            Some(&(entries[0].0.clone(), entries[49].0.clone()))
+        );
+        verify_range(&sst, &entries, Some(31), Some(63))?;
+        for i in [0, 31, 32, 98, 99] {
+            let exp = entries
+                .iter()
+                .find(|(k, _)| k.as_ref() == &gen_pk(i))
+                .cloned();
+            assert_eq!(sst.get_one(&gen_pk(i)).transpose()?, exp, "{version} {i}");
+        }
+        assert_eq!(sst.into_iter_kv().collect::<Result<Vec<_>>>()?, entries);
+    }
+
+    Ok(())
+}
+
+/// Files of the unversioned format consist of the entries only. They lack a trailer.
+#[test]
+fn unversioned_format() -> Result<()> {
+    let entries = gen_entries(1000);
+    let file_path = gen_file_path("unversioned_format");
+
+    let mut file = vec![];
+    for (k, v) in entries.iter() {
+        k.ser(&mut file)?;
+        v.ser(&mut file)?;
+    }
+    fs::write(&file_path, &file)?;
+
+    let sst = TestSSTable::load(&file_path)?;
+    assert!(sst.block_offsets.len() > 1);
+    assert_eq!(sst.entry_count(), entries.len() as u64);
+    assert_eq!(sst.data_len(), file.len() as u64);
+    let (min_k, max_k) = sst.key_bounds().unwrap();
+    assert_eq!(
+        (min_k, max_k),
+        (&entries[0].0, &entries[entries.len() - 1].0)
+    );
+
+    for lo_hi in [(None, None), (Some(31), Some(63)), (Some(700), None)] {
+        verify_range(&sst, &entries, lo_hi.0, lo_hi.1)?;
+    }
+    for i in [0, 31, 32, 1998, 1999] {
+        let exp = entries
+            .iter()
+            .find(|(k, _)| k.as_ref() == &gen_pk(i))
+            .cloned();
+        assert_eq!(sst.get_one(&gen_pk(i)).transpose()?, exp, "{i}");
+    }
+
+    Ok(())
+}
+
+/// A file without a trailer that does not scan as an unversioned file is corrupt, rather than of the unversioned format.
+#[test]
+fn unversioned_format_corruption() -> Result<()> {
+    let entries = gen_entries(50);
+    let file_path = gen_file_path("unversioned_format_corruption");
+
+    let verify_err = |file: &[u8]| -> Result<()> {
+        fs::write(&file_path, file)?;
+        let e = TestSSTable::load(&file_path).err().unwrap();
+        let e = e.downcast_ref::<CorruptionError>().unwrap();
+        assert_eq!(e.file_path, file_path);
+        Ok(())
+    };
+
+    /* An unversioned file that ends mid-entry. */
+
+    let mut unversioned = vec![];
+    for (k, v) in entries.iter() {
+        k.ser(&mut unversioned)?;
+        v.ser(&mut unversioned)?;
+    }
+    verify_err(&unversioned[..unversioned.len() - 1])?;
+
+    /* An unversioned file whose entries are out of order. */
+
+    let mut unsorted = vec![];
+    for (k, v) in entries.iter().rev() {
+        k.ser(&mut unsorted)?;
+        v.ser(&mut unsorted)?;
+    }
+    verify_err(&unsorted)?;
+
+    /* A file of the current format version that lost its trailer, e.g. by an interrupted write. */
+
+    for codec in [BlockCodec::None, BlockCodec::Lz4] {
+        fs::remove_file(&file_path)?;
+        build(&file_path, &entries, 40, codec)?;
+        let intact = fs::read(&file_path)?;
+        for truncated_len in [1, Trailer::MAX_LEN, intact.len() / 2] {
+            verify_err(&intact[..intact.len() - truncated_len])?;
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn corruption() -> Result<()> {
+    let entries = gen_entries(50);
+    let file_path = gen_file_path("corruption");
+
+    let sst = build(&file_path, &entries, 40, DATA_BLOCK_CODEC)?;
+    let intact = fs::read(&file_path)?;
+
+    let verify_err = |res: Result<()>, exp_offset: u64| {
+        let e = res.err().unwrap();
+        let e = e.downcast_ref::<CorruptionError>().unwrap();
+        assert_eq!(e.file_path, file_path);
+        assert_eq!(e.offset, exp_offset);
+    };
+
+    /* A data block. */
+
+    let (_, block_offset) = sst.block_offsets[1];
+    let mut corrupt = intact.clone();
+    corrupt[block_offset.0 as usize + 1] ^= 0x10;
+    fs::write(&file_path, &corrupt)?;
+
+    let sst = TestSSTable::load(&file_path)?;
+    assert_eq!(
+        sst.get_one(&gen_pk(0)).transpose()?,
+        Some(entries[0].clone())
+    );
+    verify_err(
+        sst.get_range::<PrimaryKey>(None, None)
+            .try_for_each(|res| res.map(|_| ())),
+        block_offset.0,
+    );
+    verify_err(
+        sst.into_iter_kv().try_for_each(|res| res.map(|_| ())),
+        block_offset.0,
+    );
+
+    /* The key bounds, near the end of the metadata. */
+
+    let mut corrupt = intact.clone();
+    let meta_byte_offset = intact.len() - Trailer::MAX_LEN - 2;
+    corrupt[meta_byte_offset] ^= 0x10;
+    fs::write(&file_path, &corrupt)?;
+
+    verify_err(
+        TestSSTable::load(&file_path).map(|_| ()),
+        sst_data_end(&intact)?,
+    );
+
+    /* The trailer. */
+
+    let mut corrupt = intact.clone();
+    let entry_count_offset = intact.len() - Trailer::MAX_LEN + 24;
+    corrupt[entry_count_offset] ^= 0x10;
+    fs::write(&file_path, &corrupt)?;
+
+    verify_err(
+        TestSSTable::load(&file_path).map(|_| ()),
+        sst_data_end(&intact)?,
+    );
+
+    Ok(())
+}
+
+fn sst_data_end(file: &[u8]) -> Result<u64> {
+    let (trailer, _) = Trailer::deser(&file[file.len() - Trailer::MAX_LEN..])?;
+    Ok(trailer.index_offset.0)
+}
+
+#[test]
+fn unrecognized_file() -> Result<()> {
+    let file_path = gen_file_path("unrecognized_file");
+
+    for file in [&b"short"[..], &[0u8; 100][..]] {
+        fs::write(&file_path, file)?;
+        let e = TestSSTable::load(&file_path).err().unwrap();
+        assert!(e.downcast_ref::<CorruptionError>().is_some());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use pancake_engine_common::fs_utils;
+use pancake_types::{io_utils, types::ValueConstraint};
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::{BufReader, BufWriter, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The [`ValueConstraint`]s that all values in the primary keyspace satisfy.
+///
+/// They are persisted in one file, one constraint per line.
+pub struct Constraints {
+    file_path: PathBuf,
+    constraints: HashSet<Arc<ValueConstraint>>,
+}
+
+#[allow(clippy::write_with_newline)] // We must be consistent re: '\n' vs '\r\n'.
+impl Constraints {
+    pub fn load_or_new<P: AsRef<Path>>(file_path: P) -> Result<Self> {
+        let file_path = file_path.as_ref();
+
+        let mut constraints = HashSet::new();
+        if file_path.exists() {
+            let file = fs_utils::open_file(file_path, OpenOptions::new().read(true))?;
+            let mut r = BufReader::new(file);
+            let mut buf = vec![];
+            loop {
+                buf.clear();
+                io_utils::read_until_then_trim(&mut r, b'\n', &mut buf)?;
+                if buf.is_empty() {
+                    break;
+                }
+                let mut line_reader = BufReader::new(Cursor::new(&buf));
+                let constraint = ValueConstraint::deser(&mut line_reader)
+                    .with_context(|| format!("{file_path:?}"))?;
+                constraints.insert(Arc::new(constraint));
+            }
+        }
+
+        Ok(Self {
+            file_path: file_path.into(),
+            constraints,
+        })
+    }
+
+    fn ser(&self) -> Result<()> {
+        let file =
+            fs_utils::open_file(&self.file_path, OpenOptions::new().create(true).write(true))?;
+        file.set_len(0)?;
+        let mut w = BufWriter::new(file);
+        for constraint in self.constraints.iter() {
+            constraint.ser(&mut w)?;
+            write!(w, "\n")?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<ValueConstraint>> {
+        self.constraints.iter()
+    }
+
+    pub fn contains(&self, constraint: &ValueConstraint) -> bool {
+        self.constraints.contains(constraint)
+    }
+
+    pub fn insert(&mut self, constraint: Arc<ValueConstraint>) -> Result<()> {
+        if self.constraints.insert(constraint) {
+            self.ser()?;
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, constraint: &ValueConstraint) -> Result<()> {
+        if self.constraints.remove(constraint) {
+            self.ser()?;
+        }
+        Ok(())
+    }
+}
@@ -1,27 +1,51 @@
-use crate::{lsm::LSMTree, scnd_idx::SecondaryIndex};
+use crate::{
+    constraints::Constraints,
+    fc_worker::{FCJob, FlushingAndCompactionWorker},
+    lsm::{compaction_policy, LSMTree},
+    scnd_idx::SecondaryIndex,
+};
 use anyhow::{anyhow, Context, Result};
 use pancake_engine_common::{
     fs_utils::{self, AntiCollisionParentDir, NamePattern},
-    Entry,
+    EngineOptions, Entry,
+};
+use pancake_types::{
+    serde::{self, OptDatum},
+    types::{PKShared, PVShared, PrimaryKey, ScndIdxSpec, SubValue, ValueConstraint},
 };
-use pancake_types::types::{PKShared, PVShared, PrimaryKey, SubValue, SubValueSpec};
 use std::collections::HashMap;
 use std::fs::File;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
 
 const PRIM_LSM_DIR_NAME: &str = "prim_lsm";
 const ALL_SCND_IDXS_PARENT_DIR_NAME: &str = "scnd_idxs";
+const CONSTRAINTS_FILE_NAME: &str = "constraints.txt";
 
 pub struct DB {
     _lock_dir: File,
     prim_lsm: LSMTree<PKShared, PVShared>,
-    scnd_idxs: HashMap<Arc<SubValueSpec>, SecondaryIndex>,
+    scnd_idxs: HashMap<Arc<ScndIdxSpec>, SecondaryIndex>,
     all_scnd_idxs_parent_dir: AntiCollisionParentDir,
+    constraints: Constraints,
+    opts: Arc<EngineOptions>,
+    fc_jobs_tx: mpsc::Sender<FCJob>,
 }
 
 impl DB {
-    pub fn load_or_new<P: AsRef<Path>>(db_dir_path: P) -> Result<DB> {
+    /// The returned [`FlushingAndCompactionWorker`] must be run by the caller.
+    ///
+    /// @return Err if `opts` are invalid. See [`EngineOptions::validate()`].
+    pub fn load_or_new<P: AsRef<Path>>(
+        db_dir_path: P,
+        opts: EngineOptions,
+    ) -> Result<(DB, FlushingAndCompactionWorker)> {
+        opts.validate()?;
+        let opts = Arc::new(opts);
+
+        let (fc_jobs_tx, fc_jobs_rx) = mpsc::channel();
+
         let db_dir_path = db_dir_path.as_ref();
 
         fs_utils::create_dir_all(db_dir_path)?;
@@ -29,8 +53,14 @@ impl DB {
 
         let prim_lsm_dir_path = db_dir_path.join(PRIM_LSM_DIR_NAME);
         let all_scnd_idxs_parent_dir_path = db_dir_path.join(ALL_SCND_IDXS_PARENT_DIR_NAME);
+        let constraints_file_path = db_dir_path.join(CONSTRAINTS_FILE_NAME);
 
-        let prim_lsm = LSMTree::load_or_new(prim_lsm_dir_path)?;
+        let prim_lsm = LSMTree::load_or_new(
+            prim_lsm_dir_path,
+            compaction_policy::from_opts(&opts),
+            Arc::clone(&opts),
+            fc_jobs_tx.clone(),
+        )?;
 
         let mut scnd_idxs = HashMap::new();
         let all_scnd_idxs_parent_dir = AntiCollisionParentDir::load_or_new(
@@ -39,7 +69,8 @@ impl DB {
             |child_path, res_child_num| -> Result<()> {
                 res_child_num.with_context(|| format!("The \"all secondary indexes\" dir contains an unexpected child path {child_path:?}"))?;
 
-                let scnd_idx = SecondaryIndex::load(child_path)?;
+                let scnd_idx =
+                    SecondaryIndex::load(child_path, Arc::clone(&opts), fc_jobs_tx.clone())?;
                 let spec = scnd_idx.spec().clone();
                 scnd_idxs.insert(spec, scnd_idx);
 
@@ -47,25 +78,90 @@ impl DB {
             },
         )?;
 
-        Ok(DB {
+        let constraints = Constraints::load_or_new(constraints_file_path)?;
+
+        let db = DB {
             _lock_dir: lock_dir,
             prim_lsm,
             scnd_idxs,
             all_scnd_idxs_parent_dir,
-        })
+            constraints,
+            opts,
+            fc_jobs_tx,
+        };
+        let fc_worker = FlushingAndCompactionWorker { fc_jobs_rx };
+
+        Ok((db, fc_worker))
     }
 
+    /// @return Err of [`ConstraintViolation`](pancake_types::types::ConstraintViolation)
+    ///     or [`UniquenessViolation`](pancake_types::types::UniquenessViolation), without writing anything.
     pub fn put(&mut self, pk: PKShared, pv: Option<PVShared>) -> Result<()> {
-        let opt_entry = self.prim_lsm.get_one(&pk);
+        self.put_optdat(pk, OptDatum::from(pv))
+    }
+
+    /// Puts an entry that expires after `ttl`. Once expired, the entry is equivalent to a deleted one.
+    ///
+    /// @return Err of [`ConstraintViolation`](pancake_types::types::ConstraintViolation)
+    ///     or [`UniquenessViolation`](pancake_types::types::UniquenessViolation), without writing anything.
+    pub fn put_with_ttl(&mut self, pk: PKShared, pv: PVShared, ttl: Duration) -> Result<()> {
+        self.put_optdat(pk, OptDatum::new_expiring(pv, ttl))
+    }
+
+    fn put_optdat(&mut self, pk: PKShared, new_optdat: OptDatum<PVShared>) -> Result<()> {
+        let now = serde::now_micros();
+        let new_pv = new_optdat.as_live(now);
+        let new_expires_at = new_optdat.expires_at();
+
+        if let Some(pv) = new_pv {
+            ValueConstraint::check_all(self.constraints.iter(), &pk, pv)?;
+        }
+
+        let opt_entry = self.prim_lsm.get_one_optdat(&pk);
         let opt_res_pkpv = opt_entry.as_ref().map(|entry| entry.try_borrow());
         let opt_pkpv = opt_res_pkpv.transpose()?;
-        let old_pv: Option<&PVShared> = opt_pkpv.map(|(_, pv)| pv);
+        let old_optdat: Option<&OptDatum<PVShared>> = opt_pkpv.map(|(_, optdat)| optdat);
+        let old_pv: Option<&PVShared> = old_optdat.and_then(|optdat| optdat.as_live(now));
+        let old_expires_at = old_pv.and(old_optdat.and_then(|optdat| optdat.expires_at()));
+
+        for (_spec, scnd_idx) in self.scnd_idxs.iter() {
+            scnd_idx.check_unique(&pk, old_pv, new_pv)?;
+        }
 
         for (_spec, scnd_idx) in self.scnd_idxs.iter_mut() {
-            scnd_idx.put(&pk, old_pv, pv.as_ref())?;
+            scnd_idx.put(&pk, old_pv, old_expires_at, new_pv, new_expires_at)?;
+        }
+
+        self.prim_lsm.put(pk, new_optdat)?;
+
+        Ok(())
+    }
+
+    /// Deletes all entries whose primary keys are within `[pk_lo, pk_hi]`.
+    ///
+    /// The primary keyspace is written one range tombstone.
+    /// Each secondary index is written a tombstone per live entry in the range, hence those are read first.
+    pub fn delete_range(&mut self, pk_lo: PKShared, pk_hi: PKShared) -> Result<()> {
+        if self.scnd_idxs.is_empty() == false {
+            let now = serde::now_micros();
+            let old_entries = self
+                .prim_lsm
+                .get_range_optdat(Some(&pk_lo), Some(&pk_hi))
+                .map(|entry| entry.into_owned_kv())
+                .collect::<Result<Vec<_>>>()?;
+            for (pk, old_optdat) in old_entries {
+                let old_pv = match old_optdat.as_live(now) {
+                    None => continue,
+                    Some(pv) => pv,
+                };
+                let old_expires_at = old_optdat.expires_at();
+                for (_spec, scnd_idx) in self.scnd_idxs.iter_mut() {
+                    scnd_idx.put(&pk, Some(old_pv), old_expires_at, None, None)?;
+                }
+            }
         }
 
-        self.prim_lsm.put(pk, pv)?;
+        self.prim_lsm.delete_range(pk_lo, pk_hi)?;
 
         Ok(())
     }
@@ -82,9 +178,35 @@ impl DB {
         self.prim_lsm.get_range(pk_lo, pk_hi)
     }
 
+    /// @return The entries of whole values.
+    ///     If the index does not store whole values, then they are looked up in the primary keyspace.
     pub fn get_sv_range<'a>(
         &'a self,
-        spec: &'a SubValueSpec,
+        spec: &'a ScndIdxSpec,
+        sv_lo: Option<&'a SubValue>,
+        sv_hi: Option<&'a SubValue>,
+    ) -> Result<impl 'a + Iterator<Item = Entry<'a, PKShared, PVShared>>> {
+        let is_projection_whole = spec.is_projection_whole();
+        let entries = self.get_sv_range_projected(spec, sv_lo, sv_hi)?;
+        let entries = entries.filter_map(move |entry| {
+            if is_projection_whole {
+                return Some(entry);
+            }
+            let pk = match entry.into_owned_k() {
+                Err(e) => return Some(Entry::Own(Err(e))),
+                Ok(pk) => pk,
+            };
+            self.prim_lsm
+                .get_one(&pk)
+                .map(|entry| Entry::Own(entry.into_owned_kv()))
+        });
+        Ok(entries)
+    }
+
+    /// @return The entries of values as projected by the index. See [`ScndIdxSpec::project()`].
+    pub fn get_sv_range_projected<'a>(
+        &'a self,
+        spec: &'a ScndIdxSpec,
         sv_lo: Option<&'a SubValue>,
         sv_hi: Option<&'a SubValue>,
     ) -> Result<impl 'a + Iterator<Item = Entry<'a, PKShared, PVShared>>> {
@@ -95,19 +217,27 @@ impl DB {
         Err(anyhow!("Secondary index does not exist for {spec:?}"))
     }
 
-    pub fn create_scnd_idx(&mut self, spec: Arc<SubValueSpec>) -> Result<()> {
+    /// @return Err of [`UniquenessViolation`](pancake_types::types::UniquenessViolation),
+    ///     if the index is unique and existing values violate it. The index is not created.
+    pub fn create_scnd_idx(&mut self, spec: Arc<ScndIdxSpec>) -> Result<()> {
         if self.scnd_idxs.get(&spec).is_some() {
             return Ok(());
         }
 
         let dir_path = self.all_scnd_idxs_parent_dir.format_new_child_path();
-        let scnd_idx = SecondaryIndex::new(dir_path, Arc::clone(&spec), &self.prim_lsm)?;
+        let scnd_idx = SecondaryIndex::new(
+            dir_path,
+            Arc::clone(&spec),
+            &self.prim_lsm,
+            Arc::clone(&self.opts),
+            self.fc_jobs_tx.clone(),
+        )?;
         self.scnd_idxs.insert(spec, scnd_idx);
 
         Ok(())
     }
 
-    pub fn delete_scnd_idx(&mut self, spec: &SubValueSpec) -> Result<()> {
+    pub fn delete_scnd_idx(&mut self, spec: &ScndIdxSpec) -> Result<()> {
         let scnd_idx = self.scnd_idxs.remove(spec);
         if let Some(scnd_idx) = scnd_idx {
             scnd_idx.remove_dir()?;
@@ -115,4 +245,27 @@ impl DB {
 
         Ok(())
     }
+
+    /// Before creating the constraint, verifies that all existing values satisfy it.
+    ///
+    /// @return Err of [`ConstraintViolation`](pancake_types::types::ConstraintViolation),
+    ///     if an existing value violates the constraint.
+    pub fn create_constraint(&mut self, constraint: Arc<ValueConstraint>) -> Result<()> {
+        if self.constraints.contains(&constraint) {
+            return Ok(());
+        }
+
+        for entry in self.prim_lsm.get_whole_range() {
+            let (pk, pv) = entry.try_borrow()?;
+            ValueConstraint::check_all([&constraint], pk, pv)?;
+        }
+
+        self.constraints.insert(constraint)?;
+
+        Ok(())
+    }
+
+    pub fn delete_constraint(&mut self, constraint: &ValueConstraint) -> Result<()> {
+        self.constraints.remove(constraint)
+    }
 }
@@ -0,0 +1,25 @@
+use std::sync::mpsc;
+
+/// A flushing or compaction job of one [`LSMTree`](crate::lsm::LSMTree).
+/// The job reports its outcome back to the [`LSMTree`](crate::lsm::LSMTree) on its own.
+pub(crate) type FCJob = Box<dyn FnOnce() + Send>;
+
+/// Runs the flushing and compaction jobs of all LSMTrees of a [`DB`](crate::DB), one job at a time,
+/// in the order of submission.
+///
+/// It must be run for as long as the [`DB`](crate::DB) is in use, typically on a dedicated thread.
+/// Otherwise memtables are never flushed, and deleting a secondary index blocks.
+pub struct FlushingAndCompactionWorker {
+    pub(crate) fc_jobs_rx: mpsc::Receiver<FCJob>,
+}
+
+impl FlushingAndCompactionWorker {
+    /// Blocks until the [`DB`](crate::DB) is dropped.
+    pub fn run(self) {
+        for job in self.fc_jobs_rx {
+            job();
+        }
+
+        println!("F+C is exiting.");
+    }
+}
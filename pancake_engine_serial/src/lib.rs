@@ -1,5 +1,8 @@
+mod constraints;
 mod db;
+mod fc_worker;
 mod lsm;
 mod scnd_idx;
 
 pub use db::DB;
+pub use fc_worker::FlushingAndCompactionWorker;
@@ -0,0 +1,95 @@
+use pancake_engine_common::{CompactionStrategy, EngineOptions};
+
+/// Decides when and which [`SortedRun`](super::SortedRun)s of an [`LSMTree`](super::LSMTree) to compact.
+///
+/// Only the newest runs are ever compacted together, into one run.
+/// Hence the output run is newer than all remaining runs, and its files are named accordingly,
+/// which retains the order of runs when the [`LSMTree`](super::LSMTree) is reloaded.
+pub trait CompactionPolicy: Send + Sync {
+    /// @arg `run_lens`: The data length of each run, from older to newer.
+    /// @return The index of the oldest run to compact. It is compacted together with all newer runs.
+    ///     None if no compaction is due.
+    fn pick(&self, run_lens: &[u64]) -> Option<usize>;
+}
+
+pub fn from_opts(opts: &EngineOptions) -> Box<dyn CompactionPolicy> {
+    let compact_count = opts.sstable_compact_count;
+    match opts.compaction_strategy {
+        CompactionStrategy::Full => Box::new(FullCompaction { compact_count }),
+        CompactionStrategy::SizeTiered => Box::new(SizeTieredCompaction { compact_count }),
+    }
+}
+
+/// See [`CompactionStrategy::Full`].
+pub struct FullCompaction {
+    compact_count: usize,
+}
+
+impl CompactionPolicy for FullCompaction {
+    fn pick(&self, run_lens: &[u64]) -> Option<usize> {
+        if run_lens.len() >= self.compact_count {
+            return Some(0);
+        }
+        None
+    }
+}
+
+/// See [`CompactionStrategy::SizeTiered`].
+///
+/// Starting from the newest run, older runs are included one at a time,
+/// as long as each is no longer than all included runs combined.
+/// Compaction is due iff at least `compact_count` runs are included.
+///
+/// For example, with `compact_count = 4`, runs of lengths `[80, 10, 10, 10, 10]` compact the four `10`s into one `40`.
+/// The next compaction would then compact all of `[80, 40, 10, 10, 10, 10]`.
+pub struct SizeTieredCompaction {
+    compact_count: usize,
+}
+
+impl CompactionPolicy for SizeTieredCompaction {
+    fn pick(&self, run_lens: &[u64]) -> Option<usize> {
+        let mut included_len = 0u64;
+        let mut oldest_included_i = run_lens.len();
+        for (run_i, run_len) in run_lens.iter().enumerate().rev() {
+            if (oldest_included_i < run_lens.len()) && (*run_len > included_len) {
+                break;
+            }
+            included_len += run_len;
+            oldest_included_i = run_i;
+        }
+
+        if run_lens.len() - oldest_included_i >= self.compact_count {
+            return Some(oldest_included_i);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn full() {
+        let policy = FullCompaction { compact_count: 3 };
+        assert_eq!(policy.pick(&[]), None);
+        assert_eq!(policy.pick(&[1, 100]), None);
+        assert_eq!(policy.pick(&[1, 100, 1]), Some(0));
+    }
+
+    #[test]
+    fn size_tiered() {
+        let policy = SizeTieredCompaction { compact_count: 4 };
+        assert_eq!(policy.pick(&[]), None);
+        assert_eq!(policy.pick(&[10, 10, 10]), None);
+        assert_eq!(policy.pick(&[10, 10, 10, 10]), Some(0));
+        assert_eq!(policy.pick(&[40, 10, 10, 10]), None);
+        assert_eq!(policy.pick(&[40, 10, 10, 10, 10]), Some(0));
+        assert_eq!(policy.pick(&[80, 10, 10, 10, 10]), Some(1));
+        assert_eq!(policy.pick(&[80, 40, 10, 10, 10, 10]), Some(0));
+        assert_eq!(policy.pick(&[1000, 80, 40, 10, 10, 10, 10]), Some(1));
+        /* A newer run that is longer than older runs does not stop the inclusion. */
+        assert_eq!(policy.pick(&[10, 10, 10, 50]), Some(0));
+        assert_eq!(policy.pick(&[100, 0, 0, 0]), None);
+    }
+}
@@ -1,17 +1,25 @@
+use crate::fc_worker::FCJob;
+use crate::lsm::{compaction_policy::CompactionPolicy, SortedRun};
 use anyhow::{Context, Result};
 use pancake_engine_common::fs_utils::{self, AntiCollisionParentDir, NamePattern};
-use pancake_engine_common::{SSTable, WritableMemLog};
-use pancake_types::{serde::OptDatum, types::Serializable};
+use pancake_engine_common::{EngineOptions, ReadonlyMemLog, SSTable, WritableMemLog};
+use pancake_types::{
+    serde::OptDatum,
+    types::{PartialOrdSer, Serializable},
+};
 use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
 
-const LOG_FILE_NAME: &str = "commit_log.kv";
+/// The log file of versions that did not have multiple log files.
+const LEGACY_LOG_FILE_NAME: &str = "commit_log.kv";
+const MEMLOGS_DIR_NAME: &str = "memlogs";
 const SSTABLES_DIR_NAME: &str = "sstables";
 
 /// An LSMTree is an abstraction of a sorted dictionary.
 ///
 /// ### API:
 ///
-/// The exposed operations are: `put one`, `get one`, `get range`.
+/// The exposed operations are: `put one`, `delete range`, `get one`, `get range`.
 ///
 /// Values are immutable. They cannot be modified in-place, and must be replaced.
 ///
@@ -19,37 +27,122 @@ const SSTABLES_DIR_NAME: &str = "sstables";
 ///
 /// One [`WritableMemLog`] holds the most recently inserted `{key: value}` in a sorted in-memory table.
 ///
-/// The [`WritableMemLog`] is occasionally flushed into an [`SSTable`].
+/// Once the [`WritableMemLog`] is full, it is replaced by a new empty one, and becomes immutable.
+/// Each immutable [`ReadonlyMemLog`] is flushed into an [`SSTable`], which forms a new [`SortedRun`].
 ///
-/// Multiple [`SSTable`]s are occasionally compacted into one [`SSTable`].
+/// Multiple [`SortedRun`]s are occasionally compacted into one [`SortedRun`] of [`SSTable`]s of a target size.
+/// The [`CompactionPolicy`] decides which ones.
+///
+/// Flushing and compaction run as jobs on the [`FlushingAndCompactionWorker`](crate::FlushingAndCompactionWorker),
+/// at most one job per LSMTree at a time. Their outcomes are installed upon a subsequent `put`.
 ///
 /// ### Querying:
 ///
 /// A `put` operation accesses the Memtable of the [`WritableMemLog`] only.
+/// It never waits for flushing or compaction.
 ///
-/// A `get` operation generally accesses the [`WritableMemLog`] and all [`SSTable`]s.
+/// A `get` operation generally accesses the [`WritableMemLog`], all immutable [`ReadonlyMemLog`]s,
+/// and all [`SortedRun`]s.
 ///
 /// When the same key exists in multiple internal tables, only the result from the newest table is retrieved.
+/// A range tombstone in a table hides the results from all older tables within its range.
 pub struct LSMTree<K, V> {
     memlog: WritableMemLog<K, OptDatum<V>>,
 
+    /// Each is being, or waiting to be, flushed. From older to newer.
+    imm_memlogs: Vec<Arc<ReadonlyMemLog<K, OptDatum<V>>>>,
+
+    memlogs_dir: AntiCollisionParentDir,
+
     /// From older to newer.
-    sstables: Vec<SSTable<K, OptDatum<V>>>,
+    runs: Vec<Arc<SortedRun<K, OptDatum<V>>>>,
+
+    sstables_dir: Arc<AntiCollisionParentDir>,
+
+    compaction_policy: Box<dyn CompactionPolicy>,
+
+    opts: Arc<EngineOptions>,
+
+    fc_jobs_tx: mpsc::Sender<FCJob>,
+    fc_outcomes_tx: mpsc::Sender<FCOutcome<K, V>>,
+    /// The mutex only makes the receiver `Sync`. It is always accessed via `&mut self`.
+    fc_outcomes_rx: Mutex<mpsc::Receiver<FCOutcome<K, V>>>,
+    is_fc_job_in_flight: bool,
+}
 
-    sstables_dir: AntiCollisionParentDir,
+/// The result of a job, to be installed into the LSMTree.
+enum FCOutcome<K, V> {
+    /// The oldest immutable memlog was flushed.
+    Flushed(Result<SSTable<K, OptDatum<V>>>),
+    /// The run at `run_i_lo` and all newer runs were compacted.
+    Compacted {
+        run_i_lo: usize,
+        res_ssts: Result<Vec<SSTable<K, OptDatum<V>>>>,
+    },
 }
 
 impl<K, V> LSMTree<K, V>
 where
-    K: Serializable + Ord + Clone,
+    K: 'static + Serializable + Ord + PartialOrdSer<K> + Clone + Send + Sync,
+    V: 'static + Send + Sync,
     OptDatum<V>: Serializable,
 {
-    pub fn load_or_new<P: AsRef<Path>>(lsm_dir_path: P) -> Result<Self> {
-        let log_file_path = lsm_dir_path.as_ref().join(LOG_FILE_NAME);
-        let sstables_dir_path = lsm_dir_path.as_ref().join(SSTABLES_DIR_NAME);
+    /// Upon loading, the newest log file becomes the writable memlog, and any older ones are flushed anew.
+    ///
+    /// [`SSTable`]s are grouped into [`SortedRun`]s anew.
+    /// Each run consists of consecutively named [`SSTable`]s that have disjoint and ascending key ranges.
+    pub fn load_or_new<P: AsRef<Path>>(
+        lsm_dir_path: P,
+        compaction_policy: Box<dyn CompactionPolicy>,
+        opts: Arc<EngineOptions>,
+        fc_jobs_tx: mpsc::Sender<FCJob>,
+    ) -> Result<Self> {
+        let lsm_dir_path = lsm_dir_path.as_ref();
+        let legacy_log_file_path = lsm_dir_path.join(LEGACY_LOG_FILE_NAME);
+        let memlogs_dir_path = lsm_dir_path.join(MEMLOGS_DIR_NAME);
+        let sstables_dir_path = lsm_dir_path.join(SSTABLES_DIR_NAME);
+        fs_utils::create_dir_all(&memlogs_dir_path)?;
         fs_utils::create_dir_all(&sstables_dir_path)?;
 
-        let memlog = WritableMemLog::load_or_new(log_file_path)?;
+        /* MemLogs. */
+
+        let mut memlog_file_paths = vec![];
+        let memlogs_dir = AntiCollisionParentDir::load_or_new(
+            memlogs_dir_path,
+            NamePattern::new("", ".kv"),
+            |child_path, res_child_num| -> Result<()> {
+                let child_num = res_child_num.with_context(|| {
+                    format!("A memlogs dir contains an unexpected child path {child_path:?}")
+                })?;
+
+                memlog_file_paths.push((child_path, child_num));
+
+                Ok(())
+            },
+        )?;
+        memlog_file_paths.sort_by_key(|(_child_path, child_num)| *child_num);
+        let mut memlog_file_paths = memlog_file_paths
+            .into_iter()
+            .map(|(child_path, _child_num)| child_path)
+            .collect::<Vec<_>>();
+
+        if legacy_log_file_path.exists() {
+            let memlog_file_path = memlogs_dir.format_new_child_path();
+            fs_utils::rename_file(&legacy_log_file_path, &memlog_file_path)?;
+            memlog_file_paths.push(memlog_file_path);
+        }
+
+        let memlog_file_path = memlog_file_paths
+            .pop()
+            .unwrap_or_else(|| memlogs_dir.format_new_child_path());
+        let memlog = WritableMemLog::load_or_new(memlog_file_path)?;
+
+        let imm_memlogs = memlog_file_paths
+            .into_iter()
+            .map(|child_path| ReadonlyMemLog::load(child_path).map(Arc::new))
+            .collect::<Result<Vec<_>>>()?;
+
+        /* SSTables. */
 
         let mut sstable_file_paths = vec![];
         let sstables_dir = AntiCollisionParentDir::load_or_new(
@@ -67,18 +160,40 @@ where
         )?;
 
         sstable_file_paths.sort_by_key(|(_child_path, child_num)| *child_num);
-        let sstables = sstable_file_paths
-            .into_iter()
-            .map(|(child_path, _child_num)| SSTable::load(child_path))
-            .collect::<Result<Vec<_>>>()?;
+        let mut runs: Vec<SortedRun<K, OptDatum<V>>> = vec![];
+        for (child_path, _child_num) in sstable_file_paths {
+            let sst = SSTable::load(child_path)?;
+            match runs.last_mut() {
+                Some(run) if run.can_append(&sst) => run.push(sst),
+                _ => runs.push(SortedRun::from(vec![sst])),
+            }
+        }
+        let runs = runs.into_iter().map(Arc::new).collect();
+
+        let (fc_outcomes_tx, fc_outcomes_rx) = mpsc::channel();
 
-        Ok(Self {
+        let mut lsm = Self {
             memlog,
-            sstables,
-            sstables_dir,
-        })
+            imm_memlogs,
+            memlogs_dir,
+            runs,
+            sstables_dir: Arc::new(sstables_dir),
+            compaction_policy,
+            opts,
+            fc_jobs_tx,
+            fc_outcomes_tx,
+            fc_outcomes_rx: Mutex::new(fc_outcomes_rx),
+            is_fc_job_in_flight: false,
+        };
+
+        lsm.maybe_submit_fc_job()?;
+
+        Ok(lsm)
     }
 }
 
 mod gc;
 mod opers;
+
+#[cfg(test)]
+mod test;
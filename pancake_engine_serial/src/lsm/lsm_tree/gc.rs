@@ -1,67 +1,187 @@
-use super::LSMTree;
-use crate::lsm::merging;
-use anyhow::Result;
-use pancake_engine_common::{Entry, SSTable};
-use pancake_types::{serde::OptDatum, types::Serializable};
+use super::{FCOutcome, LSMTree};
+use crate::lsm::{merging, SortedRun};
+use anyhow::{anyhow, Result};
+use pancake_engine_common::{
+    fs_utils, Entry, RangeTombstones, ReadonlyMemLog, SSTable, WritableMemLog,
+};
+use pancake_types::{
+    serde::{self, OptDatum},
+    types::{PartialOrdSer, Serializable},
+};
 use std::mem;
-
-/// These thresholds are exaggeratedly small, so as to be helpful with debugging.
-/// In the future, we'll allow setting them from env vars.
-static MEMTABLE_FLUSH_SIZE_THRESH: usize = 7;
-static SSTABLE_COMPACT_COUNT_THRESH: usize = 4;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 impl<K, V> LSMTree<K, V>
 where
-    K: Serializable + Ord + Clone,
+    K: 'static + Serializable + Ord + PartialOrdSer<K> + Clone + Send + Sync,
+    V: 'static + Send + Sync,
     OptDatum<V>: Serializable,
 {
+    /// Never waits for flushing or compaction.
+    ///
+    /// @return Err if a finished job had failed. The failed job is retried later.
     pub fn maybe_run_gc(&mut self) -> Result<()> {
-        if self.memlog.r_memlog().mem_len() >= MEMTABLE_FLUSH_SIZE_THRESH {
-            self.flush_memtable()?;
+        self.install_fc_outcomes()?;
+
+        if (self.memlog.r_memlog().mem_len() >= self.opts.memtable_flush_entry_count)
+            || (self.memlog.log_len() >= self.opts.memtable_flush_byte_size)
+        {
+            self.rotate_memlog()?;
         }
-        if self.sstables.len() >= SSTABLE_COMPACT_COUNT_THRESH {
-            self.compact_sstables()?;
+
+        self.maybe_submit_fc_job()?;
+
+        Ok(())
+    }
+
+    /// Waits for the in-flight job, if any, and discards its outcome.
+    /// Hence the job no longer writes into this LSMTree's dir.
+    pub fn abandon_fc_job(&mut self) {
+        if self.is_fc_job_in_flight {
+            let fc_outcomes_rx = self.fc_outcomes_rx.get_mut().unwrap();
+            fc_outcomes_rx.recv().ok();
+            self.is_fc_job_in_flight = false;
         }
+    }
+
+    fn rotate_memlog(&mut self) -> Result<()> {
+        let memlog_path = self.memlogs_dir.format_new_child_path();
+        let new_memlog = WritableMemLog::load_or_new(memlog_path)?;
+
+        let old_memlog = mem::replace(&mut self.memlog, new_memlog);
+        self.imm_memlogs
+            .push(Arc::new(ReadonlyMemLog::from(old_memlog)));
+
         Ok(())
     }
 
-    fn flush_memtable(&mut self) -> Result<()> {
-        let sst_path = self.sstables_dir.format_new_child_path();
+    fn install_fc_outcomes(&mut self) -> Result<()> {
+        loop {
+            let fc_outcomes_rx = self.fc_outcomes_rx.get_mut().unwrap();
+            let outcome = match fc_outcomes_rx.try_recv() {
+                Err(_) => return Ok(()),
+                Ok(outcome) => outcome,
+            };
+            self.is_fc_job_in_flight = false;
+
+            match outcome {
+                FCOutcome::Flushed(res_sst) => {
+                    let new_sst = res_sst?;
+
+                    let old_memlog = self.imm_memlogs.remove(0);
+                    self.runs.push(Arc::new(SortedRun::from(vec![new_sst])));
+
+                    fs_utils::remove_file(&old_memlog.log_path)?;
+                }
+                FCOutcome::Compacted { run_i_lo, res_ssts } => {
+                    let new_ssts = res_ssts?;
+
+                    let old_runs = self.runs.split_off(run_i_lo);
+                    if new_ssts.is_empty() == false {
+                        self.runs.push(Arc::new(SortedRun::from(new_ssts)));
+                    }
+                    for run in old_runs {
+                        run.remove_files()?;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Flushing takes precedence over compaction, so that memory is freed first.
+    ///
+    /// Only one job is in flight at a time. Hence a job's output files are always named newer
+    /// than the existing ones, and the runs that a compaction job reads are unchanged when its outcome is installed.
+    pub(super) fn maybe_submit_fc_job(&mut self) -> Result<()> {
+        if self.is_fc_job_in_flight {
+            return Ok(());
+        }
 
-        let entries = self.memlog.r_memlog().get_whole_range().map(Entry::Ref);
+        if let Some(old_memlog) = self.imm_memlogs.first() {
+            let old_memlog = Arc::clone(old_memlog);
+            let sst_path = self.sstables_dir.format_new_child_path();
+            let block_size = self.opts.sstable_data_block_size;
+            let fc_outcomes_tx = self.fc_outcomes_tx.clone();
 
-        let new_sst = SSTable::new(entries, sst_path)?;
+            return self.submit_fc_job(move || {
+                let now = serde::now_micros();
+                let entries = old_memlog
+                    .get_whole_range()
+                    .map(Entry::Ref)
+                    .filter_map(|entry| entry.to_compacted_entry(now, false));
+                let range_tombstones = old_memlog.range_tombstones.clone();
+                let res_sst = SSTable::new(entries, range_tombstones, sst_path, block_size);
+                fc_outcomes_tx.send(FCOutcome::Flushed(res_sst)).ok();
+            });
+        }
 
-        self.sstables.push(new_sst);
+        let run_lens = self
+            .runs
+            .iter()
+            .map(|run| run.data_len())
+            .collect::<Vec<_>>();
+        if let Some(run_i_lo) = self.compaction_policy.pick(&run_lens) {
+            let old_runs = self.runs[run_i_lo..].to_vec();
+            let sstables_dir = Arc::clone(&self.sstables_dir);
+            let opts = Arc::clone(&self.opts);
+            let fc_outcomes_tx = self.fc_outcomes_tx.clone();
 
-        self.memlog.clear()?;
+            return self.submit_fc_job(move || {
+                let res_ssts = Self::compact_runs(
+                    &old_runs,
+                    run_i_lo == 0,
+                    || sstables_dir.format_new_child_path(),
+                    opts.sstable_data_block_size,
+                    opts.sstable_target_file_size,
+                );
+                let outcome = FCOutcome::Compacted { run_i_lo, res_ssts };
+                fc_outcomes_tx.send(outcome).ok();
+            });
+        }
+
+        Ok(())
+    }
 
+    fn submit_fc_job(&mut self, job: impl 'static + FnOnce() + Send) -> Result<()> {
+        self.fc_jobs_tx
+            .send(Box::new(job))
+            .map_err(|_| anyhow!("The flushing and compaction worker has exited."))?;
+        self.is_fc_job_in_flight = true;
         Ok(())
     }
 
-    /// For now, always compact all SSTables into one SSTable.
-    fn compact_sstables(&mut self) -> Result<()> {
-        let sst_path = self.sstables_dir.format_new_child_path();
-
-        let entries = merging::merge_sstables(&self.sstables[..], None, None)
-            // skip tombstones
-            .filter(|res| match res {
-                Err(_) => true,
-                Ok((_k, optdat_v)) => match optdat_v {
-                    OptDatum::Tombstone => false,
-                    OptDatum::Some(_) => true,
-                },
-            })
-            .map(Entry::Own);
-
-        let new_sst = SSTable::new(entries, sst_path)?;
-
-        let new_ssts = vec![new_sst];
-        let old_ssts = mem::replace(&mut self.sstables, new_ssts);
-        for sst in old_ssts {
-            sst.remove_file()?;
+    /// Compacts the given runs into one run.
+    ///
+    /// Expired entries become tombstones.
+    /// Entries covered by range tombstones of newer runs are dropped.
+    /// Tombstones and range tombstones are skipped iff the oldest run is compacted,
+    /// as then there is no older entry for them to shadow.
+    fn compact_runs(
+        old_runs: &[Arc<SortedRun<K, OptDatum<V>>>],
+        skip_tombstones: bool,
+        gen_sst_path: impl FnMut() -> PathBuf,
+        block_size: u64,
+        file_size: u64,
+    ) -> Result<Vec<SSTable<K, OptDatum<V>>>> {
+        let now = serde::now_micros();
+        let entries = merging::merge_runs(vec![], old_runs, None::<&K>, None::<&K>)
+            .map(Entry::Own)
+            .filter_map(|entry| entry.to_compacted_entry(now, skip_tombstones));
+
+        let mut range_tombstones = RangeTombstones::default();
+        if skip_tombstones == false {
+            for run in old_runs.iter() {
+                range_tombstones.extend_from(run.range_tombstones());
+            }
         }
 
-        Ok(())
+        SSTable::new_multi(
+            entries,
+            range_tombstones,
+            gen_sst_path,
+            block_size,
+            file_size,
+        )
     }
 }
@@ -2,16 +2,19 @@ use super::LSMTree;
 use crate::lsm::merging;
 use anyhow::Result;
 use pancake_engine_common::Entry;
-use pancake_types::{serde::OptDatum, types::Serializable};
+use pancake_types::{
+    serde::{self, OptDatum},
+    types::{PartialOrdSer, Ser, Serializable},
+};
 use std::borrow::Borrow;
 
 impl<K, V> LSMTree<K, V>
 where
-    K: Serializable + Ord + Clone,
+    K: 'static + Serializable + Ord + PartialOrdSer<K> + Clone + Send + Sync,
+    V: 'static + Send + Sync,
     OptDatum<V>: Serializable,
 {
-    pub fn put(&mut self, k: K, v: Option<V>) -> Result<()> {
-        let v = OptDatum::from(v);
+    pub fn put(&mut self, k: K, v: OptDatum<V>) -> Result<()> {
         self.memlog.put(k, v)?;
         self.memlog.flush()?;
 
@@ -20,17 +23,56 @@ where
         Ok(())
     }
 
+    /// Deletes all entries within `[k_lo, k_hi]`, by one range tombstone.
+    pub fn delete_range(&mut self, k_lo: K, k_hi: K) -> Result<()> {
+        self.memlog.delete_range(k_lo, k_hi)?;
+        self.memlog.flush()?;
+
+        self.maybe_run_gc()?;
+
+        Ok(())
+    }
+
     pub fn get_one<'a, Q>(&'a self, k: &'a Q) -> Option<Entry<'a, K, V>>
     where
-        K: Borrow<Q> + PartialOrd<Q>,
-        Q: Ord,
+        K: Borrow<Q> + PartialOrdSer<Q>,
+        Q: Ord + Ser,
+    {
+        let now = serde::now_micros();
+        self.get_one_optdat(k)
+            .and_then(|entry| entry.to_option_entry(now))
+    }
+
+    /// Like [`Self::get_one()`], but the entry may be a tombstone or expired.
+    ///
+    /// @return None if the key is absent or covered by a range tombstone.
+    pub fn get_one_optdat<'a, Q>(&'a self, k: &'a Q) -> Option<Entry<'a, K, OptDatum<V>>>
+    where
+        K: Borrow<Q> + PartialOrdSer<Q>,
+        Q: Ord + Ser,
     {
-        if let Some(kv) = self.memlog.r_memlog().get_one(k) {
-            return Entry::Ref(kv).to_option_entry();
+        let r_memlog = self.memlog.r_memlog();
+        if let Some(kv) = r_memlog.get_one(k) {
+            return Some(Entry::Ref(kv));
+        }
+        if r_memlog.range_tombstones.covers(k) {
+            return None;
+        }
+        for imm_memlog in self.imm_memlogs.iter().rev() {
+            if let Some(kv) = imm_memlog.get_one(k) {
+                return Some(Entry::Ref(kv));
+            }
+            if imm_memlog.range_tombstones.covers(k) {
+                return None;
+            }
         }
-        // TODO bloom filter here
-        if let Some(res) = self.sstables.iter().rev().find_map(|sst| sst.get_one(k)) {
-            return Entry::Own(res).to_option_entry();
+        for run in self.runs.iter().rev() {
+            if let Some(res) = run.get_one(k) {
+                return Some(Entry::Own(res));
+            }
+            if run.range_tombstones().covers(k) {
+                return None;
+            }
         }
         None
     }
@@ -41,13 +83,32 @@ where
         k_hi: Option<&'a Q>,
     ) -> impl 'a + Iterator<Item = Entry<'a, K, V>>
     where
-        K: PartialOrd<Q>,
+        K: PartialOrdSer<Q>,
     {
-        merging::merge_memlog_and_sstables(&self.memlog, &self.sstables[..], k_lo, k_hi)
-            .filter_map(|entry| entry.to_option_entry())
+        let now = serde::now_micros();
+        self.get_range_optdat(k_lo, k_hi)
+            .filter_map(move |entry| entry.to_option_entry(now))
     }
 
     pub fn get_whole_range<'a>(&'a self) -> impl 'a + Iterator<Item = Entry<'a, K, V>> {
-        self.get_range(None, None)
+        self.get_range(None::<&K>, None::<&K>)
+    }
+
+    /// Like [`Self::get_range()`], but the entries may be tombstones or expired.
+    pub fn get_range_optdat<'a, Q>(
+        &'a self,
+        k_lo: Option<&'a Q>,
+        k_hi: Option<&'a Q>,
+    ) -> impl 'a + Iterator<Item = Entry<'a, K, OptDatum<V>>>
+    where
+        K: PartialOrdSer<Q>,
+    {
+        merging::merge_memlogs_and_runs(
+            &self.memlog,
+            &self.imm_memlogs[..],
+            &self.runs[..],
+            k_lo,
+            k_hi,
+        )
     }
 }
@@ -0,0 +1,281 @@
+use super::*;
+use crate::lsm::compaction_policy;
+use crate::FlushingAndCompactionWorker;
+use pancake_engine_common::CompactionStrategy;
+use pancake_types::{
+    serde::{self, Datum},
+    types::{PKShared, PVShared, PrimaryKey, Value},
+};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+type TestLSMTree = LSMTree<PKShared, PVShared>;
+
+fn gen_dir_path(test_name: &str) -> PathBuf {
+    let dir_path = env::temp_dir()
+        .join("pancake_lsm_tree_test")
+        .join(test_name);
+    if dir_path.exists() {
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+    dir_path
+}
+
+fn gen_opts() -> Arc<EngineOptions> {
+    let opts = EngineOptions {
+        memtable_flush_entry_count: 3,
+        compaction_strategy: CompactionStrategy::Full,
+        sstable_compact_count: 2,
+        ..EngineOptions::default()
+    };
+    Arc::new(opts)
+}
+
+fn load(dir_path: &Path, fc_jobs_tx: mpsc::Sender<FCJob>) -> Result<TestLSMTree> {
+    load_with_opts(dir_path, gen_opts(), fc_jobs_tx)
+}
+
+fn load_with_opts(
+    dir_path: &Path,
+    opts: Arc<EngineOptions>,
+    fc_jobs_tx: mpsc::Sender<FCJob>,
+) -> Result<TestLSMTree> {
+    TestLSMTree::load_or_new(
+        dir_path,
+        compaction_policy::from_opts(&opts),
+        opts,
+        fc_jobs_tx,
+    )
+}
+
+fn put(
+    lsm: &mut TestLSMTree,
+    exp: &mut BTreeMap<i64, String>,
+    k: i64,
+    v: Option<&str>,
+) -> Result<()> {
+    let pk = Arc::new(PrimaryKey(Datum::I64(k)));
+    let pv = v.map(|v| Arc::new(Value(Datum::Str(String::from(v)))));
+    lsm.put(pk, OptDatum::from(pv))?;
+
+    match v {
+        None => exp.remove(&k),
+        Some(v) => exp.insert(k, String::from(v)),
+    };
+
+    Ok(())
+}
+
+fn delete_range(
+    lsm: &mut TestLSMTree,
+    exp: &mut BTreeMap<i64, String>,
+    k_lo: i64,
+    k_hi: i64,
+) -> Result<()> {
+    let pk_lo = Arc::new(PrimaryKey(Datum::I64(k_lo)));
+    let pk_hi = Arc::new(PrimaryKey(Datum::I64(k_hi)));
+    lsm.delete_range(pk_lo, pk_hi)?;
+
+    exp.retain(|k, _| (k_lo <= *k && *k <= k_hi) == false);
+
+    Ok(())
+}
+
+fn verify(lsm: &TestLSMTree, exp: &BTreeMap<i64, String>) -> Result<()> {
+    let mut act = BTreeMap::new();
+    for entry in lsm.get_whole_range() {
+        let (pk, pv) = entry.try_borrow()?;
+        match (&pk.0, &pv.0) {
+            (Datum::I64(k), Datum::Str(v)) => act.insert(*k, v.clone()),
+            _ => panic!("Unexpected entry {pk:?} {pv:?}"),
+        };
+    }
+    assert_eq!(&act, exp);
+
+    for k in 0..20 {
+        let pk = PrimaryKey(Datum::I64(k));
+        let act = lsm
+            .get_one(&pk)
+            .map(|entry| entry.into_owned_v())
+            .transpose()?;
+        let act = act.map(|pv| pv.0.clone());
+        let exp = exp.get(&k).map(|v| Datum::Str(v.clone()));
+        assert_eq!(act, exp, "{k}");
+    }
+
+    Ok(())
+}
+
+/// Repeatedly installs outcomes, until no more job is due.
+fn settle(lsm: &mut TestLSMTree) -> Result<()> {
+    loop {
+        lsm.maybe_run_gc()?;
+        if lsm.is_fc_job_in_flight == false {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn immutable_memlogs() -> Result<()> {
+    let dir_path = gen_dir_path("immutable_memlogs");
+    let mut exp = BTreeMap::new();
+
+    /* Without a worker, the memlogs pile up, and remain readable. */
+
+    let (fc_jobs_tx, fc_jobs_rx) = mpsc::channel();
+    let mut lsm = load(&dir_path, fc_jobs_tx)?;
+    for k in 0..10 {
+        put(&mut lsm, &mut exp, k, Some(&format!("a{k}")))?;
+    }
+    assert_eq!(lsm.imm_memlogs.len(), 3);
+    assert!(lsm.runs.is_empty());
+    verify(&lsm, &exp)?;
+
+    drop(lsm);
+    drop(fc_jobs_rx);
+
+    /* Upon reloading, the leftover memlogs are flushed. Updates shadow older values. */
+
+    let (fc_jobs_tx, fc_jobs_rx) = mpsc::channel();
+    let fc_worker = FlushingAndCompactionWorker { fc_jobs_rx };
+    let fc_thread = thread::spawn(move || fc_worker.run());
+
+    let mut lsm = load(&dir_path, fc_jobs_tx)?;
+    assert_eq!(lsm.imm_memlogs.len(), 3);
+    verify(&lsm, &exp)?;
+
+    for k in 5..15 {
+        let v = match k % 3 {
+            0 => None,
+            _ => Some(format!("b{k}")),
+        };
+        put(&mut lsm, &mut exp, k, v.as_deref())?;
+        verify(&lsm, &exp)?;
+    }
+
+    settle(&mut lsm)?;
+    assert!(lsm.imm_memlogs.is_empty());
+    assert_eq!(lsm.runs.len(), 1);
+    verify(&lsm, &exp)?;
+
+    drop(lsm);
+    fc_thread.join().unwrap();
+
+    /* The flushed and compacted state is reloaded. */
+
+    let (fc_jobs_tx, _fc_jobs_rx) = mpsc::channel();
+    let lsm = load(&dir_path, fc_jobs_tx)?;
+    assert!(lsm.imm_memlogs.is_empty());
+    verify(&lsm, &exp)?;
+
+    Ok(())
+}
+
+#[test]
+fn expired_entries() -> Result<()> {
+    let dir_path = gen_dir_path("expired_entries");
+    let mut exp = BTreeMap::new();
+
+    let (fc_jobs_tx, fc_jobs_rx) = mpsc::channel();
+    let fc_worker = FlushingAndCompactionWorker { fc_jobs_rx };
+    let fc_thread = thread::spawn(move || fc_worker.run());
+
+    let mut lsm = load(&dir_path, fc_jobs_tx)?;
+
+    /* Even keys expire in the past; odd keys expire in the far future. Each shadows an older value. */
+    for k in 0..10 {
+        put(&mut lsm, &mut exp, k, Some(&format!("a{k}")))?;
+    }
+    for k in 0..10 {
+        let pk = Arc::new(PrimaryKey(Datum::I64(k)));
+        let pv = Arc::new(Value(Datum::Str(format!("b{k}"))));
+        if k % 2 == 0 {
+            lsm.put(pk, OptDatum::Expiring(pv, 0))?;
+            exp.remove(&k);
+        } else {
+            lsm.put(pk, OptDatum::new_expiring(pv, Duration::from_secs(3600)))?;
+            exp.insert(k, format!("b{k}"));
+        }
+    }
+    verify(&lsm, &exp)?;
+
+    /* Upon the full compaction, the expired entries are dropped physically, rather than becoming tombstones. */
+    settle(&mut lsm)?;
+    assert!(lsm.imm_memlogs.is_empty());
+    assert_eq!(lsm.runs.len(), 1);
+    verify(&lsm, &exp)?;
+    for res in lsm.runs[0].get_range(None::<&PKShared>, None::<&PKShared>) {
+        let (pk, optdat_pv) = res?;
+        assert!(optdat_pv.as_live(serde::now_micros()).is_some(), "{pk:?}");
+    }
+
+    drop(lsm);
+    fc_thread.join().unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn range_deletes() -> Result<()> {
+    for compaction_strategy in [CompactionStrategy::Full, CompactionStrategy::SizeTiered] {
+        let dir_path = gen_dir_path(&format!("range_deletes_{compaction_strategy:?}"));
+        let opts = Arc::new(EngineOptions {
+            compaction_strategy,
+            ..(*gen_opts()).clone()
+        });
+        let mut exp = BTreeMap::new();
+
+        /* Without a worker, range tombstones shadow older memlogs, but not newer entries in the same memlog. */
+
+        let (fc_jobs_tx, fc_jobs_rx) = mpsc::channel();
+        let mut lsm = load_with_opts(&dir_path, Arc::clone(&opts), fc_jobs_tx)?;
+        for k in 0..10 {
+            put(&mut lsm, &mut exp, k, Some(&format!("a{k}")))?;
+        }
+        delete_range(&mut lsm, &mut exp, 2, 7)?;
+        put(&mut lsm, &mut exp, 5, Some("b5"))?;
+        delete_range(&mut lsm, &mut exp, 9, 8)?;
+        verify(&lsm, &exp)?;
+
+        drop(lsm);
+        drop(fc_jobs_rx);
+
+        /* With a worker, range tombstones are flushed and compacted. */
+
+        let (fc_jobs_tx, fc_jobs_rx) = mpsc::channel();
+        let fc_worker = FlushingAndCompactionWorker { fc_jobs_rx };
+        let fc_thread = thread::spawn(move || fc_worker.run());
+
+        let mut lsm = load_with_opts(&dir_path, Arc::clone(&opts), fc_jobs_tx)?;
+        verify(&lsm, &exp)?;
+
+        for round in 0..8 {
+            for k in (round * 3)..(round * 3 + 6) {
+                put(&mut lsm, &mut exp, k % 20, Some(&format!("c{round}_{k}")))?;
+            }
+            let k_lo = (round * 7) % 20;
+            delete_range(&mut lsm, &mut exp, k_lo, k_lo + round % 4)?;
+            verify(&lsm, &exp)?;
+        }
+
+        settle(&mut lsm)?;
+        verify(&lsm, &exp)?;
+
+        drop(lsm);
+        fc_thread.join().unwrap();
+
+        /* The flushed and compacted state is reloaded. */
+
+        let (fc_jobs_tx, _fc_jobs_rx) = mpsc::channel();
+        let lsm = load_with_opts(&dir_path, opts, fc_jobs_tx)?;
+        verify(&lsm, &exp)?;
+    }
+
+    Ok(())
+}
@@ -1,41 +1,74 @@
+use crate::lsm::SortedRun;
 use anyhow::Result;
-use pancake_engine_common::{merging, Entry, SSTable, WritableMemLog};
-use pancake_types::types::Deser;
-use std::cmp::{Ord, PartialOrd};
+use itertools::Either;
+use pancake_engine_common::{merging, Entry, RangeTombstones, ReadonlyMemLog, WritableMemLog};
+use pancake_types::types::{Deser, PartialOrdSer};
+use std::cmp::Ord;
+use std::sync::Arc;
 
-/// @arg sstables: From older to newer. (The *opposite* of the convention in [`pancake_engine_common::merging`].)
-pub fn merge_sstables<'a, K, V, Q>(
-    sstables: &'a [SSTable<K, V>],
+/// The entries that are covered by range tombstones of newer runs are dropped.
+///
+/// @arg newer_range_tombstones: Those of the entrysets that are newer than all of `runs`.
+/// @arg runs: From older to newer. (The *opposite* of the convention in [`pancake_engine_common::merging`].)
+pub fn merge_runs<'a, K, V, Q>(
+    newer_range_tombstones: Vec<&'a RangeTombstones<K>>,
+    runs: &'a [Arc<SortedRun<K, V>>],
     k_lo: Option<&'a Q>,
     k_hi: Option<&'a Q>,
 ) -> impl 'a + Iterator<Item = Result<(K, V)>>
 where
-    K: Deser + Ord + PartialOrd<Q>,
+    K: Ord + Clone + PartialOrdSer<Q>,
     V: Deser,
 {
-    let entry_iters = sstables
+    let entry_iters = runs
         .iter()
         .rev()
-        .map(move |sst| sst.get_range(k_lo, k_hi));
+        .map(move |run| (run.get_range(k_lo, k_hi), run.range_tombstones()));
+    let entry_iters = merging::drop_range_deleted(newer_range_tombstones, entry_iters);
 
     merging::merge_entry_iters(entry_iters)
 }
 
-/// @arg sstables: From older to newer. (The *opposite* of the convention in [`pancake_engine_common::merging`].)
-pub fn merge_memlog_and_sstables<'a, K, V, Q>(
+/// The entries that are covered by range tombstones of newer memlogs or runs are dropped.
+///
+/// @arg imm_memlogs, runs: Each from older to newer. (The *opposite* of the convention in [`pancake_engine_common::merging`].)
+pub fn merge_memlogs_and_runs<'a, K, V, Q>(
     memlog: &'a WritableMemLog<K, V>,
-    sstables: &'a [SSTable<K, V>],
+    imm_memlogs: &'a [Arc<ReadonlyMemLog<K, V>>],
+    runs: &'a [Arc<SortedRun<K, V>>],
     k_lo: Option<&'a Q>,
     k_hi: Option<&'a Q>,
 ) -> impl 'a + Iterator<Item = Entry<'a, K, V>>
 where
-    K: Deser + Ord + PartialOrd<Q>,
+    K: Ord + Clone + PartialOrdSer<Q>,
     V: Deser,
 {
     let memlog_entry_iter = memlog.r_memlog().get_range(k_lo, k_hi);
     let memlog_entry_iter = Some(memlog_entry_iter);
 
-    let sstables_entry_iter = merge_sstables(sstables, k_lo, k_hi).map(Entry::Own);
+    let memlog_range_tombstones = &memlog.r_memlog().range_tombstones;
+    let imm_memlogs_entry_iters = imm_memlogs.iter().rev().map(move |imm_memlog| {
+        let entry_iter = imm_memlog.get_range(k_lo, k_hi).map(Entry::Ref);
+        (entry_iter, &imm_memlog.range_tombstones)
+    });
+    let imm_memlogs_entry_iters =
+        merging::drop_range_deleted(vec![memlog_range_tombstones], imm_memlogs_entry_iters);
 
-    merging::merge_differently_typed_entry_iters(memlog_entry_iter, sstables_entry_iter)
+    let newer_range_tombstones = [memlog_range_tombstones]
+        .into_iter()
+        .chain(
+            imm_memlogs
+                .iter()
+                .map(|imm_memlog| &imm_memlog.range_tombstones),
+        )
+        .collect::<Vec<_>>();
+    let runs_entry_iter = merge_runs(newer_range_tombstones, runs, k_lo, k_hi).map(Entry::Own);
+
+    let older_entry_iter = merging::merge_entry_iters(
+        imm_memlogs_entry_iters
+            .map(Either::Left)
+            .chain([Either::Right(runs_entry_iter)]),
+    );
+
+    merging::merge_differently_typed_entry_iters(memlog_entry_iter, older_entry_iter)
 }
@@ -1,4 +1,7 @@
+pub mod compaction_policy;
 mod lsm_tree;
 pub mod merging;
+mod sorted_run;
 
 pub use lsm_tree::LSMTree;
+pub use sorted_run::SortedRun;
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use pancake_engine_common::{RangeTombstones, SSTable};
+use pancake_types::types::{Deser, PartialOrdSer, Ser};
+use std::cmp::Ord;
+
+/// A sequence of [`SSTable`]s with disjoint key ranges, ordered by key.
+///
+/// Hence any key is found in at most one of the [`SSTable`]s, and the run as a whole is a sorted dictionary.
+///
+/// The run's range tombstones are the union of those of its [`SSTable`]s.
+pub struct SortedRun<K, V> {
+    sstables: Vec<SSTable<K, V>>,
+    range_tombstones: RangeTombstones<K>,
+}
+
+impl<K, V> From<Vec<SSTable<K, V>>> for SortedRun<K, V>
+where
+    K: Deser + Ord + Clone,
+    V: Deser,
+{
+    fn from(sstables: Vec<SSTable<K, V>>) -> Self {
+        let mut range_tombstones = RangeTombstones::default();
+        for sst in sstables.iter() {
+            range_tombstones.extend_from(sst.range_tombstones());
+        }
+        Self {
+            sstables,
+            range_tombstones,
+        }
+    }
+}
+
+impl<K, V> SortedRun<K, V>
+where
+    K: Deser + Ord + Clone,
+    V: Deser,
+{
+    /// @return Whether `sst` may be appended while keeping the key ranges disjoint and ordered.
+    ///     An [`SSTable`] that has range tombstones always begins a run,
+    ///     lest they cover the entries of the older [`SSTable`]s in the run.
+    pub fn can_append(&self, sst: &SSTable<K, V>) -> bool {
+        if sst.range_tombstones().is_empty() == false {
+            return false;
+        }
+
+        let prev_max_k = self
+            .sstables
+            .iter()
+            .rev()
+            .find_map(|prev_sst| prev_sst.key_bounds().map(|(_, max_k)| max_k));
+        match (prev_max_k, sst.key_bounds()) {
+            (Some(prev_max_k), Some((min_k, _))) => prev_max_k < min_k,
+            _ => true,
+        }
+    }
+
+    pub fn push(&mut self, sst: SSTable<K, V>) {
+        self.range_tombstones.extend_from(sst.range_tombstones());
+        self.sstables.push(sst);
+    }
+
+    pub fn range_tombstones(&self) -> &RangeTombstones<K> {
+        &self.range_tombstones
+    }
+
+    pub fn data_len(&self) -> u64 {
+        self.sstables.iter().map(SSTable::data_len).sum()
+    }
+
+    pub fn get_one<Q>(&self, k: &Q) -> Option<Result<(K, V)>>
+    where
+        K: PartialOrdSer<Q>,
+        Q: Ser,
+    {
+        self.sstables.iter().find_map(|sst| sst.get_one(k))
+    }
+
+    /// Each [`SSTable`] skips reading its file if its key range is out of the range.
+    pub fn get_range<'a, Q>(
+        &'a self,
+        k_lo: Option<&'a Q>,
+        k_hi: Option<&'a Q>,
+    ) -> impl 'a + Iterator<Item = Result<(K, V)>>
+    where
+        K: PartialOrdSer<Q>,
+    {
+        self.sstables
+            .iter()
+            .flat_map(move |sst| sst.get_range(k_lo, k_hi))
+    }
+
+    pub fn remove_files(&self) -> Result<()> {
+        for sst in self.sstables.iter() {
+            sst.remove_file()?;
+        }
+        Ok(())
+    }
+}
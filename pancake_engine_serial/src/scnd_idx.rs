@@ -1,27 +1,31 @@
-use crate::lsm::LSMTree;
+use crate::fc_worker::FCJob;
+use crate::lsm::{compaction_policy, LSMTree};
 use anyhow::Result;
-use pancake_engine_common::{fs_utils, Entry};
-use pancake_types::types::{PKShared, PVShared, SVPKShared, SubValue, SubValueSpec};
+use pancake_engine_common::{fs_utils, EngineOptions, Entry};
+use pancake_types::{
+    serde::{self, OptDatum},
+    types::{PKShared, PVShared, SVPKShared, ScndIdxSpec, SubValue},
+};
 use std::fs::OpenOptions;
 use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
 
 const SPEC_FILE_NAME: &str = "sv_spec.txt";
 const LSM_DIR_NAME: &str = "lsm";
 
 /// A secondary index is an abstraction of a sorted dictionary mapping
-/// `(sub-portion of value , primary key) : value`.
+/// `(sub-portion of value , primary key) : projection of value`.
 ///
-/// Clients may query for `(primary key, value)` entries based on bounds
+/// Clients may query for `(primary key, projection of value)` entries based on bounds
 /// over `sub-portion of value`.
 ///
-/// Each instance of [`SecondaryIndex`] is defined by a [`SubValueSpec`],
+/// Each instance of [`SecondaryIndex`] is defined by a [`ScndIdxSpec`],
 /// which specifies the `sub-portion of value` that this [`SecondaryIndex`]
-/// indexes on.
+/// indexes on, as well as the `projection of value`.
 pub struct SecondaryIndex {
     dir_path: PathBuf,
-    spec: Arc<SubValueSpec>,
+    spec: Arc<ScndIdxSpec>,
     lsm: LSMTree<SVPKShared, PVShared>,
 }
 
@@ -33,16 +37,25 @@ impl SecondaryIndex {
         scnd_idx_dir_path.as_ref().join(LSM_DIR_NAME)
     }
 
-    pub fn load<P: AsRef<Path>>(scnd_idx_dir_path: P) -> Result<Self> {
+    pub fn load<P: AsRef<Path>>(
+        scnd_idx_dir_path: P,
+        opts: Arc<EngineOptions>,
+        fc_jobs_tx: mpsc::Sender<FCJob>,
+    ) -> Result<Self> {
         let spec_file_path = Self::spec_file_path(&scnd_idx_dir_path);
         let lsm_dir_path = Self::lsm_dir_path(&scnd_idx_dir_path);
 
         let spec_file = fs_utils::open_file(spec_file_path, OpenOptions::new().read(true))?;
         let mut spec_reader = BufReader::new(spec_file);
-        let spec = SubValueSpec::deser(&mut spec_reader)?;
+        let spec = ScndIdxSpec::deser(&mut spec_reader)?;
         let spec = Arc::new(spec);
 
-        let lsm = LSMTree::load_or_new(lsm_dir_path)?;
+        let lsm = LSMTree::load_or_new(
+            lsm_dir_path,
+            compaction_policy::from_opts(&opts),
+            opts,
+            fc_jobs_tx,
+        )?;
 
         Ok(Self {
             dir_path: scnd_idx_dir_path.as_ref().into(),
@@ -53,8 +66,10 @@ impl SecondaryIndex {
 
     pub fn new<P: AsRef<Path>>(
         scnd_idx_dir_path: P,
-        spec: Arc<SubValueSpec>,
+        spec: Arc<ScndIdxSpec>,
         prim_lsm: &LSMTree<PKShared, PVShared>,
+        opts: Arc<EngineOptions>,
+        fc_jobs_tx: mpsc::Sender<FCJob>,
     ) -> Result<Self> {
         let spec_file_path = Self::spec_file_path(&scnd_idx_dir_path);
         let lsm_dir_path = Self::lsm_dir_path(&scnd_idx_dir_path);
@@ -68,69 +83,132 @@ impl SecondaryIndex {
         spec.ser(&mut spec_writer)?;
         spec_writer.flush()?;
 
-        let mut scnd_lsm = LSMTree::load_or_new(&lsm_dir_path)?;
-        for entry in prim_lsm.get_whole_range() {
-            let (_pk, pv) = entry.try_borrow()?;
-            if let Some(sv) = spec.extract(pv) {
-                let (pk, pv) = entry.into_owned_kv()?;
-                let svpk = SVPKShared { sv, pk };
-                scnd_lsm.put(svpk, Some(pv))?;
+        let mut scnd_lsm = LSMTree::load_or_new(
+            &lsm_dir_path,
+            compaction_policy::from_opts(&opts),
+            opts,
+            fc_jobs_tx,
+        )?;
+        let now = serde::now_micros();
+        for entry in prim_lsm.get_range_optdat(None::<&PKShared>, None::<&PKShared>) {
+            let (pk, optdat_pv) = entry.try_borrow()?;
+            let pv = match optdat_pv.as_live(now) {
+                None => continue,
+                Some(pv) => pv,
+            };
+            let svs = spec.extract(pv);
+            if svs.is_empty() == false {
+                let proj_pv = spec.project(pv);
+                for sv in svs {
+                    let svpk = SVPKShared { sv, pk: pk.clone() };
+                    let optdat = OptDatum::new_with_expiry(proj_pv.clone(), optdat_pv.expires_at());
+                    scnd_lsm.put(svpk, optdat)?;
+                }
             }
         }
 
-        Ok(Self {
+        let scnd_idx = Self {
             dir_path: scnd_idx_dir_path.as_ref().into(),
             spec,
             lsm: scnd_lsm,
-        })
+        };
+
+        if let Err(e) = scnd_idx.check_unique_all() {
+            scnd_idx.remove_dir()?;
+            return Err(e);
+        }
+
+        Ok(scnd_idx)
+    }
+
+    /// @return Err of [`UniquenessViolation`](pancake_types::types::UniquenessViolation),
+    ///     if the index is unique and any two entries share a sub-value.
+    fn check_unique_all(&self) -> Result<()> {
+        if self.spec.is_unique == false {
+            return Ok(());
+        }
+
+        /* Entries are sorted by sub-value. Hence entries sharing a sub-value are adjacent. */
+        let mut prev_svpk: Option<SVPKShared> = None;
+        for entry in self.lsm.get_whole_range() {
+            let svpk = entry.into_owned_k()?;
+            if let Some(prev_svpk) = prev_svpk.as_ref() {
+                if prev_svpk.sv == svpk.sv {
+                    self.spec.check_unique(&svpk.sv, &svpk.pk, &prev_svpk.pk)?;
+                }
+            }
+            prev_svpk = Some(svpk);
+        }
+
+        Ok(())
     }
 
-    pub fn remove_dir(&self) -> Result<()> {
+    /// Waits for the index's in-flight flushing or compaction job, if any, before removing the dir.
+    pub fn remove_dir(mut self) -> Result<()> {
+        self.lsm.abandon_fc_job();
         fs_utils::remove_dir_all(&self.dir_path)?;
         Ok(())
     }
 
-    pub fn spec(&self) -> &Arc<SubValueSpec> {
+    pub fn spec(&self) -> &Arc<ScndIdxSpec> {
         &self.spec
     }
 
+    /// @return Err of [`UniquenessViolation`](pancake_types::types::UniquenessViolation),
+    ///     if the index is unique and the put would index a sub-value under both `pk` and another primary key.
+    pub fn check_unique(
+        &self,
+        pk: &PKShared,
+        old_pv: Option<&PVShared>,
+        new_pv: Option<&PVShared>,
+    ) -> Result<()> {
+        if self.spec.is_unique == false {
+            return Ok(());
+        }
+
+        let (_old_svs, new_svs) = self.spec.extract_delta(old_pv, new_pv);
+        for new_sv in new_svs {
+            for entry in self.get_range(Some(&new_sv), Some(&new_sv)) {
+                let (other_pk, _) = entry.try_borrow()?;
+                self.spec.check_unique(&new_sv, pk, other_pk)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// @arg old_expires_at, new_expires_at: The expiry times of `old_pv` and `new_pv`, if they're expiring.
     pub fn put(
         &mut self,
         pk: &PKShared,
         old_pv: Option<&PVShared>,
+        old_expires_at: Option<i64>,
         new_pv: Option<&PVShared>,
+        new_expires_at: Option<i64>,
     ) -> Result<()> {
-        let old_sv = old_pv.and_then(|old_pv| self.spec.extract(old_pv));
-        let new_sv = new_pv.and_then(|new_pv| self.spec.extract(new_pv));
-
-        // Assign old_sv to be Some iff we need to tombstone the old entry.
-        // Assign new_sv to be Some iff we need to put the new entry.
-        let (old_sv, new_sv) = match (old_sv, new_sv) {
-            (Some(old_sv), Some(new_sv)) => {
-                if old_sv != new_sv {
-                    (Some(old_sv), Some(new_sv))
-                } else if old_pv != new_pv {
-                    (None, Some(new_sv))
-                } else {
-                    (None, None)
-                }
-            }
-            pair => pair,
-        };
+        let (old_svs, mut new_svs) = self.spec.extract_delta(old_pv, new_pv);
+        if old_expires_at != new_expires_at {
+            // Even if the PV is unchanged, every entry needs to be updated to the new expiry.
+            (_, new_svs) = self.spec.extract_delta(None, new_pv);
+        }
 
-        if let Some(old_sv) = old_sv {
+        for old_sv in old_svs {
             let svpk = SVPKShared {
                 sv: old_sv,
                 pk: pk.clone(),
             };
-            self.lsm.put(svpk, None)?;
+            self.lsm.put(svpk, OptDatum::Tombstone)?;
         }
-        if let Some(new_sv) = new_sv {
-            let svpk = SVPKShared {
-                sv: new_sv,
-                pk: pk.clone(),
-            };
-            self.lsm.put(svpk, new_pv.cloned())?;
+        if let Some(new_pv) = new_pv {
+            let new_proj_pv = self.spec.project(new_pv);
+            for new_sv in new_svs {
+                let svpk = SVPKShared {
+                    sv: new_sv,
+                    pk: pk.clone(),
+                };
+                let optdat = OptDatum::new_with_expiry(new_proj_pv.clone(), new_expires_at);
+                self.lsm.put(svpk, optdat)?;
+            }
         }
 
         Ok(())
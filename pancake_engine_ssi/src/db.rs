@@ -1,10 +1,10 @@
 use crate::{
     db_state::DbState,
     lsm::{unit::CommitVer, ListVer, LsmDir, LsmState},
-    opers::{fc::FlushingAndCompactionWorker, sicr::ScndIdxCreationsDir},
+    opers::{fc::FlushingAndCompactionWorker, sicr::ScndIdxCreationsDir, txn::SsiState},
 };
 use anyhow::Result;
-use pancake_engine_common::fs_utils;
+use pancake_engine_common::{fs_utils, EngineOptions};
 use shorthand::ShortHand;
 use std::fs::File;
 use std::path::Path;
@@ -12,22 +12,23 @@ use std::sync::Arc;
 use tokio::sync::{mpsc, watch, Mutex, RwLock};
 
 const SCND_IDXS_STATE_FILE_NAME: &str = "scnd_idxs_state.txt";
+const CONSTRAINTS_STATE_FILE_NAME: &str = "constraints_state.txt";
 const LSM_DIR_NAME: &str = "lsm";
 const ALL_SCND_IDX_CREATION_JOBS_DIR_NAME: &str = "scnd_idx_creation";
 
-/// This capacity is exaggeratedly small, in order to observe effects of lost messages.
-/// In the future, we'll allow setting it from an env var.
-const FC_ABLE_COMMIT_VERS_CAPACITY: usize = 5;
-
 #[derive(ShortHand)]
 #[shorthand(visibility("pub(in crate)"))]
 pub struct DB {
     _lock_dir: File,
 
+    opts: EngineOptions,
+
     db_state: RwLock<DbState>,
 
     lsm_dir: LsmDir,
     lsm_state: Mutex<LsmState>,
+    /// Locked only while `lsm_state` is locked.
+    ssi_state: Mutex<SsiState>,
 
     si_cr_dir: ScndIdxCreationsDir,
     si_cr_mutex: Mutex<()>,
@@ -38,37 +39,46 @@ pub struct DB {
 }
 
 impl DB {
+    /// @return Err if `opts` are invalid. See [`EngineOptions::validate()`].
     pub fn load_or_new<P: AsRef<Path>>(
         db_dir_path: P,
+        opts: EngineOptions,
     ) -> Result<(Arc<Self>, FlushingAndCompactionWorker)> {
+        opts.validate()?;
+
         let db_dir_path = db_dir_path.as_ref();
 
         fs_utils::create_dir_all(db_dir_path)?;
         let lock_dir = fs_utils::lock_file(db_dir_path)?;
 
         let si_state_file_path = db_dir_path.join(SCND_IDXS_STATE_FILE_NAME);
+        let c_state_file_path = db_dir_path.join(CONSTRAINTS_STATE_FILE_NAME);
         let lsm_dir_path = db_dir_path.join(LSM_DIR_NAME);
         let si_cr_dir_path = db_dir_path.join(ALL_SCND_IDX_CREATION_JOBS_DIR_NAME);
 
-        let db_state = DbState::load_or_new(si_state_file_path)?;
+        let db_state = DbState::load_or_new(si_state_file_path, c_state_file_path)?;
 
-        let (lsm_dir, lsm_state) = LsmDir::load_or_new(lsm_dir_path)?;
+        let (lsm_dir, lsm_state) =
+            LsmDir::load_or_new(lsm_dir_path, opts.commit_ver_retention_count)?;
 
         let si_cr_dir = ScndIdxCreationsDir::load_or_new(si_cr_dir_path)?;
         let si_cr_mutex = Mutex::new(());
 
         let (fc_able_commit_vers_tx, fc_able_commit_vers_rx) =
-            mpsc::channel(FC_ABLE_COMMIT_VERS_CAPACITY);
+            mpsc::channel(opts.fc_able_commit_vers_capacity);
         let (min_held_list_ver_tx, min_held_list_ver_rx) = watch::channel(ListVer::AT_BOOTUP);
         let (is_terminating_tx, is_terminating_rx) = watch::channel(());
 
         let db = Self {
             _lock_dir: lock_dir,
 
+            opts,
+
             db_state: RwLock::new(db_state),
 
             lsm_dir,
             lsm_state: Mutex::new(lsm_state),
+            ssi_state: Mutex::new(SsiState::default()),
 
             si_cr_dir,
             si_cr_mutex,
@@ -103,6 +113,14 @@ impl DB {
         });
     }
 
+    /// The commit versions that a read-only txn can read as of. See [`Txn::begin_read_only_as_of()`](crate::Txn::begin_read_only_as_of).
+    ///
+    /// @return From older to newer. The last one is the current version.
+    pub async fn retained_commit_vers(&self) -> Vec<CommitVer> {
+        let lsm_state = self.lsm_state.lock().await;
+        lsm_state.retained_commit_vers()
+    }
+
     pub async fn terminate(&self) {
         {
             let mut db_state = self.db_state.write().await;
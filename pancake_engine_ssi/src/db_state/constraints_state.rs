@@ -0,0 +1,96 @@
+use anyhow::{anyhow, Result};
+use pancake_engine_common::fs_utils;
+use pancake_types::{io_utils, types::ValueConstraint};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{BufReader, BufWriter, Cursor, Read, Write};
+use std::path::Path;
+use std::str;
+use std::sync::Arc;
+
+mod test;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ConstraintState {
+    /// Whether all values that existed at the time of the constraint's creation were found to satisfy it.
+    /// Values written after the creation are checked regardless.
+    pub is_validated: bool,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct ConstraintsState {
+    pub(super) constraints: HashMap<Arc<ValueConstraint>, ConstraintState>,
+}
+
+#[allow(clippy::write_with_newline)] // We must be consistent re: '\n' vs '\r\n'.
+impl ConstraintsState {
+    pub fn new_empty() -> Self {
+        Self {
+            constraints: Default::default(),
+        }
+    }
+
+    fn do_ser<W: Write>(&self, w: &mut BufWriter<W>) -> Result<()> {
+        for (constraint, constraint_state) in self.constraints.iter() {
+            /* constraint */
+            constraint.ser(w)?;
+            write!(w, "\n")?;
+
+            /* constraint_state */
+            write!(
+                w,
+                "{}\n",
+                if constraint_state.is_validated {
+                    'T'
+                } else {
+                    'F'
+                }
+            )?;
+        }
+
+        Ok(())
+    }
+    fn do_deser<R: Read>(r: &mut BufReader<R>) -> Result<Self> {
+        let mut buf = vec![];
+
+        let mut constraints = HashMap::new();
+        loop {
+            buf.clear();
+
+            /* constraint */
+            io_utils::read_until_then_trim(r, b'\n', &mut buf)?;
+            if buf.is_empty() {
+                break;
+            }
+            let mut line_reader = BufReader::new(Cursor::new(&buf));
+            let constraint = ValueConstraint::deser(&mut line_reader)?;
+
+            /* constraint_state */
+            buf.clear();
+            io_utils::read_until_then_trim(r, b'\n', &mut buf)?;
+            let is_validated = match str::from_utf8(&buf)? {
+                "T" => true,
+                "F" => false,
+                "" => return Err(anyhow!("constraint without constraint_state.")),
+                _ => return Err(anyhow!("Invalid is_validated")),
+            };
+
+            constraints.insert(Arc::new(constraint), ConstraintState { is_validated });
+        }
+
+        Ok(Self { constraints })
+    }
+    pub fn ser<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = fs_utils::open_file(path, OpenOptions::new().create(true).write(true))?;
+        file.set_len(0)?;
+        let mut w = BufWriter::new(file);
+        self.do_ser(&mut w)?;
+        w.flush()?;
+        Ok(())
+    }
+    pub fn deser<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = fs_utils::open_file(path, OpenOptions::new().read(true))?;
+        let mut r = BufReader::new(file);
+        Self::do_deser(&mut r)
+    }
+}
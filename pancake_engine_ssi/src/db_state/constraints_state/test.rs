@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod test {
+    use super::super::*;
+    use pancake_types::serde::DatumType;
+    use pancake_types::types::{MemberKey, ValueShape};
+
+    fn ser_then_deser(orig: ConstraintsState) {
+        let mut buf = vec![];
+
+        orig.do_ser(&mut BufWriter::new(Cursor::new(&mut buf)))
+            .unwrap();
+
+        let deserialized =
+            ConstraintsState::do_deser(&mut BufReader::new(Cursor::new(&buf))).unwrap();
+
+        assert_eq!(orig, deserialized);
+    }
+
+    #[test]
+    fn ser_then_deser_examples() {
+        /* empty */
+        ser_then_deser(ConstraintsState::new_empty());
+
+        /* multiple constraints */
+        let mut constraints = HashMap::new();
+        constraints.insert(
+            Arc::new(ValueConstraint {
+                member_path: vec![],
+                shape: ValueShape::MinTupleArity(2),
+            }),
+            ConstraintState { is_validated: true },
+        );
+        constraints.insert(
+            Arc::new(ValueConstraint {
+                member_path: vec![MemberKey::Idx(0), MemberKey::from("th,r\nee")],
+                shape: ValueShape::Type(DatumType::I64),
+            }),
+            ConstraintState {
+                is_validated: false,
+            },
+        );
+        ser_then_deser(ConstraintsState { constraints });
+    }
+
+    #[test]
+    fn state_without_constraint_state() {
+        let content = "a2;\n";
+        let mut r = BufReader::new(Cursor::new(content.as_bytes()));
+        assert!(ConstraintsState::do_deser(&mut r).is_err());
+    }
+}
@@ -1,7 +1,7 @@
-use crate::db_state::{ScndIdxNum, ScndIdxState, ScndIdxsState};
+use crate::db_state::{ConstraintState, ConstraintsState, ScndIdxNum, ScndIdxState, ScndIdxsState};
 use anyhow::{anyhow, Context, Result};
 use pancake_engine_common::fs_utils;
-use pancake_types::types::SubValueSpec;
+use pancake_types::types::{ScndIdxSpec, ValueConstraint};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -10,11 +10,17 @@ pub struct DbState {
     scnd_idxs_state: ScndIdxsState,
     scnd_idxs_state_file_path: PathBuf,
 
+    constraints_state: ConstraintsState,
+    constraints_state_file_path: PathBuf,
+
     pub is_terminating: bool,
 }
 
 impl DbState {
-    pub fn load_or_new<P: AsRef<Path>>(scnd_idxs_state_file_path: P) -> Result<Self> {
+    pub fn load_or_new<P: AsRef<Path>, Q: AsRef<Path>>(
+        scnd_idxs_state_file_path: P,
+        constraints_state_file_path: Q,
+    ) -> Result<Self> {
         let sis_path = scnd_idxs_state_file_path.as_ref();
         let scnd_idxs_state;
         if sis_path.exists() {
@@ -32,24 +38,41 @@ impl DbState {
             scnd_idxs_state.ser(sis_path)?;
         }
 
+        let cs_path = constraints_state_file_path.as_ref();
+        let constraints_state;
+        if cs_path.exists() {
+            constraints_state = ConstraintsState::deser(cs_path).context(format!("{cs_path:?}"))?;
+            for (constraint, c_state) in constraints_state.constraints.iter() {
+                if c_state.is_validated == false {
+                    return Err(anyhow!("Prior constraint creation never completed for {constraint:?}. You should remove this constraint's info manually from {cs_path:?}", ));
+                }
+            }
+        } else {
+            constraints_state = ConstraintsState::new_empty();
+            constraints_state.ser(cs_path)?;
+        }
+
         Ok(Self {
             scnd_idxs_state,
             scnd_idxs_state_file_path: sis_path.into(),
 
+            constraints_state,
+            constraints_state_file_path: cs_path.into(),
+
             is_terminating: false,
         })
     }
 
-    pub fn scnd_idxs(&self) -> &HashMap<Arc<SubValueSpec>, ScndIdxState> {
+    pub fn scnd_idxs(&self) -> &HashMap<Arc<ScndIdxSpec>, ScndIdxState> {
         &self.scnd_idxs_state.scnd_idxs
     }
 
-    pub fn get_scnd_idx_defn(&self, sv_spec: &SubValueSpec) -> Option<ScndIdxState> {
+    pub fn get_scnd_idx_defn(&self, sv_spec: &ScndIdxSpec) -> Option<ScndIdxState> {
         self.scnd_idxs_state.scnd_idxs.get(sv_spec).cloned()
     }
     pub fn define_new_scnd_idx(
         &mut self,
-        sv_spec: &Arc<SubValueSpec>,
+        sv_spec: &Arc<ScndIdxSpec>,
     ) -> Result<ScndIdxNewDefnResult> {
         match self.get_scnd_idx_defn(sv_spec) {
             Some(si_state) => return Ok(ScndIdxNewDefnResult::Existent(si_state)),
@@ -67,7 +90,7 @@ impl DbState {
         Ok(ScndIdxNewDefnResult::DidDefineNew(scnd_idx_num))
     }
 
-    pub fn set_scnd_idx_as_readable(&mut self, sv_spec: &SubValueSpec) -> Result<()> {
+    pub fn set_scnd_idx_as_readable(&mut self, sv_spec: &ScndIdxSpec) -> Result<()> {
         let sis = &mut self.scnd_idxs_state;
         match sis.scnd_idxs.get_mut(sv_spec) {
             None => return Err(anyhow!("No state for {sv_spec:?}")),
@@ -79,7 +102,16 @@ impl DbState {
         }
     }
 
-    pub fn can_scnd_idx_be_removed(&self, sv_spec: &SubValueSpec) -> ScndIdxRemovalResult {
+    /// Removes a secondary index regardless of whether it is readable.
+    pub fn undefine_scnd_idx(&mut self, sv_spec: &ScndIdxSpec) -> Result<()> {
+        let sis = &mut self.scnd_idxs_state;
+        if sis.scnd_idxs.remove(sv_spec).is_some() {
+            sis.ser(&self.scnd_idxs_state_file_path)?;
+        }
+        Ok(())
+    }
+
+    pub fn can_scnd_idx_be_removed(&self, sv_spec: &ScndIdxSpec) -> ScndIdxRemovalResult {
         let sis = &self.scnd_idxs_state;
         match sis.scnd_idxs.get(sv_spec) {
             None => ScndIdxRemovalResult::DoesNotExist,
@@ -89,7 +121,7 @@ impl DbState {
             Some(_) => ScndIdxRemovalResult::Deletable,
         }
     }
-    pub fn remove_scnd_idx(&mut self, sv_spec: &SubValueSpec) -> Result<ScndIdxRemovalResult> {
+    pub fn remove_scnd_idx(&mut self, sv_spec: &ScndIdxSpec) -> Result<ScndIdxRemovalResult> {
         let eligibility = self.can_scnd_idx_be_removed(sv_spec);
         match eligibility {
             ScndIdxRemovalResult::DoesNotExist | ScndIdxRemovalResult::CreationInProgress => {}
@@ -103,6 +135,81 @@ impl DbState {
     }
 }
 
+/* Constraints. */
+impl DbState {
+    pub fn constraints(&self) -> &HashMap<Arc<ValueConstraint>, ConstraintState> {
+        &self.constraints_state.constraints
+    }
+
+    pub fn get_constraint_defn(&self, constraint: &ValueConstraint) -> Option<ConstraintState> {
+        self.constraints_state.constraints.get(constraint).cloned()
+    }
+    /// The new constraint is not validated yet. Regardless, it applies to all writes from now on.
+    pub fn define_new_constraint(
+        &mut self,
+        constraint: &Arc<ValueConstraint>,
+    ) -> Result<ConstraintNewDefnResult> {
+        match self.get_constraint_defn(constraint) {
+            Some(c_state) => return Ok(ConstraintNewDefnResult::Existent(c_state)),
+            None => {}
+        }
+
+        let cs = &mut self.constraints_state;
+        let c_state = ConstraintState {
+            is_validated: false,
+        };
+        cs.constraints.insert(Arc::clone(constraint), c_state);
+        cs.ser(&self.constraints_state_file_path)?;
+        Ok(ConstraintNewDefnResult::DidDefineNew)
+    }
+
+    pub fn set_constraint_as_validated(&mut self, constraint: &ValueConstraint) -> Result<()> {
+        let cs = &mut self.constraints_state;
+        match cs.constraints.get_mut(constraint) {
+            None => return Err(anyhow!("No state for {constraint:?}")),
+            Some(c_state) => {
+                c_state.is_validated = true;
+                cs.ser(&self.constraints_state_file_path)?;
+                return Ok(());
+            }
+        }
+    }
+
+    /// Removes a constraint regardless of whether it has been validated.
+    pub fn undefine_constraint(&mut self, constraint: &ValueConstraint) -> Result<()> {
+        let cs = &mut self.constraints_state;
+        if cs.constraints.remove(constraint).is_some() {
+            cs.ser(&self.constraints_state_file_path)?;
+        }
+        Ok(())
+    }
+
+    pub fn can_constraint_be_removed(
+        &self,
+        constraint: &ValueConstraint,
+    ) -> ConstraintRemovalResult {
+        match self.constraints_state.constraints.get(constraint) {
+            None => ConstraintRemovalResult::DoesNotExist,
+            Some(c_state) if c_state.is_validated != true => {
+                ConstraintRemovalResult::CreationInProgress
+            }
+            Some(_) => ConstraintRemovalResult::Deletable,
+        }
+    }
+    pub fn remove_constraint(
+        &mut self,
+        constraint: &ValueConstraint,
+    ) -> Result<ConstraintRemovalResult> {
+        let eligibility = self.can_constraint_be_removed(constraint);
+        match eligibility {
+            ConstraintRemovalResult::DoesNotExist | ConstraintRemovalResult::CreationInProgress => {
+            }
+            ConstraintRemovalResult::Deletable => self.undefine_constraint(constraint)?,
+        }
+        Ok(eligibility)
+    }
+}
+
 pub enum ScndIdxNewDefnResult {
     Existent(ScndIdxState),
     DidDefineNew(ScndIdxNum),
@@ -113,3 +220,14 @@ pub enum ScndIdxRemovalResult {
     CreationInProgress,
     Deletable,
 }
+
+pub enum ConstraintNewDefnResult {
+    Existent(ConstraintState),
+    DidDefineNew,
+}
+
+pub enum ConstraintRemovalResult {
+    DoesNotExist,
+    CreationInProgress,
+    Deletable,
+}
@@ -1,6 +1,8 @@
+mod constraints_state;
 #[allow(clippy::module_inception)]
 mod db_state;
 mod scnd_idxs_state;
 
+pub use constraints_state::*;
 pub use db_state::*;
 pub use scnd_idxs_state::*;
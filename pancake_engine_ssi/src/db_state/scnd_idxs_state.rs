@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Context, Result};
 use pancake_engine_common::fs_utils::{self, PathNameNum};
-use pancake_types::{io_utils, types::SubValueSpec};
+use pancake_types::{io_utils, types::ScndIdxSpec};
 use std::collections::HashMap;
 use std::fs::OpenOptions;
 use std::io::{BufReader, BufWriter, Cursor, Read, Write};
@@ -83,7 +83,7 @@ impl ScndIdxState {
 
 #[derive(PartialEq, Eq, Debug)]
 pub struct ScndIdxsState {
-    pub(super) scnd_idxs: HashMap<Arc<SubValueSpec>, ScndIdxState>,
+    pub(super) scnd_idxs: HashMap<Arc<ScndIdxSpec>, ScndIdxState>,
     pub(super) next_scnd_idx_num: ScndIdxNum,
 }
 
@@ -131,7 +131,7 @@ impl ScndIdxsState {
                 break;
             }
             let mut line_reader = BufReader::new(Cursor::new(&buf));
-            let sv_spec = SubValueSpec::deser(&mut line_reader)?;
+            let sv_spec = ScndIdxSpec::deser(&mut line_reader)?;
 
             /* scnd_idx_state */
             let scnd_idx_state = ScndIdxState::do_deser(r)?
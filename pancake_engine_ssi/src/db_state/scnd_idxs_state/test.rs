@@ -2,6 +2,7 @@
 mod test {
     use super::super::*;
     use pancake_types::serde::DatumType;
+    use pancake_types::types::{MemberKey, ScndIdxProjection, SubValueSpec};
     use rand::prelude::*;
 
     #[test]
@@ -26,16 +27,49 @@ mod test {
         assert_eq!(orig, deserialized);
     }
 
-    fn gen_sv_spec_whole() -> Arc<SubValueSpec> {
-        Arc::new(SubValueSpec {
-            member_idxs: vec![],
+    fn gen_sv_spec_whole() -> Arc<ScndIdxSpec> {
+        Arc::new(ScndIdxSpec::from(SubValueSpec {
+            member_path: vec![],
             datum_type: DatumType::I64,
-        })
+        }))
     }
-    fn gen_sv_spec_partial() -> Arc<SubValueSpec> {
-        Arc::new(SubValueSpec {
-            member_idxs: vec![6, 5, 4, 3],
+    fn gen_sv_spec_partial() -> Arc<ScndIdxSpec> {
+        Arc::new(ScndIdxSpec::from(SubValueSpec {
+            member_path: vec![
+                MemberKey::Idx(6),
+                MemberKey::from("five"),
+                MemberKey::Idx(4),
+                MemberKey::from("th,r\nee"),
+            ],
             datum_type: DatumType::I64,
+        }))
+    }
+    fn gen_sv_spec_composite() -> Arc<ScndIdxSpec> {
+        Arc::new(ScndIdxSpec {
+            columns: vec![
+                SubValueSpec {
+                    member_path: vec![MemberKey::from("t\tenant")],
+                    datum_type: DatumType::Str,
+                },
+                SubValueSpec {
+                    member_path: vec![MemberKey::Each, MemberKey::Idx(1)],
+                    datum_type: DatumType::Timestamp,
+                },
+            ],
+            projection: ScndIdxProjection::Whole,
+            filter: None,
+            is_unique: false,
+        })
+    }
+    fn gen_sv_spec_projected() -> Arc<ScndIdxSpec> {
+        Arc::new(ScndIdxSpec {
+            columns: vec![SubValueSpec::whole(DatumType::Str)],
+            projection: ScndIdxProjection::Columns(vec![SubValueSpec {
+                member_path: vec![MemberKey::from("na\tme")],
+                datum_type: DatumType::Str,
+            }]),
+            filter: None,
+            is_unique: false,
         })
     }
 
@@ -55,7 +89,12 @@ mod test {
     #[test]
     fn ser_then_deser_arbitrary_examples() {
         /* setup */
-        let gen_sv_fns = [gen_sv_spec_whole, gen_sv_spec_partial];
+        let gen_sv_fns = [
+            gen_sv_spec_whole,
+            gen_sv_spec_partial,
+            gen_sv_spec_composite,
+            gen_sv_spec_projected,
+        ];
         let gen_si_state_fns = [gen_si_state_sample1, gen_si_state_sample2];
         let mut rand_rng = rand::thread_rng();
 
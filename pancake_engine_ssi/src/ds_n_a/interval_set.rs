@@ -34,6 +34,23 @@ impl<T> IntervalSet<T> {
         self.itvs.clear();
         self.is_merged = true;
     }
+
+    /// Maps each bound, e.g. from a borrowed to an owned type.
+    /// `f` must preserve the order of bounds.
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> IntervalSet<U> {
+        let itvs = self
+            .itvs
+            .iter()
+            .map(|itv| Interval {
+                lo_incl: itv.lo_incl.as_ref().map(&mut f),
+                hi_incl: itv.hi_incl.as_ref().map(&mut f),
+            })
+            .collect();
+        IntervalSet {
+            itvs,
+            is_merged: self.is_merged,
+        }
+    }
 }
 
 impl<T> IntervalSet<T>
@@ -89,6 +106,49 @@ pub struct MergedIntervalSet<'a, T> {
 }
 
 impl<'a, T> MergedIntervalSet<'a, T> {
+    /// @return Whether any interval overlaps the inclusive range from `lo_incl` to `hi_incl`.
+    ///     An incomparable pair of bounds is regarded as overlapping.
+    pub fn overlaps_with_range<P>(&self, lo_incl: &P, hi_incl: &P) -> bool
+    where
+        P: PartialOrd<T>,
+    {
+        self.itvset.itvs.iter().any(|itv| {
+            let is_itv_lo_ok = match itv.lo_incl.as_ref() {
+                None => true,
+                Some(itv_lo) => hi_incl.partial_cmp(itv_lo) != Some(Ordering::Less),
+            };
+            let is_itv_hi_ok = match itv.hi_incl.as_ref() {
+                None => true,
+                Some(itv_hi) => lo_incl.partial_cmp(itv_hi) != Some(Ordering::Greater),
+            };
+            is_itv_lo_ok && is_itv_hi_ok
+        })
+    }
+
+    /// @return Whether any interval overlaps any interval of `other`.
+    pub fn overlaps_with_itvs(&self, other: &MergedIntervalSet<T>) -> bool
+    where
+        T: Ord,
+    {
+        let is_before = |a: &Interval<T>, b: &Interval<T>| match (&a.hi_incl, &b.lo_incl) {
+            (Some(a_hi), Some(b_lo)) => a_hi < b_lo,
+            _ => false,
+        };
+
+        let mut a_iter = self.itvset.itvs.iter().peekable();
+        let mut b_iter = other.itvset.itvs.iter().peekable();
+        while let (Some(a), Some(b)) = (a_iter.peek(), b_iter.peek()) {
+            if is_before(a, b) {
+                a_iter.next();
+            } else if is_before(b, a) {
+                b_iter.next();
+            } else {
+                return true;
+            }
+        }
+        false
+    }
+
     pub fn overlaps_with<P, E>(&self, point_iter: impl Iterator<Item = P>) -> Result<bool, E>
     where
         P: TryPartialOrd<T, E>,
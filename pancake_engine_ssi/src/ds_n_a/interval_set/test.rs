@@ -185,4 +185,65 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn overlapping_range() {
+        let mut is = IntervalSet::<i32>::new();
+
+        let mis = is.merge();
+        assert!(mis.overlaps_with_range(&1, &3) == false);
+
+        add_helper(&mut is, Some(20), Some(30));
+        add_helper(&mut is, Some(40), Some(50));
+        let mis = is.merge();
+        assert!(mis.overlaps_with_range(&10, &19) == false);
+        assert!(mis.overlaps_with_range(&31, &39) == false);
+        assert!(mis.overlaps_with_range(&51, &60) == false);
+        assert!(mis.overlaps_with_range(&10, &20));
+        assert!(mis.overlaps_with_range(&30, &40));
+        assert!(mis.overlaps_with_range(&22, &28));
+        assert!(mis.overlaps_with_range(&10, &60));
+
+        add_helper(&mut is, Some(60), None);
+        let mis = is.merge();
+        assert!(mis.overlaps_with_range(&51, &59) == false);
+        assert!(mis.overlaps_with_range(&999, &1000));
+    }
+
+    #[test]
+    fn overlapping_itvs() {
+        let mut is_a = IntervalSet::<i32>::new();
+        let mut is_b = IntervalSet::<i32>::new();
+        assert!(is_a.merge().overlaps_with_itvs(&is_b.merge()) == false);
+
+        add_helper(&mut is_a, Some(20), Some(30));
+        add_helper(&mut is_a, Some(50), Some(60));
+        assert!(is_a.merge().overlaps_with_itvs(&is_b.merge()) == false);
+
+        add_helper(&mut is_b, Some(10), Some(19));
+        add_helper(&mut is_b, Some(31), Some(49));
+        assert!(is_a.merge().overlaps_with_itvs(&is_b.merge()) == false);
+        assert!(is_b.merge().overlaps_with_itvs(&is_a.merge()) == false);
+
+        add_helper(&mut is_b, Some(60), Some(70));
+        assert!(is_a.merge().overlaps_with_itvs(&is_b.merge()));
+        assert!(is_b.merge().overlaps_with_itvs(&is_a.merge()));
+
+        let mut is_c = IntervalSet::<i32>::new();
+        add_helper(&mut is_c, None, Some(19));
+        assert!(is_a.merge().overlaps_with_itvs(&is_c.merge()) == false);
+        add_helper(&mut is_c, Some(61), None);
+        assert!(is_a.merge().overlaps_with_itvs(&is_c.merge()) == false);
+        add_helper(&mut is_c, Some(40), Some(45));
+        assert!(is_a.merge().overlaps_with_itvs(&is_c.merge()) == false);
+        add_helper(&mut is_c, Some(25), Some(25));
+        assert!(is_a.merge().overlaps_with_itvs(&is_c.merge()));
+
+        let mut is_d = IntervalSet::<i32>::new();
+        add_helper(&mut is_d, None, None);
+        assert!(is_a.merge().overlaps_with_itvs(&is_d.merge()));
+
+        let is_e = is_a.map(|bound| bound * 10);
+        assert_content(&is_e, vec![(Some(200), Some(300)), (Some(500), Some(600))]);
+    }
 }
@@ -5,8 +5,11 @@ mod lsm;
 mod opers;
 
 pub use db::DB;
+pub use lsm::unit::CommitVer;
 pub use opers::{
     sicr::ScndIdxCreationJobErr,
     sidel::ScndIdxDeletionJobErr,
-    txn::{ClientCommitDecision, Txn},
+    txn::{ClientCommitDecision, IsolationLevel, Txn},
+    vccr::ConstraintCreationJobErr,
+    vcdel::ConstraintDeletionJobErr,
 };
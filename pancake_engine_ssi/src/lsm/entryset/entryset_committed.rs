@@ -1,11 +1,21 @@
-use pancake_engine_common::{Entry, ReadonlyMemLog, SSTable};
-use pancake_types::types::Deser;
+use crate::ds_n_a::interval_set::MergedIntervalSet;
+use anyhow::Result;
+use itertools::Either;
+use pancake_engine_common::{Entry, RangeTombstones, ReadonlyMemLog, SSTable};
+use pancake_types::types::{Deser, PartialOrdSer, Ser};
 use std::borrow::Borrow;
 use std::iter;
 
+/// Either an entry borrowed from a [`ReadonlyMemLog`], or an entry read from an [`SSTable`] with its value as a `V2`.
+pub type EntryAs<'a, K, V, V2> = Either<(&'a K, &'a V), Result<(K, V2)>>;
+
 pub enum CommittedEntrySet<K, V> {
     RMemLog(ReadonlyMemLog<K, V>),
-    SSTable(SSTable<K, V>),
+    /// A sorted run. The [`SSTable`]s have disjoint and ascending key ranges.
+    /// Each [`SSTable`] skips reading its file if its key range does not overlap the queried range.
+    ///
+    /// Along with the union of the [`SSTable`]s' range tombstones. See [`Self::from_sstables()`].
+    SSTables(Vec<SSTable<K, V>>, RangeTombstones<K>),
 }
 
 impl<K, V> CommittedEntrySet<K, V>
@@ -13,14 +23,53 @@ where
     K: Deser + Ord,
     V: Deser,
 {
+    pub fn from_sstables(sstables: Vec<SSTable<K, V>>) -> Self
+    where
+        K: Clone,
+    {
+        let mut range_tombstones = RangeTombstones::default();
+        for sstable in sstables.iter() {
+            range_tombstones.extend_from(sstable.range_tombstones());
+        }
+        Self::SSTables(sstables, range_tombstones)
+    }
+
+    /// They shadow older entrysets only.
+    pub fn range_tombstones(&self) -> &RangeTombstones<K> {
+        match self {
+            Self::RMemLog(r_memlog) => &r_memlog.range_tombstones,
+            Self::SSTables(_, range_tombstones) => range_tombstones,
+        }
+    }
+
     pub fn get_one<'a, Q>(&'a self, k: &'a Q) -> Option<Entry<'a, K, V>>
     where
-        K: Borrow<Q> + PartialOrd<Q>,
-        Q: Ord,
+        K: Borrow<Q> + PartialOrdSer<Q>,
+        Q: Ord + Ser,
     {
         match self {
             Self::RMemLog(r_memlog) => r_memlog.get_one(k).map(Entry::Ref),
-            Self::SSTable(sstable) => sstable.get_one(k).map(Entry::Own),
+            Self::SSTables(sstables, _) => sstables
+                .iter()
+                .find_map(|sstable| sstable.get_one(k))
+                .map(Entry::Own),
+        }
+    }
+
+    /// Like [`Self::get_one()`], but a value that is read from an [`SSTable`] is read as a `V2` instead of a `V`,
+    /// eg as its serialized bytes. A value in the [`ReadonlyMemLog`] is borrowed as is.
+    pub fn get_one_as<'a, Q, V2>(&'a self, k: &'a Q) -> Option<EntryAs<'a, K, V, V2>>
+    where
+        K: Borrow<Q> + PartialOrdSer<Q>,
+        Q: Ord + Ser,
+        V2: Deser,
+    {
+        match self {
+            Self::RMemLog(r_memlog) => r_memlog.get_one(k).map(Either::Left),
+            Self::SSTables(sstables, _) => sstables
+                .iter()
+                .find_map(|sstable| sstable.get_one_as(k))
+                .map(Either::Right),
         }
     }
 
@@ -30,7 +79,7 @@ where
         k_hi: Option<&'a Q>,
     ) -> impl Iterator<Item = Entry<'a, K, V>>
     where
-        K: PartialOrd<Q>,
+        K: PartialOrdSer<Q>,
     {
         let mut rml_iter = None;
         let mut sst_iter = None;
@@ -39,8 +88,11 @@ where
                 let iter = r_memlog.get_range(k_lo, k_hi).map(Entry::Ref);
                 rml_iter = Some(iter);
             }
-            Self::SSTable(sstable) => {
-                let iter = sstable.get_range(k_lo, k_hi).map(Entry::Own);
+            Self::SSTables(sstables, _) => {
+                let iter = sstables
+                    .iter()
+                    .flat_map(move |sstable| sstable.get_range(k_lo, k_hi))
+                    .map(Entry::Own);
                 sst_iter = Some(iter);
             }
         }
@@ -57,7 +109,15 @@ where
         iter::from_fn(ret_iter_fn)
     }
 
-    pub fn get_all_keys(&self) -> impl Iterator<Item = Entry<K, ()>> {
+    /// @return All keys of the MemLog, or all keys of those [`SSTable`]s whose key ranges overlap `itvs`.
+    ///     The other [`SSTable`]s are not read.
+    pub fn get_all_keys_of_overlapping_files<'a, T>(
+        &'a self,
+        itvs: &'a MergedIntervalSet<T>,
+    ) -> impl Iterator<Item = Entry<'a, K, ()>>
+    where
+        K: PartialOrd<T>,
+    {
         let mut rml_iter = None;
         let mut sst_iter = None;
         match self {
@@ -65,9 +125,14 @@ where
                 let iter = r_memlog.memtable.keys().map(|k| Entry::Ref((k, &())));
                 rml_iter = Some(iter);
             }
-            Self::SSTable(sstable) => {
-                let iter = sstable
-                    .get_all_keys()
+            Self::SSTables(sstables, _) => {
+                let iter = sstables
+                    .iter()
+                    .filter(|sstable| match sstable.key_bounds() {
+                        None => false,
+                        Some((min_k, max_k)) => itvs.overlaps_with_range(min_k, max_k),
+                    })
+                    .flat_map(|sstable| sstable.get_all_keys())
                     .map(|res_k| Entry::Own(res_k.map(|k| (k, ()))));
                 sst_iter = Some(iter);
             }
@@ -1,23 +1,31 @@
 use crate::lsm::entryset::CommittedEntrySet;
-use pancake_engine_common::{merging, Entry, WritableMemLog};
-use pancake_types::types::Deser;
-use std::cmp::{Ord, PartialOrd};
+use pancake_engine_common::{merging, Entry, RangeTombstones, WritableMemLog};
+use pancake_types::types::{Deser, PartialOrdSer};
+use std::cmp::Ord;
 
+/// The entries that are covered by range tombstones of newer entrysets are dropped.
+///
+/// @arg newer_range_tombstones: Those of the entrysets that are newer than all of `entrysets`.
 /// @arg entrysets: From newer to older. (Same as the convention in [`pancake_engine_common::merging`].)
 pub fn merge_committed_entrysets<'a, K, V, Q>(
-    entrysets: impl Iterator<Item = &'a CommittedEntrySet<K, V>>,
+    newer_range_tombstones: Vec<&'a RangeTombstones<K>>,
+    entrysets: impl 'a + Iterator<Item = &'a CommittedEntrySet<K, V>>,
     k_lo: Option<&'a Q>,
     k_hi: Option<&'a Q>,
 ) -> impl 'a + Iterator<Item = Entry<'a, K, V>>
 where
-    K: 'a + Deser + Ord + PartialOrd<Q>,
+    K: 'a + Ord + PartialOrdSer<Q>,
     V: 'a + Deser,
 {
-    let entry_iters = entrysets.map(move |entryset| entryset.get_range(k_lo, k_hi));
+    let entry_iters = entrysets
+        .map(move |entryset| (entryset.get_range(k_lo, k_hi), entryset.range_tombstones()));
+    let entry_iters = merging::drop_range_deleted(newer_range_tombstones, entry_iters);
 
     merging::merge_entry_iters(entry_iters)
 }
 
+/// The entries that are covered by range tombstones of newer entrysets, including the staging one, are dropped.
+///
 /// @arg entrysets: From newer to older. (Same as the convention in [`pancake_engine_common::merging`].)
 pub fn merge_txnlocal_and_committed_entrysets<'a, K, V, Q>(
     staging: Option<&'a WritableMemLog<K, V>>,
@@ -26,12 +34,17 @@ pub fn merge_txnlocal_and_committed_entrysets<'a, K, V, Q>(
     k_hi: Option<&'a Q>,
 ) -> impl 'a + Iterator<Item = Entry<'a, K, V>>
 where
-    K: 'a + Deser + Ord + PartialOrd<Q>,
+    K: 'a + Ord + PartialOrdSer<Q>,
     V: 'a + Deser,
 {
     let staging_entry_iter = staging.map(|w_memlog| w_memlog.r_memlog().get_range(k_lo, k_hi));
 
-    let committed_entry_iter = merge_committed_entrysets(committed_entrysets, k_lo, k_hi);
+    let staging_range_tombstones = staging
+        .map(|w_memlog| &w_memlog.r_memlog().range_tombstones)
+        .into_iter()
+        .collect::<Vec<_>>();
+    let committed_entry_iter =
+        merge_committed_entrysets(staging_range_tombstones, committed_entrysets, k_lo, k_hi);
 
     merging::merge_differently_typed_entry_iters(staging_entry_iter, committed_entry_iter)
 }
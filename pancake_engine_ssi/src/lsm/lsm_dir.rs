@@ -13,14 +13,18 @@ pub struct LsmDir {
 }
 
 impl LsmDir {
-    pub fn load_or_new<P: AsRef<Path>>(lsm_dir_path: P) -> Result<(Self, LsmState)> {
+    /// @arg `commit_ver_retention_count`: See [`LsmState::new()`].
+    pub fn load_or_new<P: AsRef<Path>>(
+        lsm_dir_path: P,
+        commit_ver_retention_count: u64,
+    ) -> Result<(Self, LsmState)> {
         let lsm_dir_path = lsm_dir_path.as_ref();
 
         let (pq, dir) = Self::collect_committed_unit_dirs(lsm_dir_path)?;
 
         let (committed_units, curr_commit_ver) = Self::load_committed_units(pq)?;
 
-        let lsm_state = LsmState::new(committed_units, curr_commit_ver);
+        let lsm_state = LsmState::new(committed_units, curr_commit_ver, commit_ver_retention_count);
 
         let lsm_dir = Self { dir };
 
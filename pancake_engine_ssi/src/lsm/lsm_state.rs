@@ -31,6 +31,11 @@ pub struct LsmState {
     curr_commit_ver_hold_count: u32,
     boundaries: OrderedDict<CommitVer, Boundary>,
 
+    /// The versions in `[min_retained_commit_ver, curr_commit_ver]` can be read as of.
+    /// The boundary of each retained version, other than `curr_commit_ver`, is held once on behalf of the retention.
+    commit_ver_retention_count: u64,
+    min_retained_commit_ver: CommitVer,
+
     curr_list_ver: ListVer,
     held_list_vers: Multiset<ListVer>,
     min_held_list_ver: ListVer,
@@ -38,9 +43,11 @@ pub struct LsmState {
 
 impl LsmState {
     /// @arg `committed_units`: From newer to older.
+    /// @arg `commit_ver_retention_count`: See [`EngineOptions::commit_ver_retention_count`](pancake_engine_common::EngineOptions::commit_ver_retention_count).
     pub fn new(
         committed_units: impl IntoIterator<Item = CommittedUnit>,
         curr_commit_ver: CommitVer,
+        commit_ver_retention_count: u64,
     ) -> Self {
         let list = AtomicLinkedList::from_elems(committed_units.into_iter());
 
@@ -51,6 +58,9 @@ impl LsmState {
             curr_commit_ver_hold_count: 0,
             boundaries: OrderedDict::new(),
 
+            commit_ver_retention_count,
+            min_retained_commit_ver: curr_commit_ver,
+
             curr_list_ver: ListVer::AT_BOOTUP,
             held_list_vers: Multiset::default(),
             min_held_list_ver: ListVer::AT_BOOTUP,
@@ -82,6 +92,33 @@ impl LsmState {
         self.curr_commit_ver
     }
 
+    /// @return Err if `arg_ver` is not retained. See [`Self::retained_commit_vers()`].
+    pub fn hold_retained_commit_ver(&mut self, arg_ver: CommitVer) -> Result<CommitVer> {
+        if arg_ver == self.curr_commit_ver {
+            return Ok(self.hold_curr_commit_ver());
+        }
+        if (arg_ver < self.min_retained_commit_ver) || (self.curr_commit_ver < arg_ver) {
+            return Err(anyhow!("Commit version {arg_ver} is not retained."));
+        }
+        let boundary = self
+            .boundaries
+            .get_mut(&arg_ver)
+            .ok_or_else(|| anyhow!("A retained boundary does not exist."))?;
+        boundary.hold_count += 1;
+        Ok(arg_ver)
+    }
+
+    /// @return From older to newer. The last one is the current version.
+    pub fn retained_commit_vers(&self) -> Vec<CommitVer> {
+        let mut vers = vec![];
+        let mut ver = self.min_retained_commit_ver;
+        while ver <= self.curr_commit_ver {
+            vers.push(ver);
+            ver = ver.new_inc();
+        }
+        vers
+    }
+
     /// @return [`CommitVer`]s that are members of newly-found isolated sequences of non-held boundaries.
     pub fn unhold_commit_vers<const LEN: usize>(
         &mut self,
@@ -130,7 +167,9 @@ impl LsmState {
         }
     }
 
-    pub fn bump_commit_ver(&mut self, staging_unit: StagingUnit) -> Result<()> {
+    /// @return The [`CommitVer`] that ceased to be retained, iff it became isolatedly non-held.
+    /// See [`Self::unhold_commit_ver()`].
+    pub fn bump_commit_ver(&mut self, staging_unit: StagingUnit) -> Result<Option<CommitVer>> {
         /* Save pre-bump info. */
 
         let penult_commit_ver = self.curr_commit_ver;
@@ -155,15 +194,39 @@ impl LsmState {
         /* Create a boundary at penult_commit_ver.
         Do this even if penult_commit_ver_hold_count == 0. */
 
+        let retention_hold_count = (self.commit_ver_retention_count > 0) as u32;
+
         self.boundaries.insert(
             penult_commit_ver,
             Boundary {
-                hold_count: penult_commit_ver_hold_count,
+                hold_count: penult_commit_ver_hold_count + retention_hold_count,
                 node_newer: new_node_ptr,
             },
         );
 
-        Ok(())
+        /* Slide the retention window. */
+
+        self.unretain_expired_commit_ver()
+    }
+
+    fn unretain_expired_commit_ver(&mut self) -> Result<Option<CommitVer>> {
+        if self.commit_ver_retention_count == 0 {
+            self.min_retained_commit_ver = self.curr_commit_ver;
+            return Ok(None);
+        }
+
+        let retained_count =
+            u64::from(self.curr_commit_ver) - u64::from(self.min_retained_commit_ver);
+        if retained_count > self.commit_ver_retention_count {
+            let expired_ver = self.min_retained_commit_ver;
+            self.min_retained_commit_ver = expired_ver.new_inc();
+
+            let is_fc_able = self.unhold_commit_ver(&expired_ver)?;
+            if is_fc_able {
+                return Ok(Some(expired_ver));
+            }
+        }
+        return Ok(None);
     }
 
     /// @return
@@ -1,18 +1,20 @@
 use anyhow::{anyhow, Result};
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::{FromPrimitive, ToPrimitive};
-use pancake_engine_common::fs_utils;
+use pancake_engine_common::{fs_utils, CorruptionError};
 use shorthand::ShortHand;
 use std::any;
 use std::cmp::{self, Ord, PartialOrd};
+use std::fmt;
 use std::fs::OpenOptions;
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::Path;
+use std::str;
 
 /// The commit version uniquely identifies every commitment as well as the datastore state after the commitment.
 ///
 /// The datastore's commit version increases for the whole lifetime of the datastore instance.
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct CommitVer(u64);
 
 impl CommitVer {
@@ -23,6 +25,24 @@ impl CommitVer {
     }
 }
 
+impl From<u64> for CommitVer {
+    fn from(ver: u64) -> Self {
+        Self(ver)
+    }
+}
+
+impl From<CommitVer> for u64 {
+    fn from(ver: CommitVer) -> Self {
+        ver.0
+    }
+}
+
+impl fmt::Display for CommitVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// The ordered number that disambiguates 2+ [`CommitInfo`] instances.
 ///
 /// In case 2+ [`CommitInfo`]s overlap in their [`CommitVer`] intervals,
@@ -53,7 +73,7 @@ impl ReplacementNum {
 }
 
 /// The disambiguator of key-value files on disk, as to whether belonging to a MemLog or an SSTable.
-#[derive(FromPrimitive, ToPrimitive, PartialEq, Eq)]
+#[derive(FromPrimitive, ToPrimitive, PartialEq, Eq, Clone, Copy)]
 pub enum CommitDataType {
     MemLog = 0,
     SSTable = 1,
@@ -67,23 +87,38 @@ pub struct CommitInfo {
     pub data_type: CommitDataType,
 }
 
+/// The serialized format is `hi,lo,replacement_num,data_type,checksum`,
+/// where `checksum` is the CRC-32 of the preceding text, excluding the last comma.
+///
+/// A format lacking `checksum` was written before checksums were introduced, and is read without verification.
 impl CommitInfo {
     fn do_ser<W: Write>(&self, w: &mut BufWriter<W>) -> Result<()> {
-        write!(
-            w,
+        let fields = format!(
             "{},{},{},{}",
             self.commit_ver_hi_incl.0,
             self.commit_ver_lo_incl.0,
             self.replacement_num.0,
             self.data_type.to_u8().unwrap(),
-        )?;
+        );
+        let checksum = crc32fast::hash(fields.as_bytes());
+        write!(w, "{fields},{checksum}")?;
         Ok(())
     }
-    fn do_deser<R: Read>(r: &mut BufReader<R>) -> Result<Self> {
-        let mut s = String::new();
-        r.read_to_string(&mut s)?;
+    fn do_deser(s: &str) -> Result<Self> {
+        let fields = match s.rsplit_once(',') {
+            Some((fields, checksum)) if fields.matches(',').count() == 3 => {
+                let checksum = checksum
+                    .parse::<u32>()
+                    .map_err(|_| anyhow!("Invalid checksum"))?;
+                if crc32fast::hash(fields.as_bytes()) != checksum {
+                    return Err(anyhow!("Checksum mismatch"));
+                }
+                fields
+            }
+            _ => s,
+        };
 
-        let tokens = s.split(',').collect::<Vec<&str>>();
+        let tokens = fields.split(',').collect::<Vec<&str>>();
         match tokens.try_into() as Result<[&str; 4], _> {
             Err(_) => Err(anyhow!(
                 "Incorrect format for {}.",
@@ -117,15 +152,62 @@ impl CommitInfo {
         }
     }
     pub fn ser<P: AsRef<Path>>(&self, p: P) -> Result<()> {
-        let file = fs_utils::open_file(p, OpenOptions::new().create(true).write(true))?;
+        let file = fs_utils::open_file(
+            p,
+            OpenOptions::new().create(true).write(true).truncate(true),
+        )?;
         let mut w = BufWriter::new(file);
         self.do_ser(&mut w)?;
         w.flush()?;
         Ok(())
     }
+    /// @return Err of [`CorruptionError`] if the file content is malformed or fails verification.
     pub fn deser<P: AsRef<Path>>(p: P) -> Result<Self> {
+        let p = p.as_ref();
         let file = fs_utils::open_file(p, OpenOptions::new().read(true))?;
         let mut r = BufReader::new(file);
-        Self::do_deser(&mut r)
+        let mut buf = vec![];
+        r.read_to_end(&mut buf)?;
+
+        let corruption = |e: anyhow::Error| CorruptionError::new(p, 0, e.to_string());
+        let s = str::from_utf8(&buf).map_err(|e| corruption(e.into()))?;
+        let moi = Self::do_deser(s).map_err(corruption)?;
+        Ok(moi)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn ser_deser() -> Result<()> {
+        let dir_path = env::temp_dir().join("pancake_commit_info_test");
+        fs::create_dir_all(&dir_path)?;
+        let file_path = dir_path.join("commit_info");
+
+        let ci = CommitInfo {
+            commit_ver_hi_incl: CommitVer(12),
+            commit_ver_lo_incl: CommitVer(3),
+            replacement_num: ReplacementNum(1),
+            data_type: CommitDataType::SSTable,
+        };
+        ci.ser(&file_path)?;
+        assert!(CommitInfo::deser(&file_path)? == ci);
+
+        /* Written before checksums were introduced. */
+        fs::write(&file_path, "12,3,1,1")?;
+        assert!(CommitInfo::deser(&file_path)? == ci);
+
+        let checksum = crc32fast::hash(b"12,3,1,1");
+        for content in [format!("12,4,1,1,{checksum}"), String::from("12,3,1")] {
+            fs::write(&file_path, content)?;
+            let e = CommitInfo::deser(&file_path).err().unwrap();
+            assert!(e.downcast_ref::<CorruptionError>().is_some());
+        }
+
+        Ok(())
     }
 }
@@ -8,13 +8,14 @@ use crate::{
         },
     },
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use pancake_engine_common::{fs_utils, ReadonlyMemLog, SSTable};
 use pancake_types::{
     serde::OptDatum,
-    types::{PKShared, PVShared, SVPKShared},
+    types::{PKShared, PVShared, SVPKShared, Serializable},
 };
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 pub struct CommittedUnit {
     pub prim: Option<CommittedEntrySet<PKShared, OptDatum<PVShared>>>,
@@ -61,12 +62,12 @@ impl CommittedUnit {
     /// Cost:
     /// - This constructor serializes CommitInfo. The caller shouldn't do it before.
     pub fn from_compacted(compacted: CompactedUnit, commit_info: CommitInfo) -> Result<Self> {
-        let prim = compacted.prim.map(CommittedEntrySet::SSTable);
+        let prim = compacted.prim.map(CommittedEntrySet::from_sstables);
 
         let scnds = compacted
             .scnds
             .into_iter()
-            .map(|(si_num, sstable)| (si_num, CommittedEntrySet::SSTable(sstable)))
+            .map(|(si_num, sstables)| (si_num, CommittedEntrySet::from_sstables(sstables)))
             .collect::<HashMap<_, _>>();
 
         let commit_info_path = compacted.dir.format_commit_info_file_path();
@@ -81,28 +82,16 @@ impl CommittedUnit {
     }
 
     pub fn load(dir: UnitDir, commit_info: CommitInfo) -> Result<Self> {
-        let prim_path = dir.format_prim_file_path();
-        let prim = if prim_path.exists() {
-            let entryset = match commit_info.data_type() {
-                CommitDataType::MemLog => {
-                    CommittedEntrySet::RMemLog(ReadonlyMemLog::load(prim_path)?)
-                }
-                CommitDataType::SSTable => CommittedEntrySet::SSTable(SSTable::load(prim_path)?),
-            };
-            Some(entryset)
+        let prim_paths = dir.list_prim_file_paths()?;
+        let prim = if prim_paths.is_empty() == false {
+            Some(Self::load_entryset(prim_paths, &commit_info)?)
         } else {
             None
         };
 
         let mut scnds = HashMap::new();
-        for res in dir.list_scnd_file_paths()? {
-            let (scnd_path, si_num) = res?;
-            let entryset = match commit_info.data_type() {
-                CommitDataType::MemLog => {
-                    CommittedEntrySet::RMemLog(ReadonlyMemLog::load(scnd_path)?)
-                }
-                CommitDataType::SSTable => CommittedEntrySet::SSTable(SSTable::load(scnd_path)?),
-            };
+        for (si_num, scnd_paths) in dir.list_scnd_file_paths()? {
+            let entryset = Self::load_entryset(scnd_paths, &commit_info)?;
             scnds.insert(si_num, entryset);
         }
 
@@ -114,6 +103,33 @@ impl CommittedUnit {
         })
     }
 
+    /// @arg paths: Non-empty. A MemLog is always in one file.
+    fn load_entryset<K, V>(
+        mut paths: Vec<PathBuf>,
+        commit_info: &CommitInfo,
+    ) -> Result<CommittedEntrySet<K, V>>
+    where
+        K: Serializable + Ord + Clone,
+        V: Serializable,
+    {
+        let entryset = match commit_info.data_type() {
+            CommitDataType::MemLog => {
+                if paths.len() != 1 {
+                    return Err(anyhow!("A MemLog unit has multiple files. {paths:?}"));
+                }
+                CommittedEntrySet::RMemLog(ReadonlyMemLog::load(paths.remove(0))?)
+            }
+            CommitDataType::SSTable => {
+                let sstables = paths
+                    .into_iter()
+                    .map(SSTable::load)
+                    .collect::<Result<Vec<_>>>()?;
+                CommittedEntrySet::from_sstables(sstables)
+            }
+        };
+        Ok(entryset)
+    }
+
     pub fn remove_dir(self) -> Result<()> {
         fs_utils::remove_dir_all(self.dir.path())?;
         Ok(())
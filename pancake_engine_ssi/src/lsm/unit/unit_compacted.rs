@@ -8,9 +8,10 @@ use pancake_types::{
 use std::any;
 use std::collections::HashMap;
 
+/// Each index's entries are a sorted run of one or more [`SSTable`]s. See [`UnitDir`].
 pub struct CompactedUnit {
-    pub prim: Option<SSTable<PKShared, OptDatum<PVShared>>>,
-    pub scnds: HashMap<ScndIdxNum, SSTable<SVPKShared, OptDatum<PVShared>>>,
+    pub prim: Option<Vec<SSTable<PKShared, OptDatum<PVShared>>>>,
+    pub scnds: HashMap<ScndIdxNum, Vec<SSTable<SVPKShared, OptDatum<PVShared>>>>,
     pub dir: UnitDir,
 }
 
@@ -2,13 +2,20 @@ use crate::{db_state::ScndIdxNum, lsm::unit::CommitInfo};
 use anyhow::Result;
 use derive_more::From;
 use pancake_engine_common::fs_utils::{self, NamePattern, PathNameNum};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 const PI_KV_FILE_NAME: &str = "pi.kv";
+const PI_KV_PART_FILE_NAME_PFX: &str = "pi.";
 const SI_KV_FILE_NAME_PFX: &str = "si-";
-const SI_KV_FILE_NAME_EXT: &str = ".kv";
+const KV_FILE_NAME_EXT: &str = ".kv";
 const COMMIT_INFO_FILE_NAME: &str = "commit_info.txt";
 
+/// The key-value files of each index within a unit form a sorted run of one or more parts,
+/// with disjoint and ascending key ranges, in the order of part numbers.
+///
+/// Part 0 is named `pi.kv` or `si-<si_num>.kv`, as is the only file of a MemLog or of an older SSTable unit.
+/// Each further part is named `pi.<part_num>.kv` or `si-<si_num>.<part_num>.kv`.
 #[derive(From, PartialEq, Eq)]
 pub struct UnitDir(PathBuf);
 
@@ -17,37 +24,95 @@ impl UnitDir {
         &self.0
     }
 
+    fn file_name<P: AsRef<Path>>(file_path: P) -> Option<String> {
+        let file_path = file_path.as_ref();
+        let file_name = file_path.file_name().and_then(|os_str| os_str.to_str());
+        file_name.map(String::from)
+    }
+
     /* Primary index */
+    fn prim_part_file_name_pattern() -> NamePattern {
+        NamePattern::new(PI_KV_PART_FILE_NAME_PFX, KV_FILE_NAME_EXT)
+    }
     pub fn format_prim_file_path(&self) -> PathBuf {
-        self.0.join(PI_KV_FILE_NAME)
+        self.format_prim_part_file_path(0)
+    }
+    pub fn format_prim_part_file_path(&self, part_num: u64) -> PathBuf {
+        if part_num == 0 {
+            return self.0.join(PI_KV_FILE_NAME);
+        }
+        let file_name = Self::prim_part_file_name_pattern().format(PathNameNum::from(part_num));
+        self.0.join(file_name)
+    }
+    fn parse_prim_file_name(file_name: &str) -> Option<u64> {
+        if file_name == PI_KV_FILE_NAME {
+            return Some(0);
+        }
+        let part_num = Self::prim_part_file_name_pattern().parse(file_name).ok();
+        part_num.map(|part_num| *part_num)
+    }
+    /// @return From the lowest to the highest part number.
+    pub fn list_prim_file_paths(&self) -> Result<Vec<PathBuf>> {
+        let mut paths = vec![];
+        for res_path in fs_utils::read_dir(&self.0)? {
+            let path = res_path?;
+            let part_num = Self::file_name(&path).and_then(|s| Self::parse_prim_file_name(&s));
+            if let Some(part_num) = part_num {
+                paths.push((part_num, path));
+            }
+        }
+        paths.sort_by_key(|(part_num, _path)| *part_num);
+        Ok(paths.into_iter().map(|(_part_num, path)| path).collect())
     }
 
     /* Secondary indexes */
-    fn scnd_file_name_pattern() -> NamePattern {
-        NamePattern::new(SI_KV_FILE_NAME_PFX, SI_KV_FILE_NAME_EXT)
-    }
     pub fn format_scnd_file_path(&self, si_num: ScndIdxNum) -> PathBuf {
-        let path_name_num: PathNameNum = si_num.into();
-        let file_name = Self::scnd_file_name_pattern().format(path_name_num);
-        let file_path = self.0.join(file_name);
-        file_path
+        self.format_scnd_part_file_path(si_num, 0)
     }
-    fn parse_scnd_file_num<P: AsRef<Path>>(file_path: P) -> Option<ScndIdxNum> {
-        let file_path = file_path.as_ref();
-        let file_name = file_path.file_name().and_then(|os_str| os_str.to_str());
-        let path_name_num = file_name.and_then(|s| Self::scnd_file_name_pattern().parse(s).ok());
-        let si_num = path_name_num.map(ScndIdxNum::from);
-        si_num
+    pub fn format_scnd_part_file_path(&self, si_num: ScndIdxNum, part_num: u64) -> PathBuf {
+        let si_num = PathNameNum::from(si_num).format_hex();
+        let file_name = if part_num == 0 {
+            format!("{SI_KV_FILE_NAME_PFX}{si_num}{KV_FILE_NAME_EXT}")
+        } else {
+            let part_num = PathNameNum::from(part_num).format_hex();
+            format!("{SI_KV_FILE_NAME_PFX}{si_num}.{part_num}{KV_FILE_NAME_EXT}")
+        };
+        self.0.join(file_name)
     }
-    pub fn list_scnd_file_paths<'a>(
-        &'a self,
-    ) -> Result<impl 'a + Iterator<Item = Result<(PathBuf, ScndIdxNum)>>> {
-        let ret_iter = fs_utils::read_dir(&self.0)?.filter_map(|res_path| {
-            res_path
-                .map(|path| Self::parse_scnd_file_num(&path).map(|si_num| (path, si_num)))
-                .transpose()
-        });
-        Ok(ret_iter)
+    fn parse_scnd_file_name(file_name: &str) -> Option<(ScndIdxNum, u64)> {
+        let middle = file_name
+            .strip_prefix(SI_KV_FILE_NAME_PFX)?
+            .strip_suffix(KV_FILE_NAME_EXT)?;
+        let (si_num, part_num) = match middle.split_once('.') {
+            None => (middle, None),
+            Some((si_num, part_num)) => (si_num, Some(part_num)),
+        };
+        let si_num = PathNameNum::parse_hex(si_num).ok()?;
+        let part_num = match part_num {
+            None => 0,
+            Some(part_num) => *PathNameNum::parse_hex(part_num).ok()?,
+        };
+        Some((ScndIdxNum::from(si_num), part_num))
+    }
+    /// @return For each secondary index, from the lowest to the highest part number.
+    pub fn list_scnd_file_paths(&self) -> Result<HashMap<ScndIdxNum, Vec<PathBuf>>> {
+        let mut si_paths = HashMap::<_, Vec<_>>::new();
+        for res_path in fs_utils::read_dir(&self.0)? {
+            let path = res_path?;
+            let parsed = Self::file_name(&path).and_then(|s| Self::parse_scnd_file_name(&s));
+            if let Some((si_num, part_num)) = parsed {
+                si_paths.entry(si_num).or_default().push((part_num, path));
+            }
+        }
+        let si_paths = si_paths
+            .into_iter()
+            .map(|(si_num, mut paths)| {
+                paths.sort_by_key(|(part_num, _path)| *part_num);
+                let paths = paths.into_iter().map(|(_part_num, path)| path).collect();
+                (si_num, paths)
+            })
+            .collect();
+        Ok(si_paths)
     }
 
     /* Commit info */
@@ -59,3 +124,53 @@ impl UnitDir {
         CommitInfo::deser(file_path)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn part_file_paths() -> Result<()> {
+        let dir_path = env::temp_dir().join("pancake_unit_dir_test");
+        if dir_path.exists() {
+            fs::remove_dir_all(&dir_path)?;
+        }
+        fs::create_dir_all(&dir_path)?;
+        let dir = UnitDir::from(dir_path.clone());
+
+        let si_a = ScndIdxNum::from(PathNameNum::from(3));
+        let si_b = ScndIdxNum::from(PathNameNum::from(17));
+
+        assert_eq!(dir.format_prim_file_path(), dir_path.join("pi.kv"));
+        assert_eq!(
+            dir.format_scnd_file_path(si_a),
+            dir_path.join("si-0000000000000003.kv")
+        );
+
+        let mut exp_prim = vec![];
+        let mut exp_scnd_a = vec![];
+        for part_num in [0, 1, 2, 16] {
+            exp_prim.push(dir.format_prim_part_file_path(part_num));
+            exp_scnd_a.push(dir.format_scnd_part_file_path(si_a, part_num));
+        }
+        let exp_scnd_b = vec![dir.format_scnd_file_path(si_b)];
+        for path in exp_prim
+            .iter()
+            .chain(exp_scnd_a.iter())
+            .chain(exp_scnd_b.iter())
+        {
+            fs::write(path, "")?;
+        }
+        fs::write(dir.format_commit_info_file_path(), "")?;
+
+        assert_eq!(dir.list_prim_file_paths()?, exp_prim);
+        let act_scnds = dir.list_scnd_file_paths()?;
+        assert_eq!(act_scnds.len(), 2);
+        assert_eq!(act_scnds.get(&si_a), Some(&exp_scnd_a));
+        assert_eq!(act_scnds.get(&si_b), Some(&exp_scnd_b));
+
+        Ok(())
+    }
+}
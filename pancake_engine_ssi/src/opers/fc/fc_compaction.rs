@@ -7,8 +7,12 @@ use crate::{
     opers::fc::fc_segm::FCJob,
 };
 use anyhow::Result;
-use pancake_engine_common::{Entry, SSTable};
-use pancake_types::{serde::OptDatum, types::Deser};
+use pancake_engine_common::{Entry, RangeTombstones, SSTable};
+use pancake_types::{
+    serde::{self, OptDatum},
+    types::{Deser, PartialOrdSer, Serializable},
+};
+use std::path::PathBuf;
 
 impl<'job> FCJob<'job> {
     pub(super) fn do_flush_and_compact<'data>(
@@ -62,64 +66,119 @@ impl<'job> FCJob<'job> {
         for (_, ScndIdxState { scnd_idx_num, .. }) in self.db_state_guard.scnd_idxs().iter() {
             let existing_entrysets = existing_units
                 .iter()
-                .filter_map(|unit| unit.scnds.get(scnd_idx_num));
-            let compacted_entries = Self::derive_kmerged_iter(existing_entrysets, skip_tombstones);
+                .filter_map(|unit| unit.scnds.get(scnd_idx_num))
+                .collect::<Vec<_>>();
+            let compacted_entries =
+                Self::derive_kmerged_iter(existing_entrysets.clone().into_iter(), skip_tombstones);
             let mut compacted_entries = compacted_entries.peekable();
-            if compacted_entries.peek().is_some() {
+            let range_tombstones =
+                Self::derive_range_tombstones(existing_entrysets.into_iter(), skip_tombstones);
+            if compacted_entries.peek().is_some() || range_tombstones.is_empty() == false {
                 ensure_create_output_unit(&mut maybe_output_unit)?;
                 let out_unit = maybe_output_unit.as_mut().unwrap();
 
-                let out_path = out_unit.dir.format_scnd_file_path(*scnd_idx_num);
-                let out_sstable = SSTable::new(compacted_entries, out_path)?;
+                let out_dir = &out_unit.dir;
+                let mut part_num = 0;
+                let out_sstables =
+                    self.write_sstables(compacted_entries, range_tombstones, || {
+                        let out_path = out_dir.format_scnd_part_file_path(*scnd_idx_num, part_num);
+                        part_num += 1;
+                        out_path
+                    })?;
 
-                out_unit.scnds.insert(*scnd_idx_num, out_sstable);
+                out_unit.scnds.insert(*scnd_idx_num, out_sstables);
             }
         }
 
         {
-            let existing_entrysets = existing_units.iter().filter_map(|unit| unit.prim.as_ref());
-            let compacted_entries = Self::derive_kmerged_iter(existing_entrysets, skip_tombstones);
+            let existing_entrysets = existing_units
+                .iter()
+                .filter_map(|unit| unit.prim.as_ref())
+                .collect::<Vec<_>>();
+            let compacted_entries =
+                Self::derive_kmerged_iter(existing_entrysets.clone().into_iter(), skip_tombstones);
             let mut compacted_entries = compacted_entries.peekable();
-            if compacted_entries.peek().is_some() {
+            let range_tombstones =
+                Self::derive_range_tombstones(existing_entrysets.into_iter(), skip_tombstones);
+            if compacted_entries.peek().is_some() || range_tombstones.is_empty() == false {
                 ensure_create_output_unit(&mut maybe_output_unit)?;
                 let out_unit = maybe_output_unit.as_mut().unwrap();
 
-                let out_path = out_unit.dir.format_prim_file_path();
-                let out_sstable = SSTable::new(compacted_entries, out_path)?;
+                let out_dir = &out_unit.dir;
+                let mut part_num = 0;
+                let out_sstables =
+                    self.write_sstables(compacted_entries, range_tombstones, || {
+                        let out_path = out_dir.format_prim_part_file_path(part_num);
+                        part_num += 1;
+                        out_path
+                    })?;
 
-                out_unit.prim = Some(out_sstable);
+                out_unit.prim = Some(out_sstables);
             }
         }
 
         Ok(maybe_output_unit)
     }
 
+    /// Splits the entries into a sorted run of [`SSTable`]s, each of about the target file size.
+    /// See [`EngineOptions::sstable_target_file_size`](pancake_engine_common::EngineOptions::sstable_target_file_size).
+    fn write_sstables<'data, K, V>(
+        &self,
+        entries: impl Iterator<Item = Entry<'data, K, V>>,
+        range_tombstones: RangeTombstones<K>,
+        gen_path: impl FnMut() -> PathBuf,
+    ) -> Result<Vec<SSTable<K, V>>>
+    where
+        K: 'data + Serializable + Ord + Clone,
+        V: 'data + Serializable,
+    {
+        let opts = self.db.opts();
+        SSTable::new_multi(
+            entries,
+            range_tombstones,
+            gen_path,
+            opts.sstable_data_block_size,
+            opts.sstable_target_file_size,
+        )
+    }
+
+    /// Expired entries become tombstones.
+    /// Entries covered by range tombstones of newer entrysets are dropped.
     fn derive_kmerged_iter<'data, K, V>(
-        entrysets: impl Iterator<Item = &'data CommittedEntrySet<K, OptDatum<V>>>,
+        entrysets: impl 'data + Iterator<Item = &'data CommittedEntrySet<K, OptDatum<V>>>,
         skip_tombstones: bool,
     ) -> impl Iterator<Item = Entry<'data, K, OptDatum<V>>>
     where
-        K: 'data + Deser + Ord,
+        K: 'data + Ord + PartialOrdSer<K> + Clone,
         OptDatum<V>: 'data + Deser,
     {
+        let now = serde::now_micros();
         let compacted_entries =
-            merging::merge_committed_entrysets(entrysets, None::<&K>, None::<&K>);
-        let compacted_entries = compacted_entries.filter(move |entry| {
-            if skip_tombstones == true {
-                match entry.try_borrow() {
-                    Err(_) => true,
-                    Ok((_, optdat_v)) => match optdat_v {
-                        OptDatum::Tombstone => false,
-                        OptDatum::Some(_) => true,
-                    },
-                }
-            } else {
-                true
-            }
-        });
+            merging::merge_committed_entrysets(vec![], entrysets, None::<&K>, None::<&K>);
+        let compacted_entries = compacted_entries
+            .filter_map(move |entry| entry.to_compacted_entry(now, skip_tombstones));
         compacted_entries
     }
 
+    /// The union of the range tombstones, which shadow the units older than the compacted ones.
+    /// Hence they are skipped along with tombstones.
+    fn derive_range_tombstones<'data, K, V>(
+        entrysets: impl Iterator<Item = &'data CommittedEntrySet<K, V>>,
+        skip_tombstones: bool,
+    ) -> RangeTombstones<K>
+    where
+        K: 'data + Deser + Ord + Clone,
+        V: 'data + Deser,
+    {
+        let mut range_tombstones = RangeTombstones::default();
+        if skip_tombstones == false {
+            for entryset in entrysets {
+                range_tombstones.extend_from(entryset.range_tombstones());
+            }
+        }
+        range_tombstones
+    }
+
     fn derive_commit_info<'data>(units: &[&'data CommittedUnit]) -> CommitInfo {
         let commit_ver_hi_incl = *(units.first().unwrap().commit_info.commit_ver_hi_incl());
         let commit_ver_lo_incl = *(units.last().unwrap().commit_info.commit_ver_lo_incl());
@@ -138,6 +197,7 @@ impl<'job> FCJob<'job> {
     }
 }
 
+#[allow(clippy::large_enum_variant)] // Returned once per compaction. Boxing would not pay off.
 pub(super) enum CompactionResult {
     /// Flushing+compaction would not have changed the given slice of Units; hence was not executed.
     NoChange,
@@ -145,6 +205,6 @@ pub(super) enum CompactionResult {
     /// Flushing+compaction was executed, and resulted in an empty Unit.
     Empty,
 
-    /// Flushing+compaction was executed, and resulted in one Unit containing 1+ non-empty SSTables.
+    /// Flushing+compaction was executed, and resulted in one Unit containing 1+ non-empty sorted runs of SSTables.
     Some(CommittedUnit),
 }
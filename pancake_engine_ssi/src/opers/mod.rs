@@ -2,3 +2,5 @@ pub mod fc;
 pub mod sicr;
 pub mod sidel;
 pub mod txn;
+pub mod vccr;
+pub mod vcdel;
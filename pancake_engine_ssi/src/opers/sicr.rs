@@ -3,16 +3,16 @@ use crate::{
     db_state::{ScndIdxNewDefnResult, ScndIdxNum, ScndIdxState},
     ds_n_a::{atomic_linked_list::ListNode, send_ptr::NonNullSendPtr},
     lsm::{
-        entryset::CommittedEntrySet,
-        unit::{CommitVer, CommittedUnit, StagingUnit},
+        entryset::{merging, CommittedEntrySet},
+        unit::{CommitDataType, CommitVer, CommittedUnit, StagingUnit},
     },
 };
 use anyhow::{anyhow, Result};
 use derive_more::Display;
 use pancake_engine_common::{fs_utils, SSTable};
 use pancake_types::{
-    serde::OptDatum,
-    types::{PVShared, SVPKShared, SubValueSpec},
+    serde::{self, OptDatum},
+    types::{PVShared, SVPKShared, ScndIdxSpec, SubValue, UniquenessViolation},
 };
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -21,19 +21,20 @@ use tokio::sync::MutexGuard;
 mod creation;
 mod paths;
 
+pub(in crate::opers) use creation::merge_prim_entries;
 pub use paths::ScndIdxCreationsDir;
-use paths::*;
+pub(in crate::opers) use paths::*;
 
 impl DB {
+    /// If the index is unique, then it is created only after verifying that no two existing values violate it.
     pub async fn create_scnd_idx(
         &self,
-        sv_spec: &Arc<SubValueSpec>,
+        sv_spec: &Arc<ScndIdxSpec>,
     ) -> Result<(), ScndIdxCreationJobErr> {
         let mut job = ScndIdxCreationJob::new(self, sv_spec).await?;
-        job.run().await?;
+        let res = job.run().await;
         job.remove_intermediary_files()?;
-
-        Ok(())
+        res
     }
 }
 
@@ -42,7 +43,7 @@ struct ScndIdxCreationJob<'job> {
 
     _si_cr_guard: MutexGuard<'job, ()>,
 
-    sv_spec: Arc<SubValueSpec>,
+    sv_spec: Arc<ScndIdxSpec>,
 
     si_num: ScndIdxNum,
     pre_output_commit_ver: CommitVer,
@@ -50,11 +51,11 @@ struct ScndIdxCreationJob<'job> {
     output_node: NonNullSendPtr<ListNode<CommittedUnit>>,
 
     job_dir: ScndIdxCreationJobDir,
-    prim_entryset_file_paths: Vec<PathBuf>,
+    prim_entryset_file_paths: Vec<(PathBuf, CommitDataType)>,
 }
 
 impl<'job> ScndIdxCreationJob<'job> {
-    async fn new(db: &'job DB, sv_spec: &Arc<SubValueSpec>) -> Result<Self, ScndIdxCreationJobErr> {
+    async fn new(db: &'job DB, sv_spec: &Arc<ScndIdxSpec>) -> Result<Self, ScndIdxCreationJobErr> {
         let si_cr_guard = match db.si_cr_mutex().try_lock() {
             Err(_) => return Err(ScndIdxCreationJobErr::Busy),
             Ok(guard) => guard,
@@ -106,15 +107,7 @@ impl<'job> ScndIdxCreationJob<'job> {
         }
 
         let job_dir = db.si_cr_dir().create_new_job_dir()?;
-        let mut prim_entryset_file_paths = vec![];
-        for unit in snap.iter() {
-            if unit.prim.is_some() {
-                let prim_file_path = unit.dir.format_prim_file_path();
-                let stg_file_path = job_dir.format_new_kv_file_path();
-                fs_utils::hard_link_file(prim_file_path, &stg_file_path)?;
-                prim_entryset_file_paths.push(stg_file_path);
-            }
-        }
+        let prim_entryset_file_paths = job_dir.link_prim_entrysets(&snap)?;
 
         let output_node = snap.head_ptr().unwrap();
 
@@ -153,28 +146,46 @@ impl<'job> ScndIdxCreationJob<'job> {
         Ok(())
     }
 
-    async fn modify_lsm_state(&self, merged_file_path: Option<PathBuf>) -> Result<()> {
+    async fn modify_lsm_state(
+        &self,
+        merged_file_path: Option<PathBuf>,
+    ) -> Result<(), ScndIdxCreationJobErr> {
+        let res;
         {
+            /* Taking the write guard waits for all ongoing txns to finish. */
             let mut db_state = self.db.db_state().write().await;
 
-            /* We're modifying output_node, which has already been in the LL, in-place.
-            We must modify it while no other threads are traversing over the node. */
-            if let Some(orig_path) = merged_file_path {
-                let out_node_ref = unsafe { &mut *(self.output_node.as_ptr()) };
+            match self.verify_unique(merged_file_path.as_ref()).await {
+                Ok(()) => {
+                    /* We're modifying output_node, which has already been in the LL, in-place.
+                    We must modify it while no other threads are traversing over the node. */
+                    if let Some(orig_path) = merged_file_path {
+                        let out_node_ref = unsafe { &mut *(self.output_node.as_ptr()) };
 
-                let out_path = out_node_ref.elem.dir.format_scnd_file_path(self.si_num);
+                        let out_path = out_node_ref.elem.dir.format_scnd_file_path(self.si_num);
 
-                fs_utils::rename_file(orig_path, &out_path)?;
+                        fs_utils::rename_file(orig_path, &out_path)?;
 
-                /* Note, we wrote as <SVPK, PV>, but are now reading as <SVPK, OptDatum<PV>>. This is valid. */
-                let out_sstable = SSTable::<SVPKShared, OptDatum<PVShared>>::load(out_path)?;
+                        let out_sstable =
+                            SSTable::<SVPKShared, OptDatum<PVShared>>::load(out_path)?;
 
-                let out_entryset = CommittedEntrySet::SSTable(out_sstable);
+                        let out_entryset = CommittedEntrySet::from_sstables(vec![out_sstable]);
 
-                out_node_ref.elem.scnds.insert(self.si_num, out_entryset);
-            }
+                        out_node_ref.elem.scnds.insert(self.si_num, out_entryset);
+                    }
+
+                    db_state.set_scnd_idx_as_readable(&self.sv_spec)?;
+
+                    res = Ok(());
+                }
+                Err(e) => {
+                    /* The entries that txns have committed for this index are left in place,
+                    and are dropped by compaction. */
+                    db_state.undefine_scnd_idx(&self.sv_spec)?;
 
-            db_state.set_scnd_idx_as_readable(&self.sv_spec)?;
+                    res = Err(e);
+                }
+            }
         }
 
         let fc_able_commit_vers;
@@ -196,7 +207,81 @@ impl<'job> ScndIdxCreationJob<'job> {
             }
         }
 
-        Ok(())
+        res
+    }
+
+    /// Verifies the entries derived from the existing values, merged with the entries that txns have committed
+    /// since the index was defined. The latter entries were verified only against what each txn could see.
+    ///
+    /// Must be called while no txn is ongoing.
+    async fn verify_unique(
+        &self,
+        merged_file_path: Option<&PathBuf>,
+    ) -> Result<(), ScndIdxCreationJobErr> {
+        if self.sv_spec.is_unique == false {
+            return Ok(());
+        }
+
+        let merged_entryset = match merged_file_path {
+            None => None,
+            Some(path) => {
+                let sstable = SSTable::<SVPKShared, OptDatum<PVShared>>::load(path)?;
+                Some(CommittedEntrySet::from_sstables(vec![sstable]))
+            }
+        };
+
+        let (snap, snap_list_ver);
+        {
+            let mut lsm_state = self.db.lsm_state().lock().await;
+
+            snap = lsm_state.list().snap();
+
+            snap_list_ver = lsm_state.hold_curr_list_ver();
+        }
+
+        let res = (|| {
+            /* From newer to older. The output unit is the oldest unit that can contain entries of this index. */
+            let committed_entrysets = snap
+                .iter()
+                .take_while(|unit| unit.commit_info.commit_ver_hi_incl >= self.output_commit_ver)
+                .filter_map(|unit| unit.scnds.get(&self.si_num))
+                .chain(merged_entryset.iter());
+            let kmerged_entries = merging::merge_committed_entrysets(
+                vec![],
+                committed_entrysets,
+                None::<&SubValue>,
+                None,
+            );
+            let now = serde::now_micros();
+            let non_tomb_entries = kmerged_entries.filter_map(|entry| entry.to_option_entry(now));
+
+            /* Entries are sorted by sub-value. Hence entries sharing a sub-value are adjacent. */
+            let mut prev_svpk: Option<SVPKShared> = None;
+            for entry in non_tomb_entries {
+                let svpk = entry.into_owned_k()?;
+                if let Some(prev_svpk) = prev_svpk.as_ref() {
+                    if prev_svpk.sv == svpk.sv {
+                        self.sv_spec
+                            .check_unique(&svpk.sv, &svpk.pk, &prev_svpk.pk)
+                            .map_err(ScndIdxCreationJobErr::UniquenessViolated)?;
+                    }
+                }
+                prev_svpk = Some(svpk);
+            }
+            Ok(())
+        })();
+
+        let updated_mhlv;
+        {
+            let mut lsm_state = self.db.lsm_state().lock().await;
+
+            updated_mhlv = lsm_state.unhold_list_ver(snap_list_ver)?;
+        }
+        if let Some(mhlv) = updated_mhlv {
+            self.db.notify_min_held_list_ver(mhlv);
+        }
+
+        res
     }
 
     fn remove_intermediary_files(self) -> Result<()> {
@@ -209,6 +294,7 @@ impl<'job> ScndIdxCreationJob<'job> {
 pub enum ScndIdxCreationJobErr {
     Busy,
     Existent { is_readable: bool },
+    UniquenessViolated(UniquenessViolation),
     InternalError(anyhow::Error),
 }
 
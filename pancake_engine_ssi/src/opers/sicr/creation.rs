@@ -1,19 +1,74 @@
-use crate::opers::sicr::ScndIdxCreationJob;
+use crate::{lsm::unit::CommitDataType, opers::sicr::ScndIdxCreationJob};
 use anyhow::Result;
-use pancake_engine_common::{fs_utils, merging};
+use itertools::Either;
+use pancake_engine_common::{fs_utils, merging, Entry, RangeTombstones, ReadonlyMemLog, SSTable};
 use pancake_types::{
     iters::KeyValueReader,
-    serde::OptDatum,
-    types::{PKShared, PVShared, SVPKShared, Ser},
+    serde::{self, OptDatum},
+    types::{OptPVBuf, PKShared, PVShared, SVPKShared, Ser},
 };
 use std::collections::BTreeMap;
 use std::fs::OpenOptions;
 use std::io::BufWriter;
 use std::path::PathBuf;
 
-/// The period is exaggeratedly small, so as to be helpful with debugging.
-/// In the future, we'll allow setting it from an env var.
-const MEMTABLE_FLUSH_PERIOD_ITEM_COUNT: usize = 5;
+/// Merges the primary entrysets' files, which are ordered from newer to older.
+///
+/// @return The non-tombstone entries that have not expired. Each value is kept serialized. See [`OptPVBuf`].
+pub(in crate::opers) fn merge_prim_entries(
+    prim_entryset_file_paths: &[(PathBuf, CommitDataType)],
+) -> Result<impl Iterator<Item = Result<(PKShared, OptPVBuf)>>> {
+    let now = serde::now_micros();
+    let prim_entries = merge_prim_optdat_entries(prim_entryset_file_paths)?;
+    let live_entries = prim_entries.filter_map(move |res_pk_pv| {
+        let (pk, optpvbuf) = match res_pk_pv {
+            Err(e) => return Some(Err(e)),
+            Ok(pk_pv) => pk_pv,
+        };
+        match optpvbuf.view() {
+            Err(e) => Some(Err(e)),
+            Ok(optdat_pv) if optdat_pv.as_live(now).is_none() => None,
+            Ok(_) => Some(Ok((pk, optpvbuf))),
+        }
+    });
+    Ok(live_entries)
+}
+
+/// Merges the primary entrysets' files, which are ordered from newer to older.
+/// The values are not deserialized, s.t. the caller may view only those sub-portions that it needs.
+///
+/// @return All entries, including tombstones and expired ones, but excluding those covered by range tombstones.
+fn merge_prim_optdat_entries(
+    prim_entryset_file_paths: &[(PathBuf, CommitDataType)],
+) -> Result<impl Iterator<Item = Result<(PKShared, OptPVBuf)>>> {
+    let mut prim_entrysets = vec![];
+    let mut newer_range_tombstones = RangeTombstones::default();
+    for (pi_file_path, data_type) in prim_entryset_file_paths.iter() {
+        let (iter, range_tombstones) = match data_type {
+            CommitDataType::MemLog => {
+                let memlog = ReadonlyMemLog::<PKShared, OptPVBuf>::load(pi_file_path)?;
+                let iter = Either::Left(memlog.memtable.into_iter().map(Ok));
+                (iter, memlog.range_tombstones)
+            }
+            CommitDataType::SSTable => {
+                let sstable = SSTable::<PKShared, OptPVBuf>::load(pi_file_path)?;
+                let range_tombstones = sstable.range_tombstones().clone();
+                (Either::Right(sstable.into_iter_kv()), range_tombstones)
+            }
+        };
+
+        let shadowing_range_tombstones = newer_range_tombstones.clone();
+        newer_range_tombstones.extend_from(&range_tombstones);
+        let iter = iter.filter(move |res_pk_pv| match res_pk_pv {
+            Err(_) => true,
+            Ok((pk, _)) => shadowing_range_tombstones.covers(pk) == false,
+        });
+
+        prim_entrysets.push(iter);
+    }
+    let prim_entries = merging::merge_entry_iters(prim_entrysets.into_iter());
+    Ok(prim_entries)
+}
 
 impl<'job> ScndIdxCreationJob<'job> {
     pub(super) fn create_unit(&mut self) -> Result<Option<PathBuf>> {
@@ -26,34 +81,47 @@ impl<'job> ScndIdxCreationJob<'job> {
         Ok(merged_file_path)
     }
 
+    /// The entries derived from expiring primary entries expire at the same time.
+    ///
+    /// Each primary value is viewed while serialized. Only the sub-values, and the projection of a value that yields any, are allocated.
     fn derive_scnd_entries<'snap>(
         &'snap self,
-    ) -> Result<impl 'snap + Iterator<Item = Result<(SVPKShared, PVShared)>>> {
-        let mut prim_entrysets = vec![];
-        for pi_file_path in self.prim_entryset_file_paths.iter() {
-            let pi_file = fs_utils::open_file(pi_file_path, OpenOptions::new().read(true))?;
-            let reader = KeyValueReader::<_, PKShared, OptDatum<PVShared>>::from(pi_file);
-            let iter = reader.into_iter_kv();
-            prim_entrysets.push(iter);
-        }
-        let prim_entries = merging::merge_entry_iters(prim_entrysets.into_iter());
-        let nontomb_scnd_entries = prim_entries.filter_map(|res_pk_pv| match res_pk_pv {
-            Err(e) => Some(Err(e)),
-            Ok((_pk, OptDatum::Tombstone)) => None,
-            Ok((pk, OptDatum::Some(pv))) => match self.sv_spec.extract(&pv) {
-                None => None,
-                Some(sv) => {
-                    let svpk = SVPKShared { sv, pk };
-                    Some(Ok((svpk, pv)))
+    ) -> Result<impl 'snap + Iterator<Item = Result<(SVPKShared, OptDatum<PVShared>)>>> {
+        let now = serde::now_micros();
+        let prim_entries = merge_prim_optdat_entries(&self.prim_entryset_file_paths)?;
+        let scnd_entries = prim_entries.flat_map(move |res_pk_pv| {
+            let res_scnd_entries = res_pk_pv.and_then(|(pk, optpvbuf)| {
+                let optdat_pv = optpvbuf.view()?;
+                let pv = match optdat_pv.as_live(now) {
+                    None => return Ok(vec![]),
+                    Some(pv) => *pv,
+                };
+                let svs = self.sv_spec.extract_ref(pv);
+                if svs.is_empty() {
+                    return Ok(vec![]);
                 }
-            },
+                let proj_pv = self.sv_spec.project_ref(pv);
+                let expires_at = optdat_pv.expires_at();
+                let scnd_entries = svs
+                    .into_iter()
+                    .map(|sv| {
+                        let svpk = SVPKShared { sv, pk: pk.clone() };
+                        Ok((svpk, OptDatum::new_with_expiry(proj_pv.clone(), expires_at)))
+                    })
+                    .collect();
+                Ok(scnd_entries)
+            });
+            match res_scnd_entries {
+                Err(e) => vec![Err(e)],
+                Ok(scnd_entries) => scnd_entries,
+            }
         });
-        Ok(nontomb_scnd_entries)
+        Ok(scnd_entries)
     }
 
     fn create_all_intermediary_files<'a>(
         &self,
-        scnd_entries: impl 'a + Iterator<Item = Result<(SVPKShared, PVShared)>>,
+        scnd_entries: impl 'a + Iterator<Item = Result<(SVPKShared, OptDatum<PVShared>)>>,
     ) -> Result<Vec<PathBuf>> {
         let mut memtable = BTreeMap::new();
 
@@ -64,7 +132,7 @@ impl<'job> ScndIdxCreationJob<'job> {
 
             memtable.insert(svpk, pv);
 
-            if memtable.len() >= MEMTABLE_FLUSH_PERIOD_ITEM_COUNT {
+            if memtable.len() >= self.db.opts().memtable_flush_entry_count {
                 let interm_file_path = self.create_one_intermediary_file(&memtable)?;
                 interm_file_paths.push(interm_file_path);
 
@@ -82,7 +150,7 @@ impl<'job> ScndIdxCreationJob<'job> {
 
     fn create_one_intermediary_file(
         &self,
-        memtable: &BTreeMap<SVPKShared, PVShared>,
+        memtable: &BTreeMap<SVPKShared, OptDatum<PVShared>>,
     ) -> Result<PathBuf> {
         let interm_file_path = self.job_dir.format_new_kv_file_path();
         let interm_file = fs_utils::open_file(
@@ -99,37 +167,29 @@ impl<'job> ScndIdxCreationJob<'job> {
         Ok(interm_file_path)
     }
 
-    fn merge_intermediary_files(
-        &self,
-        mut interm_file_paths: Vec<PathBuf>,
-    ) -> Result<Option<PathBuf>> {
+    /// The intermediary files are plain sequences of entries.
+    /// The merged file is an SSTable, even if there is only one intermediary file.
+    fn merge_intermediary_files(&self, interm_file_paths: Vec<PathBuf>) -> Result<Option<PathBuf>> {
         if interm_file_paths.len() > 0 {
-            let merged_file_path;
-            if interm_file_paths.len() == 1 {
-                merged_file_path = interm_file_paths.pop().unwrap();
-            } else {
-                let entry_iters = interm_file_paths
-                    .into_iter()
-                    .map(|path| {
-                        let interm_file = fs_utils::open_file(path, OpenOptions::new().read(true))?;
-                        let iter = KeyValueReader::<_, SVPKShared, PVShared>::from(interm_file)
+            let entry_iters = interm_file_paths
+                .into_iter()
+                .map(|path| {
+                    let interm_file = fs_utils::open_file(path, OpenOptions::new().read(true))?;
+                    let iter =
+                        KeyValueReader::<_, SVPKShared, OptDatum<PVShared>>::from(interm_file)
                             .into_iter_kv();
-                        Ok(iter)
-                    })
-                    .collect::<Result<Vec<_>>>()?;
-                let entries = merging::merge_entry_iters(entry_iters.into_iter());
-
-                merged_file_path = self.job_dir.format_new_kv_file_path();
-                let mut merged_file = fs_utils::open_file(
-                    &merged_file_path,
-                    OpenOptions::new().create(true).write(true),
-                )?;
-                for entry in entries {
-                    let (svpk, pv) = entry?;
-                    svpk.ser(&mut merged_file)?;
-                    pv.ser(&mut merged_file)?;
-                }
-            };
+                    Ok(iter)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let entries = merging::merge_entry_iters(entry_iters.into_iter()).map(Entry::Own);
+
+            let merged_file_path = self.job_dir.format_new_kv_file_path();
+            SSTable::new(
+                entries,
+                RangeTombstones::default(),
+                merged_file_path.clone(),
+                self.db.opts().sstable_data_block_size,
+            )?;
 
             Ok(Some(merged_file_path))
         } else {
@@ -1,3 +1,7 @@
+use crate::{
+    ds_n_a::atomic_linked_list::ListSnapshot,
+    lsm::unit::{CommitDataType, CommittedUnit},
+};
 use anyhow::{anyhow, Result};
 use pancake_engine_common::fs_utils::{self, AntiCollisionParentDir, NamePattern};
 use std::path::{Path, PathBuf};
@@ -19,13 +23,13 @@ impl ScndIdxCreationsDir {
         Ok(Self { dir })
     }
 
-    pub(in crate::opers::sicr) fn create_new_job_dir(&self) -> Result<ScndIdxCreationJobDir> {
+    pub(in crate::opers) fn create_new_job_dir(&self) -> Result<ScndIdxCreationJobDir> {
         let job_dir_path = self.dir.format_new_child_path();
         ScndIdxCreationJobDir::new(job_dir_path)
     }
 }
 
-pub(in crate::opers::sicr) struct ScndIdxCreationJobDir {
+pub(in crate::opers) struct ScndIdxCreationJobDir {
     dir: AntiCollisionParentDir,
 }
 
@@ -45,6 +49,31 @@ impl ScndIdxCreationJobDir {
         self.dir.format_new_child_path()
     }
 
+    /// Hard-links the primary entrysets' files within the snapshot, from newer to older,
+    /// so that they remain readable after the snapshot is released.
+    ///
+    /// The parts of one entryset have disjoint key ranges, hence may be merged as if they were separate entrysets.
+    /// They are ordered from the last part to the first part, because the first part holds the entryset's range tombstones,
+    /// which must not shadow the other parts.
+    ///
+    /// @return Each linked file's path, with the format of the file.
+    pub fn link_prim_entrysets(
+        &self,
+        snap: &ListSnapshot<CommittedUnit>,
+    ) -> Result<Vec<(PathBuf, CommitDataType)>> {
+        let mut prim_entryset_file_paths = vec![];
+        for unit in snap.iter() {
+            if unit.prim.is_some() {
+                for prim_file_path in unit.dir.list_prim_file_paths()?.into_iter().rev() {
+                    let stg_file_path = self.format_new_kv_file_path();
+                    fs_utils::hard_link_file(prim_file_path, &stg_file_path)?;
+                    prim_entryset_file_paths.push((stg_file_path, unit.commit_info.data_type));
+                }
+            }
+        }
+        Ok(prim_entryset_file_paths)
+    }
+
     pub fn remove_dir(self) -> Result<()> {
         fs_utils::remove_dir_all(self.dir.parent_dir_path())?;
         Ok(())
@@ -1,12 +1,12 @@
 use crate::{db_state::ScndIdxRemovalResult, DB};
 use anyhow::Result;
 use derive_more::Display;
-use pancake_types::types::SubValueSpec;
+use pancake_types::types::ScndIdxSpec;
 
 impl DB {
     pub async fn delete_scnd_idx(
         &self,
-        sv_spec: &SubValueSpec,
+        sv_spec: &ScndIdxSpec,
     ) -> Result<(), ScndIdxDeletionJobErr> {
         {
             let db_state = self.db_state().read().await;
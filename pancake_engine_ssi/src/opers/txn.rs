@@ -12,15 +12,20 @@ use crate::{
     DB,
 };
 use anyhow::{anyhow, Result};
-use pancake_types::types::{PrimaryKey, SubValue};
+use pancake_types::{
+    serde,
+    types::{PrimaryKey, SVShared, SubValue},
+};
 use std::collections::HashMap;
 use tokio::sync::RwLockReadGuard;
 
 mod conflict;
+mod ssi_state;
 mod state_transition_helpers;
 mod state_transitions;
 mod stmt;
 
+pub use ssi_state::SsiState;
 use state_transitions::TryCommitResult;
 
 pub enum ClientCommitDecision<ClientOk> {
@@ -28,6 +33,34 @@ pub enum ClientCommitDecision<ClientOk> {
     Abort(ClientOk),
 }
 
+/// Which anomalies a txn is protected from, hence which dependencies it records.
+///
+/// At every level, a txn reads committed data only, and
+/// no two concurrent txns commit writes to the same key (i.e. the first committer wins).
+/// Uniqueness of unique indexes is guaranteed at every level too.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum IsolationLevel {
+    /// Each statement reads the latest committed snapshot, until the txn first writes.
+    /// From then on, the txn reads the snapshot as of its first write, plus its own writes.
+    ///
+    /// Non-repeatable reads, phantoms, lost updates and write skews are possible.
+    /// A lost update happens when the txn writes a key based on a read that preceded another txn's write to the key.
+    ReadCommitted,
+
+    /// The txn reads the snapshot as of its beginning, plus its own writes.
+    /// Only the written keys are checked for conflicts.
+    ///
+    /// Phantoms and write skews are possible.
+    SnapshotIsolation,
+
+    /// The written keys are checked for conflicts, as are the unique sub-values.
+    /// The read intervals are tracked as rw-antidependencies with concurrent txns,
+    /// and the txn is aborted only if its commit would complete a dangerous structure of them.
+    /// See [`SsiState`].
+    #[default]
+    Serializable,
+}
+
 pub struct Txn<'txn> {
     db: &'txn DB,
     db_state_guard: RwLockReadGuard<'txn, DbState>,
@@ -35,24 +68,56 @@ pub struct Txn<'txn> {
     snap: CachedSnap,
     snap_list_ver: ListVer,
 
+    isolation_level: IsolationLevel,
+    /// A read-only txn cannot write.
+    is_read_only: bool,
+
+    /// The intervals that this txn read.
     dependent_itvs_prim: IntervalSet<&'txn PrimaryKey>,
     dependent_itvs_scnds: HashMap<ScndIdxNum, IntervalSet<&'txn SubValue>>,
+    /// The sub-values of unique indexes, under which this txn verified that no other primary key is indexed.
+    dependent_unique_svs: HashMap<ScndIdxNum, IntervalSet<SVShared>>,
+
+    /// The keys and ranges that this txn wrote.
+    written_itvs_prim: IntervalSet<&'txn PrimaryKey>,
+    written_svs_scnds: HashMap<ScndIdxNum, IntervalSet<SVShared>>,
+
+    /// The snapshot as of which this txn is active in the [`SsiState`]. Some iff this txn records the intervals it reads.
+    ssi_snap_commit_ver: Option<CommitVer>,
 
     staging: Option<StagingUnit>,
 }
 
 impl<'txn> Txn<'txn> {
+    /// Runs `client_fn` in a [`IsolationLevel::Serializable`] txn.
+    /// See [`Self::run_with_isolation_level()`].
     pub async fn run<ClientOk>(
         db: &'txn DB,
         retry_limit: usize,
-        mut client_fn: impl FnMut(&mut Self) -> Result<ClientCommitDecision<ClientOk>>,
+        client_fn: impl FnMut(&mut Self) -> Result<ClientCommitDecision<ClientOk>>,
     ) -> Result<ClientOk> {
-        let db_state_guard = db.db_state().read().await;
-        if db_state_guard.is_terminating == true {
-            return Err(anyhow!("DB is terminating"));
-        }
+        Self::run_with_isolation_level(db, IsolationLevel::Serializable, retry_limit, client_fn)
+            .await
+    }
+
+    /// Runs `client_fn` in a txn. If the txn conflicts with another txn, `client_fn` is re-run in a new snapshot,
+    /// up to `retry_limit` times.
+    pub async fn run_with_isolation_level<ClientOk>(
+        db: &'txn DB,
+        isolation_level: IsolationLevel,
+        retry_limit: usize,
+        client_fn: impl FnMut(&mut Self) -> Result<ClientCommitDecision<ClientOk>>,
+    ) -> Result<ClientOk> {
+        let txn = Self::begin_with_isolation_level(db, isolation_level).await?;
+        txn.run_with_retries(retry_limit, client_fn).await
+    }
 
-        let mut txn = Self::new(db, db_state_guard).await;
+    async fn run_with_retries<ClientOk>(
+        self,
+        retry_limit: usize,
+        mut client_fn: impl FnMut(&mut Self) -> Result<ClientCommitDecision<ClientOk>>,
+    ) -> Result<ClientOk> {
+        let mut txn = self;
 
         let mut try_i = 0;
         loop {
@@ -89,12 +154,125 @@ impl<'txn> Txn<'txn> {
             }
         }
     }
+
+    /// Begins a txn whose statements are run over multiple calls, as opposed to within one [`Self::run()`].
+    ///
+    /// The txn holds its snapshot, as well as a read lock of the DB state, until it is ended
+    /// by either [`Self::commit()`] or [`Self::abort()`]. Hence the caller must end it timely.
+    pub async fn begin(db: &'txn DB) -> Result<Self> {
+        Self::begin_with_isolation_level(db, IsolationLevel::Serializable).await
+    }
+
+    /// Like [`Self::begin()`], at the given isolation level.
+    pub async fn begin_with_isolation_level(
+        db: &'txn DB,
+        isolation_level: IsolationLevel,
+    ) -> Result<Self> {
+        Self::do_begin(db, isolation_level, false, None).await
+    }
+
+    /// Like [`Self::begin()`], but the txn is read-only. Any write is rejected.
+    ///
+    /// Like any serializable txn, it records the intervals it reads. It never writes, so it's serialized as of
+    /// its snapshot. Still, it may be the `T_in` of a dangerous structure (see [`SsiState`]),
+    /// in which case it conflicts.
+    pub async fn begin_read_only(db: &'txn DB) -> Result<Self> {
+        Self::do_begin(db, IsolationLevel::Serializable, true, None).await
+    }
+
+    /// Like [`Self::run()`], but the txn is read-only. See [`Self::begin_read_only()`].
+    pub async fn run_read_only<ClientOk>(
+        db: &'txn DB,
+        retry_limit: usize,
+        mut client_fn: impl FnMut(&mut Self) -> Result<ClientOk>,
+    ) -> Result<ClientOk> {
+        let txn = Self::do_begin(db, IsolationLevel::Serializable, true, None).await?;
+        txn.run_with_retries(retry_limit, |txn| {
+            client_fn(txn).map(ClientCommitDecision::Commit)
+        })
+        .await
+    }
+
+    /// Like [`Self::begin_read_only()`], but the txn reads the snapshot as of an older commit version.
+    ///
+    /// The dependencies that the snapshot may be involved in are not retained, hence the txn is not tracked
+    /// by [`SsiState`]. It records no dependencies, and it always commits.
+    ///
+    /// @arg `commit_ver`: One of [`DB::retained_commit_vers()`]. Otherwise, Err is returned.
+    pub async fn begin_read_only_as_of(db: &'txn DB, commit_ver: CommitVer) -> Result<Self> {
+        Self::do_begin(db, IsolationLevel::Serializable, true, Some(commit_ver)).await
+    }
+
+    /// Like [`Self::run_read_only()`], but the txn reads the snapshot as of an older commit version.
+    /// See [`Self::begin_read_only_as_of()`].
+    ///
+    /// Because it never conflicts, `client_fn` is run exactly once.
+    pub async fn run_read_only_as_of<ClientOk>(
+        db: &'txn DB,
+        commit_ver: CommitVer,
+        client_fn: impl FnOnce(&mut Self) -> Result<ClientOk>,
+    ) -> Result<ClientOk> {
+        let txn = Self::do_begin(db, IsolationLevel::Serializable, true, Some(commit_ver)).await?;
+        txn.run_once(client_fn).await
+    }
+
+    async fn run_once<ClientOk>(
+        mut self,
+        client_fn: impl FnOnce(&mut Self) -> Result<ClientOk>,
+    ) -> Result<ClientOk> {
+        let client_res = client_fn(&mut self);
+        self.close().await?;
+        client_res
+    }
+
+    /// A txn as of an older commit version records no dependencies regardless of `isolation_level`.
+    async fn do_begin(
+        db: &'txn DB,
+        isolation_level: IsolationLevel,
+        is_read_only: bool,
+        as_of: Option<CommitVer>,
+    ) -> Result<Self> {
+        let db_state_guard = db.db_state().read().await;
+        if db_state_guard.is_terminating == true {
+            return Err(anyhow!("DB is terminating"));
+        }
+
+        Self::new(db, db_state_guard, isolation_level, is_read_only, as_of).await
+    }
+
+    /// The commit version as of which this txn reads.
+    pub fn snap_commit_ver(&self) -> CommitVer {
+        self.snap.commit_ver_hi_incl
+    }
+
+    /// Ends a txn that was begun by [`Self::begin()`].
+    ///
+    /// @return Whether the txn committed. If false, the txn conflicted with another txn and was aborted;
+    ///     the caller may retry all statements in a new txn.
+    pub async fn commit(self) -> Result<bool> {
+        match self.try_commit().await? {
+            TryCommitResult::Conflict(txn) => {
+                txn.close().await?;
+                Ok(false)
+            }
+            TryCommitResult::DidCommit => Ok(true),
+        }
+    }
+
+    /// Ends a txn that was begun by [`Self::begin()`], discarding its writes.
+    pub async fn abort(self) -> Result<()> {
+        self.close().await
+    }
 }
 
 struct CachedSnap {
     commit_ver_hi_incl: CommitVer,
     commit_ver_lo_excl: Option<CommitVer>,
 
+    /// The time as of which expiring entries are evaluated, in microseconds since the unix epoch.
+    /// Hence the txn sees entries expire at a single point in time, as it sees commits.
+    now: i64,
+
     /// The lifetime is marked as `'static` for our convenience.
     iter: IteratorCache<TxnSnapIterator, &'static CommittedUnit>,
 }
@@ -104,13 +282,15 @@ impl CachedSnap {
         commit_ver_lo_excl: Option<CommitVer>,
         list_snap: ListSnapshot<CommittedUnit>,
     ) -> Self {
-        let iter = TxnSnapIterator::new(list_snap.iter(), commit_ver_lo_excl);
+        let iter = TxnSnapIterator::new(list_snap.iter(), commit_ver_hi_incl, commit_ver_lo_excl);
         let iter = IteratorCache::new(iter);
 
         Self {
             commit_ver_hi_incl,
             commit_ver_lo_excl,
 
+            now: serde::now_micros(),
+
             iter,
         }
     }
@@ -120,19 +300,25 @@ impl CachedSnap {
     }
 }
 
-/// A named [`std::iter::TakeWhile`].
+/// A named [`std::iter::SkipWhile`] followed by a named [`std::iter::TakeWhile`].
+///
+/// Units newer than the snapshot exist iff the snapshot is as of an older commit version.
+/// The snapshot's boundary is held, hence no unit straddles it.
 struct TxnSnapIterator {
     iter: ListIterator<'static, CommittedUnit>,
+    commit_ver_hi_incl: CommitVer,
     commit_ver_lo_excl: Option<CommitVer>,
     iter_reached_end: bool,
 }
 impl TxnSnapIterator {
     fn new(
         iter: ListIterator<'static, CommittedUnit>,
+        commit_ver_hi_incl: CommitVer,
         commit_ver_lo_excl: Option<CommitVer>,
     ) -> Self {
         Self {
             iter,
+            commit_ver_hi_incl,
             commit_ver_lo_excl,
             iter_reached_end: false,
         }
@@ -142,7 +328,10 @@ impl Iterator for TxnSnapIterator {
     type Item = &'static CommittedUnit;
     fn next(&mut self) -> Option<Self::Item> {
         if self.iter_reached_end == false {
-            self.iter.next().and_then(|unit| {
+            let commit_ver_hi_incl = self.commit_ver_hi_incl;
+            let mut iter = (&mut self.iter)
+                .skip_while(|unit| commit_ver_hi_incl < unit.commit_info.commit_ver_hi_incl);
+            iter.next().and_then(|unit| {
                 let is_included = match self.commit_ver_lo_excl {
                     None => true,
                     Some(cmt_ver_lo) => cmt_ver_lo < unit.commit_info.commit_ver_hi_incl,
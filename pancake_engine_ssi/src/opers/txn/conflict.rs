@@ -1,29 +1,47 @@
-use crate::opers::txn::Txn;
+use crate::opers::txn::{ssi_state::TxnDeps, Txn};
 use anyhow::Result;
+use pancake_types::types::{PrimaryKey, SVShared, SubValue};
+use std::sync::Arc;
 
 impl<'txn> Txn<'txn> {
+    /// Checks the conflicts that abort a txn regardless of dependencies among txns, namely
+    /// - any written key or range that another txn wrote concurrently (i.e. the first committer wins); and
+    /// - any unique sub-value that another txn indexed concurrently.
+    ///
+    /// Read intervals are checked separately. See [`SsiState`](super::SsiState).
     pub(super) fn has_conflict(&mut self) -> Result<bool> {
-        let dep_itvs_prim = self.dependent_itvs_prim.merge();
-        let dep_scnds = self
-            .dependent_itvs_scnds
+        let written_itvs_prim = self.written_itvs_prim.merge();
+        let dep_uniques = self
+            .dependent_unique_svs
             .iter_mut()
-            .map(|(si_num, scnd_itvset)| {
-                let itvs_scnd = scnd_itvset.merge();
-                (*si_num, itvs_scnd)
+            .map(|(si_num, unique_itvset)| {
+                let itvs_unique = unique_itvset.merge();
+                (*si_num, itvs_unique)
             })
             .collect::<Vec<_>>();
 
         for unit in self.snap.iter() {
             if let Some(committed_prim) = unit.prim.as_ref() {
-                let has_conflict = dep_itvs_prim.overlaps_with(committed_prim.get_all_keys())?;
+                let has_conflict = committed_prim
+                    .range_tombstones()
+                    .iter()
+                    .any(|(lo, hi)| written_itvs_prim.overlaps_with_range(lo, hi));
+                if has_conflict {
+                    return Ok(true);
+                }
+
+                let has_conflict = written_itvs_prim.overlaps_with(
+                    committed_prim.get_all_keys_of_overlapping_files(&written_itvs_prim),
+                )?;
                 if has_conflict {
                     return Ok(true);
                 }
             }
-            for (si_num, dep_itvs_scnd) in dep_scnds.iter() {
+            for (si_num, dep_itvs_unique) in dep_uniques.iter() {
                 if let Some(committed_scnd) = unit.scnds.get(si_num) {
-                    let has_conflict =
-                        dep_itvs_scnd.overlaps_with(committed_scnd.get_all_keys())?;
+                    let has_conflict = dep_itvs_unique.overlaps_with(
+                        committed_scnd.get_all_keys_of_overlapping_files(dep_itvs_unique),
+                    )?;
                     if has_conflict {
                         return Ok(true);
                     }
@@ -33,4 +51,32 @@ impl<'txn> Txn<'txn> {
 
         Ok(false)
     }
+
+    /// Copies the read and written intervals into owned ones, s.t. they can outlive this txn.
+    pub(super) fn to_txn_deps(&self) -> TxnDeps {
+        let read_itvs_scnds = self
+            .dependent_itvs_scnds
+            .iter()
+            .map(|(si_num, itvset)| {
+                let itvset = itvset.map(|sv| SVShared::Own(Arc::new(SubValue(sv.0.clone()))));
+                (*si_num, itvset)
+            })
+            .collect();
+        let written_svs_scnds = self
+            .written_svs_scnds
+            .iter()
+            .map(|(si_num, itvset)| (*si_num, itvset.map(SVShared::clone)))
+            .collect();
+
+        TxnDeps {
+            read_itvs_prim: self
+                .dependent_itvs_prim
+                .map(|pk| Arc::new(PrimaryKey(pk.0.clone()))),
+            read_itvs_scnds,
+            written_itvs_prim: self
+                .written_itvs_prim
+                .map(|pk| Arc::new(PrimaryKey(pk.0.clone()))),
+            written_svs_scnds,
+        }
+    }
 }
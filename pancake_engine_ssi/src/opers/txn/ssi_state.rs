@@ -0,0 +1,204 @@
+use crate::{db_state::ScndIdxNum, ds_n_a::interval_set::IntervalSet, lsm::unit::CommitVer};
+use anyhow::{anyhow, Result};
+use pancake_types::types::{PKShared, SVShared};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// The rw-antidependencies among concurrent txns, as tracked by Serializable Snapshot Isolation.
+///
+/// An rw-antidependency `R -> W` exists iff `R` read an interval that `W` wrote, and `W` committed after `R`'s snapshot.
+/// Every cycle of dependencies, i.e. every non-serializable execution, contains a "dangerous structure"
+/// `T_in -> T_pivot -> T_out` of rw-antidependencies, where `T_out` commits first among the three.
+/// (`T_in` and `T_out` may be the same txn.)
+/// Hence a txn is aborted only if its commit would complete a dangerous structure.
+///
+/// Each rw-antidependency is detected when the later one of its two txns commits. Hence, the dependencies of
+/// each committed txn are kept, for as long as any active serializable txn is concurrent with it.
+///
+/// A serializable txn that writes nothing, including a read-only txn, commits no new version.
+/// It is serialized as of its snapshot instead. Hence it is kept as committed as of its snapshot,
+/// s.t. a `T_pivot` that commits later still detects it as `T_in`.
+///
+/// Only serializable txns record their reads, and only they can be `T_in` or `T_pivot`.
+/// A txn at any other isolation level is tracked as a writer only.
+#[derive(Default)]
+pub struct SsiState {
+    /// The snapshots of active serializable txns, and their counts.
+    active_snap_commit_vers: BTreeMap<CommitVer, usize>,
+
+    /// From older to newer.
+    committed: VecDeque<CommittedTxnDeps>,
+}
+
+struct CommittedTxnDeps {
+    /// For a txn that wrote nothing, its snapshot.
+    commit_ver: CommitVer,
+    deps: TxnDeps,
+    /// The oldest commit among the txns that committed before this txn, and that wrote what this txn read.
+    /// If Some, then this txn is a `T_pivot` whose `T_out` committed first.
+    out_commit_ver: Option<CommitVer>,
+}
+
+/// The intervals that one txn read and wrote.
+pub struct TxnDeps {
+    pub read_itvs_prim: IntervalSet<PKShared>,
+    pub read_itvs_scnds: HashMap<ScndIdxNum, IntervalSet<SVShared>>,
+    pub written_itvs_prim: IntervalSet<PKShared>,
+    pub written_svs_scnds: HashMap<ScndIdxNum, IntervalSet<SVShared>>,
+}
+
+impl TxnDeps {
+    /// @return Whether `self` read any interval that `writer` wrote.
+    fn has_read_written_by(&mut self, writer: &mut TxnDeps) -> bool {
+        let written_itvs_prim = writer.written_itvs_prim.merge();
+        if self
+            .read_itvs_prim
+            .merge()
+            .overlaps_with_itvs(&written_itvs_prim)
+        {
+            return true;
+        }
+        for (si_num, read_itvs_scnd) in self.read_itvs_scnds.iter_mut() {
+            if let Some(written_svs_scnd) = writer.written_svs_scnds.get_mut(si_num) {
+                let written_svs_scnd = written_svs_scnd.merge();
+                if read_itvs_scnd.merge().overlaps_with_itvs(&written_svs_scnd) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+/// The rw-antidependencies between a committing txn and the concurrent committed txns.
+pub struct Conflicts {
+    /// The oldest commit among the txns that wrote what the committing txn read.
+    pub min_out_commit_ver: Option<CommitVer>,
+    /// The newest commit among the txns that read what the committing txn wrote.
+    pub max_in_commit_ver: Option<CommitVer>,
+    /// Whether any txn that wrote what the committing txn read is a `T_pivot` whose `T_out` committed first.
+    pub is_out_to_pivot: bool,
+}
+
+impl Conflicts {
+    /// The committing txn completes a dangerous structure, either as `T_in`, or as `T_pivot`
+    /// whose `T_out` committed no later than its `T_in`.
+    pub fn is_dangerous(&self) -> bool {
+        if self.is_out_to_pivot {
+            return true;
+        }
+        match (self.min_out_commit_ver, self.max_in_commit_ver) {
+            (Some(out_ver), Some(in_ver)) => out_ver <= in_ver,
+            _ => false,
+        }
+    }
+}
+
+impl SsiState {
+    pub fn add_active(&mut self, snap_commit_ver: CommitVer) {
+        *self
+            .active_snap_commit_vers
+            .entry(snap_commit_ver)
+            .or_insert(0) += 1;
+    }
+
+    pub fn remove_active(&mut self, snap_commit_ver: CommitVer) -> Result<()> {
+        let count = self
+            .active_snap_commit_vers
+            .get_mut(&snap_commit_ver)
+            .ok_or_else(|| anyhow!("Removing a non-existent active txn."))?;
+        *count -= 1;
+        if *count == 0 {
+            self.active_snap_commit_vers.remove(&snap_commit_ver);
+        }
+
+        self.remove_unneeded_committed();
+
+        Ok(())
+    }
+
+    /// @arg snap_commit_ver: The snapshot that the committing txn read.
+    /// @arg is_writing: Whether the committing txn writes. If not, it is serialized as of its snapshot.
+    ///     Hence it completes a dangerous structure as `T_in` only if `T_out` committed no later than its snapshot.
+    pub fn find_conflicts(
+        &mut self,
+        snap_commit_ver: CommitVer,
+        is_writing: bool,
+        deps: &mut TxnDeps,
+    ) -> Conflicts {
+        let mut conflicts = Conflicts {
+            min_out_commit_ver: None,
+            max_in_commit_ver: None,
+            is_out_to_pivot: false,
+        };
+
+        for committed in self.committed.iter_mut() {
+            if committed.commit_ver <= snap_commit_ver {
+                continue;
+            }
+
+            if deps.has_read_written_by(&mut committed.deps) {
+                if conflicts.min_out_commit_ver.is_none() {
+                    conflicts.min_out_commit_ver = Some(committed.commit_ver);
+                }
+                if let Some(pivot_out_commit_ver) = committed.out_commit_ver {
+                    if is_writing || pivot_out_commit_ver <= snap_commit_ver {
+                        conflicts.is_out_to_pivot = true;
+                    }
+                }
+            }
+
+            if committed.deps.has_read_written_by(deps) {
+                conflicts.max_in_commit_ver = Some(committed.commit_ver);
+            }
+        }
+
+        conflicts
+    }
+
+    /// Keeps the dependencies of a newly committed txn, iff any active serializable txn is concurrent with it.
+    ///
+    /// The committing txn itself must have been removed from the active ones beforehand.
+    ///
+    /// @arg commit_ver: For a txn that wrote nothing, its snapshot. Hence it may be older than other committed txns.
+    /// @arg out_commit_ver: See [`Conflicts::min_out_commit_ver`].
+    pub fn add_committed(
+        &mut self,
+        commit_ver: CommitVer,
+        deps: TxnDeps,
+        out_commit_ver: Option<CommitVer>,
+    ) {
+        let min_active = self.active_snap_commit_vers.keys().next().copied();
+        let is_needed = match min_active {
+            None => false,
+            Some(min_active) => min_active < commit_ver,
+        };
+        if is_needed {
+            let idx = self
+                .committed
+                .partition_point(|committed| committed.commit_ver <= commit_ver);
+            self.committed.insert(
+                idx,
+                CommittedTxnDeps {
+                    commit_ver,
+                    deps,
+                    out_commit_ver,
+                },
+            );
+        }
+    }
+
+    /// A committed txn is no longer needed once every active txn's snapshot includes it.
+    fn remove_unneeded_committed(&mut self) {
+        let min_active = self.active_snap_commit_vers.keys().next().copied();
+        while let Some(committed) = self.committed.front() {
+            let is_unneeded = match min_active {
+                None => true,
+                Some(min_active) => committed.commit_ver <= min_active,
+            };
+            if is_unneeded == false {
+                break;
+            }
+            self.committed.pop_front();
+        }
+    }
+}
@@ -1,8 +1,11 @@
 use crate::ds_n_a::interval_set::IntervalSet;
 use crate::{
     db_state::DbState,
-    lsm::LsmState,
-    opers::txn::{CachedSnap, Txn},
+    lsm::{unit::CommitVer, LsmState},
+    opers::txn::{
+        ssi_state::{SsiState, TxnDeps},
+        CachedSnap, IsolationLevel, Txn,
+    },
     DB,
 };
 use anyhow::Result;
@@ -10,39 +13,67 @@ use std::collections::HashMap;
 use tokio::sync::{MutexGuard, RwLockReadGuard};
 
 impl<'txn> Txn<'txn> {
-    pub(super) async fn new(db: &'txn DB, db_state_guard: RwLockReadGuard<'txn, DbState>) -> Self {
+    pub(super) async fn new(
+        db: &'txn DB,
+        db_state_guard: RwLockReadGuard<'txn, DbState>,
+        isolation_level: IsolationLevel,
+        is_read_only: bool,
+        as_of: Option<CommitVer>,
+    ) -> Result<Self> {
         let snap_commit_ver_hi_incl;
         let list_snap;
         let snap_list_ver;
+        let mut ssi_snap_commit_ver = None;
         {
             let mut lsm_state = db.lsm_state().lock().await;
 
-            snap_commit_ver_hi_incl = lsm_state.hold_curr_commit_ver();
+            snap_commit_ver_hi_incl = match as_of {
+                None => lsm_state.hold_curr_commit_ver(),
+                Some(commit_ver) => lsm_state.hold_retained_commit_ver(commit_ver)?,
+            };
 
             list_snap = lsm_state.list().snap();
 
             snap_list_ver = lsm_state.hold_curr_list_ver();
+
+            if as_of.is_none() && (isolation_level == IsolationLevel::Serializable) {
+                let mut ssi_state = db.ssi_state().lock().await;
+                ssi_state.add_active(snap_commit_ver_hi_incl);
+                ssi_snap_commit_ver = Some(snap_commit_ver_hi_incl);
+            }
         }
 
         let snap = CachedSnap::new(snap_commit_ver_hi_incl, None, list_snap);
 
-        Self {
+        Ok(Self {
             db,
             db_state_guard,
 
             snap,
             snap_list_ver,
 
+            isolation_level,
+            is_read_only,
+
             dependent_itvs_prim: IntervalSet::new(),
             dependent_itvs_scnds: HashMap::new(),
+            dependent_unique_svs: HashMap::new(),
+
+            written_itvs_prim: IntervalSet::new(),
+            written_svs_scnds: HashMap::new(),
+
+            ssi_snap_commit_ver,
 
             staging: None,
-        }
+        })
     }
 
     pub(super) async fn try_commit(mut self) -> Result<TryCommitResult<'txn>> {
         match &mut self.staging {
             None => {
+                if self.try_commit_without_writes().await? == false {
+                    return Ok(TryCommitResult::Conflict(self));
+                }
                 self.close().await?;
                 return Ok(TryCommitResult::DidCommit);
             }
@@ -51,6 +82,8 @@ impl<'txn> Txn<'txn> {
             }
         }
 
+        let mut deps = self.to_txn_deps();
+
         loop {
             let lsm_state = self.db.lsm_state().lock().await;
 
@@ -60,12 +93,84 @@ impl<'txn> Txn<'txn> {
                     return Ok(TryCommitResult::Conflict(self));
                 }
             } else {
-                self.do_commit(lsm_state)?;
+                let mut ssi_state = self.db.ssi_state().lock().await;
+
+                let mut out_commit_ver = None;
+                if let Some(ssi_snap_commit_ver) = self.ssi_snap_commit_ver {
+                    let conflicts = ssi_state.find_conflicts(ssi_snap_commit_ver, true, &mut deps);
+                    if conflicts.is_dangerous() {
+                        return Ok(TryCommitResult::Conflict(self));
+                    }
+                    out_commit_ver = conflicts.min_out_commit_ver;
+                }
+
+                self.do_commit(lsm_state, &mut ssi_state, deps, out_commit_ver)?;
                 return Ok(TryCommitResult::DidCommit);
             }
         }
     }
 
+    /// A serializable txn that wrote nothing commits no new version, and is serialized as of its snapshot.
+    /// Still, it is checked as `T_in` of any dangerous structure, and its dependencies are kept as committed
+    /// as of its snapshot, s.t. a `T_pivot` that commits later detects it as `T_in`.
+    ///
+    /// The txn stays active until it's closed.
+    ///
+    /// @return Whether the txn may commit.
+    async fn try_commit_without_writes(&self) -> Result<bool> {
+        if let Some(ssi_snap_commit_ver) = self.ssi_snap_commit_ver {
+            let mut deps = self.to_txn_deps();
+
+            let _lsm_state = self.db.lsm_state().lock().await;
+            let mut ssi_state = self.db.ssi_state().lock().await;
+
+            let conflicts = ssi_state.find_conflicts(ssi_snap_commit_ver, false, &mut deps);
+            if conflicts.is_dangerous() {
+                return Ok(false);
+            }
+            ssi_state.add_committed(ssi_snap_commit_ver, deps, None);
+        }
+        Ok(true)
+    }
+
+    /// Under [`IsolationLevel::ReadCommitted`], moves the snapshot to the current commit version,
+    /// as long as the txn has not written.
+    ///
+    /// If the LSM state is contended, the current snapshot, which is committed too, is kept.
+    ///
+    /// The held [`ListVer`](crate::lsm::ListVer) is kept as is. Because it's older,
+    /// it protects the nodes of the new snapshot as well.
+    pub(super) fn refresh_snapshot_for_read_committed(&mut self) -> Result<()> {
+        if (self.isolation_level != IsolationLevel::ReadCommitted) || self.staging.is_some() {
+            return Ok(());
+        }
+
+        let mut lsm_state = match self.db.lsm_state().try_lock() {
+            Err(_) => return Ok(()),
+            Ok(lsm_state) => lsm_state,
+        };
+        if self.snap.commit_ver_hi_incl == lsm_state.curr_commit_ver() {
+            return Ok(());
+        }
+
+        let fc_able_commit_vers = lsm_state.unhold_commit_vers([
+            Some(self.snap.commit_ver_hi_incl),
+            self.snap.commit_ver_lo_excl,
+        ])?;
+
+        let snap_commit_ver_hi_incl = lsm_state.hold_curr_commit_ver();
+
+        let list_snap = lsm_state.list().snap();
+
+        drop(lsm_state);
+
+        self.snap = CachedSnap::new(snap_commit_ver_hi_incl, None, list_snap);
+
+        self.notify_fc_worker(None, fc_able_commit_vers);
+
+        Ok(())
+    }
+
     fn update_snapshot_for_conflict_checking(
         &mut self,
         mut lsm_state: MutexGuard<LsmState>,
@@ -113,6 +218,13 @@ impl<'txn> Txn<'txn> {
 
             (snap_list_ver, updated_mhlv) =
                 lsm_state.hold_and_unhold_list_ver(self.snap_list_ver)?;
+
+            if let Some(ssi_snap_commit_ver) = self.ssi_snap_commit_ver.as_mut() {
+                let mut ssi_state = self.db.ssi_state().lock().await;
+                ssi_state.remove_active(*ssi_snap_commit_ver)?;
+                ssi_state.add_active(snap_commit_ver_hi_incl);
+                *ssi_snap_commit_ver = snap_commit_ver_hi_incl;
+            }
         }
 
         self.snap = CachedSnap::new(snap_commit_ver_hi_incl, None, list_snap);
@@ -123,6 +235,9 @@ impl<'txn> Txn<'txn> {
 
         self.dependent_itvs_prim.clear();
         self.dependent_itvs_scnds.clear();
+        self.dependent_unique_svs.clear();
+        self.written_itvs_prim.clear();
+        self.written_svs_scnds.clear();
         if let Some(stg) = self.staging.as_mut() {
             stg.clear()?;
         }
@@ -142,6 +257,11 @@ impl<'txn> Txn<'txn> {
             ])?;
 
             updated_mhlv = lsm_state.unhold_list_ver(self.snap_list_ver)?;
+
+            if let Some(ssi_snap_commit_ver) = self.ssi_snap_commit_ver {
+                let mut ssi_state = self.db.ssi_state().lock().await;
+                ssi_state.remove_active(ssi_snap_commit_ver)?;
+            }
         }
 
         self.notify_fc_worker(updated_mhlv, fc_able_commit_vers);
@@ -153,14 +273,27 @@ impl<'txn> Txn<'txn> {
         Ok(())
     }
 
-    fn do_commit(mut self, mut lsm_state: MutexGuard<LsmState>) -> Result<()> {
+    /// @arg out_commit_ver: See [`SsiState::add_committed()`].
+    fn do_commit(
+        mut self,
+        mut lsm_state: MutexGuard<LsmState>,
+        ssi_state: &mut SsiState,
+        deps: TxnDeps,
+        out_commit_ver: Option<CommitVer>,
+    ) -> Result<()> {
         let stg = self.staging.take().unwrap();
-        lsm_state.bump_commit_ver(stg)?;
+        let unretained_commit_ver = lsm_state.bump_commit_ver(stg)?;
 
-        let fc_able_commit_vers = lsm_state.unhold_commit_vers([
+        if let Some(ssi_snap_commit_ver) = self.ssi_snap_commit_ver {
+            ssi_state.remove_active(ssi_snap_commit_ver)?;
+        }
+        ssi_state.add_committed(lsm_state.curr_commit_ver(), deps, out_commit_ver);
+
+        let [snap_hi, snap_lo] = lsm_state.unhold_commit_vers([
             Some(self.snap.commit_ver_hi_incl),
             self.snap.commit_ver_lo_excl,
         ])?;
+        let fc_able_commit_vers = [unretained_commit_ver, snap_hi, snap_lo];
 
         let updated_mhlv = lsm_state.unhold_list_ver(self.snap_list_ver)?;
 
@@ -172,6 +305,7 @@ impl<'txn> Txn<'txn> {
     }
 }
 
+#[allow(clippy::large_enum_variant)] // Returned once per commit attempt. Boxing would not pay off.
 pub(super) enum TryCommitResult<'txn> {
     Conflict(Txn<'txn>),
     DidCommit,
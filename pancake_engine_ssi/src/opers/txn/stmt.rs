@@ -1,37 +1,122 @@
 use crate::ds_n_a::interval_set::{Interval, IntervalSet};
 use crate::{
     db_state::ScndIdxState,
-    lsm::{entryset::merging, unit::StagingUnit},
+    lsm::{
+        entryset::{merging, CommittedEntrySet},
+        unit::StagingUnit,
+    },
     opers::txn::Txn,
 };
 use anyhow::{anyhow, Result};
-use pancake_engine_common::Entry;
+use itertools::Either;
+use pancake_engine_common::{Entry, WritableMemLog};
 use pancake_types::serde::OptDatum;
-use pancake_types::types::{PKShared, PVShared, PrimaryKey, SVPKShared, SubValue, SubValueSpec};
+use pancake_types::types::{
+    OptPVBuf, PKShared, PVShared, PrimaryKey, SVPKShared, SVShared, ScndIdxSpec, SubValue,
+    ValueConstraint,
+};
+use std::time::Duration;
 
 impl<'txn> Txn<'txn> {
     pub fn get_pk_one(&mut self, pk: &'txn PrimaryKey) -> Result<Option<(PKShared, PVShared)>> {
-        self.dependent_itvs_prim.add(Interval {
-            lo_incl: Some(pk),
-            hi_incl: Some(pk),
-        });
+        self.refresh_snapshot_for_read_committed()?;
 
-        if let Some(stg) = self.staging.as_ref() {
-            if let Some((pk, opt_pv)) = stg.prim.r_memlog().get_one(pk) {
-                let opt_pv: Option<PVShared> = opt_pv.clone().into();
-                let opt_pkpv = opt_pv.map(|pv| (pk.clone(), pv));
-                return Ok(opt_pkpv);
-            }
+        let now = self.snap.now;
+        let opt_pkpv = self.get_pk_one_optdat(pk)?;
+        let opt_pkpv = opt_pkpv.and_then(|(pk, optdat)| optdat.into_live(now).map(|pv| (pk, pv)));
+        Ok(opt_pkpv)
+    }
+
+    /// Like [`Self::get_pk_one()`], but the value may be a tombstone or expired.
+    fn get_pk_one_optdat(
+        &mut self,
+        pk: &'txn PrimaryKey,
+    ) -> Result<Option<(PKShared, OptDatum<PVShared>)>> {
+        if self.is_tracking_reads() {
+            self.dependent_itvs_prim.add(Interval {
+                lo_incl: Some(pk),
+                hi_incl: Some(pk),
+            });
         }
 
+        let stg = self.staging.as_ref().map(|stg| &stg.prim);
         let committed_entrysets = self.snap.iter().filter_map(|unit| unit.prim.as_ref());
+        Self::get_pk_one_optdat_from(stg, committed_entrysets, pk)
+    }
+
+    /// Does not record any dependency.
+    ///
+    /// @return None if the key is absent or covered by a range tombstone.
+    fn get_pk_one_optdat_from<'a>(
+        stg: Option<&WritableMemLog<PKShared, OptDatum<PVShared>>>,
+        committed_entrysets: impl Iterator<Item = &'a CommittedEntrySet<PKShared, OptDatum<PVShared>>>,
+        pk: &PrimaryKey,
+    ) -> Result<Option<(PKShared, OptDatum<PVShared>)>> {
+        if let Some(stg) = stg {
+            let r_memlog = stg.r_memlog();
+            if let Some((pk, optdat_pv)) = r_memlog.get_one(pk) {
+                return Ok(Some((pk.clone(), optdat_pv.clone())));
+            }
+            if r_memlog.range_tombstones.covers(pk) {
+                return Ok(None);
+            }
+        }
+
         for entryset in committed_entrysets {
             let gotten = entryset.get_one(pk);
             if let Some(entry) = gotten {
-                return entry
-                    .to_option_entry()
-                    .map(|entry| entry.into_owned_kv())
-                    .transpose();
+                return entry.into_owned_kv().map(Some);
+            }
+            if entryset.range_tombstones().covers(pk) {
+                return Ok(None);
+            }
+        }
+
+        return Ok(None);
+    }
+
+    /// Like [`Self::get_pk_one_optdat_from()`], but for deriving the secondary index delta of overwriting the value.
+    ///
+    /// @return The live value and its expiry time, if any. A value that is read from an SSTable is kept serialized.
+    fn get_pk_one_old_pv_from<'a>(
+        stg: Option<&WritableMemLog<PKShared, OptDatum<PVShared>>>,
+        committed_entrysets: impl Iterator<Item = &'a CommittedEntrySet<PKShared, OptDatum<PVShared>>>,
+        pk: &PrimaryKey,
+        now: i64,
+    ) -> Result<Option<(OldPV, Option<i64>)>> {
+        let from_mem = |optdat_pv: &OptDatum<PVShared>| {
+            let pv = optdat_pv.as_live(now)?;
+            Some((OldPV::Mem(pv.clone()), optdat_pv.expires_at()))
+        };
+
+        if let Some(stg) = stg {
+            let r_memlog = stg.r_memlog();
+            if let Some((_, optdat_pv)) = r_memlog.get_one(pk) {
+                return Ok(from_mem(optdat_pv));
+            }
+            if r_memlog.range_tombstones.covers(pk) {
+                return Ok(None);
+            }
+        }
+
+        for entryset in committed_entrysets {
+            match entryset.get_one_as::<_, OptPVBuf>(pk) {
+                None => {}
+                Some(Either::Left((_, optdat_pv))) => return Ok(from_mem(optdat_pv)),
+                Some(Either::Right(res)) => {
+                    let (_, optpvbuf) = res?;
+                    let (is_live, expires_at) = {
+                        let optdat_pv = optpvbuf.view()?;
+                        (optdat_pv.as_live(now).is_some(), optdat_pv.expires_at())
+                    };
+                    if is_live == false {
+                        return Ok(None);
+                    }
+                    return Ok(Some((OldPV::Ser(optpvbuf), expires_at)));
+                }
+            }
+            if entryset.range_tombstones().covers(pk) {
+                return Ok(None);
             }
         }
 
@@ -43,25 +128,60 @@ impl<'txn> Txn<'txn> {
         pk_lo: Option<&'txn PrimaryKey>,
         pk_hi: Option<&'txn PrimaryKey>,
     ) -> impl Iterator<Item = Entry<PKShared, PVShared>> {
-        self.dependent_itvs_prim.add(Interval {
-            lo_incl: pk_lo,
-            hi_incl: pk_hi,
-        });
+        /* Upon failure, the current snapshot is read, following the error. */
+        let refresh_err = self.refresh_snapshot_for_read_committed().err();
+        let refresh_err = refresh_err.map(|e| Entry::Own(Err(e)));
+
+        if self.is_tracking_reads() {
+            self.dependent_itvs_prim.add(Interval {
+                lo_incl: pk_lo,
+                hi_incl: pk_hi,
+            });
+        }
 
+        let now = self.snap.now;
         let stg = self.staging.as_ref().map(|stg| &stg.prim);
         let committed_entrysets = self.snap.iter().filter_map(|unit| unit.prim.as_ref());
         let kmerged_entries =
             merging::merge_txnlocal_and_committed_entrysets(stg, committed_entrysets, pk_lo, pk_hi);
-        let non_tomb_entries = kmerged_entries.filter_map(|entry| entry.to_option_entry());
-        non_tomb_entries
+        let non_tomb_entries = kmerged_entries.filter_map(move |entry| entry.to_option_entry(now));
+        refresh_err.into_iter().chain(non_tomb_entries)
     }
 
+    /// @return The entries of whole values.
+    ///     If the index does not store whole values, then they are looked up in the primary keyspace.
+    ///     The lookups are not recorded as dependencies, because any change to a looked-up value
+    ///     also changes the index entry. See [`ScndIdxSpec::extract_delta()`].
     pub fn get_sv_range(
         &mut self,
-        sv_spec_arg: &SubValueSpec,
+        sv_spec_arg: &ScndIdxSpec,
+        sv_lo: Option<&'txn SubValue>,
+        sv_hi: Option<&'txn SubValue>,
+    ) -> Result<impl Iterator<Item = Entry<'_, SVPKShared, PVShared>>> {
+        let is_lookup = sv_spec_arg.is_projection_whole() == false;
+        self.do_get_sv_range(sv_spec_arg, sv_lo, sv_hi, is_lookup)
+    }
+
+    /// @return The entries of values as projected by the index. See [`ScndIdxSpec::project()`].
+    pub fn get_sv_range_projected(
+        &mut self,
+        sv_spec_arg: &ScndIdxSpec,
         sv_lo: Option<&'txn SubValue>,
         sv_hi: Option<&'txn SubValue>,
-    ) -> Result<impl Iterator<Item = Entry<SVPKShared, PVShared>>> {
+    ) -> Result<impl Iterator<Item = Entry<'_, SVPKShared, PVShared>>> {
+        self.do_get_sv_range(sv_spec_arg, sv_lo, sv_hi, false)
+    }
+
+    /// @arg is_lookup Whether to replace each entry's value with the whole value in the primary keyspace.
+    fn do_get_sv_range(
+        &mut self,
+        sv_spec_arg: &ScndIdxSpec,
+        sv_lo: Option<&'txn SubValue>,
+        sv_hi: Option<&'txn SubValue>,
+        is_lookup: bool,
+    ) -> Result<impl Iterator<Item = Entry<'_, SVPKShared, PVShared>>> {
+        self.refresh_snapshot_for_read_committed()?;
+
         let ScndIdxState {
             scnd_idx_num,
             is_readable,
@@ -76,14 +196,27 @@ impl<'txn> Txn<'txn> {
             ));
         }
 
-        let itvset = self
-            .dependent_itvs_scnds
-            .entry(*scnd_idx_num)
-            .or_insert_with(IntervalSet::new);
-        itvset.add(Interval {
-            lo_incl: sv_lo,
-            hi_incl: sv_hi,
-        });
+        if self.is_tracking_reads() {
+            let itvset = self
+                .dependent_itvs_scnds
+                .entry(*scnd_idx_num)
+                .or_insert_with(IntervalSet::new);
+            itvset.add(Interval {
+                lo_incl: sv_lo,
+                hi_incl: sv_hi,
+            });
+        }
+
+        let now = self.snap.now;
+        let prim_stg = self.staging.as_ref().map(|stg| &stg.prim);
+        let prim_committed_entrysets = match is_lookup {
+            false => vec![],
+            true => self
+                .snap
+                .iter()
+                .filter_map(|unit| unit.prim.as_ref())
+                .collect::<Vec<_>>(),
+        };
 
         let stg = self
             .staging
@@ -95,53 +228,215 @@ impl<'txn> Txn<'txn> {
             .filter_map(|unit| unit.scnds.get(scnd_idx_num));
         let kmerged_entries =
             merging::merge_txnlocal_and_committed_entrysets(stg, committed_entrysets, sv_lo, sv_hi);
-        let non_tomb_entries = kmerged_entries.filter_map(|entry| entry.to_option_entry());
-        Ok(non_tomb_entries)
+        let non_tomb_entries = kmerged_entries.filter_map(move |entry| entry.to_option_entry(now));
+
+        let entries = non_tomb_entries.filter_map(move |entry| {
+            if is_lookup == false {
+                return Some(entry);
+            }
+            let svpk = match entry.into_owned_k() {
+                Err(e) => return Some(Entry::Own(Err(e))),
+                Ok(svpk) => svpk,
+            };
+            let prim_entrysets = prim_committed_entrysets.iter().cloned();
+            match Self::get_pk_one_optdat_from(prim_stg, prim_entrysets, &svpk.pk) {
+                Err(e) => Some(Entry::Own(Err(e))),
+                Ok(None) => None,
+                Ok(Some((_pk, optdat_pv))) => {
+                    let pv = optdat_pv.into_live(now)?;
+                    Some(Entry::Own(Ok((svpk, pv))))
+                }
+            }
+        });
+        Ok(entries)
     }
 
     pub fn put(&mut self, pk: &'txn PKShared, new_pv: &Option<PVShared>) -> Result<()> {
-        let old_pkpv = self.get_pk_one(pk)?;
-        let old_pv = old_pkpv.map(|(_, pv)| pv);
+        self.put_optdat(pk, OptDatum::from(new_pv.clone()))
+    }
+
+    /// Puts an entry that expires after `ttl`. Once expired, the entry is equivalent to a deleted one.
+    pub fn put_with_ttl(
+        &mut self,
+        pk: &'txn PKShared,
+        new_pv: &PVShared,
+        ttl: Duration,
+    ) -> Result<()> {
+        self.put_optdat(pk, OptDatum::new_expiring(new_pv.clone(), ttl))
+    }
+
+    fn put_optdat(&mut self, pk: &'txn PKShared, new_optdat: OptDatum<PVShared>) -> Result<()> {
+        self.check_writable()?;
+
+        self.refresh_snapshot_for_read_committed()?;
+
+        let now = self.snap.now;
+        let new_pv = new_optdat.as_live(now).cloned();
+        let new_expires_at = new_optdat.expires_at();
+
+        if let Some(new_pv) = new_pv.as_ref() {
+            let constraints = self.db_state_guard.constraints().keys();
+            ValueConstraint::check_all(constraints, pk, new_pv)?;
+        }
+
+        self.written_itvs_prim.add(Interval {
+            lo_incl: Some(pk),
+            hi_incl: Some(pk),
+        });
+        let stg = self.staging.as_ref().map(|stg| &stg.prim);
+        let committed_entrysets = self.snap.iter().filter_map(|unit| unit.prim.as_ref());
+        let old = Self::get_pk_one_old_pv_from(stg, committed_entrysets, pk, now)?;
+        let (old_pv, old_expires_at) = match old {
+            None => (None, None),
+            Some((old_pv, old_expires_at)) => (Some(old_pv), old_expires_at),
+        };
+
+        self.check_unique(pk, &old_pv, &new_pv)?;
 
         self.ensure_create_staging()?;
 
-        self.put_scnd_stg_delta(pk, &old_pv, new_pv)?;
+        self.put_scnd_stg_delta(pk, &old_pv, old_expires_at, &new_pv, new_expires_at)?;
 
-        let new_pv = OptDatum::<PVShared>::from(new_pv.clone());
         let stg = self.staging.as_mut().unwrap();
-        stg.prim.put(pk.clone(), new_pv)?;
+        stg.prim.put(pk.clone(), new_optdat)?;
+
+        Ok(())
+    }
+
+    /// Deletes all entries whose primary keys are within `[pk_lo, pk_hi]`.
+    ///
+    /// The primary keyspace is written one range tombstone.
+    /// Each secondary index is written a tombstone per live entry in the range, hence those are read first.
+    /// The whole range is recorded as written, s.t. it conflicts with any concurrent write within it.
+    pub fn delete_range(&mut self, pk_lo: &'txn PKShared, pk_hi: &'txn PKShared) -> Result<()> {
+        self.check_writable()?;
+
+        self.refresh_snapshot_for_read_committed()?;
+
+        self.written_itvs_prim.add(Interval {
+            lo_incl: Some(pk_lo),
+            hi_incl: Some(pk_hi),
+        });
+
+        let mut old_entries = vec![];
+        if self.db_state_guard.scnd_idxs().is_empty() == false {
+            let now = self.snap.now;
+            let stg = self.staging.as_ref().map(|stg| &stg.prim);
+            let committed_entrysets = self.snap.iter().filter_map(|unit| unit.prim.as_ref());
+            let kmerged_entries = merging::merge_txnlocal_and_committed_entrysets(
+                stg,
+                committed_entrysets,
+                Some(pk_lo),
+                Some(pk_hi),
+            );
+            for entry in kmerged_entries {
+                let (pk, old_optdat) = entry.into_owned_kv()?;
+                let old_expires_at = old_optdat.expires_at();
+                if let Some(old_pv) = old_optdat.into_live(now) {
+                    old_entries.push((pk, old_pv, old_expires_at));
+                }
+            }
+        }
+
+        self.ensure_create_staging()?;
+
+        for (pk, old_pv, old_expires_at) in old_entries {
+            let old_pv = Some(OldPV::Mem(old_pv));
+            self.put_scnd_stg_delta(&pk, &old_pv, old_expires_at, &None, None)?;
+        }
+
+        let stg = self.staging.as_mut().unwrap();
+        stg.prim.delete_range(pk_lo.clone(), pk_hi.clone())?;
+
+        Ok(())
+    }
+
+    /// For each unique index, verifies that no other primary key is indexed under any sub-value to be put.
+    ///
+    /// The verified sub-values are recorded as dependencies. Hence, of concurrent txns that index
+    /// the same sub-value under different primary keys, at most one commits.
+    ///
+    /// The index is verified even if it is not readable yet.
+    /// The index creation job verifies the values that this txn cannot see in the index.
+    fn check_unique(
+        &mut self,
+        pk: &PKShared,
+        old_pv: &Option<OldPV>,
+        new_pv: &Option<PVShared>,
+    ) -> Result<()> {
+        let now = self.snap.now;
+        for (sv_spec, ScndIdxState { scnd_idx_num, .. }) in self.db_state_guard.scnd_idxs().iter() {
+            if sv_spec.is_unique == false {
+                continue;
+            }
+
+            let (_old_svs, new_svs) = OldPV::extract_delta(old_pv, sv_spec, new_pv)?;
+            for new_sv in new_svs {
+                let stg = self
+                    .staging
+                    .as_ref()
+                    .and_then(|stg| stg.scnds.get(scnd_idx_num));
+                let committed_entrysets = self
+                    .snap
+                    .iter()
+                    .filter_map(|unit| unit.scnds.get(scnd_idx_num));
+                let sv: &SubValue = &new_sv;
+                let kmerged_entries = merging::merge_txnlocal_and_committed_entrysets(
+                    stg,
+                    committed_entrysets,
+                    Some(sv),
+                    Some(sv),
+                );
+                for entry in kmerged_entries.filter_map(|entry| entry.to_option_entry(now)) {
+                    let (svpk, _) = entry.try_borrow()?;
+                    sv_spec.check_unique(&new_sv, pk, &svpk.pk)?;
+                }
+
+                let itvset = self
+                    .dependent_unique_svs
+                    .entry(*scnd_idx_num)
+                    .or_insert_with(IntervalSet::new);
+                itvset.add(Interval {
+                    lo_incl: Some(new_sv.clone()),
+                    hi_incl: Some(new_sv),
+                });
+            }
+        }
 
         Ok(())
     }
 
+    /// @arg old_expires_at, new_expires_at: The expiry times of `old_pv` and `new_pv`, if they're expiring.
     fn put_scnd_stg_delta(
         &mut self,
-        pk: &'txn PKShared,
-        old_pv: &Option<PVShared>,
+        pk: &PKShared,
+        old_pv: &Option<OldPV>,
+        old_expires_at: Option<i64>,
         new_pv: &Option<PVShared>,
+        new_expires_at: Option<i64>,
     ) -> Result<()> {
         let stg = self.staging.as_mut().unwrap();
 
         for (sv_spec, ScndIdxState { scnd_idx_num, .. }) in self.db_state_guard.scnd_idxs().iter() {
-            let old_sv = old_pv.as_ref().and_then(|pv| sv_spec.extract(pv));
-            let new_sv = new_pv.as_ref().and_then(|pv| sv_spec.extract(pv));
-
-            // Assign old_sv to be Some iff we need to tombstone the old entry.
-            // Assign new_sv to be Some iff we need to put the new entry.
-            let (old_sv, new_sv) = match (old_sv, new_sv) {
-                (Some(old_sv), Some(new_sv)) => {
-                    if old_sv != new_sv {
-                        (Some(old_sv), Some(new_sv))
-                    } else if old_pv != new_pv {
-                        (None, Some(new_sv))
-                    } else {
-                        (None, None)
-                    }
-                }
-                pair => pair,
-            };
+            let (old_svs, mut new_svs) = OldPV::extract_delta(old_pv, sv_spec, new_pv)?;
+            if old_expires_at != new_expires_at {
+                // Even if the PV is unchanged, every entry needs to be updated to the new expiry.
+                (_, new_svs) = sv_spec.extract_delta(None, new_pv.as_ref());
+            }
+            let new_proj_pv = new_pv.as_ref().map(|pv| sv_spec.project(pv));
 
-            if let Some(old_sv) = old_sv {
+            let written_svs = self
+                .written_svs_scnds
+                .entry(*scnd_idx_num)
+                .or_insert_with(IntervalSet::new);
+            for sv in old_svs.iter().chain(new_svs.iter()) {
+                written_svs.add(Interval {
+                    lo_incl: Some(sv.clone()),
+                    hi_incl: Some(sv.clone()),
+                });
+            }
+
+            for old_sv in old_svs {
                 let stg_scnd = stg.ensure_create_scnd(*scnd_idx_num)?;
                 stg_scnd.put(
                     SVPKShared {
@@ -151,14 +446,14 @@ impl<'txn> Txn<'txn> {
                     OptDatum::Tombstone,
                 )?;
             }
-            if let Some(new_sv) = new_sv {
+            for new_sv in new_svs {
                 let stg_scnd = stg.ensure_create_scnd(*scnd_idx_num)?;
                 stg_scnd.put(
                     SVPKShared {
                         sv: new_sv,
                         pk: pk.clone(),
                     },
-                    OptDatum::Some(new_pv.clone().unwrap()),
+                    OptDatum::new_with_expiry(new_proj_pv.clone().unwrap(), new_expires_at),
                 )?;
             }
         }
@@ -166,6 +461,19 @@ impl<'txn> Txn<'txn> {
         Ok(())
     }
 
+    /// Only a serializable txn records the intervals it reads, unless it reads as of an older commit version.
+    /// Regardless, every txn that writes records the intervals it writes.
+    fn is_tracking_reads(&self) -> bool {
+        self.ssi_snap_commit_ver.is_some()
+    }
+
+    fn check_writable(&self) -> Result<()> {
+        if self.is_read_only == true {
+            return Err(anyhow!("A read-only txn cannot write"));
+        }
+        Ok(())
+    }
+
     fn ensure_create_staging(&mut self) -> Result<()> {
         if self.staging.is_none() {
             let unit_dir = self.db.lsm_dir().format_new_unit_dir_path();
@@ -175,3 +483,33 @@ impl<'txn> Txn<'txn> {
         Ok(())
     }
 }
+
+/// A value that is about to be overwritten. Only its sub-values are of interest, to derive the secondary index delta.
+///
+/// A value that was read from an SSTable is kept serialized, and its sub-values are extracted via a view of it.
+/// Hence the rest of the value is never deserialized.
+enum OldPV {
+    Mem(PVShared),
+    /// A live, possibly expiring, value.
+    Ser(OptPVBuf),
+}
+
+impl OldPV {
+    fn extract_delta(
+        old_pv: &Option<Self>,
+        sv_spec: &ScndIdxSpec,
+        new_pv: &Option<PVShared>,
+    ) -> Result<(Vec<SVShared>, Vec<SVShared>)> {
+        match old_pv {
+            None => Ok(sv_spec.extract_delta(None, new_pv.as_ref())),
+            Some(Self::Mem(old_pv)) => Ok(sv_spec.extract_delta(Some(old_pv), new_pv.as_ref())),
+            Some(Self::Ser(old_optpvbuf)) => {
+                let old_pv = match old_optpvbuf.view()? {
+                    OptDatum::Tombstone => None,
+                    OptDatum::Some(pv) | OptDatum::Expiring(pv, _) => Some(pv),
+                };
+                Ok(sv_spec.extract_delta_ref(old_pv, new_pv.as_ref()))
+            }
+        }
+    }
+}
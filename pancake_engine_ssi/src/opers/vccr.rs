@@ -0,0 +1,164 @@
+use crate::{
+    db::DB,
+    db_state::{ConstraintNewDefnResult, ConstraintState},
+    lsm::unit::CommitDataType,
+    opers::sicr::{merge_prim_entries, ScndIdxCreationJobDir},
+};
+use anyhow::anyhow;
+use derive_more::Display;
+use pancake_types::serde::OptDatum;
+use pancake_types::types::{ConstraintViolation, ValueConstraint};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::MutexGuard;
+
+impl DB {
+    /// Creates a constraint, after verifying that all existing values satisfy it.
+    ///
+    /// The existing values are scanned in the same way as when creating a secondary index.
+    /// Hence this job and secondary index creation jobs are mutually exclusive.
+    pub async fn create_constraint(
+        &self,
+        constraint: &Arc<ValueConstraint>,
+    ) -> Result<(), ConstraintCreationJobErr> {
+        let job = ConstraintCreationJob::new(self, constraint).await?;
+        let res = job.run().await;
+        job.remove_intermediary_files()?;
+        res
+    }
+}
+
+struct ConstraintCreationJob<'job> {
+    db: &'job DB,
+
+    _si_cr_guard: MutexGuard<'job, ()>,
+
+    constraint: Arc<ValueConstraint>,
+
+    job_dir: ScndIdxCreationJobDir,
+    prim_entryset_file_paths: Vec<(PathBuf, CommitDataType)>,
+}
+
+impl<'job> ConstraintCreationJob<'job> {
+    async fn new(
+        db: &'job DB,
+        constraint: &Arc<ValueConstraint>,
+    ) -> Result<Self, ConstraintCreationJobErr> {
+        let si_cr_guard = match db.si_cr_mutex().try_lock() {
+            Err(_) => return Err(ConstraintCreationJobErr::Busy),
+            Ok(guard) => guard,
+        };
+
+        let (snap, snap_list_ver);
+        {
+            /* Taking the write guard waits for all ongoing txns to finish.
+            All txns that begin afterwards are subject to the new constraint. */
+            let mut db_state = db.db_state().write().await;
+
+            if db_state.is_terminating == true {
+                return Err(anyhow!("DB is terminating").into());
+            }
+
+            match db_state.define_new_constraint(constraint) {
+                Err(e) => return Err(e.into()),
+                Ok(ConstraintNewDefnResult::Existent(c_state)) => return Err(c_state.into()),
+                Ok(ConstraintNewDefnResult::DidDefineNew) => {}
+            }
+
+            {
+                let mut lsm_state = db.lsm_state().lock().await;
+
+                snap = lsm_state.list().snap();
+
+                snap_list_ver = lsm_state.hold_curr_list_ver();
+            }
+        }
+
+        let job_dir = db.si_cr_dir().create_new_job_dir()?;
+        let prim_entryset_file_paths = job_dir.link_prim_entrysets(&snap)?;
+
+        let updated_mhlv;
+        {
+            let mut lsm_state = db.lsm_state().lock().await;
+
+            updated_mhlv = lsm_state.unhold_list_ver(snap_list_ver)?;
+        }
+        if let Some(mhlv) = updated_mhlv {
+            db.notify_min_held_list_ver(mhlv);
+        }
+
+        Ok(Self {
+            db,
+
+            _si_cr_guard: si_cr_guard,
+
+            constraint: Arc::clone(constraint),
+
+            job_dir,
+            prim_entryset_file_paths,
+        })
+    }
+
+    async fn run(&self) -> Result<(), ConstraintCreationJobErr> {
+        let res_validation = self.validate();
+
+        let mut db_state = self.db.db_state().write().await;
+
+        match res_validation {
+            Ok(()) => {
+                db_state.set_constraint_as_validated(&self.constraint)?;
+                return Ok(());
+            }
+            Err(e) => {
+                db_state.undefine_constraint(&self.constraint)?;
+                return Err(e);
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<(), ConstraintCreationJobErr> {
+        let prim_entries = merge_prim_entries(&self.prim_entryset_file_paths)?;
+        for res_pk_pv in prim_entries {
+            let (pk, optpvbuf) = res_pk_pv?;
+            let is_satisfied = match optpvbuf.view()? {
+                OptDatum::Tombstone => true,
+                OptDatum::Some(pv) | OptDatum::Expiring(pv, _) => {
+                    self.constraint.is_satisfied_by_ref(pv)
+                }
+            };
+            if is_satisfied == false {
+                return Err(ConstraintCreationJobErr::Violated(ConstraintViolation {
+                    constraint: Arc::clone(&self.constraint),
+                    pk,
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_intermediary_files(self) -> Result<(), ConstraintCreationJobErr> {
+        self.job_dir.remove_dir()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Display)]
+pub enum ConstraintCreationJobErr {
+    Busy,
+    Existent { is_validated: bool },
+    Violated(ConstraintViolation),
+    InternalError(anyhow::Error),
+}
+
+impl From<ConstraintState> for ConstraintCreationJobErr {
+    fn from(c_state: ConstraintState) -> Self {
+        Self::Existent {
+            is_validated: c_state.is_validated,
+        }
+    }
+}
+impl<E: Into<anyhow::Error>> From<E> for ConstraintCreationJobErr {
+    fn from(e: E) -> Self {
+        Self::InternalError(e.into())
+    }
+}
@@ -0,0 +1,42 @@
+use crate::{db_state::ConstraintRemovalResult, DB};
+use anyhow::Result;
+use derive_more::Display;
+use pancake_types::types::ValueConstraint;
+
+impl DB {
+    pub async fn delete_constraint(
+        &self,
+        constraint: &ValueConstraint,
+    ) -> Result<(), ConstraintDeletionJobErr> {
+        {
+            let db_state = self.db_state().read().await;
+
+            match db_state.can_constraint_be_removed(constraint) {
+                ConstraintRemovalResult::DoesNotExist => return Ok(()),
+                ConstraintRemovalResult::CreationInProgress => {
+                    return Err(ConstraintDeletionJobErr::CreationInProgress)
+                }
+                ConstraintRemovalResult::Deletable => {}
+            };
+        }
+
+        {
+            let mut db_state = self.db_state().write().await;
+
+            match db_state.remove_constraint(constraint) {
+                Err(e) => return Err(ConstraintDeletionJobErr::InternalError(e)),
+                Ok(ConstraintRemovalResult::DoesNotExist) => return Ok(()),
+                Ok(ConstraintRemovalResult::CreationInProgress) => {
+                    return Err(ConstraintDeletionJobErr::CreationInProgress)
+                }
+                Ok(ConstraintRemovalResult::Deletable) => return Ok(()),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Display)]
+pub enum ConstraintDeletionJobErr {
+    CreationInProgress,
+    InternalError(anyhow::Error),
+}
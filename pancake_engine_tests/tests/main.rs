@@ -1,25 +1,51 @@
 use anyhow::Result;
-use pancake_engine_common::fs_utils::{self, EngineType};
+use pancake_engine_common::{
+    fs_utils::{self, EngineType},
+    CompactionStrategy, EngineOptions,
+};
 use pancake_engine_serial::DB as SerialDb;
 use pancake_engine_ssi::DB as SsiDb;
 use std::fs;
+use std::thread;
 
 mod storage;
 use storage::concurrent_txns::test_concurrent_txns;
 use storage::helpers::one_stmt::{OneStmtSerialDbAdaptor, OneStmtSsiDbAdaptor};
 use storage::individual_stmts::test_stmts_serially;
 
+/// The thresholds are exaggeratedly small, so as to exercise flushing and compaction,
+/// as well as lost notifications to the SSI engine's flushing-and-compaction worker.
+fn gen_engine_opts(compaction_strategy: CompactionStrategy) -> EngineOptions {
+    EngineOptions {
+        memtable_flush_entry_count: 7,
+        memtable_flush_byte_size: 1024,
+        compaction_strategy,
+        sstable_compact_count: 4,
+        sstable_target_file_size: 512,
+        sstable_data_block_size: 128,
+        fc_able_commit_vers_capacity: 5,
+        commit_ver_retention_count: 3,
+    }
+}
+
 #[tokio::test()]
 async fn integration_test_serial() -> Result<()> {
-    let db_root_dir = fs_utils::default_db_root_dir(EngineType::SERIAL);
-    if db_root_dir.exists() {
-        fs::remove_dir_all(&db_root_dir)?;
-    }
+    for compaction_strategy in [CompactionStrategy::SizeTiered, CompactionStrategy::Full] {
+        let db_root_dir = fs_utils::default_db_root_dir(EngineType::SERIAL);
+        if db_root_dir.exists() {
+            fs::remove_dir_all(&db_root_dir)?;
+        }
 
-    let mut db = SerialDb::load_or_new(&db_root_dir)?;
-    let mut db_adap = OneStmtSerialDbAdaptor { db: &mut db };
+        let (mut db, fc_worker) =
+            SerialDb::load_or_new(&db_root_dir, gen_engine_opts(compaction_strategy))?;
+        let fc_thread = thread::spawn(move || fc_worker.run());
+        let mut db_adap = OneStmtSerialDbAdaptor { db: &mut db };
 
-    test_stmts_serially(&mut db_adap).await?;
+        test_stmts_serially(&mut db_adap).await?;
+
+        drop(db);
+        fc_thread.join().unwrap();
+    }
 
     Ok(())
 }
@@ -31,7 +57,8 @@ async fn integration_test_ssi() -> Result<()> {
         fs::remove_dir_all(&db_root_dir)?;
     }
 
-    let (db, fc_worker) = SsiDb::load_or_new(db_root_dir)?;
+    let (db, fc_worker) =
+        SsiDb::load_or_new(db_root_dir, gen_engine_opts(CompactionStrategy::SizeTiered))?;
     let fc_task = tokio::spawn(fc_worker.run());
     let mut db_adap = OneStmtSsiDbAdaptor { db: &db };
 
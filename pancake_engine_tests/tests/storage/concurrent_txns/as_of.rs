@@ -0,0 +1,69 @@
+use super::super::helpers::{gen, one_stmt::OneStmtSsiDbAdaptor};
+use anyhow::Result;
+use pancake_engine_ssi::{CommitVer, Txn, DB};
+use pancake_types::types::{PKShared, PVShared};
+use std::sync::Arc;
+
+/// A read-only txn reads as of any retained commit version,
+/// even after the version ceases to be retained during the txn.
+pub async fn read_as_of_retained_commit_vers(db: &'static DB) -> Result<()> {
+    let pk = Arc::new(gen::gen_str_pk("as_of.key"));
+    let pv_0 = Arc::new(gen::gen_str_pv("as_of.val.0"));
+    let pv_1 = Arc::new(gen::gen_str_pv("as_of.val.1"));
+
+    /* Set up the initial condition. Each put is one commit. */
+    let db_adap = OneStmtSsiDbAdaptor { db };
+    let mut puts = vec![];
+    for opt_pv in [Some(pv_0.clone()), Some(pv_1.clone()), None] {
+        db_adap.nonmut_put(pk.clone(), opt_pv.clone()).await?;
+        let commit_ver = *db.retained_commit_vers().await.last().unwrap();
+        puts.push((commit_ver, opt_pv));
+    }
+
+    for (commit_ver, exp_opt_pv) in puts.iter() {
+        let act_opt_pv = get_as_of(db, *commit_ver, &pk).await?;
+        assert_eq!(exp_opt_pv, &act_opt_pv);
+    }
+
+    /*
+    A txn begins as of the oldest put.
+    Meanwhile, enough other commits cause the oldest put to cease to be retained.
+    */
+    let (oldest_commit_ver, _) = puts[0];
+    let mut txn = Txn::begin_read_only_as_of(db, oldest_commit_ver).await?;
+    assert_eq!(oldest_commit_ver, txn.snap_commit_ver());
+
+    let other_pk = Arc::new(gen::gen_str_pk("as_of.other_key"));
+    for i in 0..5 {
+        let other_pv = Arc::new(gen::gen_str_pv(format!("as_of.other_val.{i}")));
+        db_adap.nonmut_put(other_pk.clone(), Some(other_pv)).await?;
+    }
+
+    let act_opt_pv = txn.get_pk_one(&pk)?.map(|(_, pv)| pv);
+    assert_eq!(Some(pv_0), act_opt_pv);
+    txn.commit().await?;
+
+    /* Check the ending condition. */
+    let retained = db.retained_commit_vers().await;
+    assert_eq!(4, retained.len()); // The retention count in the engine options, plus the current version.
+    assert!(retained.contains(&oldest_commit_ver) == false);
+
+    let get_res = get_as_of(db, oldest_commit_ver, &pk).await;
+    assert_eq!(
+        format!("Commit version {oldest_commit_ver} is not retained."),
+        get_res.unwrap_err().to_string()
+    );
+
+    let beyond_curr = CommitVer::from(u64::from(*retained.last().unwrap()) + 1);
+    assert!(get_as_of(db, beyond_curr, &pk).await.is_err());
+
+    Ok(())
+}
+
+async fn get_as_of(db: &DB, commit_ver: CommitVer, pk: &PKShared) -> Result<Option<PVShared>> {
+    Txn::run_read_only_as_of(db, commit_ver, |txn| {
+        let opt_pv = txn.get_pk_one(pk)?.map(|(_, pv)| pv);
+        Ok(opt_pv)
+    })
+    .await
+}
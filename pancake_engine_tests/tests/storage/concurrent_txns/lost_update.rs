@@ -1,12 +1,12 @@
 use super::super::helpers::{
-    etc::join_tasks,
+    etc::{block_on_in_another_thread, join_tasks},
     gen,
     one_stmt::{OneStmtDbAdaptor, OneStmtSsiDbAdaptor},
 };
 use anyhow::Result;
-use pancake_engine_ssi::{ClientCommitDecision, Txn, DB};
+use pancake_engine_ssi::{ClientCommitDecision, IsolationLevel, Txn, DB};
 use pancake_types::serde::Datum;
-use pancake_types::types::Value;
+use pancake_types::types::{PKShared, Value};
 use std::sync::Arc;
 use tokio::task::JoinHandle;
 
@@ -53,3 +53,71 @@ pub async fn no_lost_update(db: &'static DB) -> Result<()> {
 
     Ok(())
 }
+
+/// A txn reads a counter; then another txn increments the counter and commits;
+/// then the former txn increments the counter based on its read.
+///
+/// Only read-committed loses the other txn's update.
+pub async fn lost_update_per_isolation_level(db: &'static DB) -> Result<()> {
+    for (isolation_level, exp_is_lost) in [
+        (IsolationLevel::ReadCommitted, true),
+        (IsolationLevel::SnapshotIsolation, false),
+        (IsolationLevel::Serializable, false),
+    ] {
+        let pk = Arc::new(gen::gen_str_pk(format!(
+            "the_counter_key.{isolation_level:?}"
+        )));
+
+        /* Set up the initial condition. */
+        let db_adap = OneStmtSsiDbAdaptor { db };
+        db_adap
+            .nonmut_put(pk.clone(), Some(gen_counter_pv(0)))
+            .await?;
+
+        let mut try_ct = 0;
+        let txn_fut = Txn::run_with_isolation_level(db, isolation_level, 1, |txn| {
+            try_ct += 1;
+
+            let prior_val = get_counter_val(txn, &pk)?;
+            if try_ct == 1 {
+                let pk = Arc::clone(&pk);
+                block_on_in_another_thread(async move {
+                    Txn::run(db, 0, |txn| {
+                        let prior_val = get_counter_val(txn, &pk)?;
+                        txn.put(&pk, &Some(gen_counter_pv(prior_val + 1)))?;
+                        Ok(ClientCommitDecision::Commit(()))
+                    })
+                    .await
+                })?;
+            }
+
+            txn.put(&pk, &Some(gen_counter_pv(prior_val + 1)))?;
+            Ok(ClientCommitDecision::Commit(()))
+        });
+        txn_fut.await?;
+
+        /* Check the ending condition. */
+        let (exp_try_ct, exp_val) = match exp_is_lost {
+            true => (1, 1),
+            false => (2, 2),
+        };
+        assert_eq!(exp_try_ct, try_ct, "{isolation_level:?}");
+        let pv = db_adap.get_pk_one(&pk).await?.map(|(_pk, pv)| pv);
+        assert_eq!(Some(gen_counter_pv(exp_val)), pv, "{isolation_level:?}");
+    }
+
+    Ok(())
+}
+
+fn gen_counter_pv(val: i64) -> Arc<Value> {
+    Arc::new(Value(Datum::I64(val)))
+}
+fn get_counter_val<'txn>(txn: &mut Txn<'txn>, pk: &'txn PKShared) -> Result<i64> {
+    match txn.get_pk_one(pk)? {
+        Some((_, pv)) => match pv.as_ref() {
+            Value(Datum::I64(val)) => Ok(*val),
+            _ => Ok(0),
+        },
+        None => Ok(0),
+    }
+}
@@ -1,7 +1,12 @@
+mod as_of;
 mod dirty_w_r;
 mod lost_update;
 mod phantom;
+mod range_delete;
+mod read_only;
 mod repeatable_read;
+mod rw_antidependency;
+mod unique;
 mod write_skew;
 
 use super::helpers::etc::coerce_ref_to_static;
@@ -21,6 +26,18 @@ pub async fn test_concurrent_txns(db: &DB) -> Result<()> {
     lost_update::no_lost_update(db_ref).await?;
     write_skew::no_write_skew(db_ref).await?;
     phantom::no_phantom(db_ref).await?;
+    lost_update::lost_update_per_isolation_level(db_ref).await?;
+    write_skew::write_skew_per_isolation_level(db_ref).await?;
+    phantom::phantom_per_isolation_level(db_ref).await?;
+    rw_antidependency::commits_with_rw_antidependency_out(db_ref).await?;
+    rw_antidependency::commits_with_rw_antidependencies_in_and_out(db_ref).await?;
+    rw_antidependency::aborts_with_dangerous_structure(db_ref).await?;
+    rw_antidependency::aborts_pivot_with_read_only_anomaly(db_ref).await?;
+    rw_antidependency::aborts_non_writer_with_dangerous_structure(db_ref).await?;
+    unique::no_duplicate_in_unique_idx(db_ref).await?;
+    range_delete::range_delete_conflicts_with_read(db_ref).await?;
+    read_only::read_only_reads_snapshot(db_ref).await?;
+    as_of::read_as_of_retained_commit_vers(db_ref).await?;
 
     Ok(())
 }
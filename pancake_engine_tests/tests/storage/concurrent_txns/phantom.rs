@@ -1,11 +1,12 @@
 use super::super::helpers::{
+    etc::block_on_in_another_thread,
     gen,
     one_stmt::{OneStmtDbAdaptor, OneStmtSsiDbAdaptor},
 };
 use anyhow::Result;
-use pancake_engine_ssi::{ClientCommitDecision, Txn, DB};
+use pancake_engine_ssi::{ClientCommitDecision, IsolationLevel, Txn, DB};
 use pancake_types::serde::{Datum, DatumType};
-use pancake_types::types::{PrimaryKey, SubValueSpec, Value};
+use pancake_types::types::{PrimaryKey, ScndIdxSpec, SubValueSpec, Value};
 use std::sync::Arc;
 use tokio::task::JoinHandle;
 
@@ -35,7 +36,7 @@ fn extract_price(pv: &Value) -> Option<i64> {
 pub async fn no_phantom(db: &'static DB) -> Result<()> {
     let db_adap = OneStmtSsiDbAdaptor { db };
 
-    let sv_spec = Arc::new(gen_sv_spec());
+    let sv_spec = Arc::new(ScndIdxSpec::from(gen_sv_spec()));
 
     db_adap.nonmut_create_scnd_idx(sv_spec.clone()).await?;
 
@@ -106,3 +107,92 @@ pub async fn no_phantom(db: &'static DB) -> Result<()> {
 
     Ok(())
 }
+
+/// A txn sums the prices of cart items; then another txn does the same, inserts an item, and commits;
+/// then the former txn inserts an item, if the sum it read leaves room under the threshold.
+///
+/// Only serializable prevents the phantom.
+/// Runs after [`no_phantom()`], which creates the secondary index.
+pub async fn phantom_per_isolation_level(db: &'static DB) -> Result<()> {
+    let db_adap = OneStmtSsiDbAdaptor { db };
+    let sv_spec = Arc::new(ScndIdxSpec::from(gen_sv_spec()));
+
+    let tot_price_thresh = 8;
+    let item_price = 8;
+
+    for (isolation_level, exp_is_phantom) in [
+        (IsolationLevel::ReadCommitted, true),
+        (IsolationLevel::SnapshotIsolation, true),
+        (IsolationLevel::Serializable, false),
+    ] {
+        let pk_prefix = format!("phantom_cart_item.{isolation_level:?}.");
+        let gen_pk = |item_i: usize| Arc::new(gen::gen_str_pk(format!("{pk_prefix}{item_i}")));
+
+        let (pk_0, pk_1) = (gen_pk(0), gen_pk(1));
+        let pv = Arc::new(gen_pv(item_price));
+        let mut try_ct = 0;
+        let txn_fut = Txn::run_with_isolation_level(db, isolation_level, 1, |txn| {
+            try_ct += 1;
+
+            let tot_price = sum_prices_in_txn(txn, &sv_spec, &pk_prefix)?;
+            if try_ct == 1 {
+                let (sv_spec, pk_prefix) = (Arc::clone(&sv_spec), pk_prefix.clone());
+                let (pk_1, pv) = (Arc::clone(&pk_1), Arc::clone(&pv));
+                block_on_in_another_thread(async move {
+                    Txn::run(db, 0, |txn| {
+                        let tot_price = sum_prices_in_txn(txn, &sv_spec, &pk_prefix)?;
+                        if tot_price + item_price > tot_price_thresh {
+                            return Ok(ClientCommitDecision::Abort(()));
+                        }
+                        txn.put(&pk_1, &Some(pv.clone()))?;
+                        Ok(ClientCommitDecision::Commit(()))
+                    })
+                    .await
+                })?;
+            }
+
+            if tot_price + item_price > tot_price_thresh {
+                return Ok(ClientCommitDecision::Abort(()));
+            }
+            txn.put(&pk_0, &Some(pv.clone()))?;
+            Ok(ClientCommitDecision::Commit(()))
+        });
+        txn_fut.await?;
+
+        /* Check the ending condition. */
+        let (exp_try_ct, exp_tot_price) = match exp_is_phantom {
+            true => (1, 2 * item_price),
+            false => (2, item_price),
+        };
+        assert_eq!(exp_try_ct, try_ct, "{isolation_level:?}");
+        let final_tot_price: i64 = db_adap
+            .get_sv_range(&sv_spec, None, None)
+            .await?
+            .iter()
+            .filter(|(pk, _pv)| pk_has_prefix(pk, &pk_prefix))
+            .filter_map(|(_pk, pv)| extract_price(pv))
+            .sum();
+        assert_eq!(exp_tot_price, final_tot_price, "{isolation_level:?}");
+    }
+
+    Ok(())
+}
+
+fn pk_has_prefix(pk: &PrimaryKey, pk_prefix: &str) -> bool {
+    if let PrimaryKey(Datum::Str(s)) = pk {
+        return s.starts_with(pk_prefix);
+    }
+    false
+}
+fn sum_prices_in_txn(txn: &mut Txn, sv_spec: &ScndIdxSpec, pk_prefix: &str) -> Result<i64> {
+    let mut tot_price = 0;
+    for entry in txn.get_sv_range(sv_spec, None, None)? {
+        let (svpk, pv) = entry.try_borrow()?;
+        if pk_has_prefix(&svpk.pk, pk_prefix) {
+            if let Some(price) = extract_price(pv) {
+                tot_price += price;
+            }
+        }
+    }
+    Ok(tot_price)
+}
@@ -0,0 +1,64 @@
+use super::super::helpers::{
+    etc::block_on_in_another_thread,
+    gen,
+    one_stmt::{OneStmtDbAdaptor, OneStmtSsiDbAdaptor},
+};
+use anyhow::Result;
+use pancake_engine_ssi::{ClientCommitDecision, Txn, DB};
+use std::sync::Arc;
+
+/// A txn that read a PK, which then got range-deleted by a concurrently committed txn,
+/// must not commit based on the stale read, if the range-deleting txn read what the former txn writes.
+pub async fn range_delete_conflicts_with_read(db: &'static DB) -> Result<()> {
+    let gen_item_pk =
+        |item_i: usize| Arc::new(gen::gen_str_pk(format!("rdel_conflict.item.{item_i}")));
+    let copy_pk = Arc::new(gen::gen_str_pk("rdel_conflict.copy"));
+
+    /* Set up the initial condition. */
+    let db_adap = OneStmtSsiDbAdaptor { db };
+    for item_i in 0..5 {
+        let pv = Arc::new(gen::gen_str_pv(format!("item {item_i}")));
+        db_adap.nonmut_put(gen_item_pk(item_i), Some(pv)).await?;
+    }
+
+    /*
+    The reading txn copies one item to another PK.
+    In its first try, after reading, another txn range-deletes items, including the one that was read,
+    after checking that no copy exists.
+    */
+    let read_pk = gen_item_pk(3);
+    let mut try_ct = 0;
+    let txn_fut = Txn::run(db, 1, |txn| {
+        try_ct += 1;
+
+        let opt_pv = txn.get_pk_one(&read_pk)?.map(|(_, pv)| pv);
+        if try_ct == 1 {
+            let (pk_lo, pk_hi) = (gen_item_pk(1), gen_item_pk(4));
+            let copy_pk = Arc::clone(&copy_pk);
+            block_on_in_another_thread(async move {
+                Txn::run(db, 0, |txn| {
+                    if txn.get_pk_one(&copy_pk)?.is_some() {
+                        return Ok(ClientCommitDecision::Abort(()));
+                    }
+                    txn.delete_range(&pk_lo, &pk_hi)?;
+                    Ok(ClientCommitDecision::Commit(()))
+                })
+                .await
+            })?;
+        }
+
+        txn.put(&copy_pk, &opt_pv)?;
+        Ok(ClientCommitDecision::Commit(()))
+    });
+    txn_fut.await?;
+
+    /* Check the ending condition. */
+    assert_eq!(2, try_ct);
+    assert!(db_adap.get_pk_one(&copy_pk).await?.is_none());
+    for item_i in 0..5 {
+        let opt_entry = db_adap.get_pk_one(&gen_item_pk(item_i)).await?;
+        assert_eq!((1..=4).contains(&item_i), opt_entry.is_none());
+    }
+
+    Ok(())
+}
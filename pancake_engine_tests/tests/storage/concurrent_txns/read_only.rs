@@ -0,0 +1,60 @@
+use super::super::helpers::{
+    etc::block_on_in_another_thread,
+    gen,
+    one_stmt::{OneStmtDbAdaptor, OneStmtSsiDbAdaptor},
+};
+use anyhow::Result;
+use pancake_engine_ssi::{ClientCommitDecision, Txn, DB};
+use std::sync::Arc;
+
+/// A read-only txn reads its snapshot, and cannot write.
+///
+/// The concurrent write forms no dangerous structure, hence the read-only txn commits without retrying.
+pub async fn read_only_reads_snapshot(db: &'static DB) -> Result<()> {
+    let pk = Arc::new(gen::gen_str_pk("read_only.key"));
+    let pv_0 = Arc::new(gen::gen_str_pv("read_only.val.0"));
+    let pv_1 = Arc::new(gen::gen_str_pv("read_only.val.1"));
+
+    /* Set up the initial condition. */
+    let db_adap = OneStmtSsiDbAdaptor { db };
+    db_adap.nonmut_put(pk.clone(), Some(pv_0.clone())).await?;
+
+    /*
+    The read-only txn reads the PK twice.
+    In between, another txn overwrites it and commits.
+    */
+    let mut run_ct = 0;
+    let read_pvs = Txn::run_read_only(db, 0, |txn| {
+        run_ct += 1;
+
+        let first_opt_pv = txn.get_pk_one(&pk)?.map(|(_, pv)| pv);
+
+        let (w_pk, w_pv) = (pk.clone(), Some(pv_1.clone()));
+        block_on_in_another_thread(async move {
+            Txn::run(db, 0, |txn| {
+                txn.put(&w_pk, &w_pv)?;
+                Ok(ClientCommitDecision::Commit(()))
+            })
+            .await
+        })?;
+
+        let second_opt_pv = txn.get_pk_one(&pk)?.map(|(_, pv)| pv);
+
+        let put_res = txn.put(&pk, &None);
+        assert_eq!(
+            "A read-only txn cannot write",
+            put_res.unwrap_err().to_string()
+        );
+
+        Ok((first_opt_pv, second_opt_pv))
+    })
+    .await?;
+
+    /* Check the ending condition. */
+    assert_eq!(1, run_ct);
+    assert_eq!((Some(pv_0.clone()), Some(pv_0)), read_pvs);
+    let act_pv = db_adap.get_pk_one(&pk).await?.map(|(_, pv)| pv);
+    assert_eq!(Some(pv_1), act_pv);
+
+    Ok(())
+}
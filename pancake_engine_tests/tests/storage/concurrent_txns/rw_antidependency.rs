@@ -0,0 +1,237 @@
+use super::super::helpers::{
+    etc::block_on_in_another_thread,
+    gen,
+    one_stmt::{OneStmtDbAdaptor, OneStmtSsiDbAdaptor},
+};
+use anyhow::Result;
+use pancake_engine_ssi::{ClientCommitDecision, Txn, DB};
+use pancake_types::types::{PKShared, PVShared};
+use std::sync::Arc;
+
+fn gen_pk(test_name: &str, key: &str) -> PKShared {
+    Arc::new(gen::gen_str_pk(format!("rw_antidep.{test_name}.{key}")))
+}
+fn gen_pv(val: &str) -> PVShared {
+    Arc::new(gen::gen_str_pv(val))
+}
+
+/// A txn reads a key; then another txn writes the key and commits; then the former txn writes another key.
+///
+/// The former txn is serialized before the latter, hence it commits without retrying.
+pub async fn commits_with_rw_antidependency_out(db: &'static DB) -> Result<()> {
+    let (pk_read, pk_written) = (gen_pk("out", "read"), gen_pk("out", "written"));
+
+    /* Set up the initial condition. */
+    let db_adap = OneStmtSsiDbAdaptor { db };
+    db_adap
+        .nonmut_put(pk_read.clone(), Some(gen_pv("old")))
+        .await?;
+
+    let mut try_ct = 0;
+    let txn_fut = Txn::run(db, 1, |txn| {
+        try_ct += 1;
+
+        let opt_pv = txn.get_pk_one(&pk_read)?.map(|(_, pv)| pv);
+        if try_ct == 1 {
+            let pk_read = Arc::clone(&pk_read);
+            block_on_in_another_thread(async move {
+                Txn::run(db, 0, |txn| {
+                    txn.put(&pk_read, &Some(gen_pv("new")))?;
+                    Ok(ClientCommitDecision::Commit(()))
+                })
+                .await
+            })?;
+        }
+
+        txn.put(&pk_written, &opt_pv)?;
+        Ok(ClientCommitDecision::Commit(()))
+    });
+    txn_fut.await?;
+
+    /* Check the ending condition. */
+    assert_eq!(1, try_ct);
+    let opt_pv = db_adap.get_pk_one(&pk_written).await?.map(|(_, pv)| pv);
+    assert_eq!(Some(gen_pv("old")), opt_pv);
+
+    Ok(())
+}
+
+/// `T_in -> T_pivot -> T_out`, where `T_in` commits first, then `T_out`, then `T_pivot`.
+///
+/// Because `T_out` does not commit first, the structure is not dangerous,
+/// and `T_pivot` commits without retrying.
+pub async fn commits_with_rw_antidependencies_in_and_out(db: &'static DB) -> Result<()> {
+    let pk_a = gen_pk("in_out", "a");
+    let pk_b = gen_pk("in_out", "b");
+    let pk_c = gen_pk("in_out", "c");
+
+    let mut try_ct = 0;
+    let txn_fut = Txn::run(db, 1, |txn| {
+        try_ct += 1;
+
+        /* T_pivot reads c. */
+        txn.get_pk_one(&pk_c)?;
+        if try_ct == 1 {
+            let (pk_a, pk_b, pk_c) = (Arc::clone(&pk_a), Arc::clone(&pk_b), Arc::clone(&pk_c));
+            block_on_in_another_thread(async move {
+                /* T_in reads b, and commits. */
+                Txn::run(db, 0, |txn| {
+                    txn.get_pk_one(&pk_b)?;
+                    txn.put(&pk_a, &Some(gen_pv("a")))?;
+                    Ok(ClientCommitDecision::Commit(()))
+                })
+                .await?;
+                /* T_out writes c, and commits. */
+                Txn::run(db, 0, |txn| {
+                    txn.put(&pk_c, &Some(gen_pv("c")))?;
+                    Ok(ClientCommitDecision::Commit(()))
+                })
+                .await
+            })?;
+        }
+
+        /* T_pivot writes b. */
+        txn.put(&pk_b, &Some(gen_pv("b")))?;
+        Ok(ClientCommitDecision::Commit(()))
+    });
+    txn_fut.await?;
+
+    /* Check the ending condition. */
+    assert_eq!(1, try_ct);
+    let db_adap = OneStmtSsiDbAdaptor { db };
+    for pk in [&pk_a, &pk_b, &pk_c] {
+        assert!(db_adap.get_pk_one(pk).await?.is_some());
+    }
+
+    Ok(())
+}
+
+/// `T_in -> T_pivot -> T_out`, where `T_out` commits first, then `T_pivot`, then `T_in`.
+///
+/// The structure is dangerous, hence `T_in`, which commits last, retries.
+pub async fn aborts_with_dangerous_structure(db: &'static DB) -> Result<()> {
+    let pk_a = gen_pk("dangerous", "a");
+    let pk_b = gen_pk("dangerous", "b");
+    let pk_c = gen_pk("dangerous", "c");
+
+    let mut try_ct = 0;
+    let txn_fut = Txn::run(db, 1, |txn| {
+        try_ct += 1;
+
+        /* T_in reads b. */
+        txn.get_pk_one(&pk_b)?;
+        if try_ct == 1 {
+            let (pk_b, pk_c) = (Arc::clone(&pk_b), Arc::clone(&pk_c));
+            block_on_in_another_thread(async move {
+                /* T_pivot reads c. */
+                let mut pivot = Txn::begin(db).await?;
+                pivot.get_pk_one(&pk_c)?;
+
+                /* T_out writes c, and commits. */
+                Txn::run(db, 0, |txn| {
+                    txn.put(&pk_c, &Some(gen_pv("c")))?;
+                    Ok(ClientCommitDecision::Commit(()))
+                })
+                .await?;
+
+                /* T_pivot writes b, and commits. */
+                pivot.put(&pk_b, &Some(gen_pv("b")))?;
+                assert!(pivot.commit().await?);
+                Ok(())
+            })?;
+        }
+
+        /* T_in writes a. */
+        txn.put(&pk_a, &Some(gen_pv("a")))?;
+        Ok(ClientCommitDecision::Commit(()))
+    });
+    txn_fut.await?;
+
+    /* Check the ending condition. */
+    assert_eq!(2, try_ct);
+    let db_adap = OneStmtSsiDbAdaptor { db };
+    for pk in [&pk_a, &pk_b, &pk_c] {
+        assert!(db_adap.get_pk_one(pk).await?.is_some());
+    }
+
+    Ok(())
+}
+
+/// The read-only anomaly: `T_in -> T_pivot -> T_out`, where `T_out` commits first, then `T_in`, which is read-only,
+/// then `T_pivot`.
+///
+/// `T_in` sees `T_out`'s write but not `T_pivot`'s, hence `T_pivot` must not commit.
+pub async fn aborts_pivot_with_read_only_anomaly(db: &'static DB) -> Result<()> {
+    let (pk_x, pk_y) = (gen_pk("ro_anomaly", "x"), gen_pk("ro_anomaly", "y"));
+
+    /* Set up the initial condition. */
+    let db_adap = OneStmtSsiDbAdaptor { db };
+    db_adap
+        .nonmut_put(pk_x.clone(), Some(gen_pv("x.old")))
+        .await?;
+    db_adap
+        .nonmut_put(pk_y.clone(), Some(gen_pv("y.old")))
+        .await?;
+
+    /* T_pivot reads x and y. */
+    let mut pivot = Txn::begin(db).await?;
+    pivot.get_pk_one(&pk_x)?;
+    pivot.get_pk_one(&pk_y)?;
+
+    /* T_out writes x, and commits. */
+    Txn::run(db, 0, |txn| {
+        txn.put(&pk_x, &Some(gen_pv("x.new")))?;
+        Ok(ClientCommitDecision::Commit(()))
+    })
+    .await?;
+
+    /* T_in reads x and y, and commits. */
+    let mut reader = Txn::begin_read_only(db).await?;
+    let opt_pv_x = reader.get_pk_one(&pk_x)?.map(|(_, pv)| pv);
+    let opt_pv_y = reader.get_pk_one(&pk_y)?.map(|(_, pv)| pv);
+    assert!(reader.commit().await?);
+    assert_eq!(Some(gen_pv("x.new")), opt_pv_x);
+    assert_eq!(Some(gen_pv("y.old")), opt_pv_y);
+
+    /* T_pivot writes y, and conflicts. */
+    pivot.put(&pk_y, &Some(gen_pv("y.new")))?;
+    assert!(pivot.commit().await? == false);
+
+    /* Check the ending condition. */
+    let opt_pv = db_adap.get_pk_one(&pk_y).await?.map(|(_, pv)| pv);
+    assert_eq!(Some(gen_pv("y.old")), opt_pv);
+
+    Ok(())
+}
+
+/// The read-only anomaly, where `T_pivot` commits before `T_in`, which writes nothing.
+///
+/// The structure is dangerous, hence `T_in` conflicts, although it does not write.
+pub async fn aborts_non_writer_with_dangerous_structure(db: &'static DB) -> Result<()> {
+    let (pk_x, pk_y) = (gen_pk("non_writer", "x"), gen_pk("non_writer", "y"));
+
+    /* T_pivot reads x. */
+    let mut pivot = Txn::begin(db).await?;
+    pivot.get_pk_one(&pk_x)?;
+
+    /* T_out writes x, and commits. */
+    Txn::run(db, 0, |txn| {
+        txn.put(&pk_x, &Some(gen_pv("x")))?;
+        Ok(ClientCommitDecision::Commit(()))
+    })
+    .await?;
+
+    /* T_in reads x and y. */
+    let mut reader = Txn::begin(db).await?;
+    reader.get_pk_one(&pk_x)?;
+    reader.get_pk_one(&pk_y)?;
+
+    /* T_pivot writes y, and commits. */
+    pivot.put(&pk_y, &Some(gen_pv("y")))?;
+    assert!(pivot.commit().await?);
+
+    /* T_in conflicts. */
+    assert!(reader.commit().await? == false);
+
+    Ok(())
+}
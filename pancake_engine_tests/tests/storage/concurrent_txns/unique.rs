@@ -0,0 +1,70 @@
+use super::super::helpers::{
+    gen,
+    one_stmt::{OneStmtDbAdaptor, OneStmtSsiDbAdaptor},
+};
+use anyhow::Result;
+use pancake_engine_ssi::{ClientCommitDecision, Txn, DB};
+use pancake_types::serde::{Datum, DatumType};
+use pancake_types::types::{
+    MemberKey, ScndIdxProjection, ScndIdxSpec, SubValueSpec, UniquenessViolation, Value,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+fn gen_pv(handle: &str) -> Value {
+    let members = BTreeMap::from([(String::from("handle"), Datum::Str(String::from(handle)))]);
+    Value(Datum::Map(members))
+}
+
+/// Of concurrent txns that each put a different primary key having the same sub-value in a unique index,
+/// exactly one commits. The others retry, then fail.
+pub async fn no_duplicate_in_unique_idx(db: &'static DB) -> Result<()> {
+    let db_adap = OneStmtSsiDbAdaptor { db };
+
+    let sv_spec = Arc::new(ScndIdxSpec {
+        columns: vec![SubValueSpec {
+            member_path: vec![MemberKey::from("handle")],
+            datum_type: DatumType::Str,
+        }],
+        projection: ScndIdxProjection::Whole,
+        filter: None,
+        is_unique: true,
+    });
+
+    db_adap.nonmut_create_scnd_idx(Arc::clone(&sv_spec)).await?;
+
+    let w_txns_ct = 20;
+    let mut tasks = vec![];
+    for txn_i in 0..w_txns_ct {
+        let retry_limit = w_txns_ct - 1;
+
+        let task_fut = async move {
+            let pk = Arc::new(gen::gen_str_pk(format!("handle_owner.{txn_i}")));
+            let pv = Arc::new(gen_pv("the_handle"));
+
+            let txn_fut = Txn::run(db, retry_limit, |txn| {
+                txn.put(&pk, &Some(pv.clone()))?;
+                Ok(ClientCommitDecision::Commit(()))
+            });
+            txn_fut.await
+        };
+        let task: JoinHandle<Result<()>> = tokio::spawn(task_fut);
+        tasks.push(task);
+    }
+
+    let mut ok_ct = 0;
+    for task in tasks.into_iter() {
+        match task.await? {
+            Ok(()) => ok_ct += 1,
+            Err(e) => assert!(e.downcast_ref::<UniquenessViolation>().is_some()),
+        }
+    }
+    assert_eq!(ok_ct, 1);
+
+    /* Check the ending condition. */
+    let entries = db_adap.get_sv_range(&sv_spec, None, None).await?;
+    assert_eq!(entries.len(), 1);
+
+    Ok(())
+}
@@ -1,12 +1,12 @@
 use super::super::helpers::{
-    etc::{join_tasks, sleep_async},
+    etc::{block_on_in_another_thread, join_tasks, sleep_async},
     gen,
     one_stmt::{OneStmtDbAdaptor, OneStmtSsiDbAdaptor},
 };
 use anyhow::Result;
-use pancake_engine_ssi::{ClientCommitDecision, Txn, DB};
+use pancake_engine_ssi::{ClientCommitDecision, IsolationLevel, Txn, DB};
 use pancake_types::serde::{Datum, DatumType};
-use pancake_types::types::{PrimaryKey, SubValueSpec, Value};
+use pancake_types::types::{PrimaryKey, ScndIdxSpec, SubValueSpec, Value};
 use std::sync::Arc;
 use tokio::task::JoinHandle;
 
@@ -36,7 +36,7 @@ fn pv_is_on_call(pv: &Value) -> bool {
 pub async fn no_write_skew(db: &'static DB) -> Result<()> {
     let db_adap = OneStmtSsiDbAdaptor { db };
 
-    let sv_spec = Arc::new(gen_sv_spec());
+    let sv_spec = Arc::new(ScndIdxSpec::from(gen_sv_spec()));
 
     db_adap.nonmut_create_scnd_idx(sv_spec.clone()).await?;
 
@@ -109,3 +109,97 @@ pub async fn no_write_skew(db: &'static DB) -> Result<()> {
 
     Ok(())
 }
+
+/// Two doctors are on-call, and at least one must remain on-call.
+/// A txn counts the on-call doctors; then another txn does the same, takes one doctor off-call, and commits;
+/// then the former txn takes the other doctor off-call based on its count.
+///
+/// Only serializable prevents the write skew.
+/// Runs after [`no_write_skew()`], which creates the secondary index.
+pub async fn write_skew_per_isolation_level(db: &'static DB) -> Result<()> {
+    let db_adap = OneStmtSsiDbAdaptor { db };
+    let sv_spec = Arc::new(ScndIdxSpec::from(gen_sv_spec()));
+
+    for (isolation_level, exp_is_skewed) in [
+        (IsolationLevel::ReadCommitted, true),
+        (IsolationLevel::SnapshotIsolation, true),
+        (IsolationLevel::Serializable, false),
+    ] {
+        let pk_prefix = format!("skew_doctor.{isolation_level:?}.");
+        let gen_pk =
+            |doctor_id: usize| Arc::new(gen::gen_str_pk(format!("{pk_prefix}{doctor_id}")));
+
+        /* Set up the initial condition. */
+        for doctor_id in 0..2 {
+            db_adap
+                .nonmut_put(gen_pk(doctor_id), Some(Arc::new(gen_pv(true))))
+                .await?;
+        }
+
+        let (pk_0, pk_1) = (gen_pk(0), gen_pk(1));
+        let off_call_pv = Arc::new(gen_pv(false));
+        let mut try_ct = 0;
+        let txn_fut = Txn::run_with_isolation_level(db, isolation_level, 1, |txn| {
+            try_ct += 1;
+
+            let on_call_count = count_on_call_in_txn(txn, &sv_spec, &pk_prefix)?;
+            if try_ct == 1 {
+                let (sv_spec, pk_prefix) = (Arc::clone(&sv_spec), pk_prefix.clone());
+                let (pk_1, off_call_pv) = (Arc::clone(&pk_1), Arc::clone(&off_call_pv));
+                block_on_in_another_thread(async move {
+                    Txn::run(db, 0, |txn| {
+                        if count_on_call_in_txn(txn, &sv_spec, &pk_prefix)? <= 1 {
+                            return Ok(ClientCommitDecision::Abort(()));
+                        }
+                        txn.put(&pk_1, &Some(off_call_pv.clone()))?;
+                        Ok(ClientCommitDecision::Commit(()))
+                    })
+                    .await
+                })?;
+            }
+
+            if on_call_count <= 1 {
+                return Ok(ClientCommitDecision::Abort(()));
+            }
+            txn.put(&pk_0, &Some(off_call_pv.clone()))?;
+            Ok(ClientCommitDecision::Commit(()))
+        });
+        txn_fut.await?;
+
+        /* Check the ending condition. */
+        let (exp_try_ct, exp_on_call_count) = match exp_is_skewed {
+            true => (1, 0),
+            false => (2, 1),
+        };
+        assert_eq!(exp_try_ct, try_ct, "{isolation_level:?}");
+        let final_on_call_count = db_adap
+            .get_sv_range(&sv_spec, None, None)
+            .await?
+            .into_iter()
+            .filter(|(pk, pv)| pk_has_prefix(pk, &pk_prefix) && pv_is_on_call(pv))
+            .count();
+        assert_eq!(
+            exp_on_call_count, final_on_call_count,
+            "{isolation_level:?}"
+        );
+    }
+
+    Ok(())
+}
+
+fn pk_has_prefix(pk: &PrimaryKey, pk_prefix: &str) -> bool {
+    if let PrimaryKey(Datum::Str(s)) = pk {
+        return s.starts_with(pk_prefix);
+    }
+    false
+}
+fn count_on_call_in_txn(txn: &mut Txn, sv_spec: &ScndIdxSpec, pk_prefix: &str) -> Result<usize> {
+    let mut on_call_count = 0;
+    for entry in txn.get_sv_range(sv_spec, None, None)? {
+        let (svpk, pv) = entry.try_borrow()?;
+        if pk_has_prefix(&svpk.pk, pk_prefix) && pv_is_on_call(pv) {
+            on_call_count += 1;
+        }
+    }
+    Ok(on_call_count)
+}
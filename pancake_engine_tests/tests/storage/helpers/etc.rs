@@ -1,6 +1,9 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::borrow::Borrow;
+use std::future::Future;
+use std::thread;
 use std::time::Duration;
+use tokio::runtime;
 use tokio::task::{JoinError, JoinHandle};
 
 pub fn sleep_sync(millis: u64) {
@@ -41,3 +44,21 @@ pub async fn join_tasks<T>(tasks: Vec<JoinHandle<Result<T>>>) -> Result<Vec<T>>
     }
     Ok(ret_items)
 }
+
+/// Runs `fut` to completion in another thread, blocking the current thread.
+///
+/// Use case:
+/// Within a txn's closure, which is sync, running another txn that must end before the closure continues.
+pub fn block_on_in_another_thread<T, F>(fut: F) -> Result<T>
+where
+    T: 'static + Send,
+    F: 'static + Send + Future<Output = Result<T>>,
+{
+    let thread = thread::spawn(move || {
+        let rt = runtime::Builder::new_current_thread().build()?;
+        rt.block_on(fut)
+    });
+    thread
+        .join()
+        .map_err(|_| anyhow!("The other thread panicked"))?
+}
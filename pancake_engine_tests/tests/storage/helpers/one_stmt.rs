@@ -1,9 +1,14 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use pancake_engine_serial::DB as SerialDb;
-use pancake_engine_ssi::{ClientCommitDecision, Txn, DB as SsiDb};
-use pancake_types::types::{PKShared, PVShared, PrimaryKey, SubValue, SubValueSpec};
+use pancake_engine_ssi::{
+    ClientCommitDecision, ConstraintCreationJobErr, ScndIdxCreationJobErr, Txn, DB as SsiDb,
+};
+use pancake_types::types::{
+    PKShared, PVShared, PrimaryKey, ScndIdxSpec, SubValue, ValueConstraint,
+};
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Adaptor for different implementations of db engines.
 #[async_trait]
@@ -18,16 +23,37 @@ pub trait OneStmtDbAdaptor {
 
     async fn get_sv_range(
         &self,
-        sv_spec: &SubValueSpec,
+        sv_spec: &ScndIdxSpec,
         sv_lo: Option<&SubValue>,
         sv_hi: Option<&SubValue>,
     ) -> Result<Vec<(PKShared, PVShared)>>;
 
+    async fn get_sv_range_projected(
+        &self,
+        sv_spec: &ScndIdxSpec,
+        sv_lo: Option<&SubValue>,
+        sv_hi: Option<&SubValue>,
+    ) -> Result<Vec<(PKShared, PVShared)>>;
+
+    /// A violation is returned as a [`pancake_types::types::ConstraintViolation`]
+    /// or a [`pancake_types::types::UniquenessViolation`].
     async fn put(&mut self, pk: PKShared, pv: Option<PVShared>) -> Result<()>;
 
-    async fn create_scnd_idx(&mut self, sv_spec: Arc<SubValueSpec>) -> Result<()>;
+    /// Like [`Self::put()`], but the entry expires after `ttl`.
+    async fn put_with_ttl(&mut self, pk: PKShared, pv: PVShared, ttl: Duration) -> Result<()>;
+
+    /// Deletes all entries whose PK is within `[pk_lo, pk_hi]`, inclusive.
+    async fn delete_range(&mut self, pk_lo: PKShared, pk_hi: PKShared) -> Result<()>;
+
+    /// A violation of a unique index by existing values is returned as a [`pancake_types::types::UniquenessViolation`].
+    async fn create_scnd_idx(&mut self, sv_spec: Arc<ScndIdxSpec>) -> Result<()>;
+
+    async fn delete_scnd_idx(&mut self, sv_spec: &ScndIdxSpec) -> Result<()>;
+
+    /// A violation by an existing value is returned as a [`pancake_types::types::ConstraintViolation`].
+    async fn create_constraint(&mut self, constraint: Arc<ValueConstraint>) -> Result<()>;
 
-    async fn delete_scnd_idx(&mut self, sv_spec: &SubValueSpec) -> Result<()>;
+    async fn delete_constraint(&mut self, constraint: &ValueConstraint) -> Result<()>;
 }
 
 pub struct OneStmtSerialDbAdaptor<'a> {
@@ -55,7 +81,7 @@ impl<'a> OneStmtDbAdaptor for OneStmtSerialDbAdaptor<'a> {
 
     async fn get_sv_range(
         &self,
-        sv_spec: &SubValueSpec,
+        sv_spec: &ScndIdxSpec,
         sv_lo: Option<&SubValue>,
         sv_hi: Option<&SubValue>,
     ) -> Result<Vec<(PKShared, PVShared)>> {
@@ -65,17 +91,45 @@ impl<'a> OneStmtDbAdaptor for OneStmtSerialDbAdaptor<'a> {
             .collect::<Result<Vec<_>>>()
     }
 
+    async fn get_sv_range_projected(
+        &self,
+        sv_spec: &ScndIdxSpec,
+        sv_lo: Option<&SubValue>,
+        sv_hi: Option<&SubValue>,
+    ) -> Result<Vec<(PKShared, PVShared)>> {
+        self.db
+            .get_sv_range_projected(sv_spec, sv_lo, sv_hi)?
+            .map(|entry| entry.into_owned_kv())
+            .collect::<Result<Vec<_>>>()
+    }
+
     async fn put(&mut self, pk: PKShared, pv: Option<PVShared>) -> Result<()> {
         self.db.put(pk, pv)
     }
 
-    async fn create_scnd_idx(&mut self, sv_spec: Arc<SubValueSpec>) -> Result<()> {
+    async fn put_with_ttl(&mut self, pk: PKShared, pv: PVShared, ttl: Duration) -> Result<()> {
+        self.db.put_with_ttl(pk, pv, ttl)
+    }
+
+    async fn delete_range(&mut self, pk_lo: PKShared, pk_hi: PKShared) -> Result<()> {
+        self.db.delete_range(pk_lo, pk_hi)
+    }
+
+    async fn create_scnd_idx(&mut self, sv_spec: Arc<ScndIdxSpec>) -> Result<()> {
         self.db.create_scnd_idx(sv_spec)
     }
 
-    async fn delete_scnd_idx(&mut self, sv_spec: &SubValueSpec) -> Result<()> {
+    async fn delete_scnd_idx(&mut self, sv_spec: &ScndIdxSpec) -> Result<()> {
         self.db.delete_scnd_idx(sv_spec)
     }
+
+    async fn create_constraint(&mut self, constraint: Arc<ValueConstraint>) -> Result<()> {
+        self.db.create_constraint(constraint)
+    }
+
+    async fn delete_constraint(&mut self, constraint: &ValueConstraint) -> Result<()> {
+        self.db.delete_constraint(constraint)
+    }
 }
 
 pub struct OneStmtSsiDbAdaptor<'a> {
@@ -92,19 +146,39 @@ impl<'a> OneStmtSsiDbAdaptor<'a> {
         res
     }
 
-    pub async fn nonmut_create_scnd_idx(&self, sv_spec: Arc<SubValueSpec>) -> Result<()> {
+    pub async fn nonmut_create_scnd_idx(&self, sv_spec: Arc<ScndIdxSpec>) -> Result<()> {
         self.db
             .create_scnd_idx(&sv_spec)
             .await
-            .map_err(|e| anyhow!(e))
+            .map_err(|e| match e {
+                ScndIdxCreationJobErr::UniquenessViolated(violation) => anyhow!(violation),
+                e => anyhow!(e),
+            })
     }
 
-    pub async fn nonmut_delete_scnd_idx(&self, sv_spec: &SubValueSpec) -> Result<()> {
+    pub async fn nonmut_delete_scnd_idx(&self, sv_spec: &ScndIdxSpec) -> Result<()> {
         self.db
             .delete_scnd_idx(sv_spec)
             .await
             .map_err(|e| anyhow!(e))
     }
+
+    pub async fn nonmut_create_constraint(&self, constraint: Arc<ValueConstraint>) -> Result<()> {
+        self.db
+            .create_constraint(&constraint)
+            .await
+            .map_err(|e| match e {
+                ConstraintCreationJobErr::Violated(violation) => anyhow!(violation),
+                e => anyhow!(e),
+            })
+    }
+
+    pub async fn nonmut_delete_constraint(&self, constraint: &ValueConstraint) -> Result<()> {
+        self.db
+            .delete_constraint(constraint)
+            .await
+            .map_err(|e| anyhow!(e))
+    }
 }
 
 #[async_trait]
@@ -136,7 +210,7 @@ impl<'a> OneStmtDbAdaptor for OneStmtSsiDbAdaptor<'a> {
 
     async fn get_sv_range(
         &self,
-        sv_spec: &SubValueSpec,
+        sv_spec: &ScndIdxSpec,
         sv_lo: Option<&SubValue>,
         sv_hi: Option<&SubValue>,
     ) -> Result<Vec<(PKShared, PVShared)>> {
@@ -151,15 +225,58 @@ impl<'a> OneStmtDbAdaptor for OneStmtSsiDbAdaptor<'a> {
         res
     }
 
+    async fn get_sv_range_projected(
+        &self,
+        sv_spec: &ScndIdxSpec,
+        sv_lo: Option<&SubValue>,
+        sv_hi: Option<&SubValue>,
+    ) -> Result<Vec<(PKShared, PVShared)>> {
+        let fut = Txn::run(self.db, 0, |txn| {
+            let entries = txn.get_sv_range_projected(sv_spec, sv_lo, sv_hi)?;
+            let entries = entries
+                .map(|entry| entry.convert::<PKShared, PVShared>().into_owned_kv())
+                .collect::<Result<Vec<_>>>()?;
+            Ok(ClientCommitDecision::Commit(entries))
+        });
+        let res = fut.await;
+        res
+    }
+
     async fn put(&mut self, pk: PKShared, pv: Option<PVShared>) -> Result<()> {
         self.nonmut_put(pk, pv).await
     }
 
-    async fn create_scnd_idx(&mut self, sv_spec: Arc<SubValueSpec>) -> Result<()> {
+    async fn put_with_ttl(&mut self, pk: PKShared, pv: PVShared, ttl: Duration) -> Result<()> {
+        let fut = Txn::run(self.db, 0, |txn| {
+            txn.put_with_ttl(&pk, &pv, ttl)?;
+            Ok(ClientCommitDecision::Commit(()))
+        });
+        let res = fut.await;
+        res
+    }
+
+    async fn delete_range(&mut self, pk_lo: PKShared, pk_hi: PKShared) -> Result<()> {
+        let fut = Txn::run(self.db, 0, |txn| {
+            txn.delete_range(&pk_lo, &pk_hi)?;
+            Ok(ClientCommitDecision::Commit(()))
+        });
+        let res = fut.await;
+        res
+    }
+
+    async fn create_scnd_idx(&mut self, sv_spec: Arc<ScndIdxSpec>) -> Result<()> {
         self.nonmut_create_scnd_idx(sv_spec).await
     }
 
-    async fn delete_scnd_idx(&mut self, sv_spec: &SubValueSpec) -> Result<()> {
+    async fn delete_scnd_idx(&mut self, sv_spec: &ScndIdxSpec) -> Result<()> {
         self.nonmut_delete_scnd_idx(sv_spec).await
     }
+
+    async fn create_constraint(&mut self, constraint: Arc<ValueConstraint>) -> Result<()> {
+        self.nonmut_create_constraint(constraint).await
+    }
+
+    async fn delete_constraint(&mut self, constraint: &ValueConstraint) -> Result<()> {
+        self.nonmut_delete_constraint(constraint).await
+    }
 }
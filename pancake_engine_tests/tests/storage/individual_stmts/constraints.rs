@@ -0,0 +1,94 @@
+use super::super::helpers::{gen, one_stmt::OneStmtDbAdaptor};
+use anyhow::Result;
+use pancake_types::serde::{Datum, DatumType};
+use pancake_types::types::{ConstraintViolation, MemberKey, Value, ValueConstraint, ValueShape};
+use std::sync::Arc;
+
+/// "Member 0 must be I64."
+fn constraint_int_at_0() -> ValueConstraint {
+    ValueConstraint {
+        member_path: vec![MemberKey::Idx(0)],
+        shape: ValueShape::Type(DatumType::I64),
+    }
+}
+
+fn gen_pv(member_0: Datum) -> Value {
+    Value(Datum::Tuple(vec![
+        member_0,
+        Datum::Str(String::from("etc")),
+    ]))
+}
+
+async fn put(db: &mut impl OneStmtDbAdaptor, pk: &str, pv: Option<Value>) -> Result<()> {
+    let pk = Arc::new(gen::gen_str_pk(pk));
+    db.put(pk, pv.map(Arc::new)).await
+}
+
+fn assert_violation(res: Result<()>, constraint: &ValueConstraint, pk: &str) {
+    let e = res.unwrap_err();
+    let violation = e.downcast_ref::<ConstraintViolation>().unwrap();
+    assert_eq!(violation.constraint.as_ref(), constraint);
+    assert_eq!(violation.pk.as_ref(), &gen::gen_str_pk(pk));
+}
+
+pub async fn delete_create_put(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
+    let constraint = Arc::new(constraint_int_at_0());
+
+    /* Delete constraint. */
+
+    db.delete_constraint(&constraint).await?;
+
+    /* Insert a violating value ; Fail to create constraint. */
+
+    put(db, "constrained.1", Some(gen_pv(Datum::Null))).await?;
+
+    let res = db.create_constraint(Arc::clone(&constraint)).await;
+    assert_violation(res, &constraint, "constrained.1");
+
+    // The failed constraint does not apply.
+    put(db, "constrained.2", Some(gen::gen_str_pv("not a tuple"))).await?;
+
+    /* Fix the violating values ; Create constraint. */
+
+    put(db, "constrained.1", Some(gen_pv(Datum::I64(1)))).await?;
+    put(db, "constrained.2", None).await?;
+
+    db.create_constraint(Arc::clone(&constraint)).await?;
+
+    /* Put. */
+
+    let res = put(db, "constrained.3", Some(gen_pv(Datum::I64(3)))).await;
+    assert!(res.is_ok());
+
+    let res = put(db, "constrained.4", Some(gen_pv(Datum::F64(4.0)))).await;
+    assert_violation(res, &constraint, "constrained.4");
+    assert_eq!(
+        None,
+        db.get_pk_one(&gen::gen_str_pk("constrained.4")).await?
+    );
+
+    let res = put(db, "constrained.3", Some(gen_pv(Datum::Null))).await;
+    assert_violation(res, &constraint, "constrained.3");
+    assert_eq!(
+        Some(Arc::new(gen_pv(Datum::I64(3)))),
+        db.get_pk_one(&gen::gen_str_pk("constrained.3"))
+            .await?
+            .map(|(_pk, pv)| pv)
+    );
+
+    // Deletion is never constrained.
+    put(db, "constrained.3", None).await?;
+
+    /* Delete constraint ; Put. */
+
+    db.delete_constraint(&constraint).await?;
+
+    put(db, "constrained.4", Some(gen_pv(Datum::F64(4.0)))).await?;
+
+    /* Clean up, so that other tests may create other constraints. */
+
+    put(db, "constrained.1", None).await?;
+    put(db, "constrained.4", None).await?;
+
+    Ok(())
+}
@@ -1,3 +1,4 @@
+pub mod constraints;
 pub mod primary;
 pub mod secondary;
 
@@ -5,13 +6,25 @@ use super::helpers::one_stmt::OneStmtDbAdaptor;
 use anyhow::Result;
 
 pub async fn test_stmts_serially(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
+    // Runs first, because creating a constraint requires all existing values to satisfy it.
+    constraints::delete_create_put(db).await?;
+
     primary::put_del_get_getrange(db).await?;
     primary::nonexistent(db).await?;
     primary::zero_byte_value(db).await?;
     primary::tuple(db).await?;
+    primary::delete_range(db).await?;
 
     secondary::whole::delete_create_get(db).await?;
     secondary::partial::delete_create_get(db).await?;
+    secondary::map::delete_create_get(db).await?;
+    secondary::multi::delete_create_get(db).await?;
+    secondary::composite::delete_create_get(db).await?;
+    secondary::projection::delete_create_get(db).await?;
+    secondary::filter::delete_create_get(db).await?;
+    secondary::unique::delete_create_put(db).await?;
+    secondary::ttl::delete_put_create_get(db).await?;
+    secondary::range_delete::delete_create_del_get(db).await?;
 
     Ok(())
 }
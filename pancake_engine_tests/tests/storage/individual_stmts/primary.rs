@@ -109,3 +109,41 @@ pub async fn tuple(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
 
     Ok(())
 }
+
+pub async fn delete_range(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
+    let gen_pk = |i: usize| Arc::new(gen::gen_str_pk(format!("rangedel.{i:02}")));
+    let gen_pv = |i: usize| Arc::new(gen::gen_str_pv(format!("val{i}")));
+
+    let mut pk_to_expected_pv = BTreeMap::<PKShared, Option<PVShared>>::new();
+
+    for i in 0..20 {
+        db.put(gen_pk(i), Some(gen_pv(i))).await?;
+        pk_to_expected_pv.insert(gen_pk(i), Some(gen_pv(i)));
+    }
+
+    db.delete_range(gen_pk(5), gen_pk(12)).await?;
+    for i in 5..=12 {
+        pk_to_expected_pv.insert(gen_pk(i), None);
+    }
+
+    // A put after the range delete is not shadowed by it.
+    db.put(gen_pk(7), Some(gen_pv(700))).await?;
+    pk_to_expected_pv.insert(gen_pk(7), Some(gen_pv(700)));
+
+    // An empty range is a no-op.
+    db.delete_range(gen_pk(18), gen_pk(15)).await?;
+
+    for (pk, exp_pv) in pk_to_expected_pv.iter() {
+        let act_pv = db.get_pk_one(pk).await?.map(|(_k, v)| v);
+        assert_eq!(*exp_pv, act_pv, "{pk:?}");
+    }
+
+    let exp_range = pk_to_expected_pv
+        .into_iter()
+        .filter_map(|(pk, opt_pv)| opt_pv.map(|pv| (pk, pv)))
+        .collect::<Vec<_>>();
+    let act_range = db.get_pk_range(Some(&gen_pk(0)), Some(&gen_pk(19))).await?;
+    assert_eq!(exp_range, act_range);
+
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use super::super::super::helpers::gen;
+use super::super::OneStmtDbAdaptor;
+use super::helper_verify::verify_get;
+use anyhow::Result;
+use pancake_types::serde::{Datum, DatumType};
+use pancake_types::types::{
+    MemberKey, PrimaryKey, ScndIdxProjection, ScndIdxSpec, SubValue, SubValueSpec, Value,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// A composite SVSpec that extracts `(PV.tenant: str, PV.created_at: ts)`.
+fn spec_tenant_created_at() -> ScndIdxSpec {
+    ScndIdxSpec {
+        columns: vec![
+            SubValueSpec {
+                member_path: vec![MemberKey::from("tenant")],
+                datum_type: DatumType::Str,
+            },
+            SubValueSpec {
+                member_path: vec![MemberKey::from("created_at")],
+                datum_type: DatumType::Timestamp,
+            },
+        ],
+        projection: ScndIdxProjection::Whole,
+        filter: None,
+        is_unique: false,
+    }
+}
+
+/// A PV that is typed `{tenant: str, created_at: ts}`. Either member may be absent.
+fn gen_pv(tenant: &str, created_at: Option<i64>) -> Value {
+    let mut members = BTreeMap::new();
+    members.insert(String::from("tenant"), Datum::Str(String::from(tenant)));
+    if let Some(created_at) = created_at {
+        members.insert(String::from("created_at"), Datum::Timestamp(created_at));
+    }
+    Value(Datum::Map(members))
+}
+
+fn gen_pkv(pk: &str, tenant: &str, created_at: Option<i64>) -> (PrimaryKey, Value) {
+    (gen::gen_str_pk(pk), gen_pv(tenant, created_at))
+}
+
+/// A SV holding a prefix of the columns.
+fn gen_sv(tenant: &str, created_at: Option<i64>) -> SubValue {
+    let mut members = vec![Datum::Str(String::from(tenant))];
+    if let Some(created_at) = created_at {
+        members.push(Datum::Timestamp(created_at));
+    }
+    SubValue(Datum::Tuple(members))
+}
+
+fn gen_sv_hi(spec: &ScndIdxSpec, tenant: &str, created_at: Option<i64>) -> Result<SubValue> {
+    let sv = gen_sv(tenant, created_at);
+    Ok(spec.prefix_hi(&sv)?.unwrap_or(sv))
+}
+
+async fn put(
+    db: &mut impl OneStmtDbAdaptor,
+    pk: &str,
+    tenant: &str,
+    created_at: Option<i64>,
+) -> Result<()> {
+    let (pk, pv) = gen_pkv(pk, tenant, created_at);
+    db.put(Arc::new(pk), Some(Arc::new(pv))).await
+}
+
+async fn del(db: &mut impl OneStmtDbAdaptor, pk: &str) -> Result<()> {
+    let pk = gen::gen_str_pk(pk);
+    db.put(Arc::new(pk), None).await
+}
+
+pub async fn delete_create_get(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
+    let spec = Arc::new(spec_tenant_created_at());
+
+    /* Delete scnd idx. */
+
+    db.delete_scnd_idx(&spec).await?;
+
+    verify_get(db, &spec, None, None, Err(())).await?;
+
+    /* Insert ; Create scnd idx ; Insert more. */
+
+    put(db, "composite.1", "t1", Some(200)).await?;
+    put(db, "composite.2", "t2", Some(100)).await?;
+
+    db.create_scnd_idx(Arc::clone(&spec)).await?;
+
+    put(db, "composite.3", "t1", Some(100)).await?;
+    // A PV lacking one column is not indexed.
+    put(db, "composite.4", "t1", None).await?;
+
+    /* Get all. Ordered by tenant, then by created_at. */
+
+    verify_get(
+        db,
+        &spec,
+        None,
+        None,
+        Ok(vec![
+            gen_pkv("composite.3", "t1", Some(100)),
+            gen_pkv("composite.1", "t1", Some(200)),
+            gen_pkv("composite.2", "t2", Some(100)),
+        ]),
+    )
+    .await?;
+
+    /* Get by all columns. */
+
+    verify_get(
+        db,
+        &spec,
+        Some(gen_sv("t1", Some(200))),
+        Some(gen_sv_hi(&spec, "t1", Some(200))?),
+        Ok(vec![gen_pkv("composite.1", "t1", Some(200))]),
+    )
+    .await?;
+
+    /* Get by a prefix of columns. */
+
+    verify_get(
+        db,
+        &spec,
+        Some(gen_sv("t1", None)),
+        Some(gen_sv_hi(&spec, "t1", None)?),
+        Ok(vec![
+            gen_pkv("composite.3", "t1", Some(100)),
+            gen_pkv("composite.1", "t1", Some(200)),
+        ]),
+    )
+    .await?;
+
+    verify_get(
+        db,
+        &spec,
+        Some(gen_sv("t1", Some(150))),
+        Some(gen_sv_hi(&spec, "t2", None)?),
+        Ok(vec![
+            gen_pkv("composite.1", "t1", Some(200)),
+            gen_pkv("composite.2", "t2", Some(100)),
+        ]),
+    )
+    .await?;
+
+    /* Modify ; Get. */
+
+    put(db, "composite.1", "t2", Some(50)).await?;
+
+    verify_get(
+        db,
+        &spec,
+        Some(gen_sv("t2", None)),
+        Some(gen_sv_hi(&spec, "t2", None)?),
+        Ok(vec![
+            gen_pkv("composite.1", "t2", Some(50)),
+            gen_pkv("composite.2", "t2", Some(100)),
+        ]),
+    )
+    .await?;
+
+    /* Delete ; Get. */
+
+    del(db, "composite.2").await?;
+
+    verify_get(
+        db,
+        &spec,
+        None,
+        None,
+        Ok(vec![
+            gen_pkv("composite.3", "t1", Some(100)),
+            gen_pkv("composite.1", "t2", Some(50)),
+        ]),
+    )
+    .await?;
+
+    Ok(())
+}
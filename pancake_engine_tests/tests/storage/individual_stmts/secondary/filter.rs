@@ -0,0 +1,129 @@
+use super::super::super::helpers::gen;
+use super::super::OneStmtDbAdaptor;
+use super::helper_verify::verify_get;
+use anyhow::Result;
+use pancake_types::serde::{Datum, DatumType};
+use pancake_types::types::{
+    MemberKey, PredicateTest, PrimaryKey, ScndIdxProjection, ScndIdxSpec, SubValue, SubValueSpec,
+    Value, ValuePredicate,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// A SVSpec that extracts `PV.age: int`, from PVs whose `PV.status` is `"active"`.
+fn spec_active_age_int() -> ScndIdxSpec {
+    ScndIdxSpec {
+        columns: vec![SubValueSpec {
+            member_path: vec![MemberKey::from("age")],
+            datum_type: DatumType::I64,
+        }],
+        projection: ScndIdxProjection::Whole,
+        filter: Some(ValuePredicate {
+            member_path: vec![MemberKey::from("status")],
+            test: PredicateTest::Eq(Datum::Str(String::from("active"))),
+        }),
+        is_unique: false,
+    }
+}
+
+/// A PV that is typed `{age: int, status: str}`.
+fn gen_pv(age: i64, status: &str) -> Value {
+    let members = BTreeMap::from([
+        (String::from("age"), Datum::I64(age)),
+        (String::from("status"), Datum::Str(String::from(status))),
+    ]);
+    Value(Datum::Map(members))
+}
+
+fn gen_pkv(pk: &str, age: i64, status: &str) -> (PrimaryKey, Value) {
+    (gen::gen_str_pk(pk), gen_pv(age, status))
+}
+
+fn gen_sv(age: i64) -> SubValue {
+    SubValue(Datum::I64(age))
+}
+
+async fn put(db: &mut impl OneStmtDbAdaptor, pk: &str, age: i64, status: &str) -> Result<()> {
+    let (pk, pv) = gen_pkv(pk, age, status);
+    db.put(Arc::new(pk), Some(Arc::new(pv))).await
+}
+
+pub async fn delete_create_get(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
+    let spec = Arc::new(spec_active_age_int());
+
+    /* Delete scnd idx. */
+
+    db.delete_scnd_idx(&spec).await?;
+
+    verify_get(db, &spec, None, None, Err(())).await?;
+
+    /* Insert ; Create scnd idx ; Insert more. */
+
+    put(db, "filter.1", 30, "active").await?;
+    put(db, "filter.2", 25, "closed").await?;
+
+    db.create_scnd_idx(Arc::clone(&spec)).await?;
+
+    put(db, "filter.3", 20, "active").await?;
+    put(db, "filter.4", 35, "closed").await?;
+
+    verify_get(
+        db,
+        &spec,
+        None,
+        None,
+        Ok(vec![
+            gen_pkv("filter.3", 20, "active"),
+            gen_pkv("filter.1", 30, "active"),
+        ]),
+    )
+    .await?;
+
+    /* Make values stop and start satisfying the filter ; Get. */
+
+    put(db, "filter.1", 30, "closed").await?;
+    put(db, "filter.2", 25, "active").await?;
+
+    verify_get(
+        db,
+        &spec,
+        None,
+        None,
+        Ok(vec![
+            gen_pkv("filter.3", 20, "active"),
+            gen_pkv("filter.2", 25, "active"),
+        ]),
+    )
+    .await?;
+
+    /* Modify the indexed sub-value of a satisfying value ; Get. */
+
+    put(db, "filter.3", 40, "active").await?;
+
+    verify_get(
+        db,
+        &spec,
+        Some(gen_sv(22)),
+        None,
+        Ok(vec![
+            gen_pkv("filter.2", 25, "active"),
+            gen_pkv("filter.3", 40, "active"),
+        ]),
+    )
+    .await?;
+
+    /* Delete ; Get. */
+
+    db.put(Arc::new(gen::gen_str_pk("filter.2")), None).await?;
+
+    verify_get(
+        db,
+        &spec,
+        None,
+        None,
+        Ok(vec![gen_pkv("filter.3", 40, "active")]),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -1,11 +1,11 @@
 use super::super::super::helpers::one_stmt::OneStmtDbAdaptor;
 use anyhow::Result;
-use pancake_types::types::{PrimaryKey, SubValue, SubValueSpec, Value};
+use pancake_types::types::{PrimaryKey, ScndIdxSpec, SubValue, Value};
 use std::sync::Arc;
 
 pub async fn verify_get(
     db: &mut impl OneStmtDbAdaptor,
-    sv_spec: &SubValueSpec,
+    sv_spec: &ScndIdxSpec,
     sv_lo: Option<SubValue>,
     sv_hi: Option<SubValue>,
     exp: Result<Vec<(PrimaryKey, Value)>, ()>,
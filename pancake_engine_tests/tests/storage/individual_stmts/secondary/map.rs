@@ -0,0 +1,175 @@
+use super::super::super::helpers::gen;
+use super::super::OneStmtDbAdaptor;
+use super::helper_verify::verify_get;
+use anyhow::Result;
+use pancake_types::serde::{Datum, DatumType};
+use pancake_types::types::{MemberKey, PrimaryKey, ScndIdxSpec, SubValue, SubValueSpec, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// A SVSpec that extracts `PV.user.address.zip: str`.
+fn spec_zip_str() -> SubValueSpec {
+    SubValueSpec {
+        member_path: vec![
+            MemberKey::from("user"),
+            MemberKey::from("address"),
+            MemberKey::from("zip"),
+        ],
+        datum_type: DatumType::Str,
+    }
+}
+
+/// A SVSpec that extracts `PV.tags[1]: str`.
+fn spec_tags_1_str() -> SubValueSpec {
+    SubValueSpec {
+        member_path: vec![MemberKey::from("tags"), MemberKey::Idx(1)],
+        datum_type: DatumType::Str,
+    }
+}
+
+fn map<const N: usize>(members: [(&str, Datum); N]) -> Datum {
+    let members = members
+        .into_iter()
+        .map(|(key, val)| (String::from(key), val));
+    Datum::Map(BTreeMap::from_iter(members))
+}
+
+/// A PV that is typed `{user: {address: {zip: str}}, tags: (str, str)}`.
+fn gen_pv(zip: &str, tag: &str) -> Value {
+    let pv = map([
+        (
+            "user",
+            map([("address", map([("zip", Datum::Str(String::from(zip)))]))]),
+        ),
+        (
+            "tags",
+            Datum::Tuple(vec![
+                Datum::Str(String::from("tag-0")),
+                Datum::Str(String::from(tag)),
+            ]),
+        ),
+    ]);
+    Value(pv)
+}
+
+fn gen_pkv(pk: &str, zip: &str, tag: &str) -> (PrimaryKey, Value) {
+    (gen::gen_str_pk(pk), gen_pv(zip, tag))
+}
+
+fn gen_sv(sv: &str) -> SubValue {
+    gen::gen_str_sv(sv)
+}
+
+async fn put(db: &mut impl OneStmtDbAdaptor, pk: &str, zip: &str, tag: &str) -> Result<()> {
+    let (pk, pv) = gen_pkv(pk, zip, tag);
+    db.put(Arc::new(pk), Some(Arc::new(pv))).await
+}
+
+async fn del(db: &mut impl OneStmtDbAdaptor, pk: &str) -> Result<()> {
+    let pk = gen::gen_str_pk(pk);
+    db.put(Arc::new(pk), None).await
+}
+
+pub async fn delete_create_get(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
+    let spec_zip_str = Arc::new(ScndIdxSpec::from(spec_zip_str()));
+    let spec_tags_1_str = Arc::new(ScndIdxSpec::from(spec_tags_1_str()));
+
+    /* Delete scnd idxs. */
+
+    db.delete_scnd_idx(&spec_zip_str).await?;
+    db.delete_scnd_idx(&spec_tags_1_str).await?;
+
+    verify_get(db, &spec_zip_str, None, None, Err(())).await?;
+    verify_get(db, &spec_tags_1_str, None, None, Err(())).await?;
+
+    /* Insert ; Create scnd idxs ; Insert more. */
+
+    put(db, "doc.4", "zip-40", "tag-d").await?;
+    put(db, "doc.3", "zip-30", "tag-c").await?;
+    // A PV whose shape does not match either spec.
+    db.put(
+        Arc::new(gen::gen_str_pk("doc.0")),
+        Some(Arc::new(Value(map([("user", Datum::I64(0))])))),
+    )
+    .await?;
+
+    db.create_scnd_idx(Arc::clone(&spec_zip_str)).await?;
+    db.create_scnd_idx(Arc::clone(&spec_tags_1_str)).await?;
+
+    put(db, "doc.2", "zip-20", "tag-b").await?;
+    put(db, "doc.1", "zip-10", "tag-a").await?;
+
+    /* Get by range of SVs. */
+
+    verify_get(
+        db,
+        &spec_zip_str,
+        None,
+        None,
+        Ok(vec![
+            gen_pkv("doc.1", "zip-10", "tag-a"),
+            gen_pkv("doc.2", "zip-20", "tag-b"),
+            gen_pkv("doc.3", "zip-30", "tag-c"),
+            gen_pkv("doc.4", "zip-40", "tag-d"),
+        ]),
+    )
+    .await?;
+
+    verify_get(
+        db,
+        &spec_zip_str,
+        Some(gen_sv("zip-15")),
+        Some(gen_sv("zip-30")),
+        Ok(vec![
+            gen_pkv("doc.2", "zip-20", "tag-b"),
+            gen_pkv("doc.3", "zip-30", "tag-c"),
+        ]),
+    )
+    .await?;
+
+    verify_get(
+        db,
+        &spec_tags_1_str,
+        Some(gen_sv("tag-b")),
+        None,
+        Ok(vec![
+            gen_pkv("doc.2", "zip-20", "tag-b"),
+            gen_pkv("doc.3", "zip-30", "tag-c"),
+            gen_pkv("doc.4", "zip-40", "tag-d"),
+        ]),
+    )
+    .await?;
+
+    /* Modify ; Delete ; Get. */
+
+    put(db, "doc.1", "zip-50", "tag-a").await?;
+    del(db, "doc.3").await?;
+
+    verify_get(
+        db,
+        &spec_zip_str,
+        None,
+        None,
+        Ok(vec![
+            gen_pkv("doc.2", "zip-20", "tag-b"),
+            gen_pkv("doc.4", "zip-40", "tag-d"),
+            gen_pkv("doc.1", "zip-50", "tag-a"),
+        ]),
+    )
+    .await?;
+
+    verify_get(
+        db,
+        &spec_tags_1_str,
+        None,
+        None,
+        Ok(vec![
+            gen_pkv("doc.1", "zip-50", "tag-a"),
+            gen_pkv("doc.2", "zip-20", "tag-b"),
+            gen_pkv("doc.4", "zip-40", "tag-d"),
+        ]),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -1,3 +1,11 @@
+pub mod composite;
+pub mod filter;
 pub mod helper_verify;
+pub mod map;
+pub mod multi;
 pub mod partial;
+pub mod projection;
+pub mod range_delete;
+pub mod ttl;
+pub mod unique;
 pub mod whole;
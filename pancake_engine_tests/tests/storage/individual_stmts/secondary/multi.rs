@@ -0,0 +1,128 @@
+use super::super::super::helpers::gen;
+use super::super::OneStmtDbAdaptor;
+use super::helper_verify::verify_get;
+use anyhow::Result;
+use pancake_types::serde::{Datum, DatumType};
+use pancake_types::types::{MemberKey, PrimaryKey, ScndIdxSpec, SubValue, SubValueSpec, Value};
+use std::sync::Arc;
+
+/// A multi-valued SVSpec that extracts every `PV[1][*]: str`.
+fn spec_tags_str() -> SubValueSpec {
+    SubValueSpec {
+        member_path: vec![MemberKey::Idx(1), MemberKey::Each],
+        datum_type: DatumType::Str,
+    }
+}
+
+/// A PV that is typed `(int, list<str>)`.
+fn gen_pv(tags: &[&str]) -> Value {
+    let tags = tags
+        .iter()
+        .map(|tag| Datum::Str(String::from(*tag)))
+        .collect::<Vec<_>>();
+    Value(Datum::Tuple(vec![Datum::I64(0), Datum::List(tags)]))
+}
+
+fn gen_pkv(pk: &str, tags: &[&str]) -> (PrimaryKey, Value) {
+    (gen::gen_str_pk(pk), gen_pv(tags))
+}
+
+fn gen_sv(sv: &str) -> SubValue {
+    gen::gen_str_sv(sv)
+}
+
+async fn put(db: &mut impl OneStmtDbAdaptor, pk: &str, tags: &[&str]) -> Result<()> {
+    let (pk, pv) = gen_pkv(pk, tags);
+    db.put(Arc::new(pk), Some(Arc::new(pv))).await
+}
+
+async fn del(db: &mut impl OneStmtDbAdaptor, pk: &str) -> Result<()> {
+    let pk = gen::gen_str_pk(pk);
+    db.put(Arc::new(pk), None).await
+}
+
+pub async fn delete_create_get(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
+    let spec = Arc::new(ScndIdxSpec::from(spec_tags_str()));
+
+    /* Delete scnd idx. */
+
+    db.delete_scnd_idx(&spec).await?;
+
+    verify_get(db, &spec, None, None, Err(())).await?;
+
+    /* Insert ; Create scnd idx ; Insert more. */
+
+    put(db, "tagged.1", &["tag-a", "tag-b", "tag-a"]).await?;
+    put(db, "tagged.2", &[]).await?;
+
+    db.create_scnd_idx(Arc::clone(&spec)).await?;
+
+    put(db, "tagged.3", &["tag-c", "tag-b"]).await?;
+
+    /* Get. Each PV is found once per distinct tag. */
+
+    verify_get(
+        db,
+        &spec,
+        None,
+        None,
+        Ok(vec![
+            gen_pkv("tagged.1", &["tag-a", "tag-b", "tag-a"]),
+            gen_pkv("tagged.1", &["tag-a", "tag-b", "tag-a"]),
+            gen_pkv("tagged.3", &["tag-c", "tag-b"]),
+            gen_pkv("tagged.3", &["tag-c", "tag-b"]),
+        ]),
+    )
+    .await?;
+
+    verify_get(
+        db,
+        &spec,
+        Some(gen_sv("tag-b")),
+        Some(gen_sv("tag-b")),
+        Ok(vec![
+            gen_pkv("tagged.1", &["tag-a", "tag-b", "tag-a"]),
+            gen_pkv("tagged.3", &["tag-c", "tag-b"]),
+        ]),
+    )
+    .await?;
+
+    /* Modify ; Get. Only the removed tags are tombstoned. */
+
+    put(db, "tagged.1", &["tag-b", "tag-d"]).await?;
+    put(db, "tagged.2", &["tag-a"]).await?;
+
+    verify_get(
+        db,
+        &spec,
+        None,
+        None,
+        Ok(vec![
+            gen_pkv("tagged.2", &["tag-a"]),
+            gen_pkv("tagged.1", &["tag-b", "tag-d"]),
+            gen_pkv("tagged.3", &["tag-c", "tag-b"]),
+            gen_pkv("tagged.3", &["tag-c", "tag-b"]),
+            gen_pkv("tagged.1", &["tag-b", "tag-d"]),
+        ]),
+    )
+    .await?;
+
+    /* Delete ; Get. */
+
+    del(db, "tagged.3").await?;
+
+    verify_get(
+        db,
+        &spec,
+        None,
+        None,
+        Ok(vec![
+            gen_pkv("tagged.2", &["tag-a"]),
+            gen_pkv("tagged.1", &["tag-b", "tag-d"]),
+            gen_pkv("tagged.1", &["tag-b", "tag-d"]),
+        ]),
+    )
+    .await?;
+
+    Ok(())
+}
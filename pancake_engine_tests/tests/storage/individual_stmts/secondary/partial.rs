@@ -3,13 +3,13 @@ use super::super::OneStmtDbAdaptor;
 use super::helper_verify::verify_get;
 use anyhow::Result;
 use pancake_types::serde::{Datum, DatumType};
-use pancake_types::types::{PrimaryKey, SubValue, SubValueSpec, Value};
+use pancake_types::types::{MemberKey, PrimaryKey, ScndIdxSpec, SubValue, SubValueSpec, Value};
 use std::sync::Arc;
 
 /// A SVSpec that extracts `PV[1][2]: str`.
 fn spec_1_2_str() -> SubValueSpec {
     SubValueSpec {
-        member_idxs: vec![1, 2],
+        member_path: vec![MemberKey::Idx(1), MemberKey::Idx(2)],
         datum_type: DatumType::Str,
     }
 }
@@ -18,7 +18,7 @@ fn spec_1_2_str() -> SubValueSpec {
 /// The contents of the targeted tuple are opaque in the view of this SVSpec.
 fn spec_1_tup() -> SubValueSpec {
     SubValueSpec {
-        member_idxs: vec![1],
+        member_path: vec![MemberKey::Idx(1)],
         datum_type: DatumType::Tuple,
     }
 }
@@ -66,8 +66,8 @@ async fn del(db: &mut impl OneStmtDbAdaptor, pk: &str) -> Result<()> {
 }
 
 pub async fn delete_create_get(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
-    let spec_1_2_str = Arc::new(spec_1_2_str());
-    let spec_1_tup = Arc::new(spec_1_tup());
+    let spec_1_2_str = Arc::new(ScndIdxSpec::from(spec_1_2_str()));
+    let spec_1_tup = Arc::new(ScndIdxSpec::from(spec_1_tup()));
 
     /* Delete scnd idxs. */
 
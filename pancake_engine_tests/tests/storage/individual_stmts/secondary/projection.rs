@@ -0,0 +1,187 @@
+use super::super::super::helpers::gen;
+use super::super::OneStmtDbAdaptor;
+use super::helper_verify::verify_get;
+use anyhow::Result;
+use pancake_types::serde::{Datum, DatumType};
+use pancake_types::types::{
+    MemberKey, PrimaryKey, ScndIdxProjection, ScndIdxSpec, SubValue, SubValueSpec, Value,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// A SVSpec that extracts `PV.age: int`.
+fn spec_age_int(projection: ScndIdxProjection) -> ScndIdxSpec {
+    ScndIdxSpec {
+        columns: vec![SubValueSpec {
+            member_path: vec![MemberKey::from("age")],
+            datum_type: DatumType::I64,
+        }],
+        projection,
+        filter: None,
+        is_unique: false,
+    }
+}
+
+/// A projection of `(PV.name: str)`.
+fn projection_name() -> ScndIdxProjection {
+    ScndIdxProjection::Columns(vec![SubValueSpec {
+        member_path: vec![MemberKey::from("name")],
+        datum_type: DatumType::Str,
+    }])
+}
+
+/// A PV that is typed `{age: int, name: str}`.
+fn gen_pv(age: i64, name: &str) -> Value {
+    let members = BTreeMap::from([
+        (String::from("age"), Datum::I64(age)),
+        (String::from("name"), Datum::Str(String::from(name))),
+    ]);
+    Value(Datum::Map(members))
+}
+
+fn gen_pkv(pk: &str, age: i64, name: &str) -> (PrimaryKey, Value) {
+    (gen::gen_str_pk(pk), gen_pv(age, name))
+}
+
+fn gen_sv(age: i64) -> SubValue {
+    SubValue(Datum::I64(age))
+}
+
+async fn put(db: &mut impl OneStmtDbAdaptor, pk: &str, age: i64, name: &str) -> Result<()> {
+    let (pk, pv) = gen_pkv(pk, age, name);
+    db.put(Arc::new(pk), Some(Arc::new(pv))).await
+}
+
+async fn verify_get_projected(
+    db: &mut impl OneStmtDbAdaptor,
+    sv_spec: &ScndIdxSpec,
+    sv_lo: Option<SubValue>,
+    sv_hi: Option<SubValue>,
+    exp: Vec<(&str, Datum)>,
+) -> Result<()> {
+    let act = db
+        .get_sv_range_projected(sv_spec, sv_lo.as_ref(), sv_hi.as_ref())
+        .await?;
+    let exp = exp
+        .into_iter()
+        .map(|(pk, proj)| (Arc::new(gen::gen_str_pk(pk)), Arc::new(Value(proj))))
+        .collect::<Vec<_>>();
+    assert_eq!(exp, act);
+    Ok(())
+}
+
+pub async fn delete_create_get(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
+    let spec_keys = Arc::new(spec_age_int(ScndIdxProjection::KeysOnly));
+    let spec_name = Arc::new(spec_age_int(projection_name()));
+
+    /* Delete scnd idxs. */
+
+    db.delete_scnd_idx(&spec_keys).await?;
+    db.delete_scnd_idx(&spec_name).await?;
+
+    verify_get(db, &spec_keys, None, None, Err(())).await?;
+
+    /* Insert ; Create scnd idxs ; Insert more. */
+
+    put(db, "projection.1", 30, "alice").await?;
+
+    db.create_scnd_idx(Arc::clone(&spec_keys)).await?;
+    db.create_scnd_idx(Arc::clone(&spec_name)).await?;
+
+    put(db, "projection.2", 20, "bob").await?;
+
+    /* Get whole values. They are looked up in the primary keyspace. */
+
+    for spec in [&spec_keys, &spec_name] {
+        verify_get(
+            db,
+            spec,
+            None,
+            None,
+            Ok(vec![
+                gen_pkv("projection.2", 20, "bob"),
+                gen_pkv("projection.1", 30, "alice"),
+            ]),
+        )
+        .await?;
+    }
+
+    /* Get projected values. */
+
+    verify_get_projected(
+        db,
+        &spec_keys,
+        None,
+        None,
+        vec![
+            ("projection.2", Datum::Tuple(vec![])),
+            ("projection.1", Datum::Tuple(vec![])),
+        ],
+    )
+    .await?;
+
+    verify_get_projected(
+        db,
+        &spec_name,
+        Some(gen_sv(25)),
+        None,
+        vec![(
+            "projection.1",
+            Datum::Tuple(vec![Datum::Str(String::from("alice"))]),
+        )],
+    )
+    .await?;
+
+    /* Modify the non-indexed sub-value ; Get. */
+
+    put(db, "projection.1", 30, "carol").await?;
+
+    verify_get(
+        db,
+        &spec_keys,
+        Some(gen_sv(25)),
+        None,
+        Ok(vec![gen_pkv("projection.1", 30, "carol")]),
+    )
+    .await?;
+
+    verify_get_projected(
+        db,
+        &spec_name,
+        Some(gen_sv(25)),
+        None,
+        vec![(
+            "projection.1",
+            Datum::Tuple(vec![Datum::Str(String::from("carol"))]),
+        )],
+    )
+    .await?;
+
+    /* Delete ; Get. */
+
+    db.put(Arc::new(gen::gen_str_pk("projection.2")), None)
+        .await?;
+
+    verify_get(
+        db,
+        &spec_keys,
+        None,
+        None,
+        Ok(vec![gen_pkv("projection.1", 30, "carol")]),
+    )
+    .await?;
+
+    verify_get_projected(
+        db,
+        &spec_name,
+        None,
+        None,
+        vec![(
+            "projection.1",
+            Datum::Tuple(vec![Datum::Str(String::from("carol"))]),
+        )],
+    )
+    .await?;
+
+    Ok(())
+}
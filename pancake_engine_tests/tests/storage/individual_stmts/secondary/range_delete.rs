@@ -0,0 +1,70 @@
+use super::super::super::helpers::gen;
+use super::super::OneStmtDbAdaptor;
+use super::helper_verify::verify_get;
+use anyhow::Result;
+use pancake_types::serde::DatumType;
+use pancake_types::types::{ScndIdxSpec, SubValueSpec};
+use std::sync::Arc;
+
+async fn put(db: &mut impl OneStmtDbAdaptor, pk: &str, pv: &str) -> Result<()> {
+    let (pk, pv) = gen::gen_str_pkv(pk, pv);
+    db.put(Arc::new(pk), Some(Arc::new(pv))).await
+}
+
+async fn delete_range(db: &mut impl OneStmtDbAdaptor, pk_lo: &str, pk_hi: &str) -> Result<()> {
+    let pk_lo = gen::gen_str_pk(pk_lo);
+    let pk_hi = gen::gen_str_pk(pk_hi);
+    db.delete_range(Arc::new(pk_lo), Arc::new(pk_hi)).await
+}
+
+pub async fn delete_create_del_get(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
+    let spec = Arc::new(ScndIdxSpec::from(SubValueSpec::whole(DatumType::Str)));
+
+    /* Delete scnd idx ; Insert ; Create scnd idx. */
+
+    db.delete_scnd_idx(&spec).await?;
+
+    put(db, "rdel.1", "rdeltest-val-1").await?;
+    put(db, "rdel.2", "rdeltest-val-2").await?;
+    put(db, "rdel.3", "rdeltest-val-3").await?;
+
+    db.create_scnd_idx(Arc::clone(&spec)).await?;
+
+    put(db, "rdel.4", "rdeltest-val-4").await?;
+    put(db, "rdel.5", "rdeltest-val-5").await?;
+
+    /* Delete a range that spans entries inserted before and after the scnd idx creation. */
+
+    delete_range(db, "rdel.2", "rdel.4").await?;
+
+    verify_get(
+        db,
+        &spec,
+        Some(gen::gen_str_sv("rdeltest-val-0")),
+        Some(gen::gen_str_sv("rdeltest-val-9")),
+        Ok(vec![
+            gen::gen_str_pkv("rdel.1", "rdeltest-val-1"),
+            gen::gen_str_pkv("rdel.5", "rdeltest-val-5"),
+        ]),
+    )
+    .await?;
+
+    /* Re-insert within the deleted range. */
+
+    put(db, "rdel.3", "rdeltest-val-3").await?;
+
+    verify_get(
+        db,
+        &spec,
+        Some(gen::gen_str_sv("rdeltest-val-0")),
+        Some(gen::gen_str_sv("rdeltest-val-9")),
+        Ok(vec![
+            gen::gen_str_pkv("rdel.1", "rdeltest-val-1"),
+            gen::gen_str_pkv("rdel.3", "rdeltest-val-3"),
+            gen::gen_str_pkv("rdel.5", "rdeltest-val-5"),
+        ]),
+    )
+    .await?;
+
+    Ok(())
+}
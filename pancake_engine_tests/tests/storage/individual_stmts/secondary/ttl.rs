@@ -0,0 +1,80 @@
+use super::super::super::helpers::{etc::sleep_async, gen};
+use super::super::OneStmtDbAdaptor;
+use super::helper_verify::verify_get;
+use anyhow::Result;
+use pancake_types::serde::DatumType;
+use pancake_types::types::{ScndIdxSpec, SubValueSpec};
+use std::sync::Arc;
+use std::time::Duration;
+
+const LONG_TTL: Duration = Duration::from_secs(3600);
+const SHORT_TTL: Duration = Duration::from_millis(1);
+
+async fn put(db: &mut impl OneStmtDbAdaptor, pk: &str, pv: &str) -> Result<()> {
+    let (pk, pv) = gen::gen_str_pkv(pk, pv);
+    db.put(Arc::new(pk), Some(Arc::new(pv))).await
+}
+
+async fn put_with_ttl(
+    db: &mut impl OneStmtDbAdaptor,
+    pk: &str,
+    pv: &str,
+    ttl: Duration,
+) -> Result<()> {
+    let (pk, pv) = gen::gen_str_pkv(pk, pv);
+    db.put_with_ttl(Arc::new(pk), Arc::new(pv), ttl).await
+}
+
+async fn verify_get_pk(db: &mut impl OneStmtDbAdaptor, pk: &str, exp: Option<&str>) -> Result<()> {
+    let pk = gen::gen_str_pk(pk);
+    let act = db.get_pk_one(&pk).await?.map(|(_pk, pv)| pv);
+    let exp = exp.map(|pv| Arc::new(gen::gen_str_pv(pv)));
+    assert_eq!(exp, act, "{pk:?}");
+    Ok(())
+}
+
+pub async fn delete_put_create_get(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
+    let spec = Arc::new(ScndIdxSpec::from(SubValueSpec::whole(DatumType::Str)));
+
+    db.delete_scnd_idx(&spec).await?;
+
+    /* Put expiring entries, both before and after the index is created. */
+
+    put_with_ttl(db, "ttl.1", "ttltest-val-1", LONG_TTL).await?;
+    put_with_ttl(db, "ttl.2", "ttltest-val-2", SHORT_TTL).await?;
+
+    db.create_scnd_idx(Arc::clone(&spec)).await?;
+
+    put_with_ttl(db, "ttl.3", "ttltest-val-3", LONG_TTL).await?;
+    put_with_ttl(db, "ttl.4", "ttltest-val-4", SHORT_TTL).await?;
+
+    /* Re-put unchanged values. The index entries follow the new expiry. */
+
+    put_with_ttl(db, "ttl.1", "ttltest-val-1", SHORT_TTL).await?;
+    put_with_ttl(db, "ttl.5", "ttltest-val-5", SHORT_TTL).await?;
+    put(db, "ttl.5", "ttltest-val-5").await?;
+
+    sleep_async(10).await;
+
+    /* Expired entries are hidden, both in the primary keyspace and in the index. */
+
+    verify_get_pk(db, "ttl.1", None).await?;
+    verify_get_pk(db, "ttl.2", None).await?;
+    verify_get_pk(db, "ttl.3", Some("ttltest-val-3")).await?;
+    verify_get_pk(db, "ttl.4", None).await?;
+    verify_get_pk(db, "ttl.5", Some("ttltest-val-5")).await?;
+
+    verify_get(
+        db,
+        &spec,
+        Some(gen::gen_str_sv("ttltest-val-0")),
+        Some(gen::gen_str_sv("ttltest-val-9")),
+        Ok(vec![
+            gen::gen_str_pkv("ttl.3", "ttltest-val-3"),
+            gen::gen_str_pkv("ttl.5", "ttltest-val-5"),
+        ]),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use super::super::super::helpers::gen;
+use super::super::OneStmtDbAdaptor;
+use super::helper_verify::verify_get;
+use anyhow::Result;
+use pancake_types::serde::{Datum, DatumType};
+use pancake_types::types::{
+    MemberKey, PrimaryKey, ScndIdxProjection, ScndIdxSpec, SubValueSpec, UniquenessViolation, Value,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// A unique SVSpec that extracts `PV.email: str`.
+fn spec_unique_email() -> ScndIdxSpec {
+    ScndIdxSpec {
+        columns: vec![SubValueSpec {
+            member_path: vec![MemberKey::from("email")],
+            datum_type: DatumType::Str,
+        }],
+        projection: ScndIdxProjection::Whole,
+        filter: None,
+        is_unique: true,
+    }
+}
+
+/// A PV that is typed `{email: str, name: str}`.
+fn gen_pv(email: &str, name: &str) -> Value {
+    let members = BTreeMap::from([
+        (String::from("email"), Datum::Str(String::from(email))),
+        (String::from("name"), Datum::Str(String::from(name))),
+    ]);
+    Value(Datum::Map(members))
+}
+
+fn gen_pkv(pk: &str, email: &str, name: &str) -> (PrimaryKey, Value) {
+    (gen::gen_str_pk(pk), gen_pv(email, name))
+}
+
+async fn put(db: &mut impl OneStmtDbAdaptor, pk: &str, email: &str, name: &str) -> Result<()> {
+    let (pk, pv) = gen_pkv(pk, email, name);
+    db.put(Arc::new(pk), Some(Arc::new(pv))).await
+}
+
+fn assert_violation(res: Result<()>, pks: [&str; 2]) {
+    let e = res.unwrap_err();
+    let violation = e.downcast_ref::<UniquenessViolation>().unwrap();
+    let mut act_pks = [violation.pk.as_ref(), violation.other_pk.as_ref()];
+    act_pks.sort();
+    let exp_pks = pks.map(gen::gen_str_pk);
+    assert_eq!(act_pks, [&exp_pks[0], &exp_pks[1]]);
+}
+
+pub async fn delete_create_put(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
+    let spec = Arc::new(spec_unique_email());
+
+    /* Delete scnd idx. */
+
+    db.delete_scnd_idx(&spec).await?;
+
+    /* Insert duplicates ; Fail to create scnd idx. */
+
+    put(db, "unique.1", "a@x", "alice").await?;
+    put(db, "unique.2", "a@x", "bob").await?;
+
+    let res = db.create_scnd_idx(Arc::clone(&spec)).await;
+    assert_violation(res, ["unique.1", "unique.2"]);
+
+    verify_get(db, &spec, None, None, Err(())).await?;
+
+    /* Fix the duplicates ; Create scnd idx. */
+
+    put(db, "unique.2", "b@x", "bob").await?;
+
+    db.create_scnd_idx(Arc::clone(&spec)).await?;
+
+    /* Put a duplicate ; Fail. */
+
+    let res = put(db, "unique.3", "a@x", "carol").await;
+    assert_violation(res, ["unique.1", "unique.3"]);
+
+    /* Re-put the same sub-value under the same primary key. */
+
+    put(db, "unique.1", "a@x", "alice2").await?;
+
+    /* Move a sub-value from one primary key to another. */
+
+    put(db, "unique.1", "c@x", "alice2").await?;
+    put(db, "unique.3", "a@x", "carol").await?;
+
+    verify_get(
+        db,
+        &spec,
+        None,
+        None,
+        Ok(vec![
+            gen_pkv("unique.3", "a@x", "carol"),
+            gen_pkv("unique.2", "b@x", "bob"),
+            gen_pkv("unique.1", "c@x", "alice2"),
+        ]),
+    )
+    .await?;
+
+    /* A non-unique index is distinct, and permits duplicates. */
+
+    let spec_nonunique = Arc::new(ScndIdxSpec {
+        is_unique: false,
+        ..spec_unique_email()
+    });
+    db.delete_scnd_idx(&spec_nonunique).await?;
+    db.create_scnd_idx(Arc::clone(&spec_nonunique)).await?;
+
+    let res = put(db, "unique.4", "a@x", "dave").await;
+    assert_violation(res, ["unique.3", "unique.4"]);
+
+    db.delete_scnd_idx(&spec).await?;
+
+    put(db, "unique.4", "a@x", "dave").await?;
+
+    Ok(())
+}
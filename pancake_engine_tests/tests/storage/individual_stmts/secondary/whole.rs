@@ -3,7 +3,7 @@ use super::super::OneStmtDbAdaptor;
 use super::helper_verify::verify_get;
 use anyhow::Result;
 use pancake_types::serde::DatumType;
-use pancake_types::types::SubValueSpec;
+use pancake_types::types::{ScndIdxSpec, SubValueSpec};
 use std::sync::Arc;
 
 async fn put(db: &mut impl OneStmtDbAdaptor, pk: &str, pv: &str) -> Result<()> {
@@ -17,7 +17,7 @@ async fn del(db: &mut impl OneStmtDbAdaptor, pk: &str) -> Result<()> {
 }
 
 pub async fn delete_create_get(db: &mut impl OneStmtDbAdaptor) -> Result<()> {
-    let spec = Arc::new(SubValueSpec::whole(DatumType::Str));
+    let spec = Arc::new(ScndIdxSpec::from(SubValueSpec::whole(DatumType::Str)));
 
     /* Delete scnd idx. */
 
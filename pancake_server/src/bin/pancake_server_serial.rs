@@ -22,7 +22,10 @@ async fn main() -> Result<()> {
         PathBuf::from,
     );
 
-    let db = DB::load_or_new(root_dir)?;
+    let engine_opts = server::load_engine_options()?;
+
+    let (db, fc_worker) = DB::load_or_new(root_dir, engine_opts)?;
+    tokio::task::spawn_blocking(move || fc_worker.run());
     let db = Arc::new(RwLock::new(db));
 
     let wasm_engine = WasmEngine::new(Arc::clone(&db))?;
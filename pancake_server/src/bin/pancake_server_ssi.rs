@@ -5,6 +5,7 @@ use pancake_server::{
     common::server,
     engine_ssi::{
         route_handlers::{self, AppState},
+        txn_sessions::TxnSessions,
         wasm::WasmEngine,
     },
 };
@@ -27,19 +28,26 @@ async fn main() -> Result<()> {
             PathBuf::from,
         );
 
+        let engine_opts = server::load_engine_options()?;
+
         let fc_worker;
-        (db, fc_worker) = DB::load_or_new(root_dir)?;
+        (db, fc_worker) = DB::load_or_new(root_dir, engine_opts)?;
 
         fc_fut = fc_worker.run();
     }
 
     let wasm_engine = WasmEngine::new(Arc::clone(&db))?;
 
+    let txn_sessions = Arc::new(TxnSessions::new(
+        Arc::clone(&db),
+        server::load_txn_idle_timeout()?,
+    ));
+
     let (frontend_terminate_tx, frontend_terminate_rx) = oneshot::channel::<()>();
 
     let frontend_fut;
     {
-        let state = AppState::new(Arc::clone(&db), wasm_engine);
+        let state = AppState::new(Arc::clone(&db), wasm_engine, Arc::clone(&txn_sessions));
         let state = Arc::new(state);
 
         let router = route_handlers::create_router(state);
@@ -60,18 +68,27 @@ async fn main() -> Result<()> {
 
     let fc_task = tokio::spawn(fc_fut);
     let frontend_task = tokio::spawn(frontend_fut);
+    let reaper_task = {
+        let txn_sessions = Arc::clone(&txn_sessions);
+        tokio::spawn(async move { txn_sessions.run_idle_reaper().await })
+    };
     println!("Launched all tasks.");
 
     signal.recv().await;
     println!("Received process signal.");
 
     frontend_terminate_tx.send(()).ok();
+    // Txns hold the DB state, which the DB termination awaits.
+    let txn_sessions_res = txn_sessions.terminate().await;
     db.terminate().await;
     println!("Notified termination to each task.");
 
     let frontend_join_res = frontend_task.await;
+    let reaper_join_res = reaper_task.await;
     let fc_join_res = fc_task.await;
     fc_join_res??;
+    reaper_join_res??;
+    txn_sessions_res?;
     frontend_join_res??;
 
     Ok(())
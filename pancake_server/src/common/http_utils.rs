@@ -1,11 +1,18 @@
 use anyhow;
 use axum::{
-    http::{Request, StatusCode},
+    http::{header, HeaderMap, Request, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use derive_more::From;
 use pancake_engine_common::Entry;
+use pancake_types::{
+    json,
+    serde::Datum,
+    types::{ConstraintViolation, UniquenessViolation},
+};
+use std::borrow::Borrow;
+use std::collections::HashMap;
 use std::fmt::Debug;
 
 pub async fn logger(req: Request<axum::body::Body>, next: Next) -> impl IntoResponse {
@@ -17,7 +24,26 @@ pub async fn logger(req: Request<axum::body::Body>, next: Next) -> impl IntoResp
 pub struct AppError(pub anyhow::Error);
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (StatusCode::INTERNAL_SERVER_ERROR, self.0.to_string()).into_response()
+        /* A violation is the client's fault, not the server's. */
+        let status = if self.0.downcast_ref::<ConstraintViolation>().is_some()
+            || self.0.downcast_ref::<UniquenessViolation>().is_some()
+        {
+            StatusCode::BAD_REQUEST
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        (status, self.0.to_string()).into_response()
+    }
+}
+
+/// Parses an optional boolean query param, e.g. `?read_only=true`. An absent param is false.
+pub fn bool_param(params: &HashMap<String, String>, name: &str) -> Result<bool, anyhow::Error> {
+    match params.get(name).map(String::as_str) {
+        None | Some("false") => Ok(false),
+        Some("true") => Ok(true),
+        Some(val) => Err(anyhow::anyhow!(
+            "Expected query param {name} to be true or false but found {val}"
+        )),
     }
 }
 
@@ -30,22 +56,106 @@ pub fn ok<S: Into<String>>(body: S) -> Result<(StatusCode, String), AppError> {
     }
 }
 
-pub fn entries_to_string<'a, K, V>(
+const APPLICATION_JSON: &str = "application/json";
+
+/// The format of a request body or a response body.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BodyFormat {
+    /// Request bodies are [`query_basic`](crate::oper::query_basic) queries.
+    /// Response bodies are debug-printed entries.
+    Text,
+    /// Request bodies are [`query_json`](crate::oper::query_json) queries.
+    /// Response bodies are entries encoded by [`pancake_types::json`].
+    Json,
+}
+
+impl BodyFormat {
+    /// The request body is JSON iff the `Content-Type` is `application/json`.
+    pub fn of_request(headers: &HeaderMap) -> Self {
+        if Self::header_has_json(headers, header::CONTENT_TYPE) {
+            Self::Json
+        } else {
+            Self::Text
+        }
+    }
+
+    /// The response body is JSON iff the `Accept` header lists `application/json`,
+    /// or the request body is JSON.
+    pub fn of_response(headers: &HeaderMap) -> Self {
+        if Self::header_has_json(headers, header::ACCEPT) {
+            Self::Json
+        } else {
+            Self::of_request(headers)
+        }
+    }
+
+    fn header_has_json(headers: &HeaderMap, name: header::HeaderName) -> bool {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|hval| hval.to_str().ok())
+            .flat_map(|hval| hval.split(','))
+            .any(|media_type| {
+                let essence = media_type.split(';').next().unwrap_or("");
+                essence.trim().eq_ignore_ascii_case(APPLICATION_JSON)
+            })
+    }
+}
+
+/// Labels a successful JSON body with its `Content-Type`.
+/// Other bodies, such as error messages, remain plain text.
+pub fn respond(fmt: BodyFormat, (status, body): (StatusCode, String)) -> Response {
+    if fmt == BodyFormat::Json && status == StatusCode::OK {
+        (status, [(header::CONTENT_TYPE, APPLICATION_JSON)], body).into_response()
+    } else {
+        (status, body).into_response()
+    }
+}
+
+pub fn entries_to_body<'a, K, V>(
+    fmt: BodyFormat,
     entries: impl Iterator<Item = Entry<'a, K, V>>,
 ) -> Result<String, anyhow::Error>
 where
-    K: 'a + Debug,
-    V: 'a + Debug,
+    K: 'a + Debug + Borrow<Datum>,
+    V: 'a + Debug + Borrow<Datum>,
 {
-    let mut body = String::new();
-    for entry in entries {
-        let (pk, pv) = entry.try_borrow()?;
-        kv_to_string(&mut body, pk, pv);
+    match fmt {
+        BodyFormat::Text => {
+            let mut body = String::new();
+            for entry in entries {
+                let (pk, pv) = entry.try_borrow()?;
+                kv_to_string(&mut body, pk, pv);
+            }
+            Ok(body)
+        }
+        BodyFormat::Json => {
+            let mut kvs = vec![];
+            for entry in entries {
+                let (pk, pv) = entry.try_borrow()?;
+                kvs.push(kv_to_json(pk, pv));
+            }
+            Ok(serde_json::Value::Array(kvs).to_string())
+        }
     }
-    Ok(body)
 }
 
-pub fn kv_to_string<K, V>(body: &mut String, k: &K, v: &V)
+pub fn kv_to_body<K, V>(fmt: BodyFormat, k: &K, v: &V) -> String
+where
+    K: Debug + Borrow<Datum>,
+    V: Debug + Borrow<Datum>,
+{
+    match fmt {
+        BodyFormat::Text => {
+            let mut body = String::new();
+            kv_to_string(&mut body, k, v);
+            body
+        }
+        BodyFormat::Json => kv_to_json(k, v).to_string(),
+    }
+}
+
+fn kv_to_string<K, V>(body: &mut String, k: &K, v: &V)
 where
     K: Debug,
     V: Debug,
@@ -53,3 +163,14 @@ where
     let s = format!("Key:\r\n{k:?}\r\nValue:\r\n{v:?}\r\n");
     body.push_str(&s);
 }
+
+fn kv_to_json<K, V>(k: &K, v: &V) -> serde_json::Value
+where
+    K: Borrow<Datum>,
+    V: Borrow<Datum>,
+{
+    let mut obj = serde_json::Map::new();
+    obj.insert(String::from("key"), json::to_json(k.borrow()));
+    obj.insert(String::from("value"), json::to_json(v.borrow()));
+    serde_json::Value::Object(obj)
+}
@@ -1,9 +1,75 @@
-use pancake_engine_common::fs_utils::EngineType;
+use anyhow::{anyhow, Context, Result};
+use pancake_engine_common::{fs_utils::EngineType, EngineOptions};
+use std::env;
+use std::fs;
+use std::time::Duration;
 
 pub const ENV_VAR_ROOT_DIR: &str = "PANCAKE_ROOT_DIR";
 
 pub const ENV_VAR_BIND_ADDR: &str = "PANCAKE_BIND_ADDR";
 
+pub const ENV_VAR_ENGINE_OPTIONS_FILE: &str = "PANCAKE_ENGINE_OPTIONS_FILE";
+
+/// The number of seconds after which an idle txn session is aborted.
+pub const ENV_VAR_TXN_IDLE_TIMEOUT_SECS: &str = "PANCAKE_TXN_IDLE_TIMEOUT_SECS";
+
+const DEFAULT_TXN_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Each [`EngineOptions`] field is read from the first of these that is present:
+/// 1. The env var named `PANCAKE_` followed by the upper-cased field name, e.g. `PANCAKE_MEMTABLE_FLUSH_BYTE_SIZE`.
+/// 1. A top-level integer or string in the TOML file located by [`ENV_VAR_ENGINE_OPTIONS_FILE`], e.g. `memtable_flush_byte_size = 4194304`.
+/// 1. The default.
+pub fn load_engine_options() -> Result<EngineOptions> {
+    let mut opts = EngineOptions::default();
+
+    if let Ok(file_path) = env::var(ENV_VAR_ENGINE_OPTIONS_FILE) {
+        let content = fs::read_to_string(&file_path)
+            .with_context(|| format!("Failed to read engine options file {file_path:?}"))?;
+        let table = content
+            .parse::<toml::Table>()
+            .with_context(|| format!("Invalid engine options file {file_path:?}"))?;
+        for (name, val) in table.iter() {
+            let val = match val {
+                toml::Value::Integer(int) => int.to_string(),
+                toml::Value::String(s) => s.clone(),
+                _ => {
+                    return Err(anyhow!(
+                        "Engine option {name:?} in {file_path:?} must be an integer or a string."
+                    ))
+                }
+            };
+            opts.set_by_name(name, &val)?;
+        }
+    }
+
+    for name in EngineOptions::NAMES {
+        let env_var = format!("PANCAKE_{}", name.to_uppercase());
+        if let Ok(val) = env::var(&env_var) {
+            opts.set_by_name(name, &val)
+                .with_context(|| format!("Invalid env var {env_var}"))?;
+        }
+    }
+
+    opts.validate()?;
+
+    Ok(opts)
+}
+
+pub fn load_txn_idle_timeout() -> Result<Duration> {
+    match env::var(ENV_VAR_TXN_IDLE_TIMEOUT_SECS) {
+        Err(_) => Ok(DEFAULT_TXN_IDLE_TIMEOUT),
+        Ok(secs) => {
+            let secs = secs
+                .parse::<u64>()
+                .with_context(|| format!("Invalid env var {ENV_VAR_TXN_IDLE_TIMEOUT_SECS}"))?;
+            if secs == 0 {
+                return Err(anyhow!("{ENV_VAR_TXN_IDLE_TIMEOUT_SECS} must be positive."));
+            }
+            Ok(Duration::from_secs(secs))
+        }
+    }
+}
+
 pub fn default_bind_addr(typ: EngineType) -> &'static str {
     match typ {
         EngineType::SERIAL => "127.0.0.1:3000",
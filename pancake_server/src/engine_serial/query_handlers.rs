@@ -1,19 +1,27 @@
 use crate::{
-    common::http_utils::{self, entries_to_string, kv_to_string, AppError},
+    common::http_utils::{self, entries_to_body, kv_to_body, AppError, BodyFormat},
     oper::api::{Operation, SearchRange, Statement},
 };
 use axum::http::StatusCode;
 use pancake_engine_serial::DB;
+use pancake_types::types::{ScndIdxSpec, SubValue};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 pub async fn handle_oper(
     db: &RwLock<DB>,
     oper: Operation,
+    resp_fmt: BodyFormat,
 ) -> Result<(StatusCode, String), AppError> {
     match oper {
         Operation::Query(stmt) => {
-            return handle_stmt(db, stmt).await;
+            return handle_stmt(db, stmt, resp_fmt).await;
+        }
+        Operation::QueryAsOf(_stmt, _commit_ver) => {
+            return Ok((
+                StatusCode::BAD_REQUEST,
+                "Reading as of a commit version is not supported by the serial engine.".to_string(),
+            ));
         }
         Operation::CreateScndIdx(sv_spec) => {
             let mut db = db.write().await;
@@ -25,12 +33,23 @@ pub async fn handle_oper(
             db.delete_scnd_idx(&sv_spec)?;
             return http_utils::ok("");
         }
+        Operation::CreateConstraint(constraint) => {
+            let mut db = db.write().await;
+            db.create_constraint(Arc::new(constraint))?;
+            return http_utils::ok("");
+        }
+        Operation::DelConstraint(constraint) => {
+            let mut db = db.write().await;
+            db.delete_constraint(&constraint)?;
+            return http_utils::ok("");
+        }
     }
 }
 
 pub async fn handle_stmt(
     db: &RwLock<DB>,
     stmt: Statement,
+    resp_fmt: BodyFormat,
 ) -> Result<(StatusCode, String), AppError> {
     match stmt {
         Statement::GetPK(SearchRange::One(pk)) => {
@@ -39,8 +58,7 @@ pub async fn handle_stmt(
                 None => return Ok((StatusCode::NOT_FOUND, "".to_string())),
                 Some(entry) => {
                     let (pk, pv) = entry.try_borrow()?;
-                    let mut body = String::new();
-                    kv_to_string(&mut body, pk, pv);
+                    let body = kv_to_body(resp_fmt, pk, pv);
                     return http_utils::ok(body);
                 }
             }
@@ -48,15 +66,14 @@ pub async fn handle_stmt(
         Statement::GetPK(SearchRange::Range { lo, hi }) => {
             let db = db.read().await;
             let entries = db.get_pk_range(lo.as_ref(), hi.as_ref());
-            let body = entries_to_string(entries)?;
+            let body = entries_to_body(resp_fmt, entries)?;
             return http_utils::ok(body);
         }
         Statement::GetSV(sv_spec, sv_range) => {
-            let db = db.read().await;
-            let (sv_lo, sv_hi) = sv_range.as_ref();
-            let entries = db.get_sv_range(&sv_spec, sv_lo, sv_hi)?;
-            let body = entries_to_string(entries)?;
-            return http_utils::ok(body);
+            return handle_get_sv(db, &sv_spec, &sv_range, false, resp_fmt).await;
+        }
+        Statement::GetSVProjected(sv_spec, sv_range) => {
+            return handle_get_sv(db, &sv_spec, &sv_range, true, resp_fmt).await;
         }
         Statement::Put(pk, opt_pv) => {
             let mut db = db.write().await;
@@ -65,5 +82,40 @@ pub async fn handle_stmt(
             db.put(pk, opt_pv)?;
             return http_utils::ok("");
         }
+        Statement::PutWithTTL(pk, pv, ttl) => {
+            let mut db = db.write().await;
+            db.put_with_ttl(Arc::new(pk), Arc::new(pv), ttl)?;
+            return http_utils::ok("");
+        }
+        Statement::DeleteRange(pk_lo, pk_hi) => {
+            let mut db = db.write().await;
+            db.delete_range(Arc::new(pk_lo), Arc::new(pk_hi))?;
+            return http_utils::ok("");
+        }
     }
 }
+
+async fn handle_get_sv(
+    db: &RwLock<DB>,
+    sv_spec: &ScndIdxSpec,
+    sv_range: &SearchRange<SubValue>,
+    is_projected: bool,
+    resp_fmt: BodyFormat,
+) -> Result<(StatusCode, String), AppError> {
+    let db = db.read().await;
+    let (sv_lo, sv_hi) = sv_range.as_ref();
+    // A composite index may be ranged over a prefix of its columns.
+    let sv_hi_prefix = sv_hi
+        .map(|sv_hi| sv_spec.prefix_hi(sv_hi))
+        .transpose()?
+        .flatten();
+    let sv_hi = sv_hi_prefix.as_ref().or(sv_hi);
+    let body = if is_projected {
+        let entries = db.get_sv_range_projected(sv_spec, sv_lo, sv_hi)?;
+        entries_to_body(resp_fmt, entries)?
+    } else {
+        let entries = db.get_sv_range(sv_spec, sv_lo, sv_hi)?;
+        entries_to_body(resp_fmt, entries)?
+    };
+    return http_utils::ok(body);
+}
@@ -1,23 +1,26 @@
 use crate::{
-    common::http_utils::{self, logger, AppError},
+    common::http_utils::{self, logger, AppError, BodyFormat},
     engine_serial::{query_handlers, wasm::WasmEngine},
     oper::{
         api::{SearchRange, Statement},
         query_basic::parse as parse_query,
+        query_json::parse as parse_query_json,
     },
 };
 use anyhow::{anyhow, Result};
 use axum::{
     body::{to_bytes, Body},
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     middleware,
+    response::Response,
     routing::{delete, get, post, put},
     Router,
 };
 use derive_more::Constructor;
 use pancake_engine_serial::DB;
 use pancake_types::{
+    json,
     serde::Datum,
     types::{PrimaryKey, Value},
 };
@@ -45,44 +48,65 @@ pub fn create_router(state: Arc<AppState>) -> Router {
 async fn get_one(
     Path(key): Path<String>,
     State(state): State<Arc<AppState>>,
-) -> Result<(StatusCode, String), AppError> {
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
     let pk = PrimaryKey(Datum::Str(key));
     let stmt = Statement::GetPK(SearchRange::One(pk));
 
-    query_handlers::handle_stmt(state.db(), stmt).await
+    let resp_fmt = BodyFormat::of_response(&headers);
+    let resp = query_handlers::handle_stmt(state.db(), stmt, resp_fmt).await?;
+    Ok(http_utils::respond(resp_fmt, resp))
 }
 
 async fn put_one(
     Path(key): Path<String>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     body: String,
-) -> Result<(StatusCode, String), AppError> {
+) -> Result<Response, AppError> {
     let pk = PrimaryKey(Datum::Str(key));
-    let pv = Value(Datum::Str(body));
+    let pv = match BodyFormat::of_request(&headers) {
+        BodyFormat::Text => Value(Datum::Str(body)),
+        BodyFormat::Json => {
+            let json = serde_json::from_str(&body).map_err(|e| anyhow!(e))?;
+            Value(json::from_json(&json)?)
+        }
+    };
     let stmt = Statement::Put(pk, Some(pv));
 
-    query_handlers::handle_stmt(state.db(), stmt).await
+    let resp_fmt = BodyFormat::of_response(&headers);
+    let resp = query_handlers::handle_stmt(state.db(), stmt, resp_fmt).await?;
+    Ok(http_utils::respond(resp_fmt, resp))
 }
 
 async fn delete_one(
     Path(key): Path<String>,
     State(state): State<Arc<AppState>>,
-) -> Result<(StatusCode, String), AppError> {
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
     let pk = PrimaryKey(Datum::Str(key));
     let stmt = Statement::Put(pk, None);
 
-    query_handlers::handle_stmt(state.db(), stmt).await
+    let resp_fmt = BodyFormat::of_response(&headers);
+    let resp = query_handlers::handle_stmt(state.db(), stmt, resp_fmt).await?;
+    Ok(http_utils::respond(resp_fmt, resp))
 }
 
 async fn query(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     body: String,
-) -> Result<(StatusCode, String), AppError> {
+) -> Result<Response, AppError> {
     let db = state.db();
 
-    let oper = parse_query(&body)?;
+    let oper = match BodyFormat::of_request(&headers) {
+        BodyFormat::Text => parse_query(&body)?,
+        BodyFormat::Json => parse_query_json(&body)?,
+    };
 
-    query_handlers::handle_oper(db, oper).await
+    let resp_fmt = BodyFormat::of_response(&headers);
+    let resp = query_handlers::handle_oper(db, oper, resp_fmt).await?;
+    Ok(http_utils::respond(resp_fmt, resp))
 }
 
 async fn wasm(
@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Result};
 use pancake_engine_serial::DB;
-use pancake_types::types::{Deser, PrimaryKey, Ser, SubValue, SubValueSpec, Value};
+use pancake_types::types::{Deser, PrimaryKey, ScndIdxSpec, Ser, SubValue, Value};
 use std::borrow::BorrowMut;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -117,7 +117,7 @@ impl db::Db for DbProvider {
         sv_lo: Option<Sv>,
         sv_hi: Option<Sv>,
     ) -> anyhow::Result<Result<Vec<Pkpv>, String>> {
-        let sv_spec = SubValueSpec::deser_solo(&sv_spec.bytes)?;
+        let sv_spec = ScndIdxSpec::deser_solo(&sv_spec.bytes)?;
         let sv_lo = sv_lo
             .map(|sv| SubValue::deser_solo(&sv.bytes))
             .transpose()?;
@@ -140,6 +140,35 @@ impl db::Db for DbProvider {
         Ok(Ok(ret))
     }
 
+    fn get_sv_range_projected(
+        &mut self,
+        sv_spec: SvSpec,
+        sv_lo: Option<Sv>,
+        sv_hi: Option<Sv>,
+    ) -> anyhow::Result<Result<Vec<Pkpv>, String>> {
+        let sv_spec = ScndIdxSpec::deser_solo(&sv_spec.bytes)?;
+        let sv_lo = sv_lo
+            .map(|sv| SubValue::deser_solo(&sv.bytes))
+            .transpose()?;
+        let sv_hi = sv_hi
+            .map(|sv| SubValue::deser_solo(&sv.bytes))
+            .transpose()?;
+
+        let mut ret = vec![];
+        for entry in self
+            .db
+            .get_sv_range_projected(&sv_spec, sv_lo.as_ref(), sv_hi.as_ref())?
+        {
+            let (pk, pv) = entry.try_borrow()?;
+            let pk = pk.ser_solo()?;
+            let pv = pv.ser_solo()?;
+            let pk = Pk { bytes: pk };
+            let pv = Pv { bytes: pv };
+            ret.push(Pkpv { pk, pv });
+        }
+        Ok(Ok(ret))
+    }
+
     fn put(&mut self, pk: Pk, opt_pv: Option<Pv>) -> anyhow::Result<Result<(), String>> {
         let pk = PrimaryKey::deser_solo(&pk.bytes)?;
         let opt_pv = opt_pv.map(|pv| Value::deser_solo(&pv.bytes)).transpose()?;
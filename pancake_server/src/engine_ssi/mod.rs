@@ -1,3 +1,4 @@
 mod query_handlers;
 pub mod route_handlers;
+pub mod txn_sessions;
 pub mod wasm;
@@ -1,19 +1,37 @@
 use crate::{
-    common::http_utils::{self, entries_to_string, kv_to_string, AppError},
+    common::http_utils::{self, entries_to_body, kv_to_body, AppError, BodyFormat},
     oper::api::{Operation, SearchRange, Statement},
 };
 use anyhow::Result;
 use axum::http::StatusCode;
 use pancake_engine_ssi::{
-    ClientCommitDecision, ScndIdxCreationJobErr, ScndIdxDeletionJobErr, Txn, DB,
+    ClientCommitDecision, CommitVer, ConstraintCreationJobErr, ConstraintDeletionJobErr,
+    ScndIdxCreationJobErr, ScndIdxDeletionJobErr, Txn, DB,
 };
-use pancake_types::types::{PKShared, PVShared};
+use pancake_types::types::{PKShared, PVShared, ScndIdxSpec, SubValue};
 use std::sync::Arc;
 
-pub async fn handle_oper(db: &DB, oper: Operation) -> Result<(StatusCode, String), AppError> {
+const PUT_RETRY_LIMIT: usize = 5;
+const GET_RETRY_LIMIT: usize = 5;
+
+pub async fn handle_oper(
+    db: &DB,
+    oper: Operation,
+    resp_fmt: BodyFormat,
+) -> Result<(StatusCode, String), AppError> {
     match oper {
         Operation::Query(stmt) => {
-            return handle_stmt(db, stmt).await;
+            return handle_stmt(db, stmt, resp_fmt).await;
+        }
+        Operation::QueryAsOf(stmt, commit_ver) => {
+            let commit_ver = CommitVer::from(commit_ver);
+            if db.retained_commit_vers().await.contains(&commit_ver) == false {
+                return Ok((
+                    StatusCode::BAD_REQUEST,
+                    format!("Commit version {commit_ver} is not retained."),
+                ));
+            }
+            return handle_stmt_as_of(db, stmt, Some(commit_ver), resp_fmt).await;
         }
         Operation::CreateScndIdx(sv_spec) => {
             let sv_spec = Arc::new(sv_spec);
@@ -36,6 +54,9 @@ pub async fn handle_oper(db: &DB, oper: Operation) -> Result<(StatusCode, String
                             .to_string(),
                     ));
                 }
+                Err(ScndIdxCreationJobErr::UniquenessViolated(violation)) => {
+                    return Ok((StatusCode::BAD_REQUEST, violation.to_string()));
+                }
                 Err(ScndIdxCreationJobErr::InternalError(e)) => return Err(AppError(e)),
             }
         }
@@ -46,53 +67,99 @@ pub async fn handle_oper(db: &DB, oper: Operation) -> Result<(StatusCode, String
             }
             Err(ScndIdxDeletionJobErr::InternalError(e)) => return Err(AppError(e)),
         },
+        Operation::CreateConstraint(constraint) => {
+            let constraint = Arc::new(constraint);
+            match db.create_constraint(&constraint).await {
+                Ok(()) => return http_utils::ok(""),
+                Err(ConstraintCreationJobErr::Existent { is_validated }) => {
+                    if is_validated {
+                        return Ok((StatusCode::NOT_MODIFIED, "".to_string()));
+                    } else {
+                        return Ok((
+                            StatusCode::PROCESSING,
+                            "The constraint is being validated.".to_string(),
+                        ));
+                    }
+                }
+                Err(ConstraintCreationJobErr::Busy) => {
+                    return Ok((
+                        StatusCode::TOO_MANY_REQUESTS,
+                        "Another secondary index creation job or constraint creation job is in progress."
+                            .to_string(),
+                    ));
+                }
+                Err(ConstraintCreationJobErr::Violated(violation)) => {
+                    return Ok((StatusCode::BAD_REQUEST, violation.to_string()));
+                }
+                Err(ConstraintCreationJobErr::InternalError(e)) => return Err(AppError(e)),
+            }
+        }
+        Operation::DelConstraint(constraint) => match db.delete_constraint(&constraint).await {
+            Ok(()) => return http_utils::ok(""),
+            Err(ConstraintDeletionJobErr::CreationInProgress) => {
+                return Ok((StatusCode::BAD_REQUEST, "The constraint is being validated right now, and cannot be deleted until the validation is done.".to_string() ));
+            }
+            Err(ConstraintDeletionJobErr::InternalError(e)) => return Err(AppError(e)),
+        },
     }
 }
 
-pub async fn handle_stmt(db: &DB, stmt: Statement) -> Result<(StatusCode, String), AppError> {
+pub async fn handle_stmt(
+    db: &DB,
+    stmt: Statement,
+    resp_fmt: BodyFormat,
+) -> Result<(StatusCode, String), AppError> {
+    handle_stmt_as_of(db, stmt, None, resp_fmt).await
+}
+
+/// @arg as_of: If `Some`, only gets are supported, and they read the snapshot as of this commit version.
+async fn handle_stmt_as_of(
+    db: &DB,
+    stmt: Statement,
+    as_of: Option<CommitVer>,
+    resp_fmt: BodyFormat,
+) -> Result<(StatusCode, String), AppError> {
+    let is_get = matches!(
+        stmt,
+        Statement::GetPK(_) | Statement::GetSV(..) | Statement::GetSVProjected(..)
+    );
+    if as_of.is_some() && is_get == false {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            "Only gets can be run as of a commit version.".to_string(),
+        ));
+    }
+
     match stmt {
         Statement::GetPK(SearchRange::One(pk)) => {
-            let opt_pkpv = Txn::run(db, 0, |txn| {
-                let opt_pkpv = txn.get_pk_one(&pk)?;
-                Ok(ClientCommitDecision::Commit(opt_pkpv))
-            })
-            .await?;
+            let opt_pkpv = run_read_only(db, as_of, |txn| txn.get_pk_one(&pk)).await?;
             match opt_pkpv {
                 None => Ok((StatusCode::NOT_FOUND, "".to_string())),
                 Some((pk, pv)) => {
-                    let mut body = String::new();
-                    kv_to_string(&mut body, &pk, &pv);
+                    let body = kv_to_body(resp_fmt, &pk, &pv);
                     return http_utils::ok(body);
                 }
             }
         }
         Statement::GetPK(SearchRange::Range { lo, hi }) => {
-            let body = Txn::run(db, 0, |txn| {
+            let body = run_read_only(db, as_of, |txn| {
                 let entries = txn.get_pk_range(lo.as_ref(), hi.as_ref());
-                let body = entries_to_string(entries)?;
-                Ok(ClientCommitDecision::Commit(body))
+                entries_to_body(resp_fmt, entries)
             })
             .await?;
             return http_utils::ok(body);
         }
         Statement::GetSV(sv_spec, sv_range) => {
-            let (sv_lo, sv_hi) = sv_range.as_ref();
-            let body = Txn::run(db, 0, |txn| {
-                let scnd_entries = txn.get_sv_range(&sv_spec, sv_lo, sv_hi)?;
-                let pkpv_entries = scnd_entries.map(|entry| entry.convert::<PKShared, PVShared>());
-                let body = entries_to_string(pkpv_entries)?;
-                Ok(ClientCommitDecision::Commit(body))
-            })
-            .await?;
-            return http_utils::ok(body);
+            return handle_get_sv(db, as_of, &sv_spec, &sv_range, false, resp_fmt).await;
+        }
+        Statement::GetSVProjected(sv_spec, sv_range) => {
+            return handle_get_sv(db, as_of, &sv_spec, &sv_range, true, resp_fmt).await;
         }
         Statement::Put(pk, opt_pv) => {
             let pk = Arc::new(pk);
             let opt_pv = opt_pv.map(Arc::new);
 
-            const RETRY_LIMIT: usize = 5;
-
-            Txn::run(db, RETRY_LIMIT, |txn| {
+            Txn::run(db, PUT_RETRY_LIMIT, |txn| {
                 txn.put(&pk, &opt_pv)?;
                 Ok(ClientCommitDecision::Commit(()))
             })
@@ -100,5 +167,92 @@ pub async fn handle_stmt(db: &DB, stmt: Statement) -> Result<(StatusCode, String
 
             return http_utils::ok("");
         }
+        Statement::PutWithTTL(pk, pv, ttl) => {
+            let pk = Arc::new(pk);
+            let pv = Arc::new(pv);
+
+            Txn::run(db, PUT_RETRY_LIMIT, |txn| {
+                txn.put_with_ttl(&pk, &pv, ttl)?;
+                Ok(ClientCommitDecision::Commit(()))
+            })
+            .await?;
+
+            return http_utils::ok("");
+        }
+        Statement::DeleteRange(pk_lo, pk_hi) => {
+            let pk_lo = Arc::new(pk_lo);
+            let pk_hi = Arc::new(pk_hi);
+
+            Txn::run(db, PUT_RETRY_LIMIT, |txn| {
+                txn.delete_range(&pk_lo, &pk_hi)?;
+                Ok(ClientCommitDecision::Commit(()))
+            })
+            .await?;
+
+            return http_utils::ok("");
+        }
+    }
+}
+
+async fn handle_get_sv(
+    db: &DB,
+    as_of: Option<CommitVer>,
+    sv_spec: &ScndIdxSpec,
+    sv_range: &SearchRange<SubValue>,
+    is_projected: bool,
+    resp_fmt: BodyFormat,
+) -> Result<(StatusCode, String), AppError> {
+    let (sv_lo, sv_hi) = sv_range.as_ref();
+    let sv_hi_prefix = prefix_sv_hi(sv_spec, sv_hi)?;
+    let sv_hi = sv_hi_prefix.as_ref().or(sv_hi);
+    let body = run_read_only(db, as_of, |txn| {
+        get_sv_body(txn, sv_spec, sv_lo, sv_hi, is_projected, resp_fmt)
+    })
+    .await?;
+    return http_utils::ok(body);
+}
+
+async fn run_read_only<'txn, T>(
+    db: &'txn DB,
+    as_of: Option<CommitVer>,
+    client_fn: impl FnMut(&mut Txn<'txn>) -> Result<T>,
+) -> Result<T> {
+    match as_of {
+        None => Txn::run_read_only(db, GET_RETRY_LIMIT, client_fn).await,
+        Some(commit_ver) => Txn::run_read_only_as_of(db, commit_ver, client_fn).await,
     }
 }
+
+/// A composite index may be ranged over a prefix of its columns.
+///
+/// @return The upper bound that covers all sub-values having the prefix `sv_hi`, if `sv_hi` is a prefix.
+pub(super) fn prefix_sv_hi(
+    sv_spec: &ScndIdxSpec,
+    sv_hi: Option<&SubValue>,
+) -> Result<Option<SubValue>> {
+    let sv_hi_prefix = sv_hi
+        .map(|sv_hi| sv_spec.prefix_hi(sv_hi))
+        .transpose()?
+        .flatten();
+    Ok(sv_hi_prefix)
+}
+
+pub(super) fn get_sv_body<'txn>(
+    txn: &mut Txn<'txn>,
+    sv_spec: &'txn ScndIdxSpec,
+    sv_lo: Option<&'txn SubValue>,
+    sv_hi: Option<&'txn SubValue>,
+    is_projected: bool,
+    resp_fmt: BodyFormat,
+) -> Result<String> {
+    let body = if is_projected {
+        let scnd_entries = txn.get_sv_range_projected(sv_spec, sv_lo, sv_hi)?;
+        let pkpv_entries = scnd_entries.map(|entry| entry.convert::<PKShared, PVShared>());
+        entries_to_body(resp_fmt, pkpv_entries)?
+    } else {
+        let scnd_entries = txn.get_sv_range(sv_spec, sv_lo, sv_hi)?;
+        let pkpv_entries = scnd_entries.map(|entry| entry.convert::<PKShared, PVShared>());
+        entries_to_body(resp_fmt, pkpv_entries)?
+    };
+    Ok(body)
+}
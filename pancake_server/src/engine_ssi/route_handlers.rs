@@ -1,33 +1,42 @@
 use crate::{
-    common::http_utils::{self, logger, AppError},
-    engine_ssi::{query_handlers, wasm::WasmEngine},
+    common::http_utils::{self, logger, AppError, BodyFormat},
+    engine_ssi::{
+        query_handlers,
+        txn_sessions::{TxnSessionId, TxnSessions},
+        wasm::WasmEngine,
+    },
     oper::{
         api::{SearchRange, Statement},
         query_basic::parse as parse_query,
+        query_json::parse as parse_query_json,
     },
 };
 use anyhow::{anyhow, Result};
 use axum::{
     body::{to_bytes, Body},
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
     middleware,
+    response::Response,
     routing::{delete, get, post, put},
     Router,
 };
 use derive_more::Constructor;
 use pancake_engine_ssi::DB;
 use pancake_types::{
+    json,
     serde::Datum,
     types::{PrimaryKey, Value},
 };
 use shorthand::ShortHand;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 #[derive(ShortHand, Constructor)]
 pub struct AppState {
     db: Arc<DB>,
     wasm_engine: WasmEngine,
+    txn_sessions: Arc<TxnSessions>,
 }
 
 pub fn create_router(state: Arc<AppState>) -> Router {
@@ -36,6 +45,11 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         .route("/key/:key", put(put_one))
         .route("/key/:key", delete(delete_one))
         .route("/query", post(query))
+        .route("/commit_vers", get(commit_vers))
+        .route("/txn", post(txn_begin))
+        .route("/txn/:txn_id/query", post(txn_query))
+        .route("/txn/:txn_id/commit", post(txn_commit))
+        .route("/txn/:txn_id/abort", post(txn_abort))
         .route("/wasm", post(wasm))
         .layer(middleware::from_fn(logger))
         .with_state(state)
@@ -44,57 +58,152 @@ pub fn create_router(state: Arc<AppState>) -> Router {
 async fn get_one(
     Path(key): Path<String>,
     State(state): State<Arc<AppState>>,
-) -> Result<(StatusCode, String), AppError> {
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
     let pk = PrimaryKey(Datum::Str(key));
     let stmt = Statement::GetPK(SearchRange::One(pk));
 
-    query_handlers::handle_stmt(state.db(), stmt).await
+    let resp_fmt = BodyFormat::of_response(&headers);
+    let resp = query_handlers::handle_stmt(state.db(), stmt, resp_fmt).await?;
+    Ok(http_utils::respond(resp_fmt, resp))
 }
 
 async fn put_one(
     Path(key): Path<String>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     body: String,
-) -> Result<(StatusCode, String), AppError> {
+) -> Result<Response, AppError> {
     let pk = PrimaryKey(Datum::Str(key));
-    let pv = Value(Datum::Str(body));
+    let pv = match BodyFormat::of_request(&headers) {
+        BodyFormat::Text => Value(Datum::Str(body)),
+        BodyFormat::Json => {
+            let json = serde_json::from_str(&body).map_err(|e| anyhow!(e))?;
+            Value(json::from_json(&json)?)
+        }
+    };
     let stmt = Statement::Put(pk, Some(pv));
 
-    query_handlers::handle_stmt(state.db(), stmt).await
+    let resp_fmt = BodyFormat::of_response(&headers);
+    let resp = query_handlers::handle_stmt(state.db(), stmt, resp_fmt).await?;
+    Ok(http_utils::respond(resp_fmt, resp))
 }
 
 async fn delete_one(
     Path(key): Path<String>,
     State(state): State<Arc<AppState>>,
-) -> Result<(StatusCode, String), AppError> {
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
     let pk = PrimaryKey(Datum::Str(key));
     let stmt = Statement::Put(pk, None);
 
-    query_handlers::handle_stmt(state.db(), stmt).await
+    let resp_fmt = BodyFormat::of_response(&headers);
+    let resp = query_handlers::handle_stmt(state.db(), stmt, resp_fmt).await?;
+    Ok(http_utils::respond(resp_fmt, resp))
 }
 
 async fn query(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     body: String,
-) -> Result<(StatusCode, String), AppError> {
+) -> Result<Response, AppError> {
     let db = state.db();
 
-    let oper = parse_query(&body)?;
+    let oper = match BodyFormat::of_request(&headers) {
+        BodyFormat::Text => parse_query(&body)?,
+        BodyFormat::Json => parse_query_json(&body)?,
+    };
+
+    let resp_fmt = BodyFormat::of_response(&headers);
+    let resp = query_handlers::handle_oper(db, oper, resp_fmt).await?;
+    Ok(http_utils::respond(resp_fmt, resp))
+}
+
+/// Lists the commit versions that a get can be run as of, from older to newer.
+/// The last one is the current version.
+///
+/// The text body has one version per line. The JSON body is an array of versions.
+async fn commit_vers(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let commit_vers = state.db().retained_commit_vers().await;
+    let commit_vers = commit_vers.into_iter().map(u64::from);
 
-    query_handlers::handle_oper(db, oper).await
+    let resp_fmt = BodyFormat::of_response(&headers);
+    let body = match resp_fmt {
+        BodyFormat::Text => commit_vers
+            .map(|commit_ver| format!("{commit_ver}\r\n"))
+            .collect::<String>(),
+        BodyFormat::Json => serde_json::Value::from(commit_vers.collect::<Vec<_>>()).to_string(),
+    };
+    let resp = http_utils::ok(body)?;
+    Ok(http_utils::respond(resp_fmt, resp))
 }
 
+/// The txn is read-only iff `?read_only=true`.
+///
+/// @return The id of the new txn.
+async fn txn_begin(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<(StatusCode, String), AppError> {
+    let is_read_only = http_utils::bool_param(&params, "read_only")?;
+    let id = state.txn_sessions().begin(is_read_only).await?;
+    http_utils::ok(id.to_string())
+}
+
+async fn txn_query(
+    Path(txn_id): Path<TxnSessionId>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Response, AppError> {
+    let oper = match BodyFormat::of_request(&headers) {
+        BodyFormat::Text => parse_query(&body)?,
+        BodyFormat::Json => parse_query_json(&body)?,
+    };
+
+    let resp_fmt = BodyFormat::of_response(&headers);
+    let resp = state
+        .txn_sessions()
+        .handle_oper(txn_id, oper, resp_fmt)
+        .await?;
+    Ok(http_utils::respond(resp_fmt, resp))
+}
+
+async fn txn_commit(
+    Path(txn_id): Path<TxnSessionId>,
+    State(state): State<Arc<AppState>>,
+) -> Result<(StatusCode, String), AppError> {
+    state.txn_sessions().commit(txn_id).await
+}
+
+async fn txn_abort(
+    Path(txn_id): Path<TxnSessionId>,
+    State(state): State<Arc<AppState>>,
+) -> Result<(StatusCode, String), AppError> {
+    state.txn_sessions().abort(txn_id).await
+}
+
+/// The UDF is run in a read-only txn iff `?read_only=true`.
 async fn wasm(
     State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
     body: Body,
 ) -> Result<(StatusCode, String), AppError> {
+    let is_read_only = http_utils::bool_param(&params, "read_only")?;
+
     let bytes = to_bytes(body, i32::MAX as usize)
         .await
         .map_err(|e| anyhow!(e))?;
 
     let retry_limit = 5;
 
-    let body = state.wasm_engine().serve(&bytes, retry_limit).await?;
+    let body = state
+        .wasm_engine()
+        .serve(&bytes, retry_limit, is_read_only)
+        .await?;
 
     http_utils::ok(body)
 }
@@ -0,0 +1,333 @@
+use crate::{
+    common::http_utils::{self, entries_to_body, kv_to_body, AppError, BodyFormat},
+    engine_ssi::query_handlers::{get_sv_body, prefix_sv_hi},
+    oper::api::{Operation, SearchRange, Statement},
+};
+use anyhow::{anyhow, Result};
+use axum::http::StatusCode;
+use pancake_engine_ssi::{Txn, DB};
+use pancake_types::types::{ScndIdxSpec, SubValue};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Notify};
+
+pub type TxnSessionId = u64;
+
+/// Transactions whose statements are sent over multiple requests.
+///
+/// A client begins a txn, gets its id, sends any number of statements under that id,
+/// then commits or aborts the txn.
+///
+/// Each session's txn holds its snapshot, as well as a read lock of the DB state, for the session's whole life.
+/// The read lock blocks secondary index and constraint creation, which take the write lock.
+/// Hence a txn that stays idle for longer than the idle timeout is aborted. See [`Self::run_idle_reaper()`].
+///
+/// Until then, an idle session blocks index and constraint creation, for up to 1.5x the idle timeout.
+/// Moreover, the lock is fair, i.e. a queued writer is served before any reader that queues after it.
+/// Hence, while index or constraint creation waits for an idle session, new txns wait as well.
+pub struct TxnSessions {
+    db: Arc<DB>,
+    idle_timeout: Duration,
+
+    next_id: AtomicU64,
+    sessions: StdMutex<HashMap<TxnSessionId, Arc<Mutex<Option<TxnSession>>>>>,
+
+    is_terminating: AtomicBool,
+    terminate_notify: Notify,
+}
+
+impl TxnSessions {
+    pub fn new(db: Arc<DB>, idle_timeout: Duration) -> Self {
+        Self {
+            db,
+            idle_timeout,
+
+            next_id: AtomicU64::new(1),
+            sessions: StdMutex::new(HashMap::new()),
+
+            is_terminating: AtomicBool::new(false),
+            terminate_notify: Notify::new(),
+        }
+    }
+
+    /// @arg is_read_only Whether to begin a read-only txn. See [`Txn::begin_read_only()`].
+    pub async fn begin(&self, is_read_only: bool) -> Result<TxnSessionId> {
+        if self.is_terminating.load(Ordering::SeqCst) == true {
+            return Err(anyhow!("Txn sessions are terminating"));
+        }
+
+        let db = Arc::clone(&self.db);
+        // SAFETY: The `DB` is heap-allocated by `Arc`, hence it does not move.
+        // The session keeps `db` alive until after `txn` is dropped. See [`TxnSession`].
+        // `txn` never escapes the session, hence no borrow outlives `db`.
+        let db_ref: &'static DB = unsafe { &*Arc::as_ptr(&db) };
+        let txn = match is_read_only {
+            false => Txn::begin(db_ref).await?,
+            true => Txn::begin_read_only(db_ref).await?,
+        };
+
+        let session = TxnSession {
+            txn,
+            pinned: vec![],
+            last_active: Instant::now(),
+            _db: db,
+        };
+
+        {
+            let mut sessions = self.sessions.lock().unwrap();
+            // Checked again under the lock, s.t. `terminate()` can't miss this session.
+            if self.is_terminating.load(Ordering::SeqCst) == false {
+                let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+                sessions.insert(id, Arc::new(Mutex::new(Some(session))));
+                return Ok(id);
+            }
+        }
+        session.abort().await?;
+        Err(anyhow!("Txn sessions are terminating"))
+    }
+
+    /// Statements of one session are run one at a time, in the order of arrival.
+    pub async fn handle_oper(
+        &self,
+        id: TxnSessionId,
+        oper: Operation,
+        resp_fmt: BodyFormat,
+    ) -> Result<(StatusCode, String), AppError> {
+        let stmt = match oper {
+            Operation::Query(stmt) => stmt,
+            Operation::QueryAsOf(..) => {
+                return Ok((
+                    StatusCode::BAD_REQUEST,
+                    "A statement within a txn reads the txn's snapshot, and cannot be run as of another commit version.".to_string(),
+                ))
+            }
+            _ => {
+                return Ok((
+                    StatusCode::BAD_REQUEST,
+                    "Only statements are supported within a txn.".to_string(),
+                ))
+            }
+        };
+
+        let opt_session = self.sessions.lock().unwrap().get(&id).cloned();
+        let session = match opt_session {
+            None => return Ok(Self::not_found(id)),
+            Some(session) => session,
+        };
+        let mut session = session.lock().await;
+        let session = match session.as_mut() {
+            None => return Ok(Self::not_found(id)),
+            Some(session) => session,
+        };
+
+        let res = session.run_stmt(stmt, resp_fmt);
+        session.last_active = Instant::now();
+        res
+    }
+
+    pub async fn commit(&self, id: TxnSessionId) -> Result<(StatusCode, String), AppError> {
+        let session = match self.remove(id).await {
+            None => return Ok(Self::not_found(id)),
+            Some(session) => session,
+        };
+        if session.commit().await? {
+            return http_utils::ok("");
+        } else {
+            return Ok((
+                StatusCode::CONFLICT,
+                "The txn conflicted with another txn, and was aborted.".to_string(),
+            ));
+        }
+    }
+
+    pub async fn abort(&self, id: TxnSessionId) -> Result<(StatusCode, String), AppError> {
+        let session = match self.remove(id).await {
+            None => return Ok(Self::not_found(id)),
+            Some(session) => session,
+        };
+        session.abort().await?;
+        return http_utils::ok("");
+    }
+
+    async fn remove(&self, id: TxnSessionId) -> Option<TxnSession> {
+        let opt_session = self.sessions.lock().unwrap().remove(&id);
+        match opt_session {
+            None => None,
+            Some(session) => session.lock().await.take(),
+        }
+    }
+
+    fn not_found(id: TxnSessionId) -> (StatusCode, String) {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Txn {id} does not exist. It may have ended or timed out."),
+        )
+    }
+
+    /// Periodically aborts idle sessions, until [`Self::terminate()`] is called.
+    ///
+    /// A session is aborted after it has been idle for between 1x and 1.5x the idle timeout.
+    pub async fn run_idle_reaper(&self) -> Result<()> {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(self.idle_timeout / 2) => {}
+                _ = self.terminate_notify.notified() => {}
+            }
+            if self.is_terminating.load(Ordering::SeqCst) == true {
+                return Ok(());
+            }
+
+            for session in self.remove_idle() {
+                session.abort().await?;
+            }
+        }
+    }
+
+    fn remove_idle(&self) -> Vec<TxnSession> {
+        let now = Instant::now();
+        let mut idle_sessions = vec![];
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_id, session| {
+            // A session that is locked is running a statement, hence is not idle.
+            if let Ok(mut session) = session.try_lock() {
+                let is_idle = match session.as_ref() {
+                    None => true,
+                    Some(session) => now.duration_since(session.last_active) >= self.idle_timeout,
+                };
+                if is_idle {
+                    idle_sessions.extend(session.take());
+                    return false;
+                }
+            }
+            true
+        });
+        idle_sessions
+    }
+
+    /// Aborts all sessions, and rejects new ones.
+    pub async fn terminate(&self) -> Result<()> {
+        self.is_terminating.store(true, Ordering::SeqCst);
+        self.terminate_notify.notify_one();
+
+        let sessions = self
+            .sessions
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_id, session)| session)
+            .collect::<Vec<_>>();
+        for session in sessions {
+            if let Some(session) = session.lock().await.take() {
+                session.abort().await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The fields are dropped in the order of declaration. Hence `txn` is dropped before what it borrows.
+/// [`Self::commit()`] and [`Self::abort()`] end `txn` before dropping the rest, likewise.
+struct TxnSession {
+    /// The `'static` lifetime stands in for the lifetime of `pinned` and `_db`.
+    txn: Txn<'static>,
+    /// Arguments of statements, which `txn` references in order to track its dependencies.
+    /// Declared after `txn`, s.t. they are dropped after `txn`.
+    pinned: Vec<Box<dyn Any + Send + Sync>>,
+    last_active: Instant,
+    /// Borrowed by `txn`, including by its read guard of the DB state.
+    _db: Arc<DB>,
+}
+
+impl TxnSession {
+    /// Moves `t` to the heap, s.t. it lives until the txn ends.
+    fn pin<T: Any + Send + Sync>(&mut self, t: T) -> &'static T {
+        let t = Box::new(t);
+        let t_ptr = t.as_ref() as *const T;
+        self.pinned.push(t);
+        // SAFETY: `t` is boxed, hence it does not move when `pinned` grows.
+        // It is never removed from `pinned`, which is dropped only after `txn`, the sole borrower.
+        unsafe { &*t_ptr }
+    }
+
+    fn run_stmt(
+        &mut self,
+        stmt: Statement,
+        resp_fmt: BodyFormat,
+    ) -> Result<(StatusCode, String), AppError> {
+        match stmt {
+            Statement::GetPK(SearchRange::One(pk)) => {
+                let pk = self.pin(pk);
+                match self.txn.get_pk_one(pk)? {
+                    None => return Ok((StatusCode::NOT_FOUND, "".to_string())),
+                    Some((pk, pv)) => {
+                        let body = kv_to_body(resp_fmt, &pk, &pv);
+                        return http_utils::ok(body);
+                    }
+                }
+            }
+            Statement::GetPK(SearchRange::Range { lo, hi }) => {
+                let (lo, hi) = (self.pin(lo), self.pin(hi));
+                let entries = self.txn.get_pk_range(lo.as_ref(), hi.as_ref());
+                let body = entries_to_body(resp_fmt, entries)?;
+                return http_utils::ok(body);
+            }
+            Statement::GetSV(sv_spec, sv_range) => {
+                return self.run_get_sv(sv_spec, sv_range, false, resp_fmt);
+            }
+            Statement::GetSVProjected(sv_spec, sv_range) => {
+                return self.run_get_sv(sv_spec, sv_range, true, resp_fmt);
+            }
+            Statement::Put(pk, opt_pv) => {
+                let pk = self.pin(Arc::new(pk));
+                self.txn.put(pk, &opt_pv.map(Arc::new))?;
+                return http_utils::ok("");
+            }
+            Statement::PutWithTTL(pk, pv, ttl) => {
+                let pk = self.pin(Arc::new(pk));
+                self.txn.put_with_ttl(pk, &Arc::new(pv), ttl)?;
+                return http_utils::ok("");
+            }
+            Statement::DeleteRange(pk_lo, pk_hi) => {
+                let pk_lo = self.pin(Arc::new(pk_lo));
+                let pk_hi = self.pin(Arc::new(pk_hi));
+                self.txn.delete_range(pk_lo, pk_hi)?;
+                return http_utils::ok("");
+            }
+        }
+    }
+
+    fn run_get_sv(
+        &mut self,
+        sv_spec: ScndIdxSpec,
+        sv_range: SearchRange<SubValue>,
+        is_projected: bool,
+        resp_fmt: BodyFormat,
+    ) -> Result<(StatusCode, String), AppError> {
+        let sv_spec = self.pin(sv_spec);
+        let sv_range = self.pin(sv_range);
+        let (sv_lo, sv_hi) = sv_range.as_ref();
+        let sv_hi = match prefix_sv_hi(sv_spec, sv_hi)? {
+            None => sv_hi,
+            Some(sv_hi_prefix) => Some(self.pin(sv_hi_prefix)),
+        };
+        let body = get_sv_body(&mut self.txn, sv_spec, sv_lo, sv_hi, is_projected, resp_fmt)?;
+        return http_utils::ok(body);
+    }
+
+    async fn commit(self) -> Result<bool> {
+        let Self { txn, pinned, .. } = self;
+        let did_commit = txn.commit().await?;
+        drop(pinned);
+        Ok(did_commit)
+    }
+
+    async fn abort(self) -> Result<()> {
+        let Self { txn, pinned, .. } = self;
+        txn.abort().await?;
+        drop(pinned);
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Result};
 use pancake_engine_ssi::{ClientCommitDecision, Txn, DB};
-use pancake_types::types::{Deser, PrimaryKey, Ser, SubValue, SubValueSpec, Value};
+use pancake_types::types::{Deser, PrimaryKey, ScndIdxSpec, Ser, SubValue, Value};
 use std::sync::Arc;
 use wit_bindgen_host_wasmtime_rust::wasmtime::{
     self,
@@ -33,7 +33,14 @@ impl WasmEngine {
         Ok(Self { db, engine, linker })
     }
 
-    pub async fn serve(&self, compo_bytes: &[u8], retry_limit: usize) -> Result<String> {
+    /// @arg is_read_only Whether to run the UDF in a read-only txn. See [`Txn::begin_read_only()`].
+    ///     If so, the UDF's commit decision is moot, and any put fails.
+    pub async fn serve(
+        &self,
+        compo_bytes: &[u8],
+        retry_limit: usize,
+        is_read_only: bool,
+    ) -> Result<String> {
         let state = WasmState {
             db_provider: DbProvider { txn_ptr: 0 },
         };
@@ -42,25 +49,41 @@ impl WasmEngine {
         let compo = Component::new(&self.engine, compo_bytes)?;
         let (udf, _inst) = Udf::instantiate(&mut store, &compo, &self.linker)?;
 
+        if is_read_only {
+            let client_res =
+                Txn::run_read_only(&self.db, retry_limit, |txn| {
+                    match Self::run_udf(&udf, &mut store, txn)? {
+                        ClientCommitDecision::Commit(client_str) => Ok(client_str),
+                        ClientCommitDecision::Abort(client_str) => Ok(client_str),
+                    }
+                })
+                .await;
+            return client_res;
+        }
+
         let client_res = Txn::run(&self.db, retry_limit, |txn| {
-            store.data_mut().db_provider = DbProvider {
-                txn_ptr: txn as *mut _ as usize,
-            };
-
-            let res_commit_dec = udf.run_txn(&mut store)?;
-            match res_commit_dec {
-                Err(client_str) => Err(anyhow!(client_str)),
-                Ok(CommitDecision::Abort(client_str)) => {
-                    Ok(ClientCommitDecision::Abort(client_str))
-                }
-                Ok(CommitDecision::Commit(client_str)) => {
-                    Ok(ClientCommitDecision::Commit(client_str))
-                }
-            }
+            Self::run_udf(&udf, &mut store, txn)
         })
         .await;
         client_res
     }
+
+    fn run_udf(
+        udf: &Udf,
+        store: &mut Store<WasmState>,
+        txn: &mut Txn,
+    ) -> Result<ClientCommitDecision<String>> {
+        store.data_mut().db_provider = DbProvider {
+            txn_ptr: txn as *mut _ as usize,
+        };
+
+        let res_commit_dec = udf.run_txn(&mut *store)?;
+        match res_commit_dec {
+            Err(client_str) => Err(anyhow!(client_str)),
+            Ok(CommitDecision::Abort(client_str)) => Ok(ClientCommitDecision::Abort(client_str)),
+            Ok(CommitDecision::Commit(client_str)) => Ok(ClientCommitDecision::Commit(client_str)),
+        }
+    }
 }
 
 struct WasmState {
@@ -126,7 +149,7 @@ impl db::Db for DbProvider {
         sv_lo: Option<Sv>,
         sv_hi: Option<Sv>,
     ) -> anyhow::Result<Result<Vec<Pkpv>, String>> {
-        let sv_spec = SubValueSpec::deser_solo(&sv_spec.bytes)?;
+        let sv_spec = ScndIdxSpec::deser_solo(&sv_spec.bytes)?;
         let sv_lo = sv_lo
             .map(|sv| SubValue::deser_solo(&sv.bytes))
             .transpose()?;
@@ -149,6 +172,35 @@ impl db::Db for DbProvider {
         Ok(Ok(ret))
     }
 
+    fn get_sv_range_projected(
+        &mut self,
+        sv_spec: SvSpec,
+        sv_lo: Option<Sv>,
+        sv_hi: Option<Sv>,
+    ) -> anyhow::Result<Result<Vec<Pkpv>, String>> {
+        let sv_spec = ScndIdxSpec::deser_solo(&sv_spec.bytes)?;
+        let sv_lo = sv_lo
+            .map(|sv| SubValue::deser_solo(&sv.bytes))
+            .transpose()?;
+        let sv_hi = sv_hi
+            .map(|sv| SubValue::deser_solo(&sv.bytes))
+            .transpose()?;
+
+        let mut ret = vec![];
+        for entry in self
+            .txn()
+            .get_sv_range_projected(&sv_spec, sv_lo.as_ref(), sv_hi.as_ref())?
+        {
+            let (pk, pv) = entry.try_borrow()?;
+            let pk = pk.ser_solo()?;
+            let pv = pv.ser_solo()?;
+            let pk = Pk { bytes: pk };
+            let pv = Pv { bytes: pv };
+            ret.push(Pkpv { pk, pv });
+        }
+        Ok(Ok(ret))
+    }
+
     fn put(&mut self, pk: Pk, opt_pv: Option<Pv>) -> anyhow::Result<Result<(), String>> {
         let pk = PrimaryKey::deser_solo(&pk.bytes)?;
         let opt_pv = opt_pv.map(|pv| Value::deser_solo(&pv.bytes)).transpose()?;
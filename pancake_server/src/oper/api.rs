@@ -1,10 +1,16 @@
-use pancake_types::types::{PrimaryKey, SubValue, SubValueSpec, Value};
+use pancake_types::types::{PrimaryKey, ScndIdxSpec, SubValue, Value, ValueConstraint};
+use std::time::Duration;
 
 #[derive(PartialEq, Eq, Debug)]
 pub enum Operation {
     Query(Statement),
-    CreateScndIdx(SubValueSpec),
-    DelScndIdx(SubValueSpec),
+    /// Runs a get statement against the snapshot as of an older commit version.
+    /// Supported by the SSI engine only, for its retained commit versions.
+    QueryAsOf(Statement, u64),
+    CreateScndIdx(ScndIdxSpec),
+    DelScndIdx(ScndIdxSpec),
+    CreateConstraint(ValueConstraint),
+    DelConstraint(ValueConstraint),
 }
 
 impl From<Statement> for Operation {
@@ -13,11 +19,27 @@ impl From<Statement> for Operation {
     }
 }
 
+impl Operation {
+    pub fn query_as_of(stmt: Statement, opt_commit_ver: Option<u64>) -> Self {
+        match opt_commit_ver {
+            None => Self::Query(stmt),
+            Some(commit_ver) => Self::QueryAsOf(stmt, commit_ver),
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Debug)]
 pub enum Statement {
     GetPK(SearchRange<PrimaryKey>),
-    GetSV(SubValueSpec, SearchRange<SubValue>),
+    /// Gets whole values.
+    GetSV(ScndIdxSpec, SearchRange<SubValue>),
+    /// Gets values as projected by the index.
+    GetSVProjected(ScndIdxSpec, SearchRange<SubValue>),
     Put(PrimaryKey, Option<Value>),
+    /// Puts a value that expires after the TTL.
+    PutWithTTL(PrimaryKey, Value, Duration),
+    /// Deletes all entries whose primary keys are within the inclusive range.
+    DeleteRange(PrimaryKey, PrimaryKey),
 }
 
 #[derive(PartialEq, Eq, Debug)]
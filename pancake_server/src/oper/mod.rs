@@ -1,2 +1,3 @@
 pub mod api;
 pub mod query_basic;
+pub mod query_json;
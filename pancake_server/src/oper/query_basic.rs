@@ -10,11 +10,31 @@
 //! - `del int(100)`
 //! - `get int(100)`
 //!
+//! Other scalar types:
+//!
+//! - `put str(k1) float(-1.5)`
+//! - `put str(k2) bool(true)`
+//! - `put str(k3) null`
+//! - `put str(k4) ts(1700000000000000)` (microseconds since the unix epoch, UTC)
+//! - `put str(k5) dec(12.34)` (base-10 fixed-point)
+//!
 //! The tuple type nests other data, including other tuples.
 //!
 //! - `put int(6000) tup( str(s6000) tup( int(60) str(s60) ) int(60) )`
 //! - `get tup( str(a) int(10) )`
 //!
+//! The list type is a variable-length sequence of data.
+//!
+//! - `put int(7500) list( str(a) str(b) )`
+//!
+//! The map type nests other data under string keys. Its keys are sorted, regardless of their order in the query.
+//!
+//! - `put int(7000) map( user: map( name: str(alice) address: map( zip: str(12345) ) ) tags: tup( str(a) ) )`
+//!
+//! A put may specify a time-to-live, in seconds. Once expired, the entry is equivalent to a deleted one.
+//!
+//! - `put str(session1) str(token1) ttl(3600)`
+//!
 //! ## By range over primary key
 //!
 //! Analogous sql:
@@ -29,6 +49,22 @@
 //! - `get between _ str(foobar)`
 //! - `get between _ _`
 //!
+//! Analogous sql:
+//!
+//! - `DELETE FROM table WHERE pk BETWEEN ${pk_lo} AND ${pk_hi};`
+//!
+//! Both boundaries are required.
+//!
+//! - `del between int(50) str(foobar)`
+//!
+//! ## As of an older commit version
+//!
+//! Any get may read the snapshot as of a retained commit version. Supported by the SSI engine only.
+//!
+//! - `get int(100) as of 12`
+//! - `get between _ _ as of 12`
+//! - `get where svspec(int) _ as of 12`
+//!
 //! ## By sub-portion of value
 //!
 //! ### Index creation
@@ -55,6 +91,53 @@
 //!
 //! `create index svspec(1 0 int)`
 //!
+//! Index all entries by sub-value specification that names map members.
+//! Member names and member indexes may be mixed, and may be delimited by spaces or `.`s.
+//!
+//! `create index svspec(user.address.zip str)`
+//!
+//! `create index svspec(tags.0 str)`
+//!
+//! Index all entries by every element of a list. I.e. create an inverted index.
+//! An entry is indexed once per distinct matching element.
+//!
+//! `create index svspec(tags.* str)`
+//!
+//! Index all entries by multiple sub-value specifications, delimited by `,`s. I.e. create a composite index.
+//! The index key is the tuple of the sub-values.
+//!
+//! `create index svspec(tenant str, created_at ts)`
+//!
+//! The supported datum_types in svspec are `str`, `int`, `float`, `bool`, `null`, `ts`, `dec`, `tup`, `map` and `list`.
+//!
+//! By default, an index stores the whole values. Optionally, an index stores only its keys,
+//! or a tuple of projected sub-values, specified like a svspec.
+//! The projection is part of the index's identity, so it must be repeated in every get and delete.
+//!
+//! `create index svspec(age int) keys`
+//!
+//! `create index svspec(age int) project(name str, tags.* str)`
+//!
+//! Optionally, an index covers only the values that satisfy a filter, i.e. creates a partial index.
+//! A filter tests the sub-value at a member path, for equality, for inclusion in an inclusive range,
+//! or for datum_type. It precedes any projection.
+//! The filter is part of the index's identity, so it must be repeated in every get and delete.
+//!
+//! `create index svspec(age int) filter(status eq str(active))`
+//!
+//! `create index svspec(age int) filter(score between int(1) _) keys`
+//!
+//! `create index svspec(name str) filter(tags.* is int)`
+//!
+//! Optionally, an index is unique, i.e. no two entries may share a sub-value. It precedes any filter.
+//! Creating a unique index fails if existing values share a sub-value. Once created,
+//! any put that would share a sub-value with another entry fails.
+//! Uniqueness is part of the index's identity, so it must be repeated in every get and delete.
+//!
+//! `create index svspec(email str) unique`
+//!
+//! `create index svspec(email str) unique filter(status eq str(active)) keys`
+//!
 //! ### Index-based selection
 //!
 //! Analogous sql:
@@ -88,13 +171,58 @@
 //! - `get where svspec(1 0 int) between int(60) int(61)`
 //! - `get where svspec(1 0 int) _`
 //!
+//! Get all entries by sub-value specification that names map members.
+//!
+//! - `get where svspec(user.address.zip str) between str(10000) str(19999)`
+//!
+//! Get all entries by composite sub-value specification. A tuple of a prefix of the columns selects all entries having that prefix.
+//!
+//! - `get where svspec(tenant str, created_at ts) tup( str(t1) ts(100) )`
+//! - `get where svspec(tenant str, created_at ts) tup( str(t1) )`
+//! - `get where svspec(tenant str, created_at ts) between tup( str(t1) ts(100) ) tup( str(t1) )`
+//!
+//! Get all entries by an index that does not store the whole values.
+//! The whole values are looked up by their primary keys.
+//!
+//! - `get where svspec(age int) keys between int(20) int(30)`
+//!
+//! Get all entries as projected by the index, without looking up the whole values.
+//!
+//! - `get projected where svspec(age int) project(name str, tags.* str) between int(20) int(30)`
+//!
+//! Get all entries by a filtered index.
+//!
+//! - `get where svspec(age int) filter(status eq str(active)) between int(20) int(30)`
+//!
+//! ## Constraints on values
+//!
+//! A constraint requires every value to have a certain shape at a specific nested location.
+//! It is specified like a svspec. Creating a constraint fails if any existing value violates it.
+//! Once created, any put of a violating value fails.
+//!
+//! Require member 0 of every value to be an int.
+//!
+//! `create constraint shape(0 int)`
+//!
+//! Require every value to be a tuple of at least 2 members.
+//!
+//! `create constraint shape(arity 2)`
+//!
+//! Require every element of a list to be a str.
+//!
+//! `create constraint shape(tags.* str)`
+//!
+//! `delete constraint shape(0 int)`
+//!
 //! # Caveats
 //!
-//! The input string is split by unicode word boundary. This incurs some limitations:
+//! The input string is tokenized into numbers (such as `-12.34`), words, and individual punctuations.
+//! This incurs some limitations:
 //! - Literals such as `foo.bar` and `foo-bar` are separated into multiple tokens.
-//!     - This means any data containing such characters as `.` and `-` are unworkable with this query engine.
-//! - Literals such as `("` and `))` are not separated.
-//!     - Hence, when in doubt, add spaces.
+//!     - This means any string data containing such characters as `.` and `-` are unworkable with this query engine.
+//!     - Ditto for map keys. In addition, map keys that consist of digits only are indistinguishable from
+//!       member indexes in svspec.
+//!     - Map keys `eq`, `between` and `is` are unworkable in filter member paths.
 //!
 //! This is a simplistic, recursion-based parser.
 //! It's meant to be a stop-gap impl.
@@ -102,14 +230,19 @@
 
 use crate::oper::api::{Operation, SearchRange, Statement};
 use anyhow::{anyhow, Context, Result};
-use pancake_types::serde::{Datum, DatumType};
-use pancake_types::types::{PrimaryKey, SubValue, SubValueSpec, Value};
+use pancake_types::serde::{Datum, DatumType, Decimal};
+use pancake_types::types::{
+    MemberKey, PredicateTest, PrimaryKey, ScndIdxProjection, ScndIdxSpec, SubValue, SubValueSpec,
+    Value, ValueConstraint, ValuePredicate, ValueShape,
+};
 use regex::Regex;
+use std::collections::BTreeMap;
 use std::iter::Peekable;
+use std::time::Duration;
 
 pub fn parse(q_str: &str) -> Result<Operation> {
-    let reg = Regex::new(r"\s+|\b")?;
-    let iter = reg.split(q_str).filter(|w| w.len() > 0).peekable();
+    let reg = Regex::new(r"-?\d+(\.\d+)?\b|\w+|[^\w\s]")?;
+    let iter = reg.find_iter(q_str).map(|m| m.as_str()).peekable();
     root(iter)
 }
 
@@ -120,12 +253,37 @@ fn root<'a, I: Iterator<Item = &'a str>>(mut iter: Peekable<I>) -> Result<Operat
             let key = PrimaryKey(dat);
             let dat = datum(&mut iter)?;
             let val = Value(dat);
+            let opt_ttl = match iter.peek() {
+                Some(&"ttl") => {
+                    iter.next();
+                    let ttl_literal = literal(&mut iter, "ttl")?;
+                    let ttl_secs = ttl_literal.parse::<u64>().context(format!(
+                        "Expected ttl seconds literal but found {ttl_literal}"
+                    ))?;
+                    Some(Duration::from_secs(ttl_secs))
+                }
+                _ => None,
+            };
             eos(&mut iter)?;
 
-            let q = Operation::from(Statement::Put(key, Some(val)));
-            return Ok(q);
+            let stmt = match opt_ttl {
+                None => Statement::Put(key, Some(val)),
+                Some(ttl) => Statement::PutWithTTL(key, val, ttl),
+            };
+            return Ok(Operation::from(stmt));
         }
         Some("del") => {
+            if iter.peek() == Some(&"between") {
+                iter.next();
+
+                let pk_lo = PrimaryKey(datum(&mut iter)?);
+                let pk_hi = PrimaryKey(datum(&mut iter)?);
+                eos(&mut iter)?;
+
+                let q = Operation::from(Statement::DeleteRange(pk_lo, pk_hi));
+                return Ok(q);
+            }
+
             let dat = datum(&mut iter)?;
             eos(&mut iter)?;
 
@@ -141,20 +299,27 @@ fn root<'a, I: Iterator<Item = &'a str>>(mut iter: Peekable<I>) -> Result<Operat
                 let pk_lo = optdat.map(PrimaryKey);
                 let optdat = opt_datum(&mut iter)?;
                 let pk_hi = optdat.map(PrimaryKey);
+                let opt_as_of = opt_as_of(&mut iter)?;
                 eos(&mut iter)?;
 
-                let q = Operation::from(Statement::GetPK(SearchRange::Range {
+                let stmt = Statement::GetPK(SearchRange::Range {
                     lo: pk_lo,
                     hi: pk_hi,
-                }));
-                return Ok(q);
+                });
+                return Ok(Operation::query_as_of(stmt, opt_as_of));
             }
-            Some(&"where") => {
-                iter.next();
+            Some(&"where") | Some(&"projected") => {
+                let is_projected = iter.next() == Some("projected");
+                if is_projected {
+                    match iter.next() {
+                        Some("where") => {}
+                        x => return Err(anyhow!("Expected where but found {x:?}")),
+                    }
+                }
 
                 let spec = svspec(&mut iter)?;
 
-                match iter.peek() {
+                let range = match iter.peek() {
                     Some(&"between") => {
                         iter.next();
 
@@ -162,43 +327,39 @@ fn root<'a, I: Iterator<Item = &'a str>>(mut iter: Peekable<I>) -> Result<Operat
                         let sv_lo = optdat.map(SubValue);
                         let optdat = opt_datum(&mut iter)?;
                         let sv_hi = optdat.map(SubValue);
-                        eos(&mut iter)?;
-
-                        let q = Operation::from(Statement::GetSV(
-                            spec,
-                            SearchRange::Range {
-                                lo: sv_lo,
-                                hi: sv_hi,
-                            },
-                        ));
-                        return Ok(q);
+
+                        SearchRange::Range {
+                            lo: sv_lo,
+                            hi: sv_hi,
+                        }
                     }
                     _ => {
                         let optdat = opt_datum(&mut iter)?;
-                        eos(&mut iter)?;
 
                         match optdat {
-                            None => {
-                                let q = Operation::from(Statement::GetSV(spec, SearchRange::all()));
-                                return Ok(q);
-                            }
-                            Some(dat) => {
-                                let sv = SubValue(dat);
-                                let q =
-                                    Operation::from(Statement::GetSV(spec, SearchRange::One(sv)));
-                                return Ok(q);
-                            }
+                            None => SearchRange::all(),
+                            Some(dat) => SearchRange::One(SubValue(dat)),
                         }
                     }
-                }
+                };
+
+                let opt_as_of = opt_as_of(&mut iter)?;
+                eos(&mut iter)?;
+
+                let stmt = match is_projected {
+                    false => Statement::GetSV(spec, range),
+                    true => Statement::GetSVProjected(spec, range),
+                };
+                return Ok(Operation::query_as_of(stmt, opt_as_of));
             }
             _ => {
                 let dat = datum(&mut iter)?;
                 let key = PrimaryKey(dat);
+                let opt_as_of = opt_as_of(&mut iter)?;
                 eos(&mut iter)?;
 
-                let q = Operation::from(Statement::GetPK(SearchRange::One(key)));
-                return Ok(q);
+                let stmt = Statement::GetPK(SearchRange::One(key));
+                return Ok(Operation::query_as_of(stmt, opt_as_of));
             }
         },
         Some("create") => match iter.next() {
@@ -207,6 +368,11 @@ fn root<'a, I: Iterator<Item = &'a str>>(mut iter: Peekable<I>) -> Result<Operat
                 eos(&mut iter)?;
                 return Ok(Operation::CreateScndIdx(spec));
             }
+            Some("constraint") => {
+                let constraint = shape(&mut iter)?;
+                eos(&mut iter)?;
+                return Ok(Operation::CreateConstraint(constraint));
+            }
             x => return Err(anyhow!("Expected creatable but found {x:?}")),
         },
         Some("delete") => match iter.next() {
@@ -215,6 +381,11 @@ fn root<'a, I: Iterator<Item = &'a str>>(mut iter: Peekable<I>) -> Result<Operat
                 eos(&mut iter)?;
                 return Ok(Operation::DelScndIdx(spec));
             }
+            Some("constraint") => {
+                let constraint = shape(&mut iter)?;
+                eos(&mut iter)?;
+                return Ok(Operation::DelConstraint(constraint));
+            }
             x => return Err(anyhow!("Expected deletable but found {x:?}")),
         },
         x => return Err(anyhow!("Expected operation but found {x:?}")),
@@ -223,49 +394,68 @@ fn root<'a, I: Iterator<Item = &'a str>>(mut iter: Peekable<I>) -> Result<Operat
 
 fn datum<'a, I: Iterator<Item = &'a str>>(iter: &mut Peekable<I>) -> Result<Datum> {
     match iter.next() {
-        Some("str") => match iter.next() {
-            Some("(") => match iter.next() {
-                Some(str_literal) => match iter.next() {
-                    Some(")") => return Ok(Datum::Str(String::from(str_literal))),
-                    x => {
-                        return Err(anyhow!(
-                            "Expected closing of string literal but found {x:?}",
-                        ))
-                    }
-                },
-                None => return Err(anyhow!("Expected string literal but found EOS")),
-            },
-            x => {
-                return Err(anyhow!(
-                    "Expected opening of string literal but found {x:?}",
-                ))
-            }
+        Some("str") => {
+            let str_literal = literal(iter, "string")?;
+            return Ok(Datum::Str(String::from(str_literal)));
+        }
+        Some("int") => {
+            let int_literal = literal(iter, "int")?;
+            let int_val = int_literal
+                .parse::<i64>()
+                .context(format!("Expected i64 literal but found {int_literal}"))?;
+            return Ok(Datum::I64(int_val));
+        }
+        Some("float") => {
+            let float_literal = literal(iter, "float")?;
+            let float_val = float_literal
+                .parse::<f64>()
+                .context(format!("Expected f64 literal but found {float_literal}"))?;
+            return Ok(Datum::F64(float_val));
+        }
+        Some("bool") => match literal(iter, "bool")? {
+            "true" => return Ok(Datum::Bool(true)),
+            "false" => return Ok(Datum::Bool(false)),
+            x => return Err(anyhow!("Expected bool literal but found {x}")),
         },
-        Some("int") => match iter.next() {
-            Some("(") => match iter.next() {
-                Some(int_literal) => {
-                    let int_val = int_literal
-                        .parse::<i64>()
-                        .context(format!("Expected i64 literal but found {int_literal}"))?;
-                    match iter.next() {
-                        Some(")") => return Ok(Datum::I64(int_val)),
-                        x => {
-                            return Err(anyhow!("Expected closing of int literal but found {x:?}"))
+        Some("null") => return Ok(Datum::Null),
+        Some("ts") => {
+            let ts_literal = literal(iter, "timestamp")?;
+            let ts_val = ts_literal.parse::<i64>().context(format!(
+                "Expected timestamp literal in microseconds but found {ts_literal}"
+            ))?;
+            return Ok(Datum::Timestamp(ts_val));
+        }
+        Some("dec") => {
+            let dec_literal = literal(iter, "decimal")?;
+            let dec_val = dec_literal.parse::<Decimal>()?;
+            return Ok(Datum::Decimal(dec_val));
+        }
+        Some("tup") => match iter.next() {
+            Some("(") => {
+                let mut members = Vec::<Datum>::new();
+                loop {
+                    match iter.peek() {
+                        Some(&")") => {
+                            iter.next();
+                            return Ok(Datum::Tuple(members));
+                        }
+                        _ => {
+                            let member = datum(iter)?;
+                            members.push(member);
                         }
                     }
                 }
-                None => return Err(anyhow!("Expected int literal but found EOS")),
-            },
-            x => return Err(anyhow!("Expected opening of int literal but found {x:?}")),
+            }
+            x => return Err(anyhow!("Expected opening of tuple but found {x:?}")),
         },
-        Some("tup") => match iter.next() {
+        Some("list") => match iter.next() {
             Some("(") => {
                 let mut members = Vec::<Datum>::new();
                 loop {
                     match iter.peek() {
                         Some(&")") => {
                             iter.next();
-                            return Ok(Datum::Tuple(members));
+                            return Ok(Datum::List(members));
                         }
                         _ => {
                             let member = datum(iter)?;
@@ -274,55 +464,255 @@ fn datum<'a, I: Iterator<Item = &'a str>>(iter: &mut Peekable<I>) -> Result<Datu
                     }
                 }
             }
-            x => return Err(anyhow!("Expected opening of tuple but found {x:?}")),
+            x => return Err(anyhow!("Expected opening of list but found {x:?}")),
+        },
+        Some("map") => match iter.next() {
+            Some("(") => {
+                let mut members = BTreeMap::<String, Datum>::new();
+                loop {
+                    match iter.next() {
+                        Some(")") => return Ok(Datum::Map(members)),
+                        Some(key) => {
+                            match iter.next() {
+                                Some(":") => (),
+                                x => {
+                                    return Err(anyhow!(
+                                        "Expected colon following map key but found {x:?}"
+                                    ))
+                                }
+                            }
+                            let member = datum(iter)?;
+                            if members.insert(String::from(key), member).is_some() {
+                                return Err(anyhow!("Duplicate map key {key}"));
+                            }
+                        }
+                        None => return Err(anyhow!("Expected closing of map but found EOS")),
+                    }
+                }
+            }
+            x => return Err(anyhow!("Expected opening of map but found {x:?}")),
         },
         x => Err(anyhow!("Expected datum type but found {x:?}")),
     }
 }
 
-fn svspec<'a, I: Iterator<Item = &'a str>>(iter: &mut I) -> Result<SubValueSpec> {
+/// Parses `( literal )`, returning the literal token.
+fn literal<'a, I: Iterator<Item = &'a str>>(iter: &mut I, lit_name: &str) -> Result<&'a str> {
+    match iter.next() {
+        Some("(") => match iter.next() {
+            Some(lit) => match iter.next() {
+                Some(")") => return Ok(lit),
+                x => {
+                    return Err(anyhow!(
+                        "Expected closing of {lit_name} literal but found {x:?}"
+                    ))
+                }
+            },
+            None => return Err(anyhow!("Expected {lit_name} literal but found EOS")),
+        },
+        x => {
+            return Err(anyhow!(
+                "Expected opening of {lit_name} literal but found {x:?}"
+            ))
+        }
+    }
+}
+
+fn svspec<'a, I: Iterator<Item = &'a str>>(iter: &mut Peekable<I>) -> Result<ScndIdxSpec> {
+    let tokens = parenthesized(iter, "svspec")?;
+    let columns = svspec_columns(tokens, "svspec")?;
+
+    let is_unique = match iter.peek() {
+        Some(&"unique") => {
+            iter.next();
+            true
+        }
+        _ => false,
+    };
+
+    let filter = match iter.peek() {
+        Some(&"filter") => Some(filter(iter)?),
+        _ => None,
+    };
+
+    let projection = match iter.peek() {
+        Some(&"keys") => {
+            iter.next();
+            ScndIdxProjection::KeysOnly
+        }
+        Some(&"project") => {
+            let tokens = parenthesized(iter, "project")?;
+            ScndIdxProjection::Columns(svspec_columns(tokens, "project")?)
+        }
+        _ => ScndIdxProjection::Whole,
+    };
+
+    return Ok(ScndIdxSpec {
+        columns,
+        projection,
+        filter,
+        is_unique,
+    });
+}
+
+/// Parses `filter( member_path test )`.
+/// The member_path cannot be [`parenthesized`], because the test may contain parenthesized datums.
+fn filter<'a, I: Iterator<Item = &'a str>>(iter: &mut Peekable<I>) -> Result<ValuePredicate> {
+    iter.next();
+    match iter.next() {
+        Some("(") => {}
+        x => return Err(anyhow!("Expected opening of filter() but found {x:?}.")),
+    }
+
+    let mut path_tokens = vec![];
+    let test = loop {
+        match iter.next() {
+            Some("eq") => break PredicateTest::Eq(datum(iter)?),
+            Some("between") => {
+                let lo = opt_datum(iter)?;
+                let hi = opt_datum(iter)?;
+                break PredicateTest::Range { lo, hi };
+            }
+            Some("is") => {
+                let datum_type = match iter.next() {
+                    Some(token) => svspec_datum_type(token).ok_or_else(|| {
+                        anyhow!("Expected filter() datum_type but found {token}.")
+                    })?,
+                    None => return Err(anyhow!("Expected filter() datum_type but found EOS.")),
+                };
+                break PredicateTest::Type(datum_type);
+            }
+            Some(".") => continue,
+            Some(")") => return Err(anyhow!("filter() did not contain eq, between or is.")),
+            Some(token) => path_tokens.push(token),
+            None => return Err(anyhow!("Expected filter() defn to close but found EOS.")),
+        }
+    };
+
+    match iter.next() {
+        Some(")") => {}
+        x => return Err(anyhow!("Expected closing of filter() but found {x:?}.")),
+    }
+
+    let member_path = member_path(path_tokens, "filter")?;
+
+    return Ok(ValuePredicate { member_path, test });
+}
+
+fn svspec_columns(tokens: Vec<&str>, name: &str) -> Result<Vec<SubValueSpec>> {
+    /* Columns are delimited by `,`s. Within each column, all tokens except the last one constitute the member_path. */
+    let mut columns = vec![];
+    for col_tokens in tokens.split(|token| *token == ",") {
+        let mut col_tokens = col_tokens.to_vec();
+
+        let datum_type = match col_tokens.pop() {
+            None => return Err(anyhow!("{name}() did not contain datum_type.")),
+            Some(token) => svspec_datum_type(token)
+                .ok_or_else(|| anyhow!("Expected {name}() datum_type but found {token}."))?,
+        };
+
+        let member_path = member_path(col_tokens, name)?;
+
+        columns.push(SubValueSpec {
+            member_path,
+            datum_type,
+        });
+    }
+    Ok(columns)
+}
+
+fn shape<'a, I: Iterator<Item = &'a str>>(iter: &mut I) -> Result<ValueConstraint> {
+    /* The last one or two tokens constitute the shape. All preceding tokens constitute the member_path. */
+    let mut tokens = parenthesized(iter, "shape")?;
+
+    let shape = match tokens.pop() {
+        None => return Err(anyhow!("shape() did not contain datum_type or arity.")),
+        Some(token) => match svspec_datum_type(token) {
+            Some(datum_type) => ValueShape::Type(datum_type),
+            None => match tokens.pop() {
+                Some("arity") => {
+                    let arity = token
+                        .parse::<u32>()
+                        .context(format!("Expected shape() arity but found {token}."))?;
+                    ValueShape::MinTupleArity(arity)
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "Expected shape() datum_type or arity but found {token}."
+                    ))
+                }
+            },
+        },
+    };
+
+    let member_path = member_path(tokens, "shape")?;
+
+    return Ok(ValueConstraint { member_path, shape });
+}
+
+/// Parses `name( tokens )`, returning the tokens, excluding `.`s.
+fn parenthesized<'a, I: Iterator<Item = &'a str>>(
+    iter: &mut I,
+    name: &str,
+) -> Result<Vec<&'a str>> {
     match iter.next() {
-        Some("svspec") => match iter.next() {
+        Some(x) if x == name => match iter.next() {
             Some("(") => {
-                let mut member_idxs = vec![];
-                let mut datum_type = None;
+                let mut tokens = vec![];
                 loop {
                     match iter.next() {
-                        Some(")") => break,
-                        Some(token) => {
-                            if datum_type.is_some() {
-                                return Err(anyhow!(
-                                    "svspec() contains an extra token {token} following datum_type.",
-                                ));
-                            } else if token == "str" {
-                                datum_type = Some(DatumType::Str);
-                            } else if token == "int" {
-                                datum_type = Some(DatumType::I64);
-                            } else {
-                                let member_idx = token.parse::<u32>().context(format!(
-                                    "Expected svspec() member_idx but found {token}.",
-                                ))?;
-                                member_idxs.push(member_idx);
-                            }
-                        }
+                        Some(")") => return Ok(tokens),
+                        Some(".") => continue,
+                        Some(token) => tokens.push(token),
                         None => {
-                            return Err(anyhow!("Expected svspec() defn to close but found EOS."))
+                            return Err(anyhow!("Expected {name}() defn to close but found EOS."))
                         }
                     }
                 }
-                match datum_type {
-                    None => return Err(anyhow!("svspec() did not contain datum_type.")),
-                    Some(datum_type) => {
-                        return Ok(SubValueSpec {
-                            member_idxs,
-                            datum_type,
-                        })
-                    }
-                }
             }
-            x => return Err(anyhow!("Expected opening of svspec() but found {x:?}.")),
+            x => return Err(anyhow!("Expected opening of {name}() but found {x:?}.")),
         },
-        x => return Err(anyhow!("Expected opening of svspec() but found {x:?}.")),
+        x => return Err(anyhow!("Expected opening of {name}() but found {x:?}.")),
+    }
+}
+
+fn member_path(tokens: Vec<&str>, name: &str) -> Result<Vec<MemberKey>> {
+    let mut member_path = vec![];
+    for token in tokens {
+        if token == "*" {
+            member_path.push(MemberKey::Each);
+        } else if token.starts_with(|c: char| c.is_ascii_digit()) {
+            /* A numeric token such as `1.0` may contain multiple member_idxs. */
+            for idx_token in token.split('.') {
+                let member_idx = idx_token
+                    .parse::<u32>()
+                    .context(format!("Expected {name}() member_idx but found {token}.",))?;
+                member_path.push(MemberKey::Idx(member_idx));
+            }
+        } else if token.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            member_path.push(MemberKey::from(token));
+        } else {
+            return Err(anyhow!(
+                "Expected {name}() member_idx or member_name but found {token}."
+            ));
+        }
+    }
+    Ok(member_path)
+}
+
+pub(crate) fn svspec_datum_type(token: &str) -> Option<DatumType> {
+    match token {
+        "str" => Some(DatumType::Str),
+        "int" => Some(DatumType::I64),
+        "float" => Some(DatumType::F64),
+        "bool" => Some(DatumType::Bool),
+        "null" => Some(DatumType::Null),
+        "ts" => Some(DatumType::Timestamp),
+        "dec" => Some(DatumType::Decimal),
+        "tup" => Some(DatumType::Tuple),
+        "map" => Some(DatumType::Map),
+        "list" => Some(DatumType::List),
+        _ => None,
     }
 }
 
@@ -339,6 +729,26 @@ fn opt_datum<'a, I: Iterator<Item = &'a str>>(iter: &mut Peekable<I>) -> Result<
     }
 }
 
+fn opt_as_of<'a, I: Iterator<Item = &'a str>>(iter: &mut Peekable<I>) -> Result<Option<u64>> {
+    if iter.peek() != Some(&"as") {
+        return Ok(None);
+    }
+    iter.next();
+    match iter.next() {
+        Some("of") => {}
+        x => return Err(anyhow!("Expected of but found {x:?}")),
+    }
+    match iter.next() {
+        Some(ver_literal) => {
+            let ver = ver_literal.parse::<u64>().context(format!(
+                "Expected commit version literal but found {ver_literal}"
+            ))?;
+            return Ok(Some(ver));
+        }
+        None => return Err(anyhow!("Expected commit version literal but found EOS")),
+    }
+}
+
 fn eos<'a, I: Iterator<Item = &'a str>>(iter: &mut I) -> Result<()> {
     match iter.next() {
         None => Ok(()),
@@ -372,6 +782,157 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn put_with_ttl() -> Result<()> {
+        let q_str = "put str(session1) str(token1) ttl(3600)";
+        let exp_q_obj = Operation::from(Statement::PutWithTTL(
+            PrimaryKey(Datum::Str(String::from("session1"))),
+            Value(Datum::Str(String::from("token1"))),
+            Duration::from_secs(3600),
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        assert!(parse("put str(k) str(v) ttl(-1)").is_err());
+        assert!(parse("put str(k) str(v) ttl(1.5)").is_err());
+        assert!(parse("put str(k) str(v) ttl(60) ttl(60)").is_err());
+        assert!(parse("del str(k) ttl(60)").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn del_between() -> Result<()> {
+        let q_str = "del between int(50) str(foobar)";
+        let exp_q_obj = Operation::from(Statement::DeleteRange(
+            PrimaryKey(Datum::I64(50)),
+            PrimaryKey(Datum::Str(String::from("foobar"))),
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        assert!(parse("del between int(50) _").is_err());
+        assert!(parse("del between int(50)").is_err());
+        assert!(parse("del between int(50) int(60) int(70)").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_as_of() -> Result<()> {
+        let q_str = "get int(100) as of 12";
+        let exp_q_obj = Operation::QueryAsOf(
+            Statement::GetPK(SearchRange::One(PrimaryKey(Datum::I64(100)))),
+            12,
+        );
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        let q_str = "get where svspec(int) _ as of 12";
+        let exp_q_obj = Operation::QueryAsOf(
+            Statement::GetSV(
+                ScndIdxSpec::from(SubValueSpec::whole(DatumType::I64)),
+                SearchRange::all(),
+            ),
+            12,
+        );
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        assert!(parse("get int(100) as of").is_err());
+        assert!(parse("get int(100) as of -1").is_err());
+        assert!(parse("get int(100) as 12").is_err());
+        assert!(parse("get int(100) as of 12 13").is_err());
+        assert!(parse("put int(100) int(1) as of 12").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn put_scalars() -> Result<()> {
+        let q_str =
+            "put int(-5) tup( float(-1.5) bool(false) null ts(1700000000000000) dec(-12.340) )";
+        let exp_q_obj = Operation::from(Statement::Put(
+            PrimaryKey(Datum::I64(-5)),
+            Some(Value(Datum::Tuple(vec![
+                Datum::F64(-1.5),
+                Datum::Bool(false),
+                Datum::Null,
+                Datum::Timestamp(1700000000000000),
+                Datum::Decimal(Decimal::new(-1234, 2)?),
+            ]))),
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        let q_str = "put str(k) tup(float(2) bool(true))";
+        let exp_q_obj = Operation::from(Statement::Put(
+            PrimaryKey(Datum::Str(String::from("k"))),
+            Some(Value(Datum::Tuple(vec![
+                Datum::F64(2.0),
+                Datum::Bool(true),
+            ]))),
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        assert!(parse("put str(k) bool(yes)").is_err());
+        assert!(parse("put str(k) int(1.5)").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn put_map() -> Result<()> {
+        let q_str =
+            "put str(k) map( user: map( zip: str(12345) ) age: int(30) tags: tup( str(a) ) )";
+        let exp_q_obj = Operation::from(Statement::Put(
+            PrimaryKey(Datum::Str(String::from("k"))),
+            Some(Value(Datum::Map(BTreeMap::from([
+                (String::from("age"), Datum::I64(30)),
+                (
+                    String::from("tags"),
+                    Datum::Tuple(vec![Datum::Str(String::from("a"))]),
+                ),
+                (
+                    String::from("user"),
+                    Datum::Map(BTreeMap::from([(
+                        String::from("zip"),
+                        Datum::Str(String::from("12345")),
+                    )])),
+                ),
+            ])))),
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        let q_str = "put str(k) map()";
+        let exp_q_obj = Operation::from(Statement::Put(
+            PrimaryKey(Datum::Str(String::from("k"))),
+            Some(Value(Datum::Map(BTreeMap::new()))),
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        assert!(parse("put str(k) map( a: int(1) a: int(2) )").is_err());
+        assert!(parse("put str(k) map( a int(1) )").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn put_list() -> Result<()> {
+        let q_str = "put str(k) map( tags: list( str(b) str(a) ) empty: list() )";
+        let exp_q_obj = Operation::from(Statement::Put(
+            PrimaryKey(Datum::Str(String::from("k"))),
+            Some(Value(Datum::Map(BTreeMap::from([
+                (String::from("empty"), Datum::List(vec![])),
+                (
+                    String::from("tags"),
+                    Datum::List(vec![
+                        Datum::Str(String::from("b")),
+                        Datum::Str(String::from("a")),
+                    ]),
+                ),
+            ])))),
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        Ok(())
+    }
+
     #[test]
     fn del() -> Result<()> {
         let q_str = "del int(123)";
@@ -439,24 +1000,24 @@ mod test {
     fn get_where() -> Result<()> {
         let q_str = "get where svspec(int) _";
         let exp_q_obj = Operation::from(Statement::GetSV(
-            SubValueSpec::whole(DatumType::I64),
+            ScndIdxSpec::from(SubValueSpec::whole(DatumType::I64)),
             SearchRange::all(),
         ));
         assert_eq!(parse(q_str)?, exp_q_obj);
 
         let q_str = "get where svspec(int) int(123)";
         let exp_q_obj = Operation::from(Statement::GetSV(
-            SubValueSpec::whole(DatumType::I64),
+            ScndIdxSpec::from(SubValueSpec::whole(DatumType::I64)),
             SearchRange::One(SubValue(Datum::I64(123))),
         ));
         assert_eq!(parse(q_str)?, exp_q_obj);
 
         let q_str = "get where svspec(1 0 str) str(subval_a)";
         let exp_q_obj = Operation::from(Statement::GetSV(
-            SubValueSpec {
-                member_idxs: vec![1, 0],
+            ScndIdxSpec::from(SubValueSpec {
+                member_path: vec![MemberKey::Idx(1), MemberKey::Idx(0)],
                 datum_type: DatumType::Str,
-            },
+            }),
             SearchRange::One(SubValue(Datum::Str(String::from("subval_a")))),
         ));
         assert_eq!(parse(q_str)?, exp_q_obj);
@@ -464,11 +1025,39 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn get_where_between_scalars() -> Result<()> {
+        let q_str = "get where svspec(0 dec) between dec(0.5) dec(10)";
+        let exp_q_obj = Operation::from(Statement::GetSV(
+            ScndIdxSpec::from(SubValueSpec {
+                member_path: vec![MemberKey::Idx(0)],
+                datum_type: DatumType::Decimal,
+            }),
+            SearchRange::Range {
+                lo: Some(SubValue(Datum::Decimal(Decimal::new(5, 1)?))),
+                hi: Some(SubValue(Datum::Decimal(Decimal::new(10, 0)?))),
+            },
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        let q_str = "get where svspec(ts) between ts(-100) _";
+        let exp_q_obj = Operation::from(Statement::GetSV(
+            ScndIdxSpec::from(SubValueSpec::whole(DatumType::Timestamp)),
+            SearchRange::Range {
+                lo: Some(SubValue(Datum::Timestamp(-100))),
+                hi: None,
+            },
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        Ok(())
+    }
+
     #[test]
     fn get_where_between() -> Result<()> {
         let q_str = "get where svspec(int) between int(123) int(234)";
         let exp_q_obj = Operation::from(Statement::GetSV(
-            SubValueSpec::whole(DatumType::I64),
+            ScndIdxSpec::from(SubValueSpec::whole(DatumType::I64)),
             SearchRange::Range {
                 lo: Some(SubValue(Datum::I64(123))),
                 hi: Some(SubValue(Datum::I64(234))),
@@ -478,7 +1067,7 @@ mod test {
 
         let q_str = "get where svspec(int) between int(123) _";
         let exp_q_obj = Operation::from(Statement::GetSV(
-            SubValueSpec::whole(DatumType::I64),
+            ScndIdxSpec::from(SubValueSpec::whole(DatumType::I64)),
             SearchRange::Range {
                 lo: Some(SubValue(Datum::I64(123))),
                 hi: None,
@@ -488,7 +1077,7 @@ mod test {
 
         let q_str = "get where svspec(int) between _ int(234)";
         let exp_q_obj = Operation::from(Statement::GetSV(
-            SubValueSpec::whole(DatumType::I64),
+            ScndIdxSpec::from(SubValueSpec::whole(DatumType::I64)),
             SearchRange::Range {
                 lo: None,
                 hi: Some(SubValue(Datum::I64(234))),
@@ -498,7 +1087,7 @@ mod test {
 
         let q_str = "get where svspec(int) between _ _";
         let exp_q_obj = Operation::from(Statement::GetSV(
-            SubValueSpec::whole(DatumType::I64),
+            ScndIdxSpec::from(SubValueSpec::whole(DatumType::I64)),
             SearchRange::all(),
         ));
         assert_eq!(parse(q_str)?, exp_q_obj);
@@ -509,46 +1098,329 @@ mod test {
     #[test]
     fn create_scnd_idx() -> Result<()> {
         let q_str = "create index svspec(int)";
-        let exp_q_obj = Operation::CreateScndIdx(SubValueSpec::whole(DatumType::I64));
+        let exp_q_obj =
+            Operation::CreateScndIdx(ScndIdxSpec::from(SubValueSpec::whole(DatumType::I64)));
         assert_eq!(parse(q_str)?, exp_q_obj);
 
         let q_str = "create index svspec(2 int)";
-        let exp_q_obj = Operation::CreateScndIdx(SubValueSpec {
-            member_idxs: vec![2],
+        let exp_q_obj = Operation::CreateScndIdx(ScndIdxSpec::from(SubValueSpec {
+            member_path: vec![MemberKey::Idx(2)],
             datum_type: DatumType::I64,
-        });
+        }));
         assert_eq!(parse(q_str)?, exp_q_obj);
 
         let q_str = "create index svspec(1 0 str)";
-        let exp_q_obj = Operation::CreateScndIdx(SubValueSpec {
-            member_idxs: vec![1, 0],
+        let exp_q_obj = Operation::CreateScndIdx(ScndIdxSpec::from(SubValueSpec {
+            member_path: vec![MemberKey::Idx(1), MemberKey::Idx(0)],
             datum_type: DatumType::Str,
-        });
+        }));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        for (token, datum_type) in [
+            ("float", DatumType::F64),
+            ("bool", DatumType::Bool),
+            ("null", DatumType::Null),
+            ("ts", DatumType::Timestamp),
+            ("dec", DatumType::Decimal),
+            ("tup", DatumType::Tuple),
+            ("map", DatumType::Map),
+            ("list", DatumType::List),
+        ] {
+            let q_str = format!("create index svspec(3 {token})");
+            let exp_q_obj = Operation::CreateScndIdx(ScndIdxSpec::from(SubValueSpec {
+                member_path: vec![MemberKey::Idx(3)],
+                datum_type,
+            }));
+            assert_eq!(parse(&q_str)?, exp_q_obj);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn create_scnd_idx_member_names() -> Result<()> {
+        let q_str = "create index svspec(user.address.zip str)";
+        let exp_q_obj = Operation::CreateScndIdx(ScndIdxSpec::from(SubValueSpec {
+            member_path: vec![
+                MemberKey::from("user"),
+                MemberKey::from("address"),
+                MemberKey::from("zip"),
+            ],
+            datum_type: DatumType::Str,
+        }));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        let q_str = "create index svspec(tags.0.1 user 2 str map)";
+        let exp_q_obj = Operation::CreateScndIdx(ScndIdxSpec::from(SubValueSpec {
+            member_path: vec![
+                MemberKey::from("tags"),
+                MemberKey::Idx(0),
+                MemberKey::Idx(1),
+                MemberKey::from("user"),
+                MemberKey::Idx(2),
+                MemberKey::from("str"),
+            ],
+            datum_type: DatumType::Map,
+        }));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        let q_str = "create index svspec(docs.*.tags * str)";
+        let exp_q_obj = Operation::CreateScndIdx(ScndIdxSpec::from(SubValueSpec {
+            member_path: vec![
+                MemberKey::from("docs"),
+                MemberKey::Each,
+                MemberKey::from("tags"),
+                MemberKey::Each,
+            ],
+            datum_type: DatumType::Str,
+        }));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        assert!(parse("create index svspec(user.zip)").is_err());
+        assert!(parse("create index svspec(user.-1 str)").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn scnd_idx_composite() -> Result<()> {
+        let spec = || ScndIdxSpec {
+            columns: vec![
+                SubValueSpec {
+                    member_path: vec![MemberKey::from("tenant")],
+                    datum_type: DatumType::Str,
+                },
+                SubValueSpec {
+                    member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+                    datum_type: DatumType::Str,
+                },
+                SubValueSpec::whole(DatumType::Map),
+            ],
+            projection: ScndIdxProjection::Whole,
+            filter: None,
+            is_unique: false,
+        };
+
+        let q_str = "create index svspec(tenant str, tags.* str, map)";
+        assert_eq!(parse(q_str)?, Operation::CreateScndIdx(spec()));
+
+        let q_str = "get where svspec(tenant str, tags.* str, map) tup( str(t1) )";
+        let exp_q_obj = Operation::from(Statement::GetSV(
+            spec(),
+            SearchRange::One(SubValue(Datum::Tuple(vec![Datum::Str(String::from("t1"))]))),
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        assert!(parse("create index svspec(tenant str,)").is_err());
+        assert!(parse("create index svspec(, tenant str)").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn scnd_idx_projection() -> Result<()> {
+        let spec = |projection| ScndIdxSpec {
+            columns: vec![SubValueSpec {
+                member_path: vec![MemberKey::from("age")],
+                datum_type: DatumType::I64,
+            }],
+            projection,
+            filter: None,
+            is_unique: false,
+        };
+        let proj_name_tags = || {
+            ScndIdxProjection::Columns(vec![
+                SubValueSpec {
+                    member_path: vec![MemberKey::from("name")],
+                    datum_type: DatumType::Str,
+                },
+                SubValueSpec {
+                    member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+                    datum_type: DatumType::Str,
+                },
+            ])
+        };
+
+        let q_str = "create index svspec(age int) keys";
+        assert_eq!(
+            parse(q_str)?,
+            Operation::CreateScndIdx(spec(ScndIdxProjection::KeysOnly))
+        );
+
+        let q_str = "delete index svspec(age int) project(name str, tags.* str)";
+        assert_eq!(parse(q_str)?, Operation::DelScndIdx(spec(proj_name_tags())));
+
+        let q_str = "get where svspec(age int) keys int(30)";
+        let exp_q_obj = Operation::from(Statement::GetSV(
+            spec(ScndIdxProjection::KeysOnly),
+            SearchRange::One(SubValue(Datum::I64(30))),
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        let q_str =
+            "get projected where svspec(age int) project(name str, tags.* str) between int(20) _";
+        let exp_q_obj = Operation::from(Statement::GetSVProjected(
+            spec(proj_name_tags()),
+            SearchRange::Range {
+                lo: Some(SubValue(Datum::I64(20))),
+                hi: None,
+            },
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        let q_str = "get projected where svspec(age int) _";
+        let exp_q_obj = Operation::from(Statement::GetSVProjected(
+            spec(ScndIdxProjection::Whole),
+            SearchRange::all(),
+        ));
         assert_eq!(parse(q_str)?, exp_q_obj);
 
+        assert!(parse("get projected svspec(age int) _").is_err());
+        assert!(parse("create index svspec(age int) project()").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn scnd_idx_filter() -> Result<()> {
+        let spec = |test, projection| ScndIdxSpec {
+            columns: vec![SubValueSpec {
+                member_path: vec![MemberKey::from("age")],
+                datum_type: DatumType::I64,
+            }],
+            projection,
+            filter: Some(ValuePredicate {
+                member_path: vec![MemberKey::from("status"), MemberKey::Each],
+                test,
+            }),
+            is_unique: false,
+        };
+        let active = || PredicateTest::Eq(Datum::Str(String::from("active")));
+
+        let q_str = "create index svspec(age int) filter(status.* eq str(active))";
+        assert_eq!(
+            parse(q_str)?,
+            Operation::CreateScndIdx(spec(active(), ScndIdxProjection::Whole))
+        );
+
+        let q_str = "delete index svspec(age int) filter(status * between tup( int(1) ) _) keys";
+        let test = PredicateTest::Range {
+            lo: Some(Datum::Tuple(vec![Datum::I64(1)])),
+            hi: None,
+        };
+        assert_eq!(
+            parse(q_str)?,
+            Operation::DelScndIdx(spec(test, ScndIdxProjection::KeysOnly))
+        );
+
+        let q_str = "get where svspec(age int) filter(status.* is dec) int(30)";
+        let exp_q_obj = Operation::from(Statement::GetSV(
+            spec(
+                PredicateTest::Type(DatumType::Decimal),
+                ScndIdxProjection::Whole,
+            ),
+            SearchRange::One(SubValue(Datum::I64(30))),
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        assert!(parse("create index svspec(age int) filter(status)").is_err());
+        assert!(parse("create index svspec(age int) filter(status is i64)").is_err());
+        assert!(parse("create index svspec(age int) filter(status eq int(1) int(2))").is_err());
+        assert!(parse("create index svspec(age int) keys filter(status is int)").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn scnd_idx_unique() -> Result<()> {
+        let spec = |filter| ScndIdxSpec {
+            columns: vec![SubValueSpec {
+                member_path: vec![MemberKey::from("email")],
+                datum_type: DatumType::Str,
+            }],
+            projection: ScndIdxProjection::Whole,
+            filter,
+            is_unique: true,
+        };
+
+        let q_str = "create index svspec(email str) unique";
+        assert_eq!(parse(q_str)?, Operation::CreateScndIdx(spec(None)));
+
+        let q_str = "get where svspec(email str) unique filter(status is str) str(a)";
+        let exp_q_obj = Operation::from(Statement::GetSV(
+            spec(Some(ValuePredicate {
+                member_path: vec![MemberKey::from("status")],
+                test: PredicateTest::Type(DatumType::Str),
+            })),
+            SearchRange::One(SubValue(Datum::Str(String::from("a")))),
+        ));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        assert!(parse("create index svspec(email str) filter(status is str) unique").is_err());
+        assert!(parse("create index svspec(email str) unique unique").is_err());
+
         Ok(())
     }
 
     #[test]
     fn delete_scnd_idx() -> Result<()> {
         let q_str = "delete index svspec(int)";
-        let exp_q_obj = Operation::DelScndIdx(SubValueSpec::whole(DatumType::I64));
+        let exp_q_obj =
+            Operation::DelScndIdx(ScndIdxSpec::from(SubValueSpec::whole(DatumType::I64)));
         assert_eq!(parse(q_str)?, exp_q_obj);
 
         let q_str = "delete index svspec(2 int)";
-        let exp_q_obj = Operation::DelScndIdx(SubValueSpec {
-            member_idxs: vec![2],
+        let exp_q_obj = Operation::DelScndIdx(ScndIdxSpec::from(SubValueSpec {
+            member_path: vec![MemberKey::Idx(2)],
             datum_type: DatumType::I64,
-        });
+        }));
         assert_eq!(parse(q_str)?, exp_q_obj);
 
         let q_str = "delete index svspec(1 0 str)";
-        let exp_q_obj = Operation::DelScndIdx(SubValueSpec {
-            member_idxs: vec![1, 0],
+        let exp_q_obj = Operation::DelScndIdx(ScndIdxSpec::from(SubValueSpec {
+            member_path: vec![MemberKey::Idx(1), MemberKey::Idx(0)],
             datum_type: DatumType::Str,
+        }));
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        Ok(())
+    }
+
+    #[test]
+    fn constraint() -> Result<()> {
+        let q_str = "create constraint shape(0 int)";
+        let exp_q_obj = Operation::CreateConstraint(ValueConstraint {
+            member_path: vec![MemberKey::Idx(0)],
+            shape: ValueShape::Type(DatumType::I64),
+        });
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        let q_str = "create constraint shape(arity 2)";
+        let exp_q_obj = Operation::CreateConstraint(ValueConstraint {
+            member_path: vec![],
+            shape: ValueShape::MinTupleArity(2),
         });
         assert_eq!(parse(q_str)?, exp_q_obj);
 
+        let q_str = "create constraint shape(arity.1 arity 3)";
+        let exp_q_obj = Operation::CreateConstraint(ValueConstraint {
+            member_path: vec![MemberKey::from("arity"), MemberKey::Idx(1)],
+            shape: ValueShape::MinTupleArity(3),
+        });
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        let q_str = "delete constraint shape(tags.* str)";
+        let exp_q_obj = Operation::DelConstraint(ValueConstraint {
+            member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+            shape: ValueShape::Type(DatumType::Str),
+        });
+        assert_eq!(parse(q_str)?, exp_q_obj);
+
+        assert!(parse("create constraint shape()").is_err());
+        assert!(parse("create constraint shape(0 2)").is_err());
+        assert!(parse("create constraint shape(arity -1)").is_err());
+        assert!(parse("create constraint svspec(0 int)").is_err());
+
         Ok(())
     }
 }
@@ -0,0 +1,852 @@
+//! A JSON counterpart of [`query_basic`](super::query_basic)
+//!
+//! A query is a JSON object whose `op` member names the operation.
+//! Keys, values and sub-values are data encoded per [`pancake_types::json`].
+//!
+//! # Supported queries
+//!
+//! ## By primary key
+//!
+//! - `{"op": "put", "key": 100, "value": {"user": {"zip": "12345"}, "tags": ["a", "b"]}}`
+//! - `{"op": "put", "key": "session1", "value": "token1", "ttl": 3600}` (time-to-live in seconds)
+//! - `{"op": "del", "key": 100}`
+//! - `{"op": "get", "key": 100}`
+//!
+//! ## By range over primary key
+//!
+//! Either boundary, if absent, is unbounded. Boundaries are inclusive.
+//! Note, a `null` boundary is present; it is the null datum.
+//!
+//! - `{"op": "get_between", "lo": 50, "hi": "foobar"}`
+//! - `{"op": "get_between", "lo": 50}`
+//! - `{"op": "get_between"}`
+//!
+//! A range delete requires both boundaries.
+//!
+//! - `{"op": "del_between", "lo": 50, "hi": "foobar"}`
+//!
+//! ## As of an older commit version
+//!
+//! Any get may read the snapshot as of a retained commit version, given by the `as_of` member.
+//! Supported by the SSI engine only.
+//!
+//! - `{"op": "get", "key": 100, "as_of": 12}`
+//! - `{"op": "get_where", "svspec": {"path": [], "type": "int"}, "as_of": 12}`
+//!
+//! ## By sub-portion of value
+//!
+//! A svspec is an object having the `path` and the `type` members.
+//! The `path` is an array of member indexes, member names, and `"*"`s.
+//! The supported `type`s are the same as [`query_basic`](super::query_basic)'s.
+//!
+//! - `{"op": "create_index", "svspec": {"path": ["user", "zip"], "type": "str"}}`
+//! - `{"op": "create_index", "svspec": {"path": [1, 0], "type": "int"}}`
+//! - `{"op": "create_index", "svspec": {"path": ["tags", "*"], "type": "str"}}`
+//! - `{"op": "delete_index", "svspec": {"path": [], "type": "int"}}`
+//!
+//! A composite svspec is an array of svspec objects. Its sub-value is a tuple,
+//! which may hold a prefix of the columns, in order to select all entries having that prefix.
+//!
+//! - `{"op": "create_index", "svspec": [{"path": ["tenant"], "type": "str"}, {"path": ["created_at"], "type": "ts"}]}`
+//! - `{"op": "get_where", "svspec": [{"path": ["tenant"], "type": "str"}, {"path": ["created_at"], "type": "ts"}], "sv": {"$tuple": ["t1"]}}`
+//!
+//! If `sv` is absent, all entries matching the svspec are selected.
+//!
+//! - `{"op": "get_where", "svspec": {"path": ["user", "zip"], "type": "str"}, "sv": "12345"}`
+//! - `{"op": "get_where", "svspec": {"path": ["user", "zip"], "type": "str"}}`
+//! - `{"op": "get_where_between", "svspec": {"path": [1, 0], "type": "int"}, "lo": 60, "hi": 61}`
+//!
+//! An index stores the whole values, unless the `projection` member is either
+//! `"keys"`, for a keys-only index, or an array of svspec objects, for an index that stores those sub-values.
+//! The `projection` member is part of the index's identity, so it must be repeated in every get and delete.
+//! Gets return the whole values, looked up in the primary keyspace as necessary,
+//! unless `"projected": true` requests the values as stored by the index.
+//!
+//! - `{"op": "create_index", "svspec": {"path": ["age"], "type": "int"}, "projection": "keys"}`
+//! - `{"op": "create_index", "svspec": {"path": ["age"], "type": "int"}, "projection": [{"path": ["name"], "type": "str"}]}`
+//! - `{"op": "get_where", "svspec": {"path": ["age"], "type": "int"}, "projection": [{"path": ["name"], "type": "str"}], "sv": 30, "projected": true}`
+//!
+//! An index covers only the values that satisfy the `filter` member, if present.
+//! A filter is an object having the `path` member, as well as exactly one of:
+//! the `eq` member; the `type` member; either or both of the `lo` and `hi` members, which are inclusive.
+//! The `filter` member is part of the index's identity, so it must be repeated in every get and delete.
+//!
+//! - `{"op": "create_index", "svspec": {"path": ["age"], "type": "int"}, "filter": {"path": ["status"], "eq": "active"}}`
+//! - `{"op": "get_where_between", "svspec": {"path": ["age"], "type": "int"}, "filter": {"path": ["score"], "lo": 1}, "lo": 20, "hi": 30}`
+//!
+//! An index is unique if `"unique": true`. See [`query_basic`](super::query_basic).
+//! The `unique` member is part of the index's identity, so it must be repeated in every get and delete.
+//!
+//! - `{"op": "create_index", "svspec": {"path": ["email"], "type": "str"}, "unique": true}`
+//!
+//! ## Constraints on values
+//!
+//! A constraint is an object having the `path` member, as well as either the `type` member
+//! or the `min_tuple_arity` member.
+//!
+//! - `{"op": "create_constraint", "constraint": {"path": [0], "type": "int"}}`
+//! - `{"op": "create_constraint", "constraint": {"path": [], "min_tuple_arity": 2}}`
+//! - `{"op": "delete_constraint", "constraint": {"path": [0], "type": "int"}}`
+//!
+//! # Caveats
+//!
+//! Member names `"*"` are unworkable, as they are indistinguishable from `"*"` in svspec paths.
+
+use crate::oper::{
+    api::{Operation, SearchRange, Statement},
+    query_basic::svspec_datum_type,
+};
+use anyhow::{anyhow, Context, Result};
+use pancake_types::{
+    json::from_json,
+    serde::Datum,
+    types::{
+        MemberKey, PredicateTest, PrimaryKey, ScndIdxProjection, ScndIdxSpec, SubValue,
+        SubValueSpec, Value, ValueConstraint, ValuePredicate, ValueShape,
+    },
+};
+use serde_json::{Map, Value as JsonValue};
+use std::time::Duration;
+
+pub fn parse(q_str: &str) -> Result<Operation> {
+    let json = serde_json::from_str::<JsonValue>(q_str).context("Invalid JSON query")?;
+    let obj = json
+        .as_object()
+        .ok_or(anyhow!("Expected JSON query object but found {json}"))?;
+    root(obj)
+}
+
+fn root(obj: &Map<String, JsonValue>) -> Result<Operation> {
+    let op = obj
+        .get("op")
+        .and_then(|op| op.as_str())
+        .ok_or(anyhow!("Expected op string"))?;
+    match op {
+        "put" => {
+            let key = PrimaryKey(datum(obj, "key")?);
+            let val = Value(datum(obj, "value")?);
+            let opt_ttl = match obj.get("ttl") {
+                None => None,
+                Some(ttl) => {
+                    let ttl_secs = ttl.as_u64().ok_or(anyhow!(
+                        "Expected ttl as a non-negative integer of seconds but found {ttl}"
+                    ))?;
+                    Some(Duration::from_secs(ttl_secs))
+                }
+            };
+            members(obj, &["op", "key", "value", "ttl"])?;
+            let stmt = match opt_ttl {
+                None => Statement::Put(key, Some(val)),
+                Some(ttl) => Statement::PutWithTTL(key, val, ttl),
+            };
+            return Ok(Operation::from(stmt));
+        }
+        "del" => {
+            let key = PrimaryKey(datum(obj, "key")?);
+            members(obj, &["op", "key"])?;
+            return Ok(Operation::from(Statement::Put(key, None)));
+        }
+        "get" => {
+            let key = PrimaryKey(datum(obj, "key")?);
+            let opt_as_of = as_of(obj)?;
+            members(obj, &["op", "key", "as_of"])?;
+            let stmt = Statement::GetPK(SearchRange::One(key));
+            return Ok(Operation::query_as_of(stmt, opt_as_of));
+        }
+        "get_between" => {
+            let lo = opt_datum(obj, "lo")?.map(PrimaryKey);
+            let hi = opt_datum(obj, "hi")?.map(PrimaryKey);
+            let opt_as_of = as_of(obj)?;
+            members(obj, &["op", "lo", "hi", "as_of"])?;
+            let stmt = Statement::GetPK(SearchRange::Range { lo, hi });
+            return Ok(Operation::query_as_of(stmt, opt_as_of));
+        }
+        "del_between" => {
+            let lo = PrimaryKey(datum(obj, "lo")?);
+            let hi = PrimaryKey(datum(obj, "hi")?);
+            members(obj, &["op", "lo", "hi"])?;
+            return Ok(Operation::from(Statement::DeleteRange(lo, hi)));
+        }
+        "get_where" => {
+            let spec = svspec(obj)?;
+            let range = match opt_datum(obj, "sv")? {
+                None => SearchRange::all(),
+                Some(dat) => SearchRange::One(SubValue(dat)),
+            };
+            let is_projected = projected(obj)?;
+            let opt_as_of = as_of(obj)?;
+            members(
+                obj,
+                &[
+                    "op",
+                    "svspec",
+                    "projection",
+                    "filter",
+                    "unique",
+                    "sv",
+                    "projected",
+                    "as_of",
+                ],
+            )?;
+            let stmt = get_sv(spec, range, is_projected);
+            return Ok(Operation::query_as_of(stmt, opt_as_of));
+        }
+        "get_where_between" => {
+            let spec = svspec(obj)?;
+            let lo = opt_datum(obj, "lo")?.map(SubValue);
+            let hi = opt_datum(obj, "hi")?.map(SubValue);
+            let is_projected = projected(obj)?;
+            let opt_as_of = as_of(obj)?;
+            members(
+                obj,
+                &[
+                    "op",
+                    "svspec",
+                    "projection",
+                    "filter",
+                    "unique",
+                    "lo",
+                    "hi",
+                    "projected",
+                    "as_of",
+                ],
+            )?;
+            let stmt = get_sv(spec, SearchRange::Range { lo, hi }, is_projected);
+            return Ok(Operation::query_as_of(stmt, opt_as_of));
+        }
+        "create_index" => {
+            let spec = svspec(obj)?;
+            members(obj, &["op", "svspec", "projection", "filter", "unique"])?;
+            return Ok(Operation::CreateScndIdx(spec));
+        }
+        "delete_index" => {
+            let spec = svspec(obj)?;
+            members(obj, &["op", "svspec", "projection", "filter", "unique"])?;
+            return Ok(Operation::DelScndIdx(spec));
+        }
+        "create_constraint" => {
+            let constraint = constraint(obj)?;
+            members(obj, &["op", "constraint"])?;
+            return Ok(Operation::CreateConstraint(constraint));
+        }
+        "delete_constraint" => {
+            let constraint = constraint(obj)?;
+            members(obj, &["op", "constraint"])?;
+            return Ok(Operation::DelConstraint(constraint));
+        }
+        x => return Err(anyhow!("Expected operation but found {x}")),
+    }
+}
+
+fn datum(obj: &Map<String, JsonValue>, key: &str) -> Result<Datum> {
+    opt_datum(obj, key)?.ok_or(anyhow!("Expected {key} but found none"))
+}
+
+fn opt_datum(obj: &Map<String, JsonValue>, key: &str) -> Result<Option<Datum>> {
+    match obj.get(key) {
+        None => return Ok(None),
+        Some(json) => {
+            let dat = from_json(json).context(format!("Invalid {key}"))?;
+            return Ok(Some(dat));
+        }
+    }
+}
+
+fn get_sv(spec: ScndIdxSpec, range: SearchRange<SubValue>, is_projected: bool) -> Statement {
+    match is_projected {
+        false => Statement::GetSV(spec, range),
+        true => Statement::GetSVProjected(spec, range),
+    }
+}
+
+fn projected(obj: &Map<String, JsonValue>) -> Result<bool> {
+    match obj.get("projected") {
+        None => Ok(false),
+        Some(projected) => projected
+            .as_bool()
+            .ok_or(anyhow!("Expected projected bool but found {projected}")),
+    }
+}
+
+fn as_of(obj: &Map<String, JsonValue>) -> Result<Option<u64>> {
+    match obj.get("as_of") {
+        None => Ok(None),
+        Some(as_of) => {
+            let commit_ver = as_of.as_u64().ok_or(anyhow!(
+                "Expected as_of as a non-negative integer commit version but found {as_of}"
+            ))?;
+            Ok(Some(commit_ver))
+        }
+    }
+}
+
+fn svspec(obj: &Map<String, JsonValue>) -> Result<ScndIdxSpec> {
+    let columns = match obj.get("svspec") {
+        Some(JsonValue::Object(spec)) => vec![svspec_column(spec)?],
+        Some(JsonValue::Array(specs)) => {
+            let columns = svspec_columns(specs, "svspec")?;
+            if columns.is_empty() {
+                return Err(anyhow!("Expected svspec objects but found none"));
+            }
+            columns
+        }
+        _ => return Err(anyhow!("Expected svspec object or array")),
+    };
+
+    let projection = match obj.get("projection") {
+        None => ScndIdxProjection::Whole,
+        Some(JsonValue::String(s)) if s == "whole" => ScndIdxProjection::Whole,
+        Some(JsonValue::String(s)) if s == "keys" => ScndIdxProjection::KeysOnly,
+        Some(JsonValue::Array(specs)) => {
+            ScndIdxProjection::Columns(svspec_columns(specs, "projection")?)
+        }
+        Some(x) => {
+            return Err(anyhow!(
+                "Expected projection \"whole\", \"keys\" or array but found {x}"
+            ))
+        }
+    };
+
+    let filter = match obj.get("filter") {
+        None => None,
+        Some(JsonValue::Object(filter)) => Some(svspec_filter(filter)?),
+        Some(x) => return Err(anyhow!("Expected filter object but found {x}")),
+    };
+
+    let is_unique = match obj.get("unique") {
+        None => false,
+        Some(unique) => unique
+            .as_bool()
+            .ok_or(anyhow!("Expected unique bool but found {unique}"))?,
+    };
+
+    return Ok(ScndIdxSpec {
+        columns,
+        projection,
+        filter,
+        is_unique,
+    });
+}
+
+fn svspec_filter(filter: &Map<String, JsonValue>) -> Result<ValuePredicate> {
+    let eq = opt_datum(filter, "eq")?;
+    let lo = opt_datum(filter, "lo")?;
+    let hi = opt_datum(filter, "hi")?;
+    let test = match (eq, filter.get("type"), lo.is_some() || hi.is_some()) {
+        (Some(operand), None, false) => PredicateTest::Eq(operand),
+        (None, Some(datum_type), false) => {
+            let datum_type = datum_type
+                .as_str()
+                .ok_or(anyhow!("Expected filter type string"))?;
+            let datum_type = svspec_datum_type(datum_type)
+                .ok_or_else(|| anyhow!("Expected filter datum_type but found {datum_type}"))?;
+            PredicateTest::Type(datum_type)
+        }
+        (None, None, true) => PredicateTest::Range { lo, hi },
+        _ => {
+            return Err(anyhow!(
+                "Expected exactly one of filter eq, type, and lo/hi"
+            ))
+        }
+    };
+
+    let member_path = member_path(filter, "filter")?;
+
+    members(filter, &["path", "eq", "type", "lo", "hi"])?;
+    return Ok(ValuePredicate { member_path, test });
+}
+
+fn svspec_columns(specs: &[JsonValue], name: &str) -> Result<Vec<SubValueSpec>> {
+    let mut columns = vec![];
+    for spec in specs {
+        let spec = spec
+            .as_object()
+            .ok_or(anyhow!("Expected {name} svspec object but found {spec}"))?;
+        columns.push(svspec_column(spec)?);
+    }
+    Ok(columns)
+}
+
+fn svspec_column(spec: &Map<String, JsonValue>) -> Result<SubValueSpec> {
+    let datum_type = spec
+        .get("type")
+        .and_then(|dtype| dtype.as_str())
+        .ok_or(anyhow!("Expected svspec type string"))?;
+    let datum_type = svspec_datum_type(datum_type)
+        .ok_or_else(|| anyhow!("Expected svspec datum_type but found {datum_type}"))?;
+
+    let member_path = member_path(spec, "svspec")?;
+
+    members(spec, &["path", "type"])?;
+    return Ok(SubValueSpec {
+        member_path,
+        datum_type,
+    });
+}
+
+fn constraint(obj: &Map<String, JsonValue>) -> Result<ValueConstraint> {
+    let constraint = obj
+        .get("constraint")
+        .and_then(|constraint| constraint.as_object())
+        .ok_or(anyhow!("Expected constraint object"))?;
+
+    let shape = match (constraint.get("type"), constraint.get("min_tuple_arity")) {
+        (Some(datum_type), None) => {
+            let datum_type = datum_type
+                .as_str()
+                .ok_or(anyhow!("Expected constraint type string"))?;
+            let datum_type = svspec_datum_type(datum_type)
+                .ok_or_else(|| anyhow!("Expected constraint datum_type but found {datum_type}"))?;
+            ValueShape::Type(datum_type)
+        }
+        (None, Some(arity)) => {
+            let arity = arity
+                .as_u64()
+                .and_then(|i| u32::try_from(i).ok())
+                .ok_or(anyhow!(
+                    "Expected constraint min_tuple_arity but found {arity}"
+                ))?;
+            ValueShape::MinTupleArity(arity)
+        }
+        _ => {
+            return Err(anyhow!(
+                "Expected exactly one of constraint type and min_tuple_arity"
+            ))
+        }
+    };
+
+    let member_path = member_path(constraint, "constraint")?;
+
+    members(constraint, &["path", "type", "min_tuple_arity"])?;
+    return Ok(ValueConstraint { member_path, shape });
+}
+
+fn member_path(obj: &Map<String, JsonValue>, name: &str) -> Result<Vec<MemberKey>> {
+    let path = match obj.get("path") {
+        None => &[][..],
+        Some(path) => path
+            .as_array()
+            .ok_or(anyhow!("Expected {name} path array but found {path}"))?,
+    };
+    let mut member_path = vec![];
+    for member in path {
+        let member_key = match member {
+            JsonValue::String(s) if s == "*" => MemberKey::Each,
+            JsonValue::String(s) => MemberKey::from(s.as_str()),
+            JsonValue::Number(num) => {
+                let member_idx = num
+                    .as_u64()
+                    .and_then(|i| u32::try_from(i).ok())
+                    .ok_or(anyhow!("Expected {name} member_idx but found {num}"))?;
+                MemberKey::Idx(member_idx)
+            }
+            x => {
+                return Err(anyhow!(
+                    "Expected {name} member_idx or member_name but found {x}"
+                ))
+            }
+        };
+        member_path.push(member_key);
+    }
+    Ok(member_path)
+}
+
+/// Rejects unexpected members, which are likely typos.
+fn members(obj: &Map<String, JsonValue>, expected: &[&str]) -> Result<()> {
+    for key in obj.keys() {
+        if expected.contains(&key.as_str()) == false {
+            return Err(anyhow!("Unexpected member {key}"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pancake_types::serde::DatumType;
+    use std::collections::BTreeMap;
+
+    fn str_dat(s: &str) -> Datum {
+        Datum::Str(String::from(s))
+    }
+
+    #[test]
+    fn put_del_get() -> Result<()> {
+        assert_eq!(
+            parse(r#"{"op": "put", "key": 100, "value": {"a": ["x", {"$tuple": [1.5, null]}]}}"#)?,
+            Operation::from(Statement::Put(
+                PrimaryKey(Datum::I64(100)),
+                Some(Value(Datum::Map(BTreeMap::from([(
+                    String::from("a"),
+                    Datum::List(vec![
+                        str_dat("x"),
+                        Datum::Tuple(vec![Datum::F64(1.5), Datum::Null]),
+                    ]),
+                )])))),
+            )),
+        );
+        assert_eq!(
+            parse(r#"{"op": "put", "key": "k", "value": 1, "ttl": 60}"#)?,
+            Operation::from(Statement::PutWithTTL(
+                PrimaryKey(str_dat("k")),
+                Value(Datum::I64(1)),
+                Duration::from_secs(60),
+            )),
+        );
+        assert_eq!(
+            parse(r#"{"op": "del", "key": "k"}"#)?,
+            Operation::from(Statement::Put(PrimaryKey(str_dat("k")), None)),
+        );
+        assert_eq!(
+            parse(r#"{"op": "get", "key": {"$bytes": "0a"}}"#)?,
+            Operation::from(Statement::GetPK(SearchRange::One(PrimaryKey(
+                Datum::Bytes(vec![10])
+            )))),
+        );
+
+        assert!(parse(r#"{"op": "put", "key": 100}"#).is_err());
+        assert!(parse(r#"{"op": "put", "key": 100, "value": 1, "ttl": -1}"#).is_err());
+        assert!(parse(r#"{"op": "del", "key": 100, "ttl": 60}"#).is_err());
+        assert!(parse(r#"{"op": "get", "key": 100, "value": 1}"#).is_err());
+        assert!(parse(r#"{"op": "get", "key": {"$nope": 1}}"#).is_err());
+        assert!(parse(r#"{"op": "nope"}"#).is_err());
+        assert!(parse(r#"["get", 100]"#).is_err());
+        assert!(parse(r#"{"op": "get", "key": 100"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn get_between() -> Result<()> {
+        assert_eq!(
+            parse(r#"{"op": "get_between", "lo": 50, "hi": null}"#)?,
+            Operation::from(Statement::GetPK(SearchRange::Range {
+                lo: Some(PrimaryKey(Datum::I64(50))),
+                hi: Some(PrimaryKey(Datum::Null)),
+            })),
+        );
+        assert_eq!(
+            parse(r#"{"op": "get_between"}"#)?,
+            Operation::from(Statement::GetPK(SearchRange::all())),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn get_as_of() -> Result<()> {
+        assert_eq!(
+            parse(r#"{"op": "get", "key": 100, "as_of": 12}"#)?,
+            Operation::QueryAsOf(
+                Statement::GetPK(SearchRange::One(PrimaryKey(Datum::I64(100)))),
+                12
+            ),
+        );
+        assert_eq!(
+            parse(r#"{"op": "get_between", "as_of": 12}"#)?,
+            Operation::QueryAsOf(Statement::GetPK(SearchRange::all()), 12),
+        );
+
+        assert!(parse(r#"{"op": "get", "key": 100, "as_of": -1}"#).is_err());
+        assert!(parse(r#"{"op": "get", "key": 100, "as_of": "12"}"#).is_err());
+        assert!(parse(r#"{"op": "put", "key": 100, "value": 1, "as_of": 12}"#).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn del_between() -> Result<()> {
+        assert_eq!(
+            parse(r#"{"op": "del_between", "lo": 50, "hi": "foobar"}"#)?,
+            Operation::from(Statement::DeleteRange(
+                PrimaryKey(Datum::I64(50)),
+                PrimaryKey(str_dat("foobar")),
+            )),
+        );
+
+        assert!(parse(r#"{"op": "del_between", "lo": 50}"#).is_err());
+        assert!(parse(r#"{"op": "del_between", "lo": 50, "hi": 60, "key": 1}"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn scnd_idx() -> Result<()> {
+        let spec = || {
+            ScndIdxSpec::from(SubValueSpec {
+                member_path: vec![MemberKey::from("user"), MemberKey::Idx(1), MemberKey::Each],
+                datum_type: DatumType::Str,
+            })
+        };
+        let spec_json = r#"{"path": ["user", 1, "*"], "type": "str"}"#;
+
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "create_index", "svspec": {spec_json}}}"#
+            ))?,
+            Operation::CreateScndIdx(spec()),
+        );
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "delete_index", "svspec": {spec_json}}}"#
+            ))?,
+            Operation::DelScndIdx(spec()),
+        );
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "get_where", "svspec": {spec_json}, "sv": "a"}}"#
+            ))?,
+            Operation::from(Statement::GetSV(
+                spec(),
+                SearchRange::One(SubValue(str_dat("a")))
+            )),
+        );
+        assert_eq!(
+            parse(&format!(r#"{{"op": "get_where", "svspec": {spec_json}}}"#))?,
+            Operation::from(Statement::GetSV(spec(), SearchRange::all())),
+        );
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "get_where_between", "svspec": {spec_json}, "hi": "b"}}"#
+            ))?,
+            Operation::from(Statement::GetSV(
+                spec(),
+                SearchRange::Range {
+                    lo: None,
+                    hi: Some(SubValue(str_dat("b"))),
+                }
+            )),
+        );
+
+        assert_eq!(
+            parse(r#"{"op": "create_index", "svspec": {"type": "int"}}"#)?,
+            Operation::CreateScndIdx(ScndIdxSpec::from(SubValueSpec::whole(DatumType::I64))),
+        );
+        assert!(
+            parse(r#"{"op": "create_index", "svspec": {"path": [-1], "type": "int"}}"#).is_err()
+        );
+        assert!(parse(r#"{"op": "create_index", "svspec": {"path": [], "type": "i64"}}"#).is_err());
+        assert!(
+            parse(r#"{"op": "create_index", "svspec": {"path": [true], "type": "int"}}"#).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn scnd_idx_projection() -> Result<()> {
+        let spec = |projection| ScndIdxSpec {
+            columns: vec![SubValueSpec {
+                member_path: vec![MemberKey::from("age")],
+                datum_type: DatumType::I64,
+            }],
+            projection,
+            filter: None,
+            is_unique: false,
+        };
+        let spec_json = r#"{"path": ["age"], "type": "int"}"#;
+        let proj_name = || {
+            ScndIdxProjection::Columns(vec![SubValueSpec {
+                member_path: vec![MemberKey::from("name")],
+                datum_type: DatumType::Str,
+            }])
+        };
+        let proj_name_json = r#"[{"path": ["name"], "type": "str"}]"#;
+
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "create_index", "svspec": {spec_json}, "projection": "keys"}}"#
+            ))?,
+            Operation::CreateScndIdx(spec(ScndIdxProjection::KeysOnly)),
+        );
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "delete_index", "svspec": {spec_json}, "projection": "whole"}}"#
+            ))?,
+            Operation::DelScndIdx(spec(ScndIdxProjection::Whole)),
+        );
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "get_where", "svspec": {spec_json}, "projection": {proj_name_json}, "sv": 30}}"#
+            ))?,
+            Operation::from(Statement::GetSV(
+                spec(proj_name()),
+                SearchRange::One(SubValue(Datum::I64(30)))
+            )),
+        );
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "get_where_between", "svspec": {spec_json}, "projection": {proj_name_json}, "lo": 30, "projected": true}}"#
+            ))?,
+            Operation::from(Statement::GetSVProjected(
+                spec(proj_name()),
+                SearchRange::Range {
+                    lo: Some(SubValue(Datum::I64(30))),
+                    hi: None,
+                }
+            )),
+        );
+
+        assert!(parse(&format!(
+            r#"{{"op": "create_index", "svspec": {spec_json}, "projection": "none"}}"#
+        ))
+        .is_err());
+        assert!(parse(&format!(
+            r#"{{"op": "get_where", "svspec": {spec_json}, "projected": 1}}"#
+        ))
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn scnd_idx_filter() -> Result<()> {
+        let spec = |test| ScndIdxSpec {
+            columns: vec![SubValueSpec {
+                member_path: vec![MemberKey::from("age")],
+                datum_type: DatumType::I64,
+            }],
+            projection: ScndIdxProjection::Whole,
+            filter: Some(ValuePredicate {
+                member_path: vec![MemberKey::from("status")],
+                test,
+            }),
+            is_unique: false,
+        };
+        let spec_json = r#"{"path": ["age"], "type": "int"}"#;
+
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "create_index", "svspec": {spec_json}, "filter": {{"path": ["status"], "eq": "active"}}}}"#
+            ))?,
+            Operation::CreateScndIdx(spec(PredicateTest::Eq(str_dat("active")))),
+        );
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "delete_index", "svspec": {spec_json}, "filter": {{"path": ["status"], "type": "int"}}}}"#
+            ))?,
+            Operation::DelScndIdx(spec(PredicateTest::Type(DatumType::I64))),
+        );
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "get_where", "svspec": {spec_json}, "filter": {{"path": ["status"], "hi": 5}}}}"#
+            ))?,
+            Operation::from(Statement::GetSV(
+                spec(PredicateTest::Range {
+                    lo: None,
+                    hi: Some(Datum::I64(5)),
+                }),
+                SearchRange::all()
+            )),
+        );
+
+        for filter_json in [
+            r#"{"path": ["status"]}"#,
+            r#"{"path": ["status"], "eq": 1, "lo": 1}"#,
+            r#"{"path": ["status"], "type": "int", "eq": 1}"#,
+            r#"{"path": ["status"], "eq": 1, "ne": 1}"#,
+            r#""status""#,
+        ] {
+            assert!(parse(&format!(
+                r#"{{"op": "create_index", "svspec": {spec_json}, "filter": {filter_json}}}"#
+            ))
+            .is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn scnd_idx_unique() -> Result<()> {
+        let spec = ScndIdxSpec {
+            is_unique: true,
+            ..ScndIdxSpec::from(SubValueSpec {
+                member_path: vec![MemberKey::from("email")],
+                datum_type: DatumType::Str,
+            })
+        };
+        let spec_json = r#"{"path": ["email"], "type": "str"}"#;
+
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "create_index", "svspec": {spec_json}, "unique": true}}"#
+            ))?,
+            Operation::CreateScndIdx(spec),
+        );
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "delete_index", "svspec": {spec_json}, "unique": false}}"#
+            ))?,
+            Operation::DelScndIdx(ScndIdxSpec::from(SubValueSpec {
+                member_path: vec![MemberKey::from("email")],
+                datum_type: DatumType::Str,
+            })),
+        );
+
+        assert!(parse(&format!(
+            r#"{{"op": "create_index", "svspec": {spec_json}, "unique": "yes"}}"#
+        ))
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn scnd_idx_composite() -> Result<()> {
+        let spec = ScndIdxSpec {
+            columns: vec![
+                SubValueSpec {
+                    member_path: vec![MemberKey::from("tenant")],
+                    datum_type: DatumType::Str,
+                },
+                SubValueSpec {
+                    member_path: vec![MemberKey::from("created_at")],
+                    datum_type: DatumType::Timestamp,
+                },
+            ],
+            projection: ScndIdxProjection::Whole,
+            filter: None,
+            is_unique: false,
+        };
+        let spec_json =
+            r#"[{"path": ["tenant"], "type": "str"}, {"path": ["created_at"], "type": "ts"}]"#;
+
+        assert_eq!(
+            parse(&format!(
+                r#"{{"op": "get_where", "svspec": {spec_json}, "sv": {{"$tuple": ["t1"]}}}}"#
+            ))?,
+            Operation::from(Statement::GetSV(
+                spec,
+                SearchRange::One(SubValue(Datum::Tuple(vec![str_dat("t1")])))
+            )),
+        );
+
+        assert!(parse(r#"{"op": "create_index", "svspec": []}"#).is_err());
+        assert!(parse(r#"{"op": "create_index", "svspec": [{"type": "int"}, 1]}"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn constraint() -> Result<()> {
+        assert_eq!(
+            parse(
+                r#"{"op": "create_constraint", "constraint": {"path": ["tags", "*"], "type": "str"}}"#
+            )?,
+            Operation::CreateConstraint(ValueConstraint {
+                member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+                shape: ValueShape::Type(DatumType::Str),
+            }),
+        );
+        assert_eq!(
+            parse(r#"{"op": "delete_constraint", "constraint": {"min_tuple_arity": 2}}"#)?,
+            Operation::DelConstraint(ValueConstraint {
+                member_path: vec![],
+                shape: ValueShape::MinTupleArity(2),
+            }),
+        );
+
+        assert!(parse(r#"{"op": "create_constraint", "constraint": {"path": []}}"#).is_err());
+        assert!(parse(
+            r#"{"op": "create_constraint", "constraint": {"type": "int", "min_tuple_arity": 2}}"#
+        )
+        .is_err());
+        assert!(
+            parse(r#"{"op": "create_constraint", "constraint": {"min_tuple_arity": -1}}"#).is_err()
+        );
+        Ok(())
+    }
+}
@@ -44,11 +44,28 @@ crud() {
     req 204 POST "${db}/query" -d 'del int(102)'
     req 404 POST "${db}/query" -d 'get int(102)'
 
+    ### Query by primary key, in JSON ###
+
+    local json=(-H 'Content-Type: application/json')
+    req 204 PUT  "${db}/key/myjsonkey" "${json[@]}" -d '{"a": [1.5, {"$bytes": "00ff"}], "b": {"$i64": "9007199254740993"}}'
+    req 200 GET  "${db}/key/myjsonkey" -H 'Accept: application/json'
+    req 204 POST "${db}/query" "${json[@]}" -d '{"op": "put", "key": 103, "value": {"$tuple": ["s103", null]}}'
+    req 200 POST "${db}/query" "${json[@]}" -d '{"op": "get", "key": 103}'
+    req 200 POST "${db}/query" "${json[@]}" -d '{"op": "get_between", "lo": 100, "hi": 103}'
+    req 204 POST "${db}/query" "${json[@]}" -d '{"op": "del", "key": 103}'
+    req 404 POST "${db}/query" "${json[@]}" -d '{"op": "get", "key": 103}'
+
     req 204 POST "${db}/query" -d 'put int(6000) tup( str(s6000) tup( int(60) str(s60) ) int(60) )'
     req 200 POST "${db}/query" -d 'get int(6000)'
     req 204 POST "${db}/query" -d 'put int(6001) tup( str(s6000) tup( int(61) str(s61) ) int(61) )'
     req 200 POST "${db}/query" -d 'get int(6001)'
 
+    req 204 POST "${db}/query" -d 'put int(7000) tup( float(-1.5) bool(true) null ts(1700000000000000) dec(12.34) )'
+    req 200 POST "${db}/query" -d 'get int(7000)'
+
+    req 204 POST "${db}/query" -d 'put int(8000) map( user: map( address: map( zip: str(12345) ) ) tags: tup( str(a) ) )'
+    req 200 POST "${db}/query" -d 'get int(8000)'
+
     ### Query by primary key range ###
 
     req 200 POST "${db}/query" -d 'get between int(6000) str(mykeyz)'
@@ -84,12 +101,121 @@ crud() {
     req 200 POST "${db}/query" -d 'get where svspec(1 0 int) between int(60) int(61)'
     req 200 POST "${db}/query" -d 'get where svspec(1 0 int) _'
 
+    # Get all entries by sub-value specification of other scalar types.
+    req 204 POST "${db}/query" -d 'create index svspec(4 dec)'
+    req 200 POST "${db}/query" -d 'get where svspec(4 dec) between dec(10) dec(20.5)'
+    req 204 POST "${db}/query" -d 'delete index svspec(4 dec)'
+
+    # Get all entries by sub-value specification that names map members.
+    req 204 POST "${db}/query" -d 'create index svspec(user.address.zip str)'
+    req 200 POST "${db}/query" -d 'get where svspec(user.address.zip str) str(12345)'
+    req 204 POST "${db}/query" -d 'delete index svspec(user.address.zip str)'
+    req 204 POST "${db}/query" "${json[@]}" -d '{"op": "create_index", "svspec": {"path": ["user", "address", "zip"], "type": "str"}}'
+    req 200 POST "${db}/query" "${json[@]}" -d '{"op": "get_where", "svspec": {"path": ["user", "address", "zip"], "type": "str"}, "sv": "12345"}'
+    req 204 POST "${db}/query" "${json[@]}" -d '{"op": "delete_index", "svspec": {"path": ["user", "address", "zip"], "type": "str"}}'
+
+    # Get all entries by every element of a list.
+    req 204 POST "${db}/query" -d 'put int(9000) list( str(tag-a) str(tag-b) )'
+    req 204 POST "${db}/query" -d 'create index svspec(* str)'
+    req 200 POST "${db}/query" -d 'get where svspec(* str) str(tag-b)'
+    req 204 POST "${db}/query" -d 'delete index svspec(* str)'
+
+    # Get all entries by a composite sub-value specification, and by a prefix of its columns.
+    req 204 POST "${db}/query" -d 'put int(9200) map( tenant: str(t1) created_at: ts(100) )'
+    req 204 POST "${db}/query" -d 'create index svspec(tenant str, created_at ts)'
+    req 200 POST "${db}/query" -d 'get where svspec(tenant str, created_at ts) tup( str(t1) )'
+    req 200 POST "${db}/query" -d 'get where svspec(tenant str, created_at ts) between tup( str(t1) ts(50) ) tup( str(t1) )'
+    req 204 POST "${db}/query" -d 'delete index svspec(tenant str, created_at ts)'
+
+    # Get all entries by indexes that do not store whole values.
+    req 204 POST "${db}/query" -d 'put int(9300) map( age: int(30) name: str(alice) )'
+    req 204 POST "${db}/query" -d 'create index svspec(age int) keys'
+    req 200 POST "${db}/query" -d 'get where svspec(age int) keys between int(20) int(40)'
+    req 204 POST "${db}/query" -d 'delete index svspec(age int) keys'
+    req 204 POST "${db}/query" "${json[@]}" -d '{"op": "create_index", "svspec": {"path": ["age"], "type": "int"}, "projection": [{"path": ["name"], "type": "str"}]}'
+    req 200 POST "${db}/query" -d 'get projected where svspec(age int) project(name str) int(30)'
+    req 204 POST "${db}/query" "${json[@]}" -d '{"op": "delete_index", "svspec": {"path": ["age"], "type": "int"}, "projection": [{"path": ["name"], "type": "str"}]}'
+
+    # Get all entries by an index that covers only the values satisfying a filter.
+    req 204 POST "${db}/query" -d 'put int(9400) map( age: int(40) status: str(active) )'
+    req 204 POST "${db}/query" -d 'create index svspec(age int) filter(status eq str(active))'
+    req 200 POST "${db}/query" -d 'get where svspec(age int) filter(status eq str(active)) _'
+    req 204 POST "${db}/query" "${json[@]}" -d '{"op": "delete_index", "svspec": {"path": ["age"], "type": "int"}, "filter": {"path": ["status"], "eq": "active"}}'
+
+    # An index that rejects values sharing a key with another primary key.
+    req 204 POST "${db}/query" -d 'put int(9500) map( email: str(a@x) )'
+    req 204 POST "${db}/query" -d 'create index svspec(email str) unique'
+    req 400 POST "${db}/query" -d 'put int(9501) map( email: str(a@x) )'
+    req 204 POST "${db}/query" -d 'put int(9500) map( email: str(a@x) name: str(alice) )'
+    req 204 POST "${db}/query" "${json[@]}" -d '{"op": "delete_index", "svspec": {"path": ["email"], "type": "str"}, "unique": true}'
+
+    # Constraints on values. Existing values violate this one.
+    req 400 POST "${db}/query" -d 'create constraint shape(0 int)'
+    req 204 POST "${db}/query" -d 'put int(9100) str(not-constrained)'
+    req 204 POST "${db}/query" "${json[@]}" -d '{"op": "delete_constraint", "constraint": {"path": [0], "type": "int"}}'
+
     # Delete indexes
     req 204 POST "${db}/query" -d 'delete index svspec(int)'
     req 204 POST "${db}/query" -d 'delete index svspec(0 str)'
     # req 204 POST "${db}/query" -d 'delete index svspec(1 0 int)'   # Don't delete, b/c we want to use it later.
 }
 
+txn_sessions() {
+    local db="$1"
+
+    ### A txn over multiple requests ###
+
+    local txn_id="$(curl --no-progress-meter -X POST "${db}/txn")"
+    req 204 POST "${db}/txn/${txn_id}/query" -d 'put int(200) str(in-txn)'
+    req 200 POST "${db}/txn/${txn_id}/query" -d 'get int(200)'
+    req 404 POST "${db}/query" -d 'get int(200)'
+    req 204 POST "${db}/txn/${txn_id}/commit"
+    req 200 POST "${db}/query" -d 'get int(200)'
+    req 404 POST "${db}/txn/${txn_id}/commit"
+
+    local txn_id="$(curl --no-progress-meter -X POST "${db}/txn")"
+    req 204 POST "${db}/txn/${txn_id}/query" -d 'del int(200)'
+    req 204 POST "${db}/txn/${txn_id}/abort"
+    req 200 POST "${db}/query" -d 'get int(200)'
+
+    local txn_id="$(curl --no-progress-meter -X POST "${db}/txn?read_only=true")"
+    req 200 POST "${db}/txn/${txn_id}/query" -d 'get int(200)'
+    req 500 POST "${db}/txn/${txn_id}/query" -d 'del int(200)'
+    req 204 POST "${db}/txn/${txn_id}/commit"
+}
+
+txn_idle_timeout() {
+    local db="$1"
+    local idle_timeout_secs="$2"
+
+    ### An idle txn is aborted, and releases its holds ###
+
+    local txn_id="$(curl --no-progress-meter -X POST "${db}/txn")"
+    req 204 POST "${db}/txn/${txn_id}/query" -d 'put int(400) str(idle)'
+
+    # Index creation waits for all ongoing txns, i.e. for the idle txn to be aborted.
+    # The txn is aborted after it has been idle for at most 1.5x the timeout.
+    req 204 POST "${db}/query" --max-time "$(( idle_timeout_secs * 2 + 1 ))" -d 'create index svspec(str)'
+
+    req 404 POST "${db}/txn/${txn_id}/query" -d 'get int(400)'
+    req 404 POST "${db}/txn/${txn_id}/commit"
+    req 404 POST "${db}/query" -d 'get int(400)'
+    req 204 POST "${db}/query" -d 'delete index svspec(str)'
+}
+
+as_of() {
+    local db="$1"
+
+    ### Gets as of an older commit version ###
+
+    req 204 POST "${db}/query" -d 'put int(300) str(as-of-0)'
+    local commit_ver="$(curl --no-progress-meter "${db}/commit_vers" | tail -n 1 | tr -d '\r')"
+    req 204 POST "${db}/query" -d 'put int(300) str(as-of-1)'
+    req 200 POST "${db}/query" -d "get int(300) as of ${commit_ver}"
+    req 200 POST "${db}/query" -H 'Content-Type: application/json' -d "{\"op\": \"get\", \"key\": 300, \"as_of\": ${commit_ver}}"
+    req 400 POST "${db}/query" -d 'get int(300) as of 1000000'
+}
+
 assert_existing_data() {
     local db="$1"
 
@@ -108,6 +234,7 @@ launch_server() {
     local root_dir="$1"
     local bind_addr="$2"
     local bin_name="$3"
+    local txn_idle_timeout_secs="${4:-60}"
 
     local ct="$(count_bound_addrs "${bind_addr}")"
     if (( ct != 0 )) ; then
@@ -117,6 +244,8 @@ launch_server() {
 
     PANCAKE_ROOT_DIR="${root_dir}" \
     PANCAKE_BIND_ADDR="${bind_addr}" \
+    PANCAKE_COMMIT_VER_RETENTION_COUNT=8 \
+    PANCAKE_TXN_IDLE_TIMEOUT_SECS="${txn_idle_timeout_secs}" \
         cargo run --package pancake_server --bin "${bin_name}" &
     SERVER_PID="$!"
 
@@ -140,6 +269,11 @@ test_server() {
 
     crud "${bind_addr}"
 
+    if [[ "${bin_name}" == 'pancake_server_ssi' ]] ; then
+        txn_sessions "${bind_addr}"
+        as_of "${bind_addr}"
+    fi
+
     kill "${SERVER_PID}"
 
     ### Launch again. Then, check existing data.
@@ -149,6 +283,16 @@ test_server() {
     assert_existing_data "${bind_addr}"
 
     kill "${SERVER_PID}"
+
+    ### Launch again, with a short txn idle timeout. Then, let a txn idle.
+
+    if [[ "${bin_name}" == 'pancake_server_ssi' ]] ; then
+        launch_server "${root_dir}" "${bind_addr}" "${bin_name}" 1
+
+        txn_idle_timeout "${bind_addr}" 1
+
+        kill "${SERVER_PID}"
+    fi
 }
 
 parent_dir="/tmp/pancake-$(date +'%s.%N')"
@@ -1,23 +1,29 @@
-use crate::{iters::KeyValueReader, serde::ReadResult, types::Deser};
+use crate::{
+    iters::KeyValueReader,
+    serde::ReadResult,
+    types::{Deser, PartialOrdSer},
+};
 use anyhow::Result;
 use std::cmp::Ordering;
 use std::io::{Read, Seek};
 
 /// An iterator that reads a file that stores serialized `K` and `V` alternately, sorted by `K`.
-/// I.e. it works on SSTable files only, not write-ahead log files.
+/// I.e. it works on SSTable data blocks only, not write-ahead log files.
 ///
-/// The iterator deserializes every `K`; and if this `K` is out of the desired range,
-/// it skips deserialization of `V`.
+/// The iterator compares every `K` against the desired range while it is still serialized.
+/// Only if this `K` is in range, are `K` and `V` deserialized. Otherwise `V` is skipped.
+/// The serialized `K` is copied into a buffer that is reused across rows.
 pub struct KeyValueRangeIterator<'q, RS, K, V, Q> {
     r: KeyValueReader<RS, K, V>,
     q_lo: Option<&'q Q>,
     q_hi: Option<&'q Q>,
+    k_buf: Vec<u8>,
     state: State,
 }
 impl<'q, RS, K, V, Q> KeyValueRangeIterator<'q, RS, K, V, Q>
 where
     RS: Read + Seek,
-    K: Deser + PartialOrd<Q>,
+    K: PartialOrdSer<Q>,
     V: Deser,
 {
     pub fn new(r: KeyValueReader<RS, K, V>, q_lo: Option<&'q Q>, q_hi: Option<&'q Q>) -> Self {
@@ -25,69 +31,78 @@ where
             r,
             q_lo,
             q_hi,
+            k_buf: vec![],
             state: State::NotBegun,
         }
     }
 
-    fn cmp_k_vs_q_lo(&self, k: &K) -> Ordering {
+    fn cmp_k_vs_q_lo(&self) -> Result<Ordering> {
         match self.q_lo {
-            None => Ordering::Greater,
-            Some(q_lo) => k.partial_cmp(q_lo).unwrap_or(Ordering::Greater),
+            None => Ok(Ordering::Greater),
+            Some(q_lo) => {
+                let ord = K::partial_cmp_ser(&self.k_buf, q_lo)?;
+                Ok(ord.unwrap_or(Ordering::Greater))
+            }
         }
     }
-    fn cmp_k_vs_q_hi(&self, k: &K) -> Ordering {
+    fn cmp_k_vs_q_hi(&self) -> Result<Ordering> {
         match self.q_hi {
-            None => Ordering::Less,
-            Some(q_hi) => k.partial_cmp(q_hi).unwrap_or(Ordering::Less),
+            None => Ok(Ordering::Less),
+            Some(q_hi) => {
+                let ord = K::partial_cmp_ser(&self.k_buf, q_hi)?;
+                Ok(ord.unwrap_or(Ordering::Less))
+            }
+        }
+    }
+
+    /// @return Whether a `K` was read into `self.k_buf`.
+    fn read_k(&mut self) -> Result<bool> {
+        self.k_buf.clear();
+        match self.r.read_ser_k(&mut self.k_buf)? {
+            ReadResult::EOF => Ok(false),
+            ReadResult::Some(_, ()) => Ok(true),
         }
     }
 
-    fn get_first_k_gte_q_lo(&mut self) -> Result<Option<K>> {
+    /// @return Whether a `K` that is `>= q_lo` was read into `self.k_buf`.
+    fn read_first_k_gte_q_lo(&mut self) -> Result<bool> {
         loop {
-            match self.r.deser_k()? {
-                ReadResult::EOF => return Ok(None),
-                ReadResult::Some(_, k) => {
-                    if self.cmp_k_vs_q_lo(&k).is_lt() {
-                        self.r.skip_v()?;
-                    } else {
-                        return Ok(Some(k));
-                    }
-                }
+            if self.read_k()? == false {
+                return Ok(false);
+            }
+            if self.cmp_k_vs_q_lo()?.is_lt() {
+                self.r.skip_v()?;
+            } else {
+                return Ok(true);
             }
         }
     }
 
+    fn deser_kv(&mut self) -> Result<(K, V)> {
+        let k = K::deser_solo(&self.k_buf)?;
+        let (_, v) = self.r.deser_v()?;
+        Ok((k, v))
+    }
+
     fn get_next_kv(&mut self) -> Result<Option<(K, V)>> {
-        match self.state {
-            State::NotBegun => match self.get_first_k_gte_q_lo()? {
-                Some(k) if self.cmp_k_vs_q_hi(&k).is_le() => {
-                    self.state = State::InRange;
-                    let (_, v) = self.r.deser_v()?;
-                    return Ok(Some((k, v)));
-                }
-                _ => {
-                    self.state = State::Terminated;
-                    return Ok(None);
-                }
-            },
-            State::InRange => match self.r.deser_k()? {
-                ReadResult::Some(_, k) if self.cmp_k_vs_q_hi(&k).is_le() => {
-                    let (_, v) = self.r.deser_v()?;
-                    return Ok(Some((k, v)));
-                }
-                _ => {
-                    self.state = State::Terminated;
-                    return Ok(None);
-                }
-            },
+        let is_in_range = match self.state {
+            State::NotBegun => self.read_first_k_gte_q_lo()? && self.cmp_k_vs_q_hi()?.is_le(),
+            State::InRange => self.read_k()? && self.cmp_k_vs_q_hi()?.is_le(),
             State::Terminated => return Ok(None),
+        };
+        if is_in_range {
+            self.state = State::InRange;
+            return self.deser_kv().map(Some);
+        } else {
+            self.state = State::Terminated;
+            return Ok(None);
         }
     }
 }
 impl<'q, RS, K, V, Q> Iterator for KeyValueRangeIterator<'q, RS, K, V, Q>
 where
     RS: Read + Seek,
-    K: Deser + PartialOrd<Q>,
+    K: PartialOrdSer<Q>,
     V: Deser,
 {
     type Item = Result<(K, V)>;
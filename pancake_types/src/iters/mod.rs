@@ -15,7 +15,7 @@
 //! }
 //! ```
 //!
-//! This file format is applicable to both commit logs and SS tables.
+//! This file format is applicable to both commit logs and the data blocks of SS tables.
 
 mod iter_range;
 mod iters_simple;
@@ -1,7 +1,7 @@
 use crate::{
     iters::{KeyIterator, KeyValueIterator, KeyValueRangeIterator},
     serde::ReadResult,
-    types::Deser,
+    types::{Deser, PartialOrdSer},
 };
 use anyhow::{anyhow, Result};
 use std::any;
@@ -33,6 +33,10 @@ where
     pub fn deser_k(&mut self) -> Result<ReadResult<K>> {
         K::deser(&mut self.r)
     }
+    /// Copies the serialized bytes of the next `K`, appending them to `buf`.
+    pub fn read_ser_k(&mut self, buf: &mut Vec<u8>) -> Result<ReadResult<()>> {
+        K::read_ser(&mut self.r, buf)
+    }
     pub fn skip_k(&mut self) -> Result<ReadResult<()>> {
         K::skip(&mut self.r)
     }
@@ -101,7 +105,7 @@ where
         q_hi: Option<&'q Q>,
     ) -> KeyValueRangeIterator<'q, RS, K, V, Q>
     where
-        K: PartialOrd<Q>,
+        K: PartialOrdSer<Q>,
     {
         KeyValueRangeIterator::new(self, q_lo, q_hi)
     }
@@ -0,0 +1,321 @@
+//! # JSON codec
+//!
+//! Converts between [`Datum`] and JSON, losslessly in both directions.
+//!
+//! JSON natively represents only some [`DatumType`](crate::serde::DatumType)s.
+//! The others are represented as *tagged objects*, i.e. objects having exactly one member,
+//! whose key starts with `$`.
+//!
+//! | `Datum`                          | JSON                                          |
+//! |----------------------------------|-----------------------------------------------|
+//! | `Null`                           | `null`                                        |
+//! | `Bool`                           | `true` or `false`                             |
+//! | `I64`, where `abs <= 2^53`       | integer, e.g. `123`                           |
+//! | `I64`, where `abs > 2^53`        | `{"$i64": "9007199254740993"}`                |
+//! | `F64`, finite                    | number having a fraction, e.g. `1.0`, `1e300` |
+//! | `F64`, non-finite                | `{"$f64": "NaN"}`, `"inf"` or `"-inf"`        |
+//! | `Str`                            | string                                        |
+//! | `Bytes`                          | `{"$bytes": "<lowercase hex>"}`               |
+//! | `Timestamp`                      | `{"$ts": <micros>}`                           |
+//! | `Decimal`                        | `{"$dec": "-12.34"}`                          |
+//! | `Tuple`                          | `{"$tuple": [<members>]}`                     |
+//! | `List`                           | array                                         |
+//! | `Map`                            | object                                        |
+//! | `Map` that looks like a tag      | `{"$map": {<members>}}`                       |
+//!
+//! Integers beyond `2^53` are tagged because many JSON clients parse every number as `f64`.
+//! For the same reason, `$ts` micros beyond `2^53` are encoded as a string.
+//! When decoding, an untagged integer is accepted anywhere in the `i64` range.
+//!
+//! A JSON number is decoded as `F64` iff it has a fraction or an exponent.
+//!
+//! A `Map` that has exactly one member whose key starts with `$` would be mistaken for a tag;
+//! such a `Map` is wrapped in `$map`.
+//! When decoding, an unknown tag is an error.
+
+use crate::serde::{Datum, Decimal};
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Number, Value};
+use std::collections::BTreeMap;
+
+const TAG_I64: &str = "$i64";
+const TAG_F64: &str = "$f64";
+const TAG_BYTES: &str = "$bytes";
+const TAG_TS: &str = "$ts";
+const TAG_DEC: &str = "$dec";
+const TAG_TUPLE: &str = "$tuple";
+const TAG_MAP: &str = "$map";
+
+/// The largest magnitude that every JSON client may represent exactly.
+const MAX_SAFE_INT: i64 = 1 << 53;
+
+pub fn to_json(dat: &Datum) -> Value {
+    match dat {
+        Datum::Null => Value::Null,
+        Datum::Bool(b) => Value::Bool(*b),
+        Datum::I64(i) => match i64_to_json(*i) {
+            num @ Value::Number(_) => num,
+            s => tagged(TAG_I64, s),
+        },
+        Datum::F64(f) => match Number::from_f64(*f) {
+            Some(num) => Value::Number(num),
+            None => {
+                let s = if f.is_nan() {
+                    "NaN"
+                } else if *f > 0.0 {
+                    "inf"
+                } else {
+                    "-inf"
+                };
+                tagged(TAG_F64, Value::from(s))
+            }
+        },
+        Datum::Str(s) => Value::String(s.clone()),
+        Datum::Bytes(bytes) => tagged(TAG_BYTES, Value::String(hex_encode(bytes))),
+        Datum::Timestamp(micros) => tagged(TAG_TS, i64_to_json(*micros)),
+        Datum::Decimal(dec) => tagged(TAG_DEC, Value::String(dec.to_string())),
+        Datum::Tuple(members) => tagged(
+            TAG_TUPLE,
+            Value::Array(members.iter().map(to_json).collect()),
+        ),
+        Datum::List(elems) => Value::Array(elems.iter().map(to_json).collect()),
+        Datum::Map(members) => {
+            let obj = members
+                .iter()
+                .map(|(key, val)| (key.clone(), to_json(val)))
+                .collect::<Map<_, _>>();
+            if as_tag(&obj).is_some() {
+                tagged(TAG_MAP, Value::Object(obj))
+            } else {
+                Value::Object(obj)
+            }
+        }
+    }
+}
+
+pub fn from_json(json: &Value) -> Result<Datum> {
+    match json {
+        Value::Null => Ok(Datum::Null),
+        Value::Bool(b) => Ok(Datum::Bool(*b)),
+        Value::Number(num) => {
+            if let Some(i) = num.as_i64() {
+                Ok(Datum::I64(i))
+            } else if num.is_u64() {
+                Err(anyhow!("JSON integer {num} overflows i64"))
+            } else {
+                let f = num
+                    .as_f64()
+                    .ok_or(anyhow!("Unrepresentable JSON number {num}"))?;
+                Ok(Datum::F64(f))
+            }
+        }
+        Value::String(s) => Ok(Datum::Str(s.clone())),
+        Value::Array(elems) => {
+            let elems = elems.iter().map(from_json).collect::<Result<Vec<_>>>()?;
+            Ok(Datum::List(elems))
+        }
+        Value::Object(obj) => match as_tag(obj) {
+            None => from_json_object(obj),
+            Some((tag, val)) => from_json_tagged(tag, val),
+        },
+    }
+}
+
+fn from_json_tagged(tag: &str, val: &Value) -> Result<Datum> {
+    match tag {
+        TAG_I64 => Ok(Datum::I64(i64_from_json(val)?)),
+        TAG_F64 => match val.as_str() {
+            Some("NaN") => Ok(Datum::F64(f64::NAN)),
+            Some("inf") => Ok(Datum::F64(f64::INFINITY)),
+            Some("-inf") => Ok(Datum::F64(f64::NEG_INFINITY)),
+            _ => Err(anyhow!("Invalid {TAG_F64} {val}")),
+        },
+        TAG_BYTES => {
+            let s = val.as_str().ok_or(anyhow!("Invalid {TAG_BYTES} {val}"))?;
+            Ok(Datum::Bytes(hex_decode(s)?))
+        }
+        TAG_TS => Ok(Datum::Timestamp(i64_from_json(val)?)),
+        TAG_DEC => {
+            let s = val.as_str().ok_or(anyhow!("Invalid {TAG_DEC} {val}"))?;
+            Ok(Datum::Decimal(s.parse::<Decimal>()?))
+        }
+        TAG_TUPLE => {
+            let members = val.as_array().ok_or(anyhow!("Invalid {TAG_TUPLE} {val}"))?;
+            let members = members.iter().map(from_json).collect::<Result<Vec<_>>>()?;
+            Ok(Datum::Tuple(members))
+        }
+        TAG_MAP => {
+            let obj = val.as_object().ok_or(anyhow!("Invalid {TAG_MAP} {val}"))?;
+            from_json_object(obj)
+        }
+        _ => Err(anyhow!("Unknown JSON tag {tag}")),
+    }
+}
+
+fn from_json_object(obj: &Map<String, Value>) -> Result<Datum> {
+    let mut members = BTreeMap::new();
+    for (key, val) in obj.iter() {
+        members.insert(key.clone(), from_json(val)?);
+    }
+    Ok(Datum::Map(members))
+}
+
+/// @return The `(tag, tagged_value)`, if the object is a tagged object.
+fn as_tag(obj: &Map<String, Value>) -> Option<(&str, &Value)> {
+    if obj.len() != 1 {
+        return None;
+    }
+    let (key, val) = obj.iter().next()?;
+    if key.starts_with('$') {
+        return Some((key, val));
+    }
+    return None;
+}
+
+fn tagged(tag: &str, val: Value) -> Value {
+    let mut obj = Map::new();
+    obj.insert(String::from(tag), val);
+    Value::Object(obj)
+}
+
+fn i64_to_json(i: i64) -> Value {
+    if i.unsigned_abs() <= MAX_SAFE_INT as u64 {
+        Value::from(i)
+    } else {
+        Value::String(i.to_string())
+    }
+}
+
+/// Accepts either an integer or a string.
+fn i64_from_json(val: &Value) -> Result<i64> {
+    let opt_i = match val {
+        Value::Number(num) => num.as_i64(),
+        Value::String(s) => s.parse::<i64>().ok(),
+        _ => None,
+    };
+    opt_i.ok_or(anyhow!("Invalid JSON integer {val}"))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hex_decode(s: &str) -> Result<Vec<u8>> {
+    if s.len().is_multiple_of(2) == false || s.is_ascii() == false {
+        return Err(anyhow!("Invalid hex {s}"));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|e| anyhow!("Invalid hex {s}: {e}")))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn verify_roundtrip(dat: Datum, exp_json: Value) -> Result<()> {
+        let act_json = to_json(&dat);
+        assert_eq!(exp_json, act_json);
+
+        let json_str = act_json.to_string();
+        let reparsed = serde_json::from_str::<Value>(&json_str)?;
+        assert_eq!(dat, from_json(&reparsed)?, "{json_str}");
+        Ok(())
+    }
+
+    fn map<const N: usize>(members: [(&str, Datum); N]) -> Datum {
+        let members = members
+            .into_iter()
+            .map(|(key, val)| (String::from(key), val));
+        Datum::Map(BTreeMap::from_iter(members))
+    }
+
+    #[test]
+    fn roundtrip_scalars() -> Result<()> {
+        verify_roundtrip(Datum::Null, json!(null))?;
+        verify_roundtrip(Datum::Bool(true), json!(true))?;
+        verify_roundtrip(Datum::Str(String::from("a\"b")), json!("a\"b"))?;
+
+        verify_roundtrip(Datum::I64(-123), json!(-123))?;
+        verify_roundtrip(Datum::I64(MAX_SAFE_INT), json!(MAX_SAFE_INT))?;
+        verify_roundtrip(
+            Datum::I64(MAX_SAFE_INT + 1),
+            json!({"$i64": "9007199254740993"}),
+        )?;
+        verify_roundtrip(Datum::I64(i64::MIN), json!({"$i64": i64::MIN.to_string()}))?;
+
+        verify_roundtrip(Datum::F64(1.0), json!(1.0))?;
+        verify_roundtrip(Datum::F64(-0.0), json!(-0.0))?;
+        verify_roundtrip(Datum::F64(1.5e300), json!(1.5e300))?;
+        verify_roundtrip(Datum::F64(f64::NAN), json!({"$f64": "NaN"}))?;
+        verify_roundtrip(Datum::F64(f64::NEG_INFINITY), json!({"$f64": "-inf"}))?;
+
+        verify_roundtrip(
+            Datum::Bytes(vec![0, 0xab, 0xff]),
+            json!({"$bytes": "00abff"}),
+        )?;
+        verify_roundtrip(Datum::Bytes(vec![]), json!({"$bytes": ""}))?;
+        verify_roundtrip(Datum::Timestamp(123), json!({"$ts": 123}))?;
+        verify_roundtrip(Datum::Decimal("-12.34".parse()?), json!({"$dec": "-12.34"}))?;
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_nested() -> Result<()> {
+        verify_roundtrip(
+            Datum::Tuple(vec![
+                Datum::I64(1),
+                Datum::List(vec![Datum::Str(String::from("a")), Datum::Null]),
+                Datum::Tuple(vec![]),
+            ]),
+            json!({"$tuple": [1, ["a", null], {"$tuple": []}]}),
+        )?;
+        verify_roundtrip(
+            map([
+                ("x", Datum::F64(0.5)),
+                ("$y", Datum::Bytes(vec![1])),
+                ("z", map([])),
+            ]),
+            json!({"x": 0.5, "$y": {"$bytes": "01"}, "z": {}}),
+        )?;
+        verify_roundtrip(
+            map([("$i64", Datum::Str(String::from("1")))]),
+            json!({"$map": {"$i64": "1"}}),
+        )?;
+        verify_roundtrip(map([("$map", map([]))]), json!({"$map": {"$map": {}}}))?;
+        Ok(())
+    }
+
+    #[test]
+    fn decode_lenient() -> Result<()> {
+        let decode = |s: &str| from_json(&serde_json::from_str::<Value>(s).unwrap());
+        assert_eq!(Datum::I64(i64::MAX), decode(&i64::MAX.to_string())?);
+        assert_eq!(Datum::I64(7), decode(r#"{"$i64": 7}"#)?);
+        assert_eq!(
+            Datum::Timestamp(i64::MAX),
+            decode(&format!(r#"{{"$ts": "{}"}}"#, i64::MAX))?
+        );
+        assert_eq!(Datum::F64(100.0), decode("1e2")?);
+        assert_eq!(
+            Datum::Decimal("1.5".parse()?),
+            decode(r#"{"$dec": "1.50"}"#)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decode_invalid() {
+        let decode = |s: &str| from_json(&serde_json::from_str::<Value>(s).unwrap());
+        assert!(decode(&u64::MAX.to_string()).is_err());
+        assert!(decode(r#"{"$unknown": 1}"#).is_err());
+        assert!(decode(r#"{"$i64": "1.5"}"#).is_err());
+        assert!(decode(r#"{"$f64": 1.5}"#).is_err());
+        assert!(decode(r#"{"$bytes": "abc"}"#).is_err());
+        assert!(decode(r#"{"$bytes": "zz"}"#).is_err());
+        assert!(decode(r#"{"$dec": 1.5}"#).is_err());
+        assert!(decode(r#"{"$tuple": {}}"#).is_err());
+        assert!(decode(r#"[{"$map": []}]"#).is_err());
+    }
+}
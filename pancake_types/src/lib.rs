@@ -1,4 +1,5 @@
 pub mod io_utils;
 pub mod iters;
+pub mod json;
 pub mod serde;
 pub mod types;
@@ -1,5 +1,8 @@
-use crate::serde::DatumType;
+use crate::serde::{DatumType, Decimal};
 use std::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 mod deser;
 mod ser;
@@ -7,13 +10,34 @@ mod serde_test;
 pub use deser::*;
 pub use ser::*;
 
-#[derive(PartialEq, Eq, Debug)]
+/// Data of different [`DatumType`]s are ordered by their [`DatumType`]s.
+/// E.g. every `I64` is less than every `F64`, regardless of their numerical values.
+#[derive(Clone, Debug)]
 pub enum Datum {
     I64(i64),
     Bytes(Vec<u8>),
     Str(String),
     Tuple(Vec<Datum>),
+    /// Ordered and compared for equality by [`f64::total_cmp()`].
+    /// Hence, e.g., `-0.0 < 0.0`, and each NaN equals the bitwise-identical NaN.
+    F64(f64),
+    Bool(bool),
+    Null,
+    /// Microseconds since the unix epoch, in UTC.
+    Timestamp(i64),
+    Decimal(Decimal),
+    /// Members are sorted by key. Maps are ordered by their sorted `(key, val)` members, lexicographically.
+    Map(BTreeMap<String, Datum>),
+    /// Unlike a `Tuple`, whose members are positional fields, a `List` is a variable-length sequence of elements.
+    /// A [`SubValueSpec`](crate::types::SubValueSpec) may select every element of a `List`.
+    List(Vec<Datum>),
 }
+impl PartialEq for Datum {
+    fn eq(&self, other: &Datum) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Datum {}
 impl PartialOrd for Datum {
     fn partial_cmp(&self, other: &Datum) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -26,15 +50,90 @@ impl Ord for Datum {
             (Self::I64(slf), Self::I64(oth)) => slf.cmp(oth),
             (Self::Str(slf), Self::Str(oth)) => slf.cmp(oth),
             (Self::Tuple(slf), Self::Tuple(oth)) => slf.cmp(oth),
+            (Self::F64(slf), Self::F64(oth)) => slf.total_cmp(oth),
+            (Self::Bool(slf), Self::Bool(oth)) => slf.cmp(oth),
+            (Self::Null, Self::Null) => Ordering::Equal,
+            (Self::Timestamp(slf), Self::Timestamp(oth)) => slf.cmp(oth),
+            (Self::Decimal(slf), Self::Decimal(oth)) => slf.cmp(oth),
+            (Self::Map(slf), Self::Map(oth)) => slf.cmp(oth),
+            (Self::List(slf), Self::List(oth)) => slf.cmp(oth),
             _ => DatumType::from(self).cmp(&DatumType::from(other)),
         }
     }
 }
+/// Consistent with [`Eq`].
+impl Hash for Datum {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        DatumType::from(self).hash(state);
+        match self {
+            Self::I64(dat) | Self::Timestamp(dat) => dat.hash(state),
+            Self::Bytes(dat) => dat.hash(state),
+            Self::Str(dat) => dat.hash(state),
+            Self::Tuple(dat) | Self::List(dat) => dat.hash(state),
+            Self::F64(dat) => dat.to_bits().hash(state),
+            Self::Bool(dat) => dat.hash(state),
+            Self::Null => {}
+            Self::Decimal(dat) => dat.hash(state),
+            Self::Map(dat) => dat.hash(state),
+        }
+    }
+}
 
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum OptDatum<T> {
     Tombstone,
     Some(T),
+    /// A datum that is live until the expiry time, in microseconds since the unix epoch, in UTC.
+    /// Once expired, it is equivalent to a `Tombstone`.
+    Expiring(T, i64),
+}
+impl<T> OptDatum<T> {
+    /// @arg `ttl`: The datum expires this long after now.
+    pub fn new_expiring(t: T, ttl: Duration) -> Self {
+        let ttl = i64::try_from(ttl.as_micros()).unwrap_or(i64::MAX);
+        OptDatum::Expiring(t, now_micros().saturating_add(ttl))
+    }
+
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        match self {
+            OptDatum::Expiring(_, expires_at) => *expires_at <= now,
+            OptDatum::Tombstone | OptDatum::Some(_) => false,
+        }
+    }
+
+    /// @return The datum, unless `self` is a `Tombstone` or has expired as of `now`.
+    pub fn as_live(&self, now: i64) -> Option<&T> {
+        match self {
+            OptDatum::Tombstone => None,
+            OptDatum::Some(t) => Some(t),
+            OptDatum::Expiring(t, expires_at) => (*expires_at > now).then_some(t),
+        }
+    }
+
+    /// @return The datum, unless `self` is a `Tombstone` or has expired as of `now`.
+    pub fn into_live(self, now: i64) -> Option<T> {
+        match self {
+            OptDatum::Tombstone => None,
+            OptDatum::Some(t) => Some(t),
+            OptDatum::Expiring(t, expires_at) => (expires_at > now).then_some(t),
+        }
+    }
+
+    /// @return The expiry time, if any.
+    pub fn expires_at(&self) -> Option<i64> {
+        match self {
+            OptDatum::Expiring(_, expires_at) => Some(*expires_at),
+            OptDatum::Tombstone | OptDatum::Some(_) => None,
+        }
+    }
+
+    /// Wraps `t` with the given expiry time, if any.
+    pub fn new_with_expiry(t: T, expires_at: Option<i64>) -> Self {
+        match expires_at {
+            None => OptDatum::Some(t),
+            Some(expires_at) => OptDatum::Expiring(t, expires_at),
+        }
+    }
 }
 impl<T> From<Option<T>> for OptDatum<T> {
     fn from(opt: Option<T>) -> Self {
@@ -44,11 +143,21 @@ impl<T> From<Option<T>> for OptDatum<T> {
         }
     }
 }
+/// The expiry, if any, is disregarded.
 impl<T> From<OptDatum<T>> for Option<T> {
     fn from(optdat: OptDatum<T>) -> Option<T> {
         match optdat {
             OptDatum::Tombstone => None,
             OptDatum::Some(t) => Some(t),
+            OptDatum::Expiring(t, _) => Some(t),
         }
     }
 }
+
+/// @return The current time, in microseconds since the unix epoch, in UTC, i.e. in the unit of [`Datum::Timestamp`].
+pub fn now_micros() -> i64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO);
+    i64::try_from(since_epoch.as_micros()).unwrap_or(i64::MAX)
+}
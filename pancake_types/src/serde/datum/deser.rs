@@ -1,5 +1,9 @@
-use crate::serde::{Datum, DatumBodyLen, DatumType, DatumTypeInt, OptDatum, TupleMembersCount};
+use crate::serde::{
+    Datum, DatumBodyLen, DatumType, DatumTypeInt, Decimal, MapKeyLen, MapMembersCount, OptDatum,
+    TupleMembersCount,
+};
 use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
 use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
 use std::mem;
 
@@ -20,10 +24,9 @@ impl OptDatum<Datum> {
         let dtype = DatumType::try_from(dtype_int)?;
 
         /* datum_body_len */
-        let dbody_len = match dtype {
-            DatumType::Tombstone => 0,
-            DatumType::I64 => mem::size_of::<i64>(),
-            DatumType::Bytes | DatumType::Str | DatumType::Tuple => {
+        let dbody_len = match dtype.fixed_body_len() {
+            Some(fixed_len) => fixed_len,
+            None => {
                 let (delta_r_len, dbody_len) = DatumBodyLen::deser(r).map_err(|e| anyhow!(e))?;
                 r_len += delta_r_len;
                 *dbody_len as usize
@@ -34,6 +37,56 @@ impl OptDatum<Datum> {
         r.seek(SeekFrom::Current(dbody_len as i64))?;
         r_len += dbody_len;
 
+        /* the wrapped datum */
+        if dtype == DatumType::Expiring {
+            match Self::skip(r)? {
+                ReadResult::EOF => return Err(anyhow!("EOF while reading Expiring datum.")),
+                ReadResult::Some(delta_r_len, ()) => r_len += delta_r_len,
+            }
+        }
+
+        Ok(ReadResult::Some(r_len, ()))
+    }
+
+    /// Copies the serialized bytes of one datum, without deserializing them.
+    ///
+    /// @arg `buf`: The bytes are appended to it.
+    pub fn read_ser<R: Read>(r: &mut R, buf: &mut Vec<u8>) -> Result<ReadResult<()>> {
+        /* datum_type */
+        let (mut r_len, dtype_int) = match DatumTypeInt::deser(r) {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(ReadResult::EOF),
+            Err(e) => return Err(anyhow!(e)),
+            Ok((r_len, dtype_int)) => (r_len, dtype_int),
+        };
+        let dtype = DatumType::try_from(dtype_int)?;
+        buf.push(*dtype_int);
+
+        /* datum_body_len */
+        let dbody_len = match dtype.fixed_body_len() {
+            Some(fixed_len) => fixed_len,
+            None => {
+                let (delta_r_len, dbody_len) = DatumBodyLen::deser(r).map_err(|e| anyhow!(e))?;
+                r_len += delta_r_len;
+                buf.extend_from_slice(&dbody_len.to_le_bytes());
+                *dbody_len as usize
+            }
+        };
+
+        /* datum_body */
+        let body_start = buf.len();
+        buf.resize(body_start + dbody_len, 0);
+        r.read_exact(&mut buf[body_start..])
+            .map_err(|e| anyhow!(e))?;
+        r_len += dbody_len;
+
+        /* the wrapped datum */
+        if dtype == DatumType::Expiring {
+            match Self::read_ser(r, buf)? {
+                ReadResult::EOF => return Err(anyhow!("EOF while reading Expiring datum.")),
+                ReadResult::Some(delta_r_len, ()) => r_len += delta_r_len,
+            }
+        }
+
         Ok(ReadResult::Some(r_len, ()))
     }
 
@@ -60,6 +113,42 @@ impl OptDatum<Datum> {
                 let i = i64::from_le_bytes(buf);
                 OptDatum::Some(Datum::I64(i))
             }
+            DatumType::F64 => {
+                let mut buf = [0u8; mem::size_of::<f64>()];
+                r.read_exact(&mut buf).map_err(|e| anyhow!(e))?;
+                r_len += buf.len();
+                let f = f64::from_le_bytes(buf);
+                OptDatum::Some(Datum::F64(f))
+            }
+            DatumType::Bool => {
+                let mut buf = [0u8; mem::size_of::<u8>()];
+                r.read_exact(&mut buf).map_err(|e| anyhow!(e))?;
+                r_len += buf.len();
+                let b = match buf[0] {
+                    0 => false,
+                    1 => true,
+                    x => return Err(anyhow!("Invalid Bool body {x}")),
+                };
+                OptDatum::Some(Datum::Bool(b))
+            }
+            DatumType::Null => OptDatum::Some(Datum::Null),
+            DatumType::Timestamp => {
+                let mut buf = [0u8; mem::size_of::<i64>()];
+                r.read_exact(&mut buf).map_err(|e| anyhow!(e))?;
+                r_len += buf.len();
+                let ts = i64::from_le_bytes(buf);
+                OptDatum::Some(Datum::Timestamp(ts))
+            }
+            DatumType::Decimal => {
+                let mut mantissa_buf = [0u8; mem::size_of::<i128>()];
+                r.read_exact(&mut mantissa_buf).map_err(|e| anyhow!(e))?;
+                r_len += mantissa_buf.len();
+                let mut scale_buf = [0u8; mem::size_of::<u8>()];
+                r.read_exact(&mut scale_buf).map_err(|e| anyhow!(e))?;
+                r_len += scale_buf.len();
+                let dec = Decimal::new(i128::from_le_bytes(mantissa_buf), scale_buf[0])?;
+                OptDatum::Some(Datum::Decimal(dec))
+            }
             DatumType::Bytes => {
                 let body = Self::deser_dynalen_body(r, &mut r_len)?;
                 OptDatum::Some(Datum::Bytes(body))
@@ -75,9 +164,46 @@ impl OptDatum<Datum> {
                     r.seek(SeekFrom::Current(dbody_len_len as i64))?;
                     r_len += dbody_len_len;
                 }
-                let dat = Self::deser_tuple_body(r, &mut r_len)?;
+                let members = Self::deser_tuple_body(r, &mut r_len)?;
+                OptDatum::Some(Datum::Tuple(members))
+            }
+            DatumType::List => {
+                if IS_ROOT {
+                    let dbody_len_len = mem::size_of::<DatumBodyLen>();
+                    r.seek(SeekFrom::Current(dbody_len_len as i64))?;
+                    r_len += dbody_len_len;
+                }
+                let members = Self::deser_tuple_body(r, &mut r_len)?;
+                OptDatum::Some(Datum::List(members))
+            }
+            DatumType::Map => {
+                if IS_ROOT {
+                    let dbody_len_len = mem::size_of::<DatumBodyLen>();
+                    r.seek(SeekFrom::Current(dbody_len_len as i64))?;
+                    r_len += dbody_len_len;
+                }
+                let dat = Self::deser_map_body(r, &mut r_len)?;
                 OptDatum::Some(dat)
             }
+            DatumType::Expiring => {
+                if IS_ROOT == false {
+                    return Err(anyhow!("Expiring datum nested under another datum."));
+                }
+                let mut buf = [0u8; mem::size_of::<i64>()];
+                r.read_exact(&mut buf).map_err(|e| anyhow!(e))?;
+                r_len += buf.len();
+                let expires_at = i64::from_le_bytes(buf);
+                match Self::deser_::<true, _>(r)? {
+                    ReadResult::Some(delta_r_len, OptDatum::Some(dat)) => {
+                        r_len += delta_r_len;
+                        OptDatum::Expiring(dat, expires_at)
+                    }
+                    ReadResult::EOF => return Err(anyhow!("EOF while reading Expiring datum.")),
+                    ReadResult::Some(_, _) => {
+                        return Err(anyhow!("Tombstone or Expiring datum wrapped by Expiring."));
+                    }
+                }
+            }
         };
 
         return Ok(ReadResult::Some(r_len, optdat));
@@ -94,7 +220,8 @@ impl OptDatum<Datum> {
         Ok(buf)
     }
 
-    fn deser_tuple_body<R: Read + Seek>(r: &mut R, r_len: &mut usize) -> Result<Datum> {
+    /// Deserializes the body of either a Tuple or a List.
+    fn deser_tuple_body<R: Read + Seek>(r: &mut R, r_len: &mut usize) -> Result<Vec<Datum>> {
         /* members_count */
         let (delta_r_len, membs_ct) = TupleMembersCount::deser(r).map_err(|e| anyhow!(e))?;
         *r_len += delta_r_len;
@@ -103,12 +230,15 @@ impl OptDatum<Datum> {
         let mut members = Vec::with_capacity(*membs_ct as usize);
         for _ in 0..*membs_ct {
             match Self::deser_::<false, _>(r)? {
-                ReadResult::EOF => return Err(anyhow!("EOF while reading Tuple member.")),
+                ReadResult::EOF => return Err(anyhow!("EOF while reading Tuple/List member.")),
                 ReadResult::Some(delta_r_len, optdat) => {
                     *r_len += delta_r_len;
                     match optdat {
                         OptDatum::Tombstone => {
-                            return Err(anyhow!("Tombstone nested under Tuple."));
+                            return Err(anyhow!("Tombstone nested under Tuple/List."));
+                        }
+                        OptDatum::Expiring(_, _) => {
+                            return Err(anyhow!("Expiring datum nested under Tuple/List."));
                         }
                         OptDatum::Some(dat) => {
                             members.push(dat);
@@ -117,6 +247,49 @@ impl OptDatum<Datum> {
                 }
             }
         }
-        Ok(Datum::Tuple(members))
+        Ok(members)
+    }
+
+    fn deser_map_body<R: Read + Seek>(r: &mut R, r_len: &mut usize) -> Result<Datum> {
+        /* members_count */
+        let (delta_r_len, membs_ct) = MapMembersCount::deser(r).map_err(|e| anyhow!(e))?;
+        *r_len += delta_r_len;
+
+        /* members */
+        let mut members = BTreeMap::new();
+        for _ in 0..*membs_ct {
+            /* key */
+            let (delta_r_len, key_len) = MapKeyLen::deser(r).map_err(|e| anyhow!(e))?;
+            *r_len += delta_r_len;
+            let mut buf = vec![0u8; *key_len as usize];
+            r.read_exact(&mut buf).map_err(|e| anyhow!(e))?;
+            *r_len += buf.len();
+            let key = String::from_utf8(buf)?;
+            if let Some((prev_key, _)) = members.last_key_value() {
+                if prev_key >= &key {
+                    return Err(anyhow!("Map keys are not in strictly ascending order."));
+                }
+            }
+
+            /* val */
+            match Self::deser_::<false, _>(r)? {
+                ReadResult::EOF => return Err(anyhow!("EOF while reading Map member.")),
+                ReadResult::Some(delta_r_len, optdat) => {
+                    *r_len += delta_r_len;
+                    match optdat {
+                        OptDatum::Tombstone => {
+                            return Err(anyhow!("Tombstone nested under Map."));
+                        }
+                        OptDatum::Expiring(_, _) => {
+                            return Err(anyhow!("Expiring datum nested under Map."));
+                        }
+                        OptDatum::Some(dat) => {
+                            members.insert(key, dat);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(Datum::Map(members))
     }
 }
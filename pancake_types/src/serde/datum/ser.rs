@@ -1,4 +1,7 @@
-use crate::serde::{Datum, DatumBodyLen, DatumType, DatumTypeInt, OptDatum, TupleMembersCount};
+use crate::serde::{
+    Datum, DatumBodyLen, DatumType, DatumTypeInt, MapKeyLen, MapMembersCount, OptDatum,
+    TupleMembersCount,
+};
 use anyhow::Result;
 use derive_more::Deref;
 use std::io::Write;
@@ -21,6 +24,7 @@ impl OptDatum<Datum> {
                 Ok(WriteLen(w_len))
             }
             OptDatum::Some(datum) => datum.ser(w),
+            OptDatum::Expiring(datum, expires_at) => datum.ser_expiring(*expires_at, w),
         }
     }
 }
@@ -30,6 +34,15 @@ impl Datum {
         self.ser_::<true>(w)
     }
 
+    /// Serializes `self` wrapped as an [`OptDatum::Expiring`].
+    pub fn ser_expiring(&self, expires_at: i64, w: &mut impl Write) -> Result<WriteLen> {
+        let dtype = DatumTypeInt::from(DatumType::Expiring);
+        let mut w_len = w.write(&dtype.to_le_bytes())?;
+        w_len += w.write(&expires_at.to_le_bytes())?;
+        w_len += self.ser(w)?.0;
+        Ok(WriteLen(w_len))
+    }
+
     fn ser_<const IS_ROOT: bool>(&self, w: &mut impl Write) -> Result<WriteLen> {
         let mut w_len = WriteLen(0);
 
@@ -40,10 +53,15 @@ impl Datum {
 
         /* datum_body_len */
         let dbody_len = match self {
-            Datum::I64(_) => None,
+            Datum::I64(_)
+            | Datum::F64(_)
+            | Datum::Bool(_)
+            | Datum::Null
+            | Datum::Timestamp(_)
+            | Datum::Decimal(_) => None,
             Datum::Bytes(b) => Some(DatumBodyLen::from_dynalen_body(b)?),
             Datum::Str(s) => Some(DatumBodyLen::from_dynalen_body(s.as_bytes())?),
-            Datum::Tuple(_) => {
+            Datum::Tuple(_) | Datum::Map(_) | Datum::List(_) => {
                 if IS_ROOT {
                     Some(self.intra_tuple_datum_len::<true>()?)
                 } else {
@@ -58,9 +76,17 @@ impl Datum {
         /* datum_body */
         match self {
             Datum::I64(i) => w_len.0 += w.write(&i.to_le_bytes())?,
+            Datum::F64(f) => w_len.0 += w.write(&f.to_le_bytes())?,
+            Datum::Bool(b) => w_len.0 += w.write(&[*b as u8])?,
+            Datum::Null => (),
+            Datum::Timestamp(ts) => w_len.0 += w.write(&ts.to_le_bytes())?,
+            Datum::Decimal(dec) => {
+                w_len.0 += w.write(&dec.mantissa().to_le_bytes())?;
+                w_len.0 += w.write(&[dec.scale()])?;
+            }
             Datum::Bytes(b) => w_len.0 += w.write(b)?,
             Datum::Str(s) => w_len.0 += w.write(s.as_bytes())?,
-            Datum::Tuple(members) => {
+            Datum::Tuple(members) | Datum::List(members) => {
                 /* members_count */
                 let membs_ct = TupleMembersCount::from_members(members)?;
                 w_len.0 += w.write(&membs_ct.to_le_bytes())?;
@@ -70,6 +96,19 @@ impl Datum {
                     w_len.0 += member.ser_::<false>(w)?.0;
                 }
             }
+            Datum::Map(members) => {
+                /* members_count */
+                let membs_ct = MapMembersCount::from_members(members)?;
+                w_len.0 += w.write(&membs_ct.to_le_bytes())?;
+
+                /* members, in ascending order of keys */
+                for (key, val) in members {
+                    let key_len = MapKeyLen::from_key(key)?;
+                    w_len.0 += w.write(&key_len.to_le_bytes())?;
+                    w_len.0 += w.write(key.as_bytes())?;
+                    w_len.0 += val.ser_::<false>(w)?.0;
+                }
+            }
         }
 
         Ok(w_len)
@@ -78,29 +117,29 @@ impl Datum {
     fn intra_tuple_datum_len<const IS_ROOT: bool>(&self) -> Result<DatumBodyLen> {
         /* datum_type's len */
         let dtype_len = match self {
-            Datum::I64(_) | Datum::Bytes(_) | Datum::Str(_) => mem::size_of::<DatumTypeInt>(),
-            Datum::Tuple(_) => {
-                if IS_ROOT {
-                    0
-                } else {
-                    mem::size_of::<DatumTypeInt>()
-                }
-            }
+            Datum::Tuple(_) | Datum::Map(_) | Datum::List(_) if IS_ROOT => 0,
+            _ => mem::size_of::<DatumTypeInt>(),
         };
 
         /* datum_body_len's len */
         let dbody_len_len = match self {
-            Datum::I64(_) => 0,
             Datum::Bytes(_) | Datum::Str(_) => mem::size_of::<DatumBodyLen>(),
-            Datum::Tuple(_) => 0,
+            _ => 0,
         };
 
         /* datum_body's len */
         let dbody_len = match self {
             Datum::I64(i) => mem::size_of_val(i),
+            Datum::F64(f) => mem::size_of_val(f),
+            Datum::Bool(_) => mem::size_of::<u8>(),
+            Datum::Null => 0,
+            Datum::Timestamp(ts) => mem::size_of_val(ts),
+            Datum::Decimal(dec) => {
+                mem::size_of_val(&dec.mantissa()) + mem::size_of_val(&dec.scale())
+            }
             Datum::Bytes(b) => b.len(),
             Datum::Str(s) => s.as_bytes().len(),
-            Datum::Tuple(members) => {
+            Datum::Tuple(members) | Datum::List(members) => {
                 /* members_count's len */
                 let mut root_body_len = mem::size_of::<TupleMembersCount>();
 
@@ -110,6 +149,19 @@ impl Datum {
                     root_body_len += *memb_body_len as usize;
                 }
 
+                root_body_len
+            }
+            Datum::Map(members) => {
+                /* members_count's len */
+                let mut root_body_len = mem::size_of::<MapMembersCount>();
+
+                /* members' lens */
+                for (key, val) in members {
+                    root_body_len += mem::size_of::<MapKeyLen>() + key.len();
+                    let val_body_len = val.intra_tuple_datum_len::<false>()?;
+                    root_body_len += *val_body_len as usize;
+                }
+
                 root_body_len
             }
         };
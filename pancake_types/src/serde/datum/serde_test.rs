@@ -1,9 +1,10 @@
 #[cfg(test)]
 mod test {
-    use crate::serde::{Datum, OptDatum, ReadResult};
+    use crate::serde::{Datum, Decimal, OptDatum, ReadResult};
     use anyhow::{anyhow, Result};
     use itertools::Itertools;
     use rand::seq::SliceRandom;
+    use std::collections::BTreeMap;
     use std::io::Cursor;
 
     fn verify(pre_serialized: &Vec<OptDatum<Datum>>) -> Result<()> {
@@ -32,12 +33,12 @@ mod test {
         {
             let mut r = Cursor::new(&serialized);
             let mut r_len = 0;
-            for dat_i in 0..pre_serialized.len() {
+            for exp_w_len in w_len_at_each_dat.iter() {
                 match OptDatum::<Datum>::skip(&mut r)? {
                     ReadResult::EOF => return Err(anyhow!("Premature EOF")),
                     ReadResult::Some(delta_r_len, ()) => r_len += delta_r_len,
                 }
-                assert_eq!(w_len_at_each_dat[dat_i], r_len);
+                assert_eq!(*exp_w_len, r_len);
             }
             assert_eq!(
                 ReadResult::EOF,
@@ -46,12 +47,30 @@ mod test {
             );
         }
 
+        /* Copy the serialized bytes of each Datum. */
+        {
+            let mut r = Cursor::new(&serialized);
+            let mut copied: Vec<u8> = vec![];
+            for exp_w_len in w_len_at_each_dat.iter() {
+                match OptDatum::<Datum>::read_ser(&mut r, &mut copied)? {
+                    ReadResult::EOF => return Err(anyhow!("Premature EOF")),
+                    ReadResult::Some(_, ()) => {}
+                }
+                assert_eq!(*exp_w_len, copied.len());
+            }
+            assert_eq!(
+                ReadResult::EOF,
+                OptDatum::<Datum>::read_ser(&mut r, &mut copied)?,
+            );
+            assert_eq!(serialized, copied);
+        }
+
         /* Deserialize each Datum. */
         {
             let mut r = Cursor::new(&serialized);
             let mut r_len = 0;
             let mut deserialized: Vec<OptDatum<Datum>> = vec![];
-            for dat_i in 0..pre_serialized.len() {
+            for exp_w_len in w_len_at_each_dat.iter() {
                 match OptDatum::<Datum>::deser(&mut r)? {
                     ReadResult::EOF => return Err(anyhow!("Premature EOF")),
                     ReadResult::Some(delta_r_len, optdat) => {
@@ -59,7 +78,7 @@ mod test {
                         deserialized.push(optdat);
                     }
                 }
-                assert_eq!(w_len_at_each_dat[dat_i], r_len);
+                assert_eq!(*exp_w_len, r_len);
             }
             assert_eq!(
                 ReadResult::EOF,
@@ -87,6 +106,21 @@ mod test {
     fn gen_str() -> OptDatum<Datum> {
         OptDatum::Some(Datum::Str(String::from("asdf")))
     }
+    fn gen_f64() -> OptDatum<Datum> {
+        OptDatum::Some(Datum::F64(-1.5))
+    }
+    fn gen_bool() -> OptDatum<Datum> {
+        OptDatum::Some(Datum::Bool(true))
+    }
+    fn gen_null() -> OptDatum<Datum> {
+        OptDatum::Some(Datum::Null)
+    }
+    fn gen_timestamp() -> OptDatum<Datum> {
+        OptDatum::Some(Datum::Timestamp(1_700_000_000_000_000))
+    }
+    fn gen_decimal() -> OptDatum<Datum> {
+        OptDatum::Some(Datum::Decimal(Decimal::new(-12345, 2).unwrap()))
+    }
     fn gen_tup_depth1_memb1() -> OptDatum<Datum> {
         OptDatum::Some(Datum::Tuple(vec![Datum::Str(String::from("asdf"))]))
     }
@@ -104,6 +138,48 @@ mod test {
             Datum::I64(123),
         ]))
     }
+    fn gen_tup_scalars() -> OptDatum<Datum> {
+        OptDatum::Some(Datum::Tuple(vec![
+            Datum::F64(2.25),
+            Datum::Tuple(vec![Datum::Null, Datum::Bool(false)]),
+            Datum::Timestamp(-1),
+            Datum::Decimal(Decimal::new(7, 3).unwrap()),
+        ]))
+    }
+
+    fn gen_map_depth1() -> OptDatum<Datum> {
+        OptDatum::Some(Datum::Map(BTreeMap::from([
+            (String::from("b"), Datum::I64(2)),
+            (String::from("a"), Datum::Str(String::from("asdf"))),
+        ])))
+    }
+    fn gen_map_depth3() -> OptDatum<Datum> {
+        OptDatum::Some(Datum::Map(BTreeMap::from([
+            (String::from(""), Datum::Map(BTreeMap::new())),
+            (
+                String::from("user"),
+                Datum::Tuple(vec![
+                    Datum::I64(1),
+                    Datum::Map(BTreeMap::from([(String::from("zip"), Datum::I64(12345))])),
+                ]),
+            ),
+        ])))
+    }
+
+    fn gen_list() -> OptDatum<Datum> {
+        OptDatum::Some(Datum::List(vec![
+            Datum::Str(String::from("a")),
+            Datum::List(vec![]),
+            Datum::Tuple(vec![Datum::List(vec![Datum::I64(1)])]),
+        ]))
+    }
+
+    fn gen_expiring() -> OptDatum<Datum> {
+        OptDatum::Expiring(
+            Datum::Tuple(vec![Datum::Str(String::from("session")), Datum::I64(7)]),
+            1_700_000_000_000_000,
+        )
+    }
 
     #[test]
     fn ser_then_deser() -> Result<()> {
@@ -114,9 +190,19 @@ mod test {
             gen_i64,
             gen_bytes,
             gen_str,
+            gen_f64,
+            gen_bool,
+            gen_null,
+            gen_timestamp,
+            gen_decimal,
             gen_tup_depth1_memb1,
             gen_tup_depth1_membmult,
             gen_tup_depth3,
+            gen_tup_scalars,
+            gen_map_depth1,
+            gen_map_depth3,
+            gen_list,
+            gen_expiring,
         ];
 
         for mut gen_fns in gen_fns.iter().powerset() {
@@ -130,4 +216,16 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn expiry() {
+        let optdat = gen_expiring();
+        let expires_at = optdat.expires_at().unwrap();
+        assert!(optdat.as_live(expires_at - 1).is_some());
+        assert!(optdat.is_expired_at(expires_at));
+        assert_eq!(None, optdat.clone().into_live(expires_at));
+
+        assert_eq!(None, gen_tomb().into_live(0));
+        assert!(gen_i64().is_expired_at(i64::MAX) == false);
+    }
 }
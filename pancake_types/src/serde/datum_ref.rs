@@ -0,0 +1,569 @@
+use crate::serde::{
+    Datum, DatumBodyLen, DatumType, DatumTypeInt, Decimal, MapKeyLen, MapMembersCount, OptDatum,
+    ReadResult, TupleMembersCount,
+};
+use anyhow::{anyhow, Result};
+use std::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::mem;
+use std::str;
+
+/// [`DatumRef`] is a read-only view of a serialized [`Datum`]. It borrows from the serialized bytes.
+///
+/// Viewing does not allocate. The bytes are validated once, when the view is created;
+/// members of a Tuple, List or Map are then located lazily, by walking the bytes.
+///
+/// A [`DatumRef`] is ordered identically to the [`Datum`] it represents,
+/// and may be compared against a [`Datum`] directly.
+#[derive(Clone, Copy, Debug)]
+pub enum DatumRef<'a> {
+    I64(i64),
+    Bytes(&'a [u8]),
+    Str(&'a str),
+    Tuple(MembersRef<'a>),
+    F64(f64),
+    Bool(bool),
+    Null,
+    Timestamp(i64),
+    Decimal(Decimal),
+    Map(MapMembersRef<'a>),
+    List(MembersRef<'a>),
+}
+
+/// The serialized members of a Tuple or a List.
+#[derive(Clone, Copy, Debug)]
+pub struct MembersRef<'a> {
+    membs_ct: u32,
+    membs_buf: &'a [u8],
+}
+
+/// The serialized members of a Map, sorted by key.
+#[derive(Clone, Copy, Debug)]
+pub struct MapMembersRef<'a> {
+    membs_ct: u32,
+    membs_buf: &'a [u8],
+}
+
+/* Viewing. */
+impl<'a> OptDatum<DatumRef<'a>> {
+    /// Views the datum at the beginning of `buf`. Bytes beyond this datum are ignored.
+    ///
+    /// @return The length of the viewed datum, and the view.
+    pub fn deser(buf: &'a [u8]) -> Result<ReadResult<Self>> {
+        Self::deser_::<true>(buf)
+    }
+
+    fn deser_<const IS_ROOT: bool>(buf: &'a [u8]) -> Result<ReadResult<Self>> {
+        let mut r = buf;
+
+        /* datum_type */
+        let dtype_int = match DatumTypeInt::deser(&mut r) {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(ReadResult::EOF),
+            Err(e) => return Err(anyhow!(e)),
+            Ok((_, dtype_int)) => dtype_int,
+        };
+        let dtype = DatumType::try_from(dtype_int)?;
+
+        /* datum_body_len and datum_body */
+        let optdat = match dtype {
+            DatumType::Tombstone => OptDatum::Tombstone,
+            DatumType::I64 => OptDatum::Some(DatumRef::I64(i64::from_le_bytes(take_arr(&mut r)?))),
+            DatumType::F64 => OptDatum::Some(DatumRef::F64(f64::from_le_bytes(take_arr(&mut r)?))),
+            DatumType::Bool => match take_arr::<1>(&mut r)? {
+                [0] => OptDatum::Some(DatumRef::Bool(false)),
+                [1] => OptDatum::Some(DatumRef::Bool(true)),
+                [x] => return Err(anyhow!("Invalid Bool body {x}")),
+            },
+            DatumType::Null => OptDatum::Some(DatumRef::Null),
+            DatumType::Timestamp => {
+                OptDatum::Some(DatumRef::Timestamp(i64::from_le_bytes(take_arr(&mut r)?)))
+            }
+            DatumType::Decimal => {
+                let mantissa = i128::from_le_bytes(take_arr(&mut r)?);
+                let [scale] = take_arr::<1>(&mut r)?;
+                OptDatum::Some(DatumRef::Decimal(Decimal::new(mantissa, scale)?))
+            }
+            DatumType::Bytes => OptDatum::Some(DatumRef::Bytes(take_dynalen_body(&mut r)?)),
+            DatumType::Str => {
+                let body = take_dynalen_body(&mut r)?;
+                OptDatum::Some(DatumRef::Str(str::from_utf8(body)?))
+            }
+            DatumType::Tuple | DatumType::List => {
+                if IS_ROOT {
+                    take(&mut r, mem::size_of::<DatumBodyLen>())?;
+                }
+                let membs = MembersRef::deser(&mut r)?;
+                if dtype == DatumType::Tuple {
+                    OptDatum::Some(DatumRef::Tuple(membs))
+                } else {
+                    OptDatum::Some(DatumRef::List(membs))
+                }
+            }
+            DatumType::Map => {
+                if IS_ROOT {
+                    take(&mut r, mem::size_of::<DatumBodyLen>())?;
+                }
+                OptDatum::Some(DatumRef::Map(MapMembersRef::deser(&mut r)?))
+            }
+            DatumType::Expiring => {
+                if IS_ROOT == false {
+                    return Err(anyhow!("Expiring datum nested under another datum."));
+                }
+                let expires_at = i64::from_le_bytes(take_arr(&mut r)?);
+                match Self::deser_::<true>(r)? {
+                    ReadResult::Some(inner_len, OptDatum::Some(dat)) => {
+                        r = &r[inner_len..];
+                        OptDatum::Expiring(dat, expires_at)
+                    }
+                    ReadResult::EOF => return Err(anyhow!("EOF while viewing Expiring datum.")),
+                    ReadResult::Some(_, _) => {
+                        return Err(anyhow!("Tombstone or Expiring datum wrapped by Expiring."));
+                    }
+                }
+            }
+        };
+
+        let r_len = buf.len() - r.len();
+        return Ok(ReadResult::Some(r_len, optdat));
+    }
+
+    /// Views a nested member, which has been validated already.
+    fn deser_member(buf: &'a [u8]) -> (usize, DatumRef<'a>) {
+        match Self::deser_::<false>(buf) {
+            Ok(ReadResult::Some(r_len, OptDatum::Some(dat))) => (r_len, dat),
+            _ => panic!("A validated member failed to be viewed."),
+        }
+    }
+}
+
+impl<'a> DatumRef<'a> {
+    /// Views a non-tombstone datum at the beginning of `buf`.
+    pub fn deser_solo(buf: &'a [u8]) -> Result<Self> {
+        match OptDatum::<DatumRef>::deser(buf)? {
+            ReadResult::EOF => Err(anyhow!("No data")),
+            ReadResult::Some(_, OptDatum::Tombstone) => {
+                Err(anyhow!("Tombstone while viewing datum"))
+            }
+            ReadResult::Some(_, OptDatum::Some(dat)) => Ok(dat),
+            ReadResult::Some(_, OptDatum::Expiring(_, _)) => {
+                Err(anyhow!("Expiring datum while viewing datum"))
+            }
+        }
+    }
+
+    /// Allocates the equivalent [`Datum`].
+    pub fn to_datum(&self) -> Datum {
+        match self {
+            Self::I64(i) => Datum::I64(*i),
+            Self::Bytes(b) => Datum::Bytes(b.to_vec()),
+            Self::Str(s) => Datum::Str(String::from(*s)),
+            Self::Tuple(membs) => Datum::Tuple(membs.iter().map(|memb| memb.to_datum()).collect()),
+            Self::F64(f) => Datum::F64(*f),
+            Self::Bool(b) => Datum::Bool(*b),
+            Self::Null => Datum::Null,
+            Self::Timestamp(ts) => Datum::Timestamp(*ts),
+            Self::Decimal(dec) => Datum::Decimal(*dec),
+            Self::Map(membs) => {
+                let membs = membs
+                    .iter()
+                    .map(|(key, val)| (String::from(key), val.to_datum()))
+                    .collect::<BTreeMap<_, _>>();
+                Datum::Map(membs)
+            }
+            Self::List(membs) => Datum::List(membs.iter().map(|memb| memb.to_datum()).collect()),
+        }
+    }
+}
+
+impl<'a> MembersRef<'a> {
+    fn deser(r: &mut &'a [u8]) -> Result<Self> {
+        let (_, membs_ct) = TupleMembersCount::deser(r).map_err(|e| anyhow!(e))?;
+
+        let membs_buf = *r;
+        for _ in 0..*membs_ct {
+            match OptDatum::<DatumRef>::deser_::<false>(r)? {
+                ReadResult::EOF => return Err(anyhow!("EOF while viewing Tuple/List member.")),
+                ReadResult::Some(_, OptDatum::Tombstone) => {
+                    return Err(anyhow!("Tombstone nested under Tuple/List."));
+                }
+                ReadResult::Some(_, OptDatum::Expiring(_, _)) => {
+                    return Err(anyhow!("Expiring datum nested under Tuple/List."));
+                }
+                ReadResult::Some(r_len, OptDatum::Some(_)) => *r = &r[r_len..],
+            }
+        }
+        let membs_buf = &membs_buf[..membs_buf.len() - r.len()];
+
+        Ok(Self {
+            membs_ct: *membs_ct,
+            membs_buf,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.membs_ct as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.membs_ct == 0
+    }
+
+    pub fn iter(&self) -> impl 'a + Iterator<Item = DatumRef<'a>> {
+        let mut remaining_ct = self.membs_ct;
+        let mut r = self.membs_buf;
+        std::iter::from_fn(move || {
+            if remaining_ct == 0 {
+                return None;
+            }
+            remaining_ct -= 1;
+            let (r_len, memb) = OptDatum::<DatumRef>::deser_member(r);
+            r = &r[r_len..];
+            Some(memb)
+        })
+    }
+
+    pub fn get(&self, idx: usize) -> Option<DatumRef<'a>> {
+        self.iter().nth(idx)
+    }
+}
+
+impl<'a> MapMembersRef<'a> {
+    fn deser(r: &mut &'a [u8]) -> Result<Self> {
+        let (_, membs_ct) = MapMembersCount::deser(r).map_err(|e| anyhow!(e))?;
+
+        let membs_buf = *r;
+        let mut prev_key: Option<&str> = None;
+        for _ in 0..*membs_ct {
+            /* key */
+            let key = take_map_key(r)?;
+            if let Some(prev_key) = prev_key {
+                if prev_key >= key {
+                    return Err(anyhow!("Map keys are not in strictly ascending order."));
+                }
+            }
+            prev_key = Some(key);
+
+            /* val */
+            match OptDatum::<DatumRef>::deser_::<false>(r)? {
+                ReadResult::EOF => return Err(anyhow!("EOF while viewing Map member.")),
+                ReadResult::Some(_, OptDatum::Tombstone) => {
+                    return Err(anyhow!("Tombstone nested under Map."));
+                }
+                ReadResult::Some(_, OptDatum::Expiring(_, _)) => {
+                    return Err(anyhow!("Expiring datum nested under Map."));
+                }
+                ReadResult::Some(r_len, OptDatum::Some(_)) => *r = &r[r_len..],
+            }
+        }
+        let membs_buf = &membs_buf[..membs_buf.len() - r.len()];
+
+        Ok(Self {
+            membs_ct: *membs_ct,
+            membs_buf,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.membs_ct as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.membs_ct == 0
+    }
+
+    /// @return An iterator of `(key, val)`, in the ascending order of keys.
+    pub fn iter(&self) -> impl 'a + Iterator<Item = (&'a str, DatumRef<'a>)> {
+        let mut remaining_ct = self.membs_ct;
+        let mut r = self.membs_buf;
+        std::iter::from_fn(move || {
+            if remaining_ct == 0 {
+                return None;
+            }
+            remaining_ct -= 1;
+            let key = take_map_key(&mut r).expect("A validated map key failed to be viewed.");
+            let (r_len, val) = OptDatum::<DatumRef>::deser_member(r);
+            r = &r[r_len..];
+            Some((key, val))
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<DatumRef<'a>> {
+        for (memb_key, memb_val) in self.iter() {
+            match memb_key.cmp(key) {
+                Ordering::Less => continue,
+                Ordering::Equal => return Some(memb_val),
+                Ordering::Greater => return None,
+            }
+        }
+        None
+    }
+}
+
+fn take<'a>(r: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if r.len() < len {
+        return Err(anyhow!("EOF while viewing datum body."));
+    }
+    let (taken, rest) = r.split_at(len);
+    *r = rest;
+    Ok(taken)
+}
+
+fn take_arr<const N: usize>(r: &mut &[u8]) -> Result<[u8; N]> {
+    let taken = take(r, N)?;
+    Ok(<[u8; N]>::try_from(taken)?)
+}
+
+fn take_dynalen_body<'a>(r: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let (_, dbody_len) = DatumBodyLen::deser(r).map_err(|e| anyhow!(e))?;
+    take(r, *dbody_len as usize)
+}
+
+fn take_map_key<'a>(r: &mut &'a [u8]) -> Result<&'a str> {
+    let (_, key_len) = MapKeyLen::deser(r).map_err(|e| anyhow!(e))?;
+    let key = take(r, *key_len as usize)?;
+    Ok(str::from_utf8(key)?)
+}
+
+/* Comparison. */
+
+/// Lexicographically compares two sequences, as `Vec` and `BTreeMap` do.
+fn cmp_seqs<A, B>(
+    mut a_iter: impl Iterator<Item = A>,
+    mut b_iter: impl Iterator<Item = B>,
+    cmp: impl Fn(&A, &B) -> Ordering,
+) -> Ordering {
+    loop {
+        match (a_iter.next(), b_iter.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match cmp(&a, &b) {
+                Ordering::Equal => continue,
+                ord => return ord,
+            },
+        }
+    }
+}
+
+impl<'a> PartialEq for DatumRef<'a> {
+    fn eq(&self, other: &DatumRef<'a>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl<'a> Eq for DatumRef<'a> {}
+impl<'a> PartialOrd for DatumRef<'a> {
+    fn partial_cmp(&self, other: &DatumRef<'a>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<'a> Ord for DatumRef<'a> {
+    fn cmp(&self, other: &DatumRef<'a>) -> Ordering {
+        match (self, other) {
+            (Self::Bytes(slf), Self::Bytes(oth)) => slf.cmp(oth),
+            (Self::I64(slf), Self::I64(oth)) => slf.cmp(oth),
+            (Self::Str(slf), Self::Str(oth)) => slf.cmp(oth),
+            (Self::Tuple(slf), Self::Tuple(oth)) | (Self::List(slf), Self::List(oth)) => {
+                cmp_seqs(slf.iter(), oth.iter(), |a, b| a.cmp(b))
+            }
+            (Self::F64(slf), Self::F64(oth)) => slf.total_cmp(oth),
+            (Self::Bool(slf), Self::Bool(oth)) => slf.cmp(oth),
+            (Self::Null, Self::Null) => Ordering::Equal,
+            (Self::Timestamp(slf), Self::Timestamp(oth)) => slf.cmp(oth),
+            (Self::Decimal(slf), Self::Decimal(oth)) => slf.cmp(oth),
+            (Self::Map(slf), Self::Map(oth)) => cmp_seqs(slf.iter(), oth.iter(), |a, b| a.cmp(b)),
+            _ => DatumType::from(self).cmp(&DatumType::from(other)),
+        }
+    }
+}
+
+/* DatumRef is comparable against Datum. */
+impl<'a> PartialEq<Datum> for DatumRef<'a> {
+    fn eq(&self, other: &Datum) -> bool {
+        self.cmp_datum(other) == Ordering::Equal
+    }
+}
+impl<'a> PartialOrd<Datum> for DatumRef<'a> {
+    fn partial_cmp(&self, other: &Datum) -> Option<Ordering> {
+        Some(self.cmp_datum(other))
+    }
+}
+impl<'a> DatumRef<'a> {
+    pub fn cmp_datum(&self, other: &Datum) -> Ordering {
+        match (self, other) {
+            (Self::Bytes(slf), Datum::Bytes(oth)) => (*slf).cmp(oth.as_slice()),
+            (Self::I64(slf), Datum::I64(oth)) => slf.cmp(oth),
+            (Self::Str(slf), Datum::Str(oth)) => (*slf).cmp(oth.as_str()),
+            (Self::Tuple(slf), Datum::Tuple(oth)) | (Self::List(slf), Datum::List(oth)) => {
+                cmp_seqs(slf.iter(), oth.iter(), |a, b| a.cmp_datum(b))
+            }
+            (Self::F64(slf), Datum::F64(oth)) => slf.total_cmp(oth),
+            (Self::Bool(slf), Datum::Bool(oth)) => slf.cmp(oth),
+            (Self::Null, Datum::Null) => Ordering::Equal,
+            (Self::Timestamp(slf), Datum::Timestamp(oth)) => slf.cmp(oth),
+            (Self::Decimal(slf), Datum::Decimal(oth)) => slf.cmp(oth),
+            (Self::Map(slf), Datum::Map(oth)) => {
+                cmp_seqs(slf.iter(), oth.iter(), |(a_key, a_val), (b_key, b_val)| {
+                    (*a_key)
+                        .cmp(b_key.as_str())
+                        .then_with(|| a_val.cmp_datum(b_val))
+                })
+            }
+            _ => DatumType::from(self).cmp(&DatumType::from(other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ser(dat: &Datum) -> Vec<u8> {
+        let mut buf = vec![];
+        dat.ser(&mut buf).unwrap();
+        buf
+    }
+
+    fn gen_data() -> Vec<Datum> {
+        let s = |s: &str| Datum::Str(String::from(s));
+        let map = |membs: Vec<(&str, Datum)>| {
+            Datum::Map(
+                membs
+                    .into_iter()
+                    .map(|(key, val)| (String::from(key), val))
+                    .collect(),
+            )
+        };
+        vec![
+            Datum::I64(-1),
+            Datum::I64(1),
+            Datum::Bytes(vec![]),
+            Datum::Bytes(vec![0, 1]),
+            s(""),
+            s("a"),
+            s("ab"),
+            Datum::Tuple(vec![]),
+            Datum::Tuple(vec![Datum::I64(1)]),
+            Datum::Tuple(vec![Datum::I64(1), s("a")]),
+            Datum::Tuple(vec![Datum::I64(1), Datum::Tuple(vec![s("a")])]),
+            Datum::Tuple(vec![Datum::I64(2)]),
+            Datum::F64(f64::NEG_INFINITY),
+            Datum::F64(-0.0),
+            Datum::F64(0.0),
+            Datum::F64(f64::NAN),
+            Datum::Bool(false),
+            Datum::Bool(true),
+            Datum::Null,
+            Datum::Timestamp(0),
+            Datum::Decimal("-1.5".parse().unwrap()),
+            Datum::Decimal("0.01".parse().unwrap()),
+            map(vec![]),
+            map(vec![("a", Datum::I64(1))]),
+            map(vec![("a", Datum::I64(1)), ("b", Datum::Null)]),
+            map(vec![("a", Datum::I64(2))]),
+            map(vec![("b", map(vec![("c", Datum::List(vec![]))]))]),
+            Datum::List(vec![]),
+            Datum::List(vec![Datum::List(vec![s("a")]), Datum::I64(1)]),
+            Datum::List(vec![Datum::List(vec![s("b")])]),
+        ]
+    }
+
+    #[test]
+    fn view() -> Result<()> {
+        for dat in gen_data() {
+            let mut buf = ser(&dat);
+            let dat_len = buf.len();
+            buf.extend_from_slice(&[0xff; 3]); // Trailing bytes are ignored.
+
+            match OptDatum::<DatumRef>::deser(&buf)? {
+                ReadResult::Some(r_len, OptDatum::Some(view)) => {
+                    assert_eq!(dat_len, r_len);
+                    assert_eq!(dat, view.to_datum());
+                }
+                etc => panic!("{etc:?}"),
+            }
+        }
+
+        let mut buf = vec![];
+        OptDatum::<Datum>::Tombstone.ser(&mut buf)?;
+        assert!(matches!(
+            OptDatum::<DatumRef>::deser(&buf)?,
+            ReadResult::Some(1, OptDatum::Tombstone)
+        ));
+        assert!(matches!(OptDatum::<DatumRef>::deser(&[])?, ReadResult::EOF));
+        Ok(())
+    }
+
+    #[test]
+    fn view_invalid() {
+        for dat in gen_data() {
+            let buf = ser(&dat);
+            for trunc_len in 1..buf.len() {
+                assert!(
+                    OptDatum::<DatumRef>::deser(&buf[..trunc_len]).is_err(),
+                    "{dat:?} {trunc_len}"
+                );
+            }
+        }
+
+        let mut buf = ser(&Datum::Str(String::from("a")));
+        *buf.last_mut().unwrap() = 0xff; // Invalid utf8.
+        assert!(DatumRef::deser_solo(&buf).is_err());
+
+        let mut buf = ser(&Datum::Bool(true));
+        *buf.last_mut().unwrap() = 2;
+        assert!(DatumRef::deser_solo(&buf).is_err());
+    }
+
+    #[test]
+    fn ordering() {
+        let data = gen_data();
+        let bufs = data.iter().map(ser).collect::<Vec<_>>();
+        let views = bufs
+            .iter()
+            .map(|buf| DatumRef::deser_solo(buf).unwrap())
+            .collect::<Vec<_>>();
+
+        for (i, dat_i) in data.iter().enumerate() {
+            for (j, dat_j) in data.iter().enumerate() {
+                let exp = dat_i.cmp(dat_j);
+                assert_eq!(exp, views[i].cmp(&views[j]), "{dat_i:?} {dat_j:?}");
+                assert_eq!(exp, views[i].cmp_datum(dat_j), "{dat_i:?} {dat_j:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn members() {
+        let dat = Datum::Map(BTreeMap::from([
+            (String::from("a"), Datum::I64(1)),
+            (
+                String::from("c"),
+                Datum::List(vec![Datum::I64(2), Datum::Tuple(vec![Datum::Null])]),
+            ),
+        ]));
+        let buf = ser(&dat);
+        let view = DatumRef::deser_solo(&buf).unwrap();
+
+        let membs = match view {
+            DatumRef::Map(membs) => membs,
+            etc => panic!("{etc:?}"),
+        };
+        assert_eq!(2, membs.len());
+        assert_eq!(Some(DatumRef::I64(1)), membs.get("a"));
+        assert_eq!(None, membs.get("b"));
+        assert_eq!(None, membs.get("d"));
+
+        let list = match membs.get("c") {
+            Some(DatumRef::List(list)) => list,
+            etc => panic!("{etc:?}"),
+        };
+        assert_eq!(2, list.len());
+        assert_eq!(Some(DatumRef::I64(2)), list.get(0));
+        assert_eq!(
+            Datum::Tuple(vec![Datum::Null]),
+            list.get(1).unwrap().to_datum()
+        );
+        assert_eq!(None, list.get(2));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::serde::{Datum, OptDatum};
+use crate::serde::{Datum, DatumRef, OptDatum};
 use anyhow::{anyhow, Result};
 use derive_more::{Deref, From};
 use num_derive::{FromPrimitive, ToPrimitive};
@@ -26,8 +26,10 @@ impl DatumTypeInt {
 
 /// We manually map enum members to data_type integers because:
 /// - Rust does not support specifying discriminants on an enum containing non-simple members. [RFC](https://github.com/rust-lang/rust/issues/60553)
-/// - One member, Tombstone, is outside the Datum enum.
+/// - Two members, Tombstone and Expiring, are outside the Datum enum.
 /// - An automatic discriminant may change w/ enum definition change or compilation, according to [`std::mem::discriminant()`] doc.
+///
+/// These integers are persisted. Never renumber an existing member; append new members instead.
 #[repr(u8)]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, FromPrimitive, ToPrimitive, Debug)]
 pub enum DatumType {
@@ -36,6 +38,14 @@ pub enum DatumType {
     Bytes = 2,
     Str = 3,
     Tuple = 4,
+    F64 = 5,
+    Bool = 6,
+    Null = 7,
+    Timestamp = 8,
+    Decimal = 9,
+    Map = 10,
+    List = 11,
+    Expiring = 12,
 }
 impl TryFrom<DatumTypeInt> for DatumType {
     type Error = anyhow::Error;
@@ -44,6 +54,25 @@ impl TryFrom<DatumTypeInt> for DatumType {
             .ok_or_else(|| anyhow!("Unknown {} {}", any::type_name::<DatumTypeInt>(), int.0))
     }
 }
+impl DatumType {
+    /// @return The length of `datum_body`, if it's fixed for this type; otherwise `None`.
+    pub fn fixed_body_len(&self) -> Option<usize> {
+        match self {
+            DatumType::Tombstone | DatumType::Null => Some(0),
+            DatumType::Bool => Some(mem::size_of::<u8>()),
+            DatumType::I64 | DatumType::Timestamp => Some(mem::size_of::<i64>()),
+            // The body is the expiry time only. The wrapped datum follows it, and is read as a separate datum.
+            DatumType::Expiring => Some(mem::size_of::<i64>()),
+            DatumType::F64 => Some(mem::size_of::<f64>()),
+            DatumType::Decimal => Some(mem::size_of::<i128>() + mem::size_of::<u8>()),
+            DatumType::Bytes
+            | DatumType::Str
+            | DatumType::Tuple
+            | DatumType::Map
+            | DatumType::List => None,
+        }
+    }
+}
 impl From<&Datum> for DatumType {
     fn from(dat: &Datum) -> Self {
         match dat {
@@ -51,6 +80,30 @@ impl From<&Datum> for DatumType {
             Datum::Bytes(_) => DatumType::Bytes,
             Datum::Str(_) => DatumType::Str,
             Datum::Tuple(_) => DatumType::Tuple,
+            Datum::F64(_) => DatumType::F64,
+            Datum::Bool(_) => DatumType::Bool,
+            Datum::Null => DatumType::Null,
+            Datum::Timestamp(_) => DatumType::Timestamp,
+            Datum::Decimal(_) => DatumType::Decimal,
+            Datum::Map(_) => DatumType::Map,
+            Datum::List(_) => DatumType::List,
+        }
+    }
+}
+impl From<&DatumRef<'_>> for DatumType {
+    fn from(dat: &DatumRef) -> Self {
+        match dat {
+            DatumRef::I64(_) => DatumType::I64,
+            DatumRef::Bytes(_) => DatumType::Bytes,
+            DatumRef::Str(_) => DatumType::Str,
+            DatumRef::Tuple(_) => DatumType::Tuple,
+            DatumRef::F64(_) => DatumType::F64,
+            DatumRef::Bool(_) => DatumType::Bool,
+            DatumRef::Null => DatumType::Null,
+            DatumRef::Timestamp(_) => DatumType::Timestamp,
+            DatumRef::Decimal(_) => DatumType::Decimal,
+            DatumRef::Map(_) => DatumType::Map,
+            DatumRef::List(_) => DatumType::List,
         }
     }
 }
@@ -59,6 +112,7 @@ impl From<&OptDatum<Datum>> for DatumType {
         match optdat {
             OptDatum::Tombstone => DatumType::Tombstone,
             OptDatum::Some(dat) => Self::from(dat),
+            OptDatum::Expiring(_, _) => DatumType::Expiring,
         }
     }
 }
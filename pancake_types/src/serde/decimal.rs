@@ -0,0 +1,156 @@
+use anyhow::{anyhow, Result};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// [`Decimal`] is a base-10 fixed-point number, equalling `mantissa * 10^(-scale)`.
+///
+/// A [`Decimal`] is always normalized, i.e. the mantissa has no trailing zeros
+/// that could be removed by decrementing the scale.
+/// Hence, two numerically equal decimals (e.g. `1.50` and `1.5`) have identical representations,
+/// and the derived equality agrees with the numerical ordering.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    /// The largest scale s.t. `10^scale` fits in `i128`.
+    pub const MAX_SCALE: u8 = 38;
+
+    pub fn new(mut mantissa: i128, mut scale: u8) -> Result<Self> {
+        if scale > Self::MAX_SCALE {
+            return Err(anyhow!(
+                "Decimal scale {scale} exceeds the max of {}",
+                Self::MAX_SCALE
+            ));
+        }
+        while scale > 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            scale -= 1;
+        }
+        Ok(Self { mantissa, scale })
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.scale.cmp(&other.scale) {
+            Ordering::Equal => self.mantissa.cmp(&other.mantissa),
+            Ordering::Less => Self::cmp_rescaled(self, other),
+            Ordering::Greater => Self::cmp_rescaled(other, self).reverse(),
+        }
+    }
+}
+impl Decimal {
+    /// @arg `lo_scale`: The decimal having the smaller scale.
+    /// @arg `hi_scale`: The decimal having the larger scale.
+    fn cmp_rescaled(lo_scale: &Self, hi_scale: &Self) -> Ordering {
+        let factor = 10i128.pow((hi_scale.scale - lo_scale.scale) as u32);
+        match lo_scale.mantissa.checked_mul(factor) {
+            Some(rescaled) => rescaled.cmp(&hi_scale.mantissa),
+            /*
+            The rescaled mantissa's magnitude exceeds that of any i128,
+                hence the sign alone decides the ordering.
+            */
+            None => lo_scale.mantissa.cmp(&0),
+        }
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = anyhow::Error;
+
+    /// Parses such literals as `12`, `-12.340` and `0.5`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (int_part, frac_part) = match s.split_once('.') {
+            None => (s, ""),
+            Some((int_part, frac_part)) => (int_part, frac_part),
+        };
+        if frac_part.chars().all(|c| c.is_ascii_digit()) == false {
+            return Err(anyhow!("Invalid decimal literal {s}"));
+        }
+        let scale = u8::try_from(frac_part.len())
+            .map_err(|_| anyhow!("Too many fractional digits in decimal literal {s}"))?;
+        let mantissa = format!("{int_part}{frac_part}")
+            .parse::<i128>()
+            .map_err(|e| anyhow!("Invalid decimal literal {s}: {e}"))?;
+        Self::new(mantissa, scale)
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        if scale == 0 {
+            write!(f, "{sign}{digits}")
+        } else if digits.len() > scale {
+            let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+            write!(f, "{sign}{int_part}.{frac_part}")
+        } else {
+            write!(f, "{sign}0.{digits:0>scale$}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse::<Decimal>().unwrap()
+    }
+
+    #[test]
+    fn normalize() -> Result<()> {
+        assert_eq!(Decimal::new(150, 2)?, Decimal::new(15, 1)?);
+        assert_eq!(Decimal::new(0, 5)?, Decimal::new(0, 0)?);
+        assert_eq!(dec("-12.340"), Decimal::new(-1234, 2)?);
+        assert!(Decimal::new(1, Decimal::MAX_SCALE + 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn ordering() {
+        let sorted = [
+            "-100", "-1.5", "-1.05", "0", "0.001", "0.01", "1", "1.05", "1.5", "100",
+        ];
+        for (i, a) in sorted.iter().enumerate() {
+            for (j, b) in sorted.iter().enumerate() {
+                assert_eq!(dec(a).cmp(&dec(b)), i.cmp(&j), "{a} {b}");
+            }
+        }
+
+        /* Rescaling overflows. */
+        let big = Decimal::new(i128::MAX, 0).unwrap();
+        let small = Decimal::new(1, Decimal::MAX_SCALE).unwrap();
+        assert_eq!(big.cmp(&small), Ordering::Greater);
+        let neg_big = Decimal::new(i128::MIN + 1, 0).unwrap();
+        assert_eq!(neg_big.cmp(&small), Ordering::Less);
+    }
+
+    #[test]
+    fn display() {
+        for s in ["0", "12", "-12.34", "0.05", "-0.5"] {
+            assert_eq!(dec(s).to_string(), s);
+        }
+        assert!("1.2.3".parse::<Decimal>().is_err());
+        assert!("1.-2".parse::<Decimal>().is_err());
+    }
+}
@@ -2,6 +2,7 @@ use crate::serde::Datum;
 use anyhow::Result;
 use derive_more::Deref;
 use std::borrow::Borrow;
+use std::collections::BTreeMap;
 use std::io::{self, Read};
 use std::mem;
 
@@ -37,3 +38,33 @@ impl TupleMembersCount {
         Ok((buf.len(), Self(int)))
     }
 }
+
+#[derive(Deref, Clone, Copy)]
+pub struct MapMembersCount(u32);
+impl MapMembersCount {
+    pub fn from_members(members: &BTreeMap<String, Datum>) -> Result<Self> {
+        let membs_ct = u32::try_from(members.len())?;
+        Ok(Self(membs_ct))
+    }
+    pub fn deser(r: &mut impl Read) -> Result<(usize, Self), io::Error> {
+        let mut buf = [0u8; mem::size_of::<u32>()];
+        r.read_exact(&mut buf)?;
+        let int = u32::from_le_bytes(buf);
+        Ok((buf.len(), Self(int)))
+    }
+}
+
+#[derive(Deref, Clone, Copy)]
+pub struct MapKeyLen(u32);
+impl MapKeyLen {
+    pub fn from_key(key: &str) -> Result<Self> {
+        let int = u32::try_from(key.len())?;
+        Ok(Self(int))
+    }
+    pub fn deser(r: &mut impl Read) -> Result<(usize, Self), io::Error> {
+        let mut buf = [0u8; mem::size_of::<u32>()];
+        r.read_exact(&mut buf)?;
+        let int = u32::from_le_bytes(buf);
+        Ok((buf.len(), Self(int)))
+    }
+}
@@ -15,16 +15,48 @@
 //!
 //! A `Datum::Tuple` nests other non-`Tombstone` `Datum`s, including possibly other `Datum::Tuple`s.
 //!
+//! A `Datum::Map` nests other non-`Tombstone` `Datum`s under string keys.
+//! Its members are encoded in the ascending order of their keys, so that the encoding is canonical.
+//! A nested `Datum::Map` omits `datum_body_len`, as does a nested `Datum::Tuple`.
+//!
+//! A `Datum::List` is encoded identically to a `Datum::Tuple`, except for `datum_type`.
+//!
+//! An `OptDatum::Expiring` is encoded as its expiry time, followed by the wrapped root `Datum`.
+//! It may not be nested, nor wrap a `Tombstone`.
+//!
 //! ```text
 //! struct OptDatum::Tombstone {
 //!     datum_type:         u8,
 //! }
 //!
-//! struct Datum::I64 {
+//! struct OptDatum::Expiring {
+//!     datum_type:         u8,
+//!     datum_body:         [u8; 8], // Expiry time, as i64 microseconds since the unix epoch.
+//!     datum:              Datum::*,
+//! }
+//!
+//! struct Datum::Null {
+//!     datum_type:         u8,
+//! }
+//!
+//! struct Datum::Bool {
+//!     datum_type:         u8,
+//!     datum_body:         [u8; 1],
+//! }
+//!
+//! struct Datum::I64 or Datum::F64 or Datum::Timestamp {
 //!     datum_type:         u8,
 //!     datum_body:         [u8; 8],
 //! }
 //!
+//! struct Datum::Decimal {
+//!     datum_type:         u8,
+//!     datum_body:         {
+//!         mantissa:           [u8; 16],
+//!         scale:              u8,
+//!     },
+//! }
+//!
 //! struct Datum::Bytes or Datum::Str {
 //!     datum_type:         u8,
 //!     datum_body_len:     u32,
@@ -59,12 +91,30 @@
 //!         // Tombstone may not be nested under Tuple.
 //!     }
 //! }
+//!
+//! struct Datum::Map {
+//!     datum_type:         u8,
+//!     datum_body_len:     u32,
+//!     datum_body:         {
+//!         members_count:      u32,
+//!         member_0:           {
+//!             key_len:            u32,
+//!             key:                [u8; key_len],
+//!             val:                Datum::*,
+//!         },
+//!         ...
+//!     }
+//! }
 //! ```
 
 mod datum;
+mod datum_ref;
 mod datum_type;
+mod decimal;
 mod lengths;
 
 pub use datum::*;
+pub use datum_ref::*;
 pub use datum_type::*;
+pub use decimal::*;
 use lengths::*;
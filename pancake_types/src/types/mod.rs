@@ -5,6 +5,9 @@ mod pv;
 mod sv;
 mod svpk;
 
+mod pv_constraint;
+mod pv_predicate;
+mod si_spec;
 mod sv_spec;
 
 pub use serializable::*;
@@ -14,4 +17,7 @@ pub use pv::*;
 pub use sv::*;
 pub use svpk::*;
 
+pub use pv_constraint::*;
+pub use pv_predicate::*;
+pub use si_spec::*;
 pub use sv_spec::*;
@@ -1,4 +1,8 @@
-use crate::{serde::Datum, types::Serializable};
+use crate::{
+    serde::{Datum, DatumRef},
+    types::{PartialOrdSer, Serializable},
+};
+use anyhow::Result;
 use derive_more::{Deref, From};
 use std::borrow::Borrow;
 use std::cmp::{Ordering, PartialOrd};
@@ -33,6 +37,26 @@ impl PartialOrd<&PrimaryKey> for PKShared {
     }
 }
 
+/* PKShared is comparable against PrimaryKey, while serialized. */
+impl PartialOrdSer<PrimaryKey> for PKShared {
+    fn partial_cmp_ser(buf: &[u8], other: &PrimaryKey) -> Result<Option<Ordering>> {
+        let dat = DatumRef::deser_solo(buf)?;
+        Ok(Some(dat.cmp_datum(other)))
+    }
+}
+impl PartialOrdSer<PKShared> for PKShared {
+    fn partial_cmp_ser(buf: &[u8], other: &PKShared) -> Result<Option<Ordering>> {
+        Self::partial_cmp_ser(buf, other as &PrimaryKey)
+    }
+}
+
+/* PrimaryKey is Ser, identically to PKShared. */
+impl Borrow<Datum> for PrimaryKey {
+    fn borrow(&self) -> &Datum {
+        self
+    }
+}
+
 /* PKShared is Serializable. */
 impl Borrow<Datum> for PKShared {
     fn borrow(&self) -> &Datum {
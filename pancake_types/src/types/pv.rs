@@ -1,9 +1,11 @@
 use crate::{
-    serde::{Datum, OptDatum},
-    types::Serializable,
+    serde::{Datum, DatumRef, OptDatum, ReadResult},
+    types::{Deser, Serializable},
 };
+use anyhow::{anyhow, Result};
 use derive_more::{Deref, From};
 use std::borrow::Borrow;
+use std::io::{Read, Seek};
 use std::sync::Arc;
 
 #[derive(From, Deref, PartialEq, Eq, Debug)]
@@ -23,3 +25,75 @@ impl From<Datum> for PVShared {
     }
 }
 impl Serializable for OptDatum<PVShared> {}
+
+/// [`OptPVBuf`] is a serialized `OptDatum<PVShared>`, read as is.
+///
+/// A scan that only inspects sub-portions of each value, eg to extract sub-values,
+/// reads [`OptPVBuf`]s and walks their [`view()`](Self::view)s, rather than deserializing every whole value.
+#[derive(PartialEq, Eq, Debug)]
+pub struct OptPVBuf(pub Vec<u8>);
+
+impl Deser for OptPVBuf {
+    fn skip<R: Read + Seek>(r: &mut R) -> Result<ReadResult<()>> {
+        OptDatum::<Datum>::skip(r)
+    }
+    fn read_ser<R: Read + Seek>(r: &mut R, buf: &mut Vec<u8>) -> Result<ReadResult<()>> {
+        OptDatum::<Datum>::read_ser(r, buf)
+    }
+    fn deser<R: Read + Seek>(r: &mut R) -> Result<ReadResult<Self>> {
+        let mut buf = vec![];
+        match OptDatum::<Datum>::read_ser(r, &mut buf)? {
+            ReadResult::EOF => Ok(ReadResult::EOF),
+            ReadResult::Some(r_len, ()) => Ok(ReadResult::Some(r_len, Self(buf))),
+        }
+    }
+}
+
+impl OptPVBuf {
+    pub fn view(&self) -> Result<OptDatum<DatumRef<'_>>> {
+        match OptDatum::<DatumRef>::deser(&self.0)? {
+            ReadResult::EOF => Err(anyhow!("No data")),
+            ReadResult::Some(_, optdat) => Ok(optdat),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::Ser;
+    use std::io::Cursor;
+
+    #[test]
+    fn read_and_view() -> Result<()> {
+        let pv = PVShared::from(Datum::Tuple(vec![Datum::I64(1), Datum::Null]));
+        let optdats = [
+            OptDatum::Some(pv.clone()),
+            OptDatum::Expiring(pv.clone(), 100),
+            OptDatum::Tombstone,
+        ];
+
+        let mut buf = vec![];
+        for optdat in optdats.iter() {
+            optdat.ser(&mut buf)?;
+        }
+
+        let mut r = Cursor::new(&buf);
+        for optdat in optdats.iter() {
+            let optpvbuf = match OptPVBuf::deser(&mut r)? {
+                ReadResult::EOF => panic!("Unexpected EOF"),
+                ReadResult::Some(_, optpvbuf) => optpvbuf,
+            };
+            assert_eq!(optpvbuf.0, optdat.ser_solo()?);
+
+            let view = optpvbuf.view()?;
+            assert_eq!(view.expires_at(), optdat.expires_at());
+            let view_dat = view.as_live(0).map(|dat| dat.to_datum());
+            let dat = optdat.as_live(0).map(|pv| (pv as &Datum).clone());
+            assert_eq!(view_dat, dat);
+        }
+        assert!(matches!(OptPVBuf::deser(&mut r)?, ReadResult::EOF));
+
+        Ok(())
+    }
+}
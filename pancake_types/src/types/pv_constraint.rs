@@ -0,0 +1,345 @@
+use crate::io_utils;
+use crate::serde::{Datum, DatumRef, DatumType, DatumTypeInt};
+use crate::types::{MemberKey, PKShared, SubValueSpec, Value};
+use anyhow::{anyhow, Result};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::io::{BufRead, Cursor, Write};
+use std::str;
+use std::sync::Arc;
+
+/// [`ValueConstraint`] requires a sub-portion of every [`Value`] to have a certain shape.
+///
+/// Values are dynamically typed. A constraint lets clients opt into checking some of their shape,
+/// so that eg a [`SubValueSpec`] does not silently skip a value that was written by mistake.
+///
+/// #### Specification
+///
+/// `member_path` locates the sub-portion, in the same way as [`SubValueSpec::member_path`]:
+/// - A sub-portion that does not exist violates the constraint.
+/// - If the path contains [`MemberKey::Each`], then every element must satisfy the constraint.
+///   An empty [`Datum::List`] satisfies it.
+///
+/// `shape` is what the sub-portion must be.
+///
+/// For example, "member 0 must be I64":
+///
+/// ```text
+/// ValueConstraint {
+///     member_path: vec![MemberKey::Idx(0)],
+///     shape: ValueShape::Type(DatumType::I64),
+/// }
+/// ```
+///
+/// "The whole value must be a Tuple of arity >= 2":
+///
+/// ```text
+/// ValueConstraint {
+///     member_path: vec![],
+///     shape: ValueShape::MinTupleArity(2),
+/// }
+/// ```
+///
+/// Tombstones, i.e. deletions, are never constrained.
+#[derive(PartialEq, Eq, Hash, Debug)]
+pub struct ValueConstraint {
+    pub member_path: Vec<MemberKey>,
+    pub shape: ValueShape,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum ValueShape {
+    /// The datum is of this type.
+    Type(DatumType),
+    /// The datum is a [`Datum::Tuple`] having at least this many members.
+    MinTupleArity(u32),
+}
+
+impl ValueShape {
+    fn is_satisfied_by(&self, dat: &Datum) -> bool {
+        match (self, dat) {
+            (Self::Type(datum_type), dat) => DatumType::from(dat) == *datum_type,
+            (Self::MinTupleArity(arity), Datum::Tuple(members)) => members.len() >= *arity as usize,
+            (Self::MinTupleArity(_), _) => false,
+        }
+    }
+
+    fn is_satisfied_by_ref(&self, dat: DatumRef) -> bool {
+        match (self, dat) {
+            (Self::Type(datum_type), dat) => DatumType::from(&dat) == *datum_type,
+            (Self::MinTupleArity(arity), DatumRef::Tuple(members)) => {
+                members.len() >= *arity as usize
+            }
+            (Self::MinTupleArity(_), _) => false,
+        }
+    }
+}
+
+/* Checking. */
+impl ValueConstraint {
+    pub fn is_satisfied_by(&self, pv: &Value) -> bool {
+        self.walk(pv, &self.member_path)
+    }
+
+    fn walk(&self, dat: &Datum, member_path: &[MemberKey]) -> bool {
+        let (member_key, rest_path) = match member_path.split_first() {
+            None => return self.shape.is_satisfied_by(dat),
+            Some(pair) => pair,
+        };
+        match (dat, member_key) {
+            (Datum::Tuple(members) | Datum::List(members), MemberKey::Idx(member_idx)) => {
+                match members.get(*member_idx as usize) {
+                    None => false,
+                    Some(member) => self.walk(member, rest_path),
+                }
+            }
+            (Datum::Map(members), MemberKey::Name(member_name)) => match members.get(member_name) {
+                None => false,
+                Some(member) => self.walk(member, rest_path),
+            },
+            (Datum::List(members), MemberKey::Each) => {
+                members.iter().all(|member| self.walk(member, rest_path))
+            }
+            _ => false,
+        }
+    }
+
+    /// The counterpart of [`Self::is_satisfied_by()`], over a view of a serialized [`Value`].
+    pub fn is_satisfied_by_ref(&self, pv: DatumRef) -> bool {
+        self.walk_ref(pv, &self.member_path)
+    }
+
+    fn walk_ref(&self, dat: DatumRef, member_path: &[MemberKey]) -> bool {
+        let (member_key, rest_path) = match member_path.split_first() {
+            None => return self.shape.is_satisfied_by_ref(dat),
+            Some(pair) => pair,
+        };
+        match (dat, member_key) {
+            (DatumRef::Tuple(members) | DatumRef::List(members), MemberKey::Idx(member_idx)) => {
+                match members.get(*member_idx as usize) {
+                    None => false,
+                    Some(member) => self.walk_ref(member, rest_path),
+                }
+            }
+            (DatumRef::Map(members), MemberKey::Name(member_name)) => {
+                match members.get(member_name) {
+                    None => false,
+                    Some(member) => self.walk_ref(member, rest_path),
+                }
+            }
+            (DatumRef::List(members), MemberKey::Each) => members
+                .iter()
+                .all(|member| self.walk_ref(member, rest_path)),
+            _ => false,
+        }
+    }
+
+    /// @return Err of the first violated constraint, if any.
+    pub fn check_all<'a>(
+        constraints: impl IntoIterator<Item = &'a Arc<ValueConstraint>>,
+        pk: &PKShared,
+        pv: &Value,
+    ) -> Result<(), ConstraintViolation> {
+        for constraint in constraints {
+            if constraint.is_satisfied_by(pv) == false {
+                return Err(ConstraintViolation {
+                    constraint: Arc::clone(constraint),
+                    pk: pk.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The error of putting a [`Value`] that violates a [`ValueConstraint`],
+/// or of creating a [`ValueConstraint`] that an existing [`Value`] violates.
+#[derive(Debug)]
+pub struct ConstraintViolation {
+    pub constraint: Arc<ValueConstraint>,
+    pub pk: PKShared,
+}
+impl Display for ConstraintViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The value at {:?} violates {:?}",
+            self.pk, self.constraint
+        )
+    }
+}
+impl Error for ConstraintViolation {}
+
+/* De/Serialization. */
+impl ValueConstraint {
+    pub fn ser<W: Write>(&self, w: &mut W) -> Result<()> {
+        /* shape */
+        match self.shape {
+            ValueShape::Type(datum_type) => {
+                let datum_type_int = DatumTypeInt::from(datum_type);
+                write!(w, "t{};", *datum_type_int)?;
+            }
+            ValueShape::MinTupleArity(arity) => write!(w, "a{};", arity)?,
+        }
+
+        /* member_path */
+        SubValueSpec::ser_member_path(w, &self.member_path)?;
+
+        Ok(())
+    }
+
+    pub fn deser<R: BufRead>(r: &mut R) -> Result<Self> {
+        let mut buf = vec![];
+
+        /* shape */
+        io_utils::read_until_then_trim(r, b';', &mut buf)?;
+        let shape = match buf.split_first() {
+            Some((b't', int)) => {
+                let datum_type_int = str::from_utf8(int)?.parse::<u8>()?;
+                let datum_type = DatumType::try_from(DatumTypeInt::from(datum_type_int))?;
+                ValueShape::Type(datum_type)
+            }
+            Some((b'a', int)) => ValueShape::MinTupleArity(str::from_utf8(int)?.parse::<u32>()?),
+            _ => return Err(anyhow!("Invalid value shape {buf:?}")),
+        };
+
+        /* member_path */
+        let member_path = SubValueSpec::deser_member_path(r)?;
+
+        Ok(Self { member_path, shape })
+    }
+
+    pub fn ser_solo(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        self.ser(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn deser_solo(buf: &[u8]) -> Result<Self> {
+        let mut r = Cursor::new(&buf);
+        Self::deser(&mut r)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{PrimaryKey, Ser};
+    use std::collections::BTreeMap;
+
+    fn str_dat(s: &str) -> Datum {
+        Datum::Str(String::from(s))
+    }
+
+    /// Also checks the serialized `pv`, which must agree.
+    fn is_satisfied(constraint: &ValueConstraint, pv: &Value) -> bool {
+        let is_satisfied = constraint.is_satisfied_by(pv);
+        let pv_buf = pv.ser_solo().unwrap();
+        let pv_ref = DatumRef::deser_solo(&pv_buf).unwrap();
+        assert_eq!(constraint.is_satisfied_by_ref(pv_ref), is_satisfied);
+        is_satisfied
+    }
+
+    #[test]
+    fn satisfaction() {
+        let int_at_0 = ValueConstraint {
+            member_path: vec![MemberKey::Idx(0)],
+            shape: ValueShape::Type(DatumType::I64),
+        };
+        assert!(is_satisfied(
+            &int_at_0,
+            &Value(Datum::Tuple(vec![Datum::I64(1)]))
+        ));
+        assert!(is_satisfied(
+            &int_at_0,
+            &Value(Datum::List(vec![Datum::I64(1)]))
+        ));
+        assert!(is_satisfied(&int_at_0, &Value(Datum::Tuple(vec![str_dat("a")]))) == false);
+        assert!(is_satisfied(&int_at_0, &Value(Datum::Tuple(vec![]))) == false);
+        assert!(is_satisfied(&int_at_0, &Value(Datum::I64(1))) == false);
+
+        let arity_2 = ValueConstraint {
+            member_path: vec![],
+            shape: ValueShape::MinTupleArity(2),
+        };
+        assert!(is_satisfied(
+            &arity_2,
+            &Value(Datum::Tuple(vec![Datum::Null, Datum::Null]))
+        ));
+        assert!(is_satisfied(
+            &arity_2,
+            &Value(Datum::Tuple(vec![Datum::Null, Datum::Null, Datum::Null]))
+        ));
+        assert!(is_satisfied(&arity_2, &Value(Datum::Tuple(vec![Datum::Null]))) == false);
+        assert!(
+            is_satisfied(
+                &arity_2,
+                &Value(Datum::List(vec![Datum::Null, Datum::Null]))
+            ) == false
+        );
+
+        let each_tag_str = ValueConstraint {
+            member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+            shape: ValueShape::Type(DatumType::Str),
+        };
+        let tags = |tags: Vec<Datum>| {
+            Value(Datum::Map(BTreeMap::from([(
+                String::from("tags"),
+                Datum::List(tags),
+            )])))
+        };
+        assert!(is_satisfied(
+            &each_tag_str,
+            &tags(vec![str_dat("a"), str_dat("b")])
+        ));
+        assert!(is_satisfied(&each_tag_str, &tags(vec![])));
+        assert!(is_satisfied(&each_tag_str, &tags(vec![str_dat("a"), Datum::I64(1)])) == false);
+        assert!(is_satisfied(&each_tag_str, &Value(Datum::Map(BTreeMap::new()))) == false);
+    }
+
+    #[test]
+    fn check_all() {
+        let constraints = [
+            Arc::new(ValueConstraint {
+                member_path: vec![],
+                shape: ValueShape::MinTupleArity(1),
+            }),
+            Arc::new(ValueConstraint {
+                member_path: vec![MemberKey::Idx(0)],
+                shape: ValueShape::Type(DatumType::I64),
+            }),
+        ];
+        let pk = Arc::new(PrimaryKey(str_dat("k")));
+
+        let pv = Value(Datum::Tuple(vec![Datum::I64(1)]));
+        assert!(ValueConstraint::check_all(constraints.iter(), &pk, &pv).is_ok());
+
+        let pv = Value(Datum::Tuple(vec![str_dat("a")]));
+        let violation = ValueConstraint::check_all(constraints.iter(), &pk, &pv).unwrap_err();
+        assert_eq!(violation.constraint, constraints[1]);
+        assert_eq!(violation.pk, pk);
+    }
+
+    #[test]
+    fn ser_deser() -> Result<()> {
+        for constraint in [
+            ValueConstraint {
+                member_path: vec![],
+                shape: ValueShape::MinTupleArity(2),
+            },
+            ValueConstraint {
+                member_path: vec![MemberKey::Idx(1), MemberKey::from("a,b%"), MemberKey::Each],
+                shape: ValueShape::Type(DatumType::Decimal),
+            },
+        ] {
+            let buf = constraint.ser_solo()?;
+            assert!(buf.contains(&b'\n') == false);
+            assert_eq!(ValueConstraint::deser_solo(&buf)?, constraint);
+        }
+
+        assert!(ValueConstraint::deser_solo(b"x1;").is_err());
+        assert!(ValueConstraint::deser_solo(b"t99;").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+use crate::io_utils;
+use crate::serde::{Datum, DatumRef, DatumType, DatumTypeInt};
+use crate::types::{Deser, MemberKey, Ser, SubValueSpec, Value};
+use anyhow::{anyhow, Result};
+use std::io::{BufRead, Cursor, Write};
+use std::str;
+
+/// [`ValuePredicate`] tests a sub-portion of a [`Value`].
+///
+/// It lets a secondary index cover only the values that satisfy it. See [`ScndIdxSpec`](crate::types::ScndIdxSpec).
+///
+/// #### Specification
+///
+/// `member_path` locates the sub-portion, in the same way as [`SubValueSpec::member_path`]:
+/// - A sub-portion that does not exist fails the predicate.
+/// - If the path contains [`MemberKey::Each`], then the predicate holds if any element passes the test.
+///
+/// `test` is what the sub-portion must pass.
+///
+/// For example, "member `status` equals `"active"`":
+///
+/// ```text
+/// ValuePredicate {
+///     member_path: vec![MemberKey::from("status")],
+///     test: PredicateTest::Eq(Datum::Str(String::from("active"))),
+/// }
+/// ```
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct ValuePredicate {
+    pub member_path: Vec<MemberKey>,
+    pub test: PredicateTest,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub enum PredicateTest {
+    /// The datum equals this datum.
+    Eq(Datum),
+    /// The datum is within these inclusive bounds, and has the same type as each present bound.
+    /// Without the type requirement, e.g. every `Str` would be greater than an `I64` lower bound.
+    Range {
+        lo: Option<Datum>,
+        hi: Option<Datum>,
+    },
+    /// The datum is of this type.
+    Type(DatumType),
+}
+
+impl PredicateTest {
+    fn is_passed_by(&self, dat: &Datum) -> bool {
+        match self {
+            Self::Eq(operand) => dat == operand,
+            Self::Range { lo, hi } => {
+                let is_lo_ok = match lo {
+                    None => true,
+                    Some(lo) => DatumType::from(lo) == DatumType::from(dat) && lo <= dat,
+                };
+                let is_hi_ok = match hi {
+                    None => true,
+                    Some(hi) => DatumType::from(hi) == DatumType::from(dat) && dat <= hi,
+                };
+                is_lo_ok && is_hi_ok
+            }
+            Self::Type(datum_type) => DatumType::from(dat) == *datum_type,
+        }
+    }
+
+    fn is_passed_by_ref(&self, dat: DatumRef) -> bool {
+        match self {
+            Self::Eq(operand) => dat == *operand,
+            Self::Range { lo, hi } => {
+                let is_lo_ok = match lo {
+                    None => true,
+                    Some(lo) => DatumType::from(lo) == DatumType::from(&dat) && dat >= *lo,
+                };
+                let is_hi_ok = match hi {
+                    None => true,
+                    Some(hi) => DatumType::from(hi) == DatumType::from(&dat) && dat <= *hi,
+                };
+                is_lo_ok && is_hi_ok
+            }
+            Self::Type(datum_type) => DatumType::from(&dat) == *datum_type,
+        }
+    }
+}
+
+/* Evaluation. */
+impl ValuePredicate {
+    pub fn is_satisfied_by(&self, pv: &Value) -> bool {
+        self.walk(pv, &self.member_path)
+    }
+
+    fn walk(&self, dat: &Datum, member_path: &[MemberKey]) -> bool {
+        let (member_key, rest_path) = match member_path.split_first() {
+            None => return self.test.is_passed_by(dat),
+            Some(pair) => pair,
+        };
+        match (dat, member_key) {
+            (Datum::Tuple(members) | Datum::List(members), MemberKey::Idx(member_idx)) => {
+                match members.get(*member_idx as usize) {
+                    None => false,
+                    Some(member) => self.walk(member, rest_path),
+                }
+            }
+            (Datum::Map(members), MemberKey::Name(member_name)) => match members.get(member_name) {
+                None => false,
+                Some(member) => self.walk(member, rest_path),
+            },
+            (Datum::List(members), MemberKey::Each) => {
+                members.iter().any(|member| self.walk(member, rest_path))
+            }
+            _ => false,
+        }
+    }
+
+    /// The counterpart of [`Self::is_satisfied_by()`], over a view of a serialized [`Value`].
+    pub fn is_satisfied_by_ref(&self, pv: DatumRef) -> bool {
+        self.walk_ref(pv, &self.member_path)
+    }
+
+    fn walk_ref(&self, dat: DatumRef, member_path: &[MemberKey]) -> bool {
+        let (member_key, rest_path) = match member_path.split_first() {
+            None => return self.test.is_passed_by_ref(dat),
+            Some(pair) => pair,
+        };
+        match (dat, member_key) {
+            (DatumRef::Tuple(members) | DatumRef::List(members), MemberKey::Idx(member_idx)) => {
+                match members.get(*member_idx as usize) {
+                    None => false,
+                    Some(member) => self.walk_ref(member, rest_path),
+                }
+            }
+            (DatumRef::Map(members), MemberKey::Name(member_name)) => {
+                match members.get(member_name) {
+                    None => false,
+                    Some(member) => self.walk_ref(member, rest_path),
+                }
+            }
+            (DatumRef::List(members), MemberKey::Each) => members
+                .iter()
+                .any(|member| self.walk_ref(member, rest_path)),
+            _ => false,
+        }
+    }
+}
+
+/* De/Serialization. */
+impl ValuePredicate {
+    /// Operand datums are serialized in hex, so that they never contain delimiters.
+    pub fn ser<W: Write>(&self, w: &mut W) -> Result<()> {
+        /* test */
+        match &self.test {
+            PredicateTest::Eq(operand) => {
+                write!(w, "e")?;
+                Self::ser_operand(w, Some(operand))?;
+            }
+            PredicateTest::Range { lo, hi } => {
+                write!(w, "r")?;
+                Self::ser_operand(w, lo.as_ref())?;
+                Self::ser_operand(w, hi.as_ref())?;
+            }
+            PredicateTest::Type(datum_type) => {
+                let datum_type_int = DatumTypeInt::from(*datum_type);
+                write!(w, "t{};", *datum_type_int)?;
+            }
+        }
+
+        /* member_path */
+        SubValueSpec::ser_member_path(w, &self.member_path)?;
+
+        Ok(())
+    }
+
+    pub fn deser<R: BufRead>(r: &mut R) -> Result<Self> {
+        let mut tag = [0u8];
+        r.read_exact(&mut tag)?;
+
+        /* test */
+        let test = match &tag {
+            b"e" => {
+                let operand = Self::deser_operand(r)?
+                    .ok_or_else(|| anyhow!("Equality predicate without operand"))?;
+                PredicateTest::Eq(operand)
+            }
+            b"r" => {
+                let lo = Self::deser_operand(r)?;
+                let hi = Self::deser_operand(r)?;
+                PredicateTest::Range { lo, hi }
+            }
+            b"t" => {
+                let mut buf = vec![];
+                io_utils::read_until_then_trim(r, b';', &mut buf)?;
+                let datum_type_int = str::from_utf8(&buf)?.parse::<u8>()?;
+                let datum_type = DatumType::try_from(DatumTypeInt::from(datum_type_int))?;
+                PredicateTest::Type(datum_type)
+            }
+            _ => return Err(anyhow!("Invalid predicate test {tag:?}")),
+        };
+
+        /* member_path */
+        let member_path = SubValueSpec::deser_member_path(r)?;
+
+        Ok(Self { member_path, test })
+    }
+
+    /// Each operand is terminated by `;`. An absent operand is empty.
+    fn ser_operand<W: Write>(w: &mut W, operand: Option<&Datum>) -> Result<()> {
+        if let Some(operand) = operand {
+            for byte in operand.ser_solo()? {
+                write!(w, "{:02X}", byte)?;
+            }
+        }
+        write!(w, ";")?;
+        Ok(())
+    }
+
+    fn deser_operand<R: BufRead>(r: &mut R) -> Result<Option<Datum>> {
+        let mut buf = vec![];
+        io_utils::read_until_then_trim(r, b';', &mut buf)?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        let bytes = buf
+            .chunks(2)
+            .map(|hex| Ok(u8::from_str_radix(str::from_utf8(hex)?, 16)?))
+            .collect::<Result<Vec<_>>>()?;
+        let operand = Datum::deser_solo(&bytes)?;
+        Ok(Some(operand))
+    }
+
+    pub fn ser_solo(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        self.ser(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn deser_solo(buf: &[u8]) -> Result<Self> {
+        let mut r = Cursor::new(&buf);
+        Self::deser(&mut r)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn str_dat(s: &str) -> Datum {
+        Datum::Str(String::from(s))
+    }
+
+    /// Also evaluates over the serialized `pv`, which must agree.
+    fn is_satisfied(predicate: &ValuePredicate, pv: &Value) -> bool {
+        let is_satisfied = predicate.is_satisfied_by(pv);
+        let pv_buf = pv.ser_solo().unwrap();
+        let pv_ref = DatumRef::deser_solo(&pv_buf).unwrap();
+        assert_eq!(predicate.is_satisfied_by_ref(pv_ref), is_satisfied);
+        is_satisfied
+    }
+
+    fn gen_pv(status: Option<Datum>, tags: Vec<Datum>) -> Value {
+        let mut members = BTreeMap::new();
+        if let Some(status) = status {
+            members.insert(String::from("status"), status);
+        }
+        members.insert(String::from("tags"), Datum::List(tags));
+        Value(Datum::Map(members))
+    }
+
+    #[test]
+    fn satisfaction() {
+        let status_active = ValuePredicate {
+            member_path: vec![MemberKey::from("status")],
+            test: PredicateTest::Eq(str_dat("active")),
+        };
+        assert!(is_satisfied(
+            &status_active,
+            &gen_pv(Some(str_dat("active")), vec![])
+        ));
+        assert!(is_satisfied(&status_active, &gen_pv(Some(str_dat("closed")), vec![])) == false);
+        assert!(is_satisfied(&status_active, &gen_pv(None, vec![])) == false);
+
+        let status_1_to_5 = ValuePredicate {
+            member_path: vec![MemberKey::from("status")],
+            test: PredicateTest::Range {
+                lo: Some(Datum::I64(1)),
+                hi: Some(Datum::I64(5)),
+            },
+        };
+        assert!(is_satisfied(
+            &status_1_to_5,
+            &gen_pv(Some(Datum::I64(1)), vec![])
+        ));
+        assert!(is_satisfied(
+            &status_1_to_5,
+            &gen_pv(Some(Datum::I64(5)), vec![])
+        ));
+        assert!(is_satisfied(&status_1_to_5, &gen_pv(Some(Datum::I64(6)), vec![])) == false);
+
+        let status_from_1 = ValuePredicate {
+            member_path: vec![MemberKey::from("status")],
+            test: PredicateTest::Range {
+                lo: Some(Datum::I64(1)),
+                hi: None,
+            },
+        };
+        assert!(is_satisfied(
+            &status_from_1,
+            &gen_pv(Some(Datum::I64(100)), vec![])
+        ));
+        assert!(is_satisfied(&status_from_1, &gen_pv(Some(str_dat("a")), vec![])) == false);
+
+        let any_tag_int = ValuePredicate {
+            member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+            test: PredicateTest::Type(DatumType::I64),
+        };
+        assert!(is_satisfied(
+            &any_tag_int,
+            &gen_pv(None, vec![str_dat("a"), Datum::I64(1)])
+        ));
+        assert!(is_satisfied(&any_tag_int, &gen_pv(None, vec![str_dat("a")])) == false);
+        assert!(is_satisfied(&any_tag_int, &gen_pv(None, vec![])) == false);
+    }
+
+    #[test]
+    fn ser_deser() -> Result<()> {
+        for predicate in [
+            ValuePredicate {
+                member_path: vec![MemberKey::from("st;a\ttus")],
+                test: PredicateTest::Eq(str_dat("a;b\n,\tc")),
+            },
+            ValuePredicate {
+                member_path: vec![MemberKey::Idx(1), MemberKey::Each],
+                test: PredicateTest::Range {
+                    lo: None,
+                    hi: Some(Datum::Tuple(vec![Datum::F64(-1.5), Datum::Null])),
+                },
+            },
+            ValuePredicate {
+                member_path: vec![],
+                test: PredicateTest::Type(DatumType::Map),
+            },
+        ] {
+            let buf = predicate.ser_solo()?;
+            assert!(buf.iter().any(|b| *b == b'\n' || *b == b'\t') == false);
+            assert_eq!(ValuePredicate::deser_solo(&buf)?, predicate);
+        }
+
+        assert!(ValuePredicate::deser_solo(b"e;").is_err());
+        assert!(ValuePredicate::deser_solo(b"x;").is_err());
+        assert!(ValuePredicate::deser_solo(b"eZZ;").is_err());
+
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use crate::serde::{Datum, OptDatum, ReadResult, WriteLen};
 use anyhow::{anyhow, Result};
 use std::any;
 use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::io::{Cursor, Read, Seek, Write};
 
 pub trait Ser {
@@ -16,6 +17,8 @@ pub trait Ser {
 pub trait Deser: Sized {
     fn skip<R: Read + Seek>(r: &mut R) -> Result<ReadResult<()>>;
     fn deser<R: Read + Seek>(r: &mut R) -> Result<ReadResult<Self>>;
+    /// Copies the serialized bytes of one `Self`, appending them to `buf`.
+    fn read_ser<R: Read + Seek>(r: &mut R, buf: &mut Vec<u8>) -> Result<ReadResult<()>>;
 
     fn deser_solo(buf: &[u8]) -> Result<Self> {
         let mut r = Cursor::new(&buf);
@@ -47,6 +50,10 @@ where
                 let dat: &Datum = t.borrow();
                 dat.ser(w)
             }
+            OptDatum::Expiring(t, expires_at) => {
+                let dat: &Datum = t.borrow();
+                dat.ser_expiring(*expires_at, w)
+            }
         }
     }
 }
@@ -60,6 +67,9 @@ where
     fn skip<R: Read + Seek>(r: &mut R) -> Result<ReadResult<()>> {
         OptDatum::<Datum>::skip(r)
     }
+    fn read_ser<R: Read + Seek>(r: &mut R, buf: &mut Vec<u8>) -> Result<ReadResult<()>> {
+        OptDatum::<Datum>::read_ser(r, buf)
+    }
     fn deser<R: Read + Seek>(r: &mut R) -> Result<ReadResult<Self>> {
         match OptDatum::<Datum>::deser(r)? {
             ReadResult::EOF => Ok(ReadResult::EOF),
@@ -71,6 +81,10 @@ where
                 let moi = Self::try_from(dat).map_err(|e| anyhow!(e))?;
                 Ok(ReadResult::Some(r_len, moi))
             }
+            ReadResult::Some(_, OptDatum::Expiring(_, _)) => Err(anyhow!(
+                "Expiring datum while reading {}",
+                any::type_name::<Self>()
+            )),
         }
     }
 }
@@ -82,6 +96,9 @@ where
     fn skip<R: Read + Seek>(r: &mut R) -> Result<ReadResult<()>> {
         OptDatum::<Datum>::skip(r)
     }
+    fn read_ser<R: Read + Seek>(r: &mut R, buf: &mut Vec<u8>) -> Result<ReadResult<()>> {
+        OptDatum::<Datum>::read_ser(r, buf)
+    }
     fn deser<R: Read + Seek>(r: &mut R) -> Result<ReadResult<Self>> {
         match OptDatum::<Datum>::deser(r)? {
             ReadResult::EOF => Ok(ReadResult::EOF),
@@ -92,9 +109,21 @@ where
                 let t = T::try_from(dat).map_err(|e| anyhow!(e))?;
                 Ok(ReadResult::Some(r_len, OptDatum::Some(t)))
             }
+            ReadResult::Some(r_len, OptDatum::Expiring(dat, expires_at)) => {
+                let t = T::try_from(dat).map_err(|e| anyhow!(e))?;
+                Ok(ReadResult::Some(r_len, OptDatum::Expiring(t, expires_at)))
+            }
         }
     }
 }
 
+/* trait PartialOrdSer */
+/// A type that may be compared against `Q` while still serialized, via [`DatumRef`](crate::serde::DatumRef).
+/// Hence a reader may skip over serialized `Self`s without deserializing, i.e. allocating, each one.
+pub trait PartialOrdSer<Q>: Deser + PartialOrd<Q> {
+    /// Equivalent to `Self::deser_solo(buf)?.partial_cmp(q)`.
+    fn partial_cmp_ser(buf: &[u8], q: &Q) -> Result<Option<Ordering>>;
+}
+
 /* trait Serializable */
 pub trait Serializable: Ser + Deser {}
@@ -0,0 +1,761 @@
+use crate::io_utils;
+use crate::serde::{Datum, DatumRef, DatumType};
+use crate::types::{PKShared, PVShared, SVShared, SubValue, SubValueSpec, ValuePredicate};
+use anyhow::{anyhow, Result};
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::io::{BufRead, Cursor, Write};
+use std::str;
+use std::sync::Arc;
+
+/// [`ScndIdxSpec`] defines the key of a secondary index, as an ordered list of one or more [`SubValueSpec`]s, called columns.
+///
+/// #### Specification
+///
+/// With one column, the key is the sub-value extracted by the column.
+///
+/// With 2+ columns, i.e. if the spec is composite, the key is a [`Datum::Tuple`]
+/// whose `i`th member is the sub-value extracted by the `i`th column.
+/// - If any column extracts no sub-value, then the [`Value`](crate::types::Value) is not indexed.
+/// - If any column is multi-valued, then the keys are the cartesian product of all columns' sub-values.
+///
+/// For example, given a map-typed [`Value`](crate::types::Value), representing `{"tenant": "t1", "created_at": 100}`,
+/// if you want to index by tenant then by creation time:
+///
+/// ```text
+/// ScndIdxSpec {
+///     columns: vec![
+///         SubValueSpec {
+///             member_path: vec![MemberKey::from("tenant")],
+///             datum_type: DatumType::Str,
+///         },
+///         SubValueSpec {
+///             member_path: vec![MemberKey::from("created_at")],
+///             datum_type: DatumType::I64,
+///         },
+///     ],
+/// }
+/// ```
+///
+/// The key is `Datum::Tuple(vec![Datum::Str(String::from("t1")), Datum::I64(100)])`.
+///
+/// #### Range over a prefix of columns
+///
+/// Tuples are ordered lexicographically. Hence a lower bound may be a Tuple holding a prefix of the columns as is.
+/// An upper bound holding a prefix of the columns should be converted by [`Self::prefix_hi()`].
+///
+/// #### Projection
+///
+/// `projection` determines what each index entry stores besides its key. See [`ScndIdxProjection`].
+/// Two specs that differ only in `projection` define two distinct indexes.
+///
+/// #### Filter
+///
+/// If `filter` is present, then only the [`Value`](crate::types::Value)s that satisfy it are indexed.
+/// A write that makes a value start or stop satisfying it puts or tombstones the value's entries.
+/// Two specs that differ only in `filter` define two distinct indexes.
+///
+/// #### Uniqueness
+///
+/// If `is_unique`, then no two primary keys may share a key of the index.
+/// The engine rejects any write that would cause them to, and fails to create the index if existing values do.
+/// Two specs that differ only in `is_unique` define two distinct indexes.
+#[derive(PartialEq, Eq, Hash, Debug)]
+pub struct ScndIdxSpec {
+    /// Non-empty.
+    pub columns: Vec<SubValueSpec>,
+    pub projection: ScndIdxProjection,
+    pub filter: Option<ValuePredicate>,
+    pub is_unique: bool,
+}
+
+/// What each entry of a secondary index stores, as its value.
+#[derive(PartialEq, Eq, Hash, Debug, Default)]
+pub enum ScndIdxProjection {
+    /// The whole [`Value`](crate::types::Value). The value is duplicated once per index entry.
+    #[default]
+    Whole,
+    /// Nothing. The stored value is an empty [`Datum::Tuple`].
+    /// Reading the whole value requires a lookup in the primary keyspace.
+    KeysOnly,
+    /// A [`Datum::Tuple`] whose `i`th member is the sub-value extracted by the `i`th [`SubValueSpec`]:
+    /// - If the sub-value does not exist, then the member is [`Datum::Null`].
+    /// - If the [`SubValueSpec`] is multi-valued, then the member is a [`Datum::List`] of all sub-values.
+    ///
+    /// Reading the whole value requires a lookup in the primary keyspace.
+    Columns(Vec<SubValueSpec>),
+}
+
+impl From<SubValueSpec> for ScndIdxSpec {
+    fn from(column: SubValueSpec) -> Self {
+        Self {
+            columns: vec![column],
+            projection: ScndIdxProjection::Whole,
+            filter: None,
+            is_unique: false,
+        }
+    }
+}
+
+impl ScndIdxSpec {
+    pub fn is_composite(&self) -> bool {
+        self.columns.len() >= 2
+    }
+
+    pub fn is_multi_valued(&self) -> bool {
+        self.columns.iter().any(|column| column.is_multi_valued())
+    }
+
+    /// Whether each index entry stores the whole value.
+    pub fn is_projection_whole(&self) -> bool {
+        self.projection == ScndIdxProjection::Whole
+    }
+}
+
+/* Extraction. */
+impl ScndIdxSpec {
+    /// @return The extracted keys, sorted and deduplicated.
+    ///     Empty if `pv` does not satisfy the filter.
+    pub fn extract(&self, pv: &PVShared) -> Vec<SVShared> {
+        if let Some(filter) = self.filter.as_ref() {
+            if filter.is_satisfied_by(pv) == false {
+                return vec![];
+            }
+        }
+
+        if let [column] = &self.columns[..] {
+            return column.extract(pv);
+        }
+
+        /* Each column's sub-values are sorted. Hence the product is sorted lexicographically. */
+        let mut keys: Vec<Vec<Datum>> = vec![vec![]];
+        for column in self.columns.iter() {
+            let svs = column.extract(pv);
+            keys = keys
+                .iter()
+                .flat_map(|prefix| {
+                    svs.iter().map(move |sv| {
+                        let mut key = prefix.clone();
+                        key.push((sv as &Datum).clone());
+                        key
+                    })
+                })
+                .collect();
+        }
+        keys.into_iter()
+            .map(|key| SVShared::Own(Arc::new(SubValue(Datum::Tuple(key)))))
+            .collect()
+    }
+
+    /// @return The value that each index entry for `pv` stores.
+    pub fn project(&self, pv: &PVShared) -> PVShared {
+        match &self.projection {
+            ScndIdxProjection::Whole => Arc::clone(pv),
+            ScndIdxProjection::KeysOnly => PVShared::from(Datum::Tuple(vec![])),
+            ScndIdxProjection::Columns(proj_columns) => {
+                let members = proj_columns
+                    .iter()
+                    .map(|proj_column| {
+                        let svs = proj_column.extract(pv);
+                        let mut members = svs.iter().map(|sv| (sv as &Datum).clone());
+                        if proj_column.is_multi_valued() {
+                            Datum::List(members.collect())
+                        } else {
+                            members.next().unwrap_or(Datum::Null)
+                        }
+                    })
+                    .collect();
+                PVShared::from(Datum::Tuple(members))
+            }
+        }
+    }
+
+    /// See [`SubValueSpec::extract_delta()`].
+    ///
+    /// The sub-values to be put are the ones whose entries should be put, mapping to the projection of `new_pv`.
+    /// Regardless of the projection, they are all the new sub-values whenever the PV changed.
+    /// Hence a reader that depends on a range of this index also depends on the whole values that it looks up.
+    pub fn extract_delta(
+        &self,
+        old_pv: Option<&PVShared>,
+        new_pv: Option<&PVShared>,
+    ) -> (Vec<SVShared>, Vec<SVShared>) {
+        let old_svs = old_pv.map(|pv| self.extract(pv)).unwrap_or_default();
+        let new_svs = new_pv.map(|pv| self.extract(pv)).unwrap_or_default();
+        SubValueSpec::diff_extracted(old_svs, new_svs, old_pv != new_pv)
+    }
+
+    /// The counterpart of [`Self::extract()`], over a view of a serialized [`Value`](crate::types::Value).
+    /// Only the extracted sub-values are allocated.
+    pub fn extract_ref(&self, pv: DatumRef) -> Vec<SVShared> {
+        if let Some(filter) = self.filter.as_ref() {
+            if filter.is_satisfied_by_ref(pv) == false {
+                return vec![];
+            }
+        }
+
+        let to_sv = |dat: Datum| SVShared::Own(Arc::new(SubValue(dat)));
+
+        if let [column] = &self.columns[..] {
+            let svs = column.extract_ref(pv);
+            return svs.iter().map(|sv| to_sv(sv.to_datum())).collect();
+        }
+
+        /* Each column's sub-values are sorted. Hence the product is sorted lexicographically. */
+        let mut keys: Vec<Vec<DatumRef>> = vec![vec![]];
+        for column in self.columns.iter() {
+            let svs = column.extract_ref(pv);
+            keys = keys
+                .iter()
+                .flat_map(|prefix| {
+                    svs.iter().map(move |sv| {
+                        let mut key = prefix.clone();
+                        key.push(*sv);
+                        key
+                    })
+                })
+                .collect();
+        }
+        keys.into_iter()
+            .map(|key| to_sv(Datum::Tuple(key.iter().map(|sv| sv.to_datum()).collect())))
+            .collect()
+    }
+
+    /// The counterpart of [`Self::project()`], over a view of a serialized [`Value`](crate::types::Value).
+    pub fn project_ref(&self, pv: DatumRef) -> PVShared {
+        match &self.projection {
+            ScndIdxProjection::Whole => PVShared::from(pv.to_datum()),
+            ScndIdxProjection::KeysOnly => PVShared::from(Datum::Tuple(vec![])),
+            ScndIdxProjection::Columns(proj_columns) => {
+                let members = proj_columns
+                    .iter()
+                    .map(|proj_column| {
+                        let svs = proj_column.extract_ref(pv);
+                        let mut members = svs.iter().map(|sv| sv.to_datum());
+                        if proj_column.is_multi_valued() {
+                            Datum::List(members.collect())
+                        } else {
+                            members.next().unwrap_or(Datum::Null)
+                        }
+                    })
+                    .collect();
+                PVShared::from(Datum::Tuple(members))
+            }
+        }
+    }
+
+    /// The counterpart of [`Self::extract_delta()`], where the old value is a view of a serialized [`Value`](crate::types::Value).
+    pub fn extract_delta_ref(
+        &self,
+        old_pv: Option<DatumRef>,
+        new_pv: Option<&PVShared>,
+    ) -> (Vec<SVShared>, Vec<SVShared>) {
+        let old_svs = old_pv.map(|pv| self.extract_ref(pv)).unwrap_or_default();
+        let new_svs = new_pv.map(|pv| self.extract(pv)).unwrap_or_default();
+        let is_pv_changed = match (old_pv, new_pv) {
+            (None, None) => false,
+            (Some(old_pv), Some(new_pv)) => old_pv != ***new_pv,
+            _ => true,
+        };
+        SubValueSpec::diff_extracted(old_svs, new_svs, is_pv_changed)
+    }
+}
+
+/* Uniqueness. */
+impl ScndIdxSpec {
+    /// @arg other_pk A primary key that is already indexed under `sv`.
+    /// @return Err if `self` is unique and `other_pk` is not `pk`.
+    pub fn check_unique(
+        self: &Arc<Self>,
+        sv: &SVShared,
+        pk: &PKShared,
+        other_pk: &PKShared,
+    ) -> Result<(), UniquenessViolation> {
+        if self.is_unique && pk != other_pk {
+            return Err(UniquenessViolation {
+                spec: Arc::clone(self),
+                sv: sv.clone(),
+                pk: pk.clone(),
+                other_pk: other_pk.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The error of putting a [`Value`](crate::types::Value) whose key in a unique index is already indexed
+/// under another primary key, or of creating a unique index that existing values violate.
+#[derive(Debug)]
+pub struct UniquenessViolation {
+    pub spec: Arc<ScndIdxSpec>,
+    pub sv: SVShared,
+    pub pk: PKShared,
+    pub other_pk: PKShared,
+}
+impl Display for UniquenessViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The values at {:?} and {:?} share {:?} in unique {:?}",
+            self.pk, self.other_pk, self.sv, self.spec
+        )
+    }
+}
+impl Error for UniquenessViolation {}
+
+/* Range over a prefix of columns. */
+impl ScndIdxSpec {
+    /// Converts an upper bound holding a prefix of the columns into an upper bound
+    /// that is greater than or equal to every key starting with that prefix.
+    ///
+    /// The conversion appends a datum whose type is greater than the next column's `datum_type`.
+    /// If the next column's `datum_type` is the greatest, i.e. [`DatumType::List`], then the conversion fails.
+    ///
+    /// @return None if no conversion is necessary, i.e. if `self` is not composite, or `hi` is not a prefix.
+    pub fn prefix_hi(&self, hi: &SubValue) -> Result<Option<SubValue>> {
+        let prefix = match (self.is_composite(), hi as &Datum) {
+            (true, Datum::Tuple(prefix)) if prefix.len() < self.columns.len() => prefix,
+            _ => return Ok(None),
+        };
+
+        let next_column = &self.columns[prefix.len()];
+        if next_column.datum_type == DatumType::List {
+            return Err(anyhow!(
+                "Cannot range over a prefix of columns followed by a List-typed column {next_column:?}"
+            ));
+        }
+
+        let mut key = prefix.clone();
+        key.push(Datum::List(vec![]));
+        Ok(Some(SubValue(Datum::Tuple(key))))
+    }
+}
+
+/* De/Serialization. */
+#[allow(clippy::write_with_newline)] // We must be consistent re: '\n' vs '\r\n'.
+impl ScndIdxSpec {
+    /// Uniqueness, if so, is serialized first, as `u;`.
+    ///
+    /// The filter, if present, is serialized next, as `f;` followed by the [`ValuePredicate`] terminated by `\t`.
+    ///
+    /// The projection, unless [`ScndIdxProjection::Whole`], is serialized next:
+    /// - [`ScndIdxProjection::KeysOnly`] as `k;`.
+    /// - [`ScndIdxProjection::Columns`] as `p<column count>;`, followed by each projected column terminated by `\t`.
+    ///
+    /// Then, a non-composite spec is serialized identically to its one [`SubValueSpec`].
+    ///
+    /// A composite spec is serialized as `c<column count>;`, followed by each column terminated by `\t`.
+    /// Note, [`SubValueSpec::ser()`] never writes `\t`.
+    pub fn ser<W: Write>(&self, w: &mut W) -> Result<()> {
+        if self.is_unique {
+            write!(w, "u;")?;
+        }
+
+        if let Some(filter) = self.filter.as_ref() {
+            write!(w, "f;")?;
+            filter.ser(w)?;
+            write!(w, "\t")?;
+        }
+
+        match &self.projection {
+            ScndIdxProjection::Whole => {}
+            ScndIdxProjection::KeysOnly => write!(w, "k;")?,
+            ScndIdxProjection::Columns(proj_columns) => {
+                write!(w, "p{};", proj_columns.len())?;
+                for proj_column in proj_columns.iter() {
+                    proj_column.ser(w)?;
+                    write!(w, "\t")?;
+                }
+            }
+        }
+
+        if let [column] = &self.columns[..] {
+            return column.ser(w);
+        }
+
+        write!(w, "c{};", self.columns.len())?;
+        for column in self.columns.iter() {
+            column.ser(w)?;
+            write!(w, "\t")?;
+        }
+
+        Ok(())
+    }
+
+    pub fn deser<R: BufRead>(r: &mut R) -> Result<Self> {
+        let mut buf = vec![];
+
+        /* uniqueness, filter and projection */
+        let mut is_unique = false;
+        let mut filter = None;
+        let mut projection = ScndIdxProjection::Whole;
+        loop {
+            buf.clear();
+            io_utils::read_until_then_trim(r, b';', &mut buf)?;
+            match buf.split_first() {
+                Some((b'u', [])) => is_unique = true,
+                Some((b'f', [])) => {
+                    buf.clear();
+                    io_utils::read_until_then_trim(r, b'\t', &mut buf)?;
+                    filter = Some(ValuePredicate::deser_solo(&buf)?);
+                }
+                Some((b'k', [])) => projection = ScndIdxProjection::KeysOnly,
+                Some((b'p', col_ct)) => {
+                    let col_ct = str::from_utf8(col_ct)?.parse::<usize>()?;
+                    projection = ScndIdxProjection::Columns(Self::deser_columns(r, col_ct)?);
+                }
+                _ => break,
+            }
+        }
+
+        /* columns */
+        let col_ct = match buf.strip_prefix(b"c") {
+            None => {
+                let column = SubValueSpec::deser_after_datum_type(&buf, r)?;
+                return Ok(Self {
+                    columns: vec![column],
+                    projection,
+                    filter,
+                    is_unique,
+                });
+            }
+            Some(col_ct) => str::from_utf8(col_ct)?.parse::<usize>()?,
+        };
+        if col_ct < 2 {
+            return Err(anyhow!("Invalid column count {col_ct} of composite spec."));
+        }
+        let columns = Self::deser_columns(r, col_ct)?;
+
+        Ok(Self {
+            columns,
+            projection,
+            filter,
+            is_unique,
+        })
+    }
+
+    fn deser_columns<R: BufRead>(r: &mut R, col_ct: usize) -> Result<Vec<SubValueSpec>> {
+        let mut buf = vec![];
+        let mut columns = vec![];
+        for _ in 0..col_ct {
+            buf.clear();
+            io_utils::read_until_then_trim(r, b'\t', &mut buf)?;
+            let column = SubValueSpec::deser_solo(&buf)?;
+            columns.push(column);
+        }
+        Ok(columns)
+    }
+
+    pub fn ser_solo(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        self.ser(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn deser_solo(buf: &[u8]) -> Result<Self> {
+        let mut r = Cursor::new(&buf);
+        Self::deser(&mut r)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{MemberKey, PredicateTest, Ser, Value};
+    use std::collections::BTreeMap;
+
+    fn str_dat(s: &str) -> Datum {
+        Datum::Str(String::from(s))
+    }
+
+    fn spec_tenant_tags_ts() -> ScndIdxSpec {
+        ScndIdxSpec {
+            projection: ScndIdxProjection::Whole,
+            filter: None,
+            is_unique: false,
+            columns: vec![
+                SubValueSpec {
+                    member_path: vec![MemberKey::from("tenant")],
+                    datum_type: DatumType::Str,
+                },
+                SubValueSpec {
+                    member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+                    datum_type: DatumType::Str,
+                },
+                SubValueSpec {
+                    member_path: vec![MemberKey::from("created_at")],
+                    datum_type: DatumType::Timestamp,
+                },
+            ],
+        }
+    }
+
+    fn gen_pv(tenant: Option<&str>, tags: &[&str], created_at: i64) -> PVShared {
+        let mut members = BTreeMap::new();
+        if let Some(tenant) = tenant {
+            members.insert(String::from("tenant"), str_dat(tenant));
+        }
+        let tags = tags.iter().map(|tag| str_dat(tag)).collect();
+        members.insert(String::from("tags"), Datum::List(tags));
+        members.insert(String::from("created_at"), Datum::Timestamp(created_at));
+        PVShared::from(Datum::Map(members))
+    }
+
+    fn key(tenant: &str, tag: &str, created_at: i64) -> SubValue {
+        SubValue(Datum::Tuple(vec![
+            str_dat(tenant),
+            str_dat(tag),
+            Datum::Timestamp(created_at),
+        ]))
+    }
+
+    #[test]
+    fn extract() {
+        let spec = spec_tenant_tags_ts();
+
+        let svs = spec.extract(&gen_pv(Some("t1"), &["b", "a", "b"], 100));
+        let svs = svs.iter().map(|sv| sv as &SubValue).collect::<Vec<_>>();
+        assert_eq!(svs, vec![&key("t1", "a", 100), &key("t1", "b", 100)]);
+
+        assert!(spec.extract(&gen_pv(None, &["a"], 100)).is_empty());
+        assert!(spec.extract(&gen_pv(Some("t1"), &[], 100)).is_empty());
+
+        let spec = ScndIdxSpec::from(SubValueSpec::whole(DatumType::Str));
+        let svs = spec.extract(&PVShared::from(str_dat("a")));
+        let svs = svs.iter().map(|sv| sv as &SubValue).collect::<Vec<_>>();
+        assert_eq!(svs, vec![&SubValue(str_dat("a"))]);
+    }
+
+    #[test]
+    fn prefix_hi() -> Result<()> {
+        let spec = spec_tenant_tags_ts();
+
+        let prefix = SubValue(Datum::Tuple(vec![str_dat("t1")]));
+        let hi = spec.prefix_hi(&prefix)?.unwrap();
+        assert!(prefix < key("t1", "", i64::MIN));
+        assert!(key("t1", "zzz", i64::MAX) < hi);
+        assert!(hi < key("t2", "", i64::MIN));
+
+        let prefix = SubValue(Datum::Tuple(vec![str_dat("t1"), str_dat("a")]));
+        let hi = spec.prefix_hi(&prefix)?.unwrap();
+        assert!(key("t1", "a", i64::MAX) < hi);
+        assert!(hi < key("t1", "b", i64::MIN));
+
+        assert!(spec.prefix_hi(&key("t1", "a", 100))?.is_none());
+        assert!(spec.prefix_hi(&SubValue(str_dat("t1")))?.is_none());
+
+        let spec = ScndIdxSpec {
+            columns: vec![
+                SubValueSpec::whole(DatumType::Str),
+                SubValueSpec::whole(DatumType::List),
+            ],
+            projection: ScndIdxProjection::Whole,
+            filter: None,
+            is_unique: false,
+        };
+        let prefix = SubValue(Datum::Tuple(vec![str_dat("a")]));
+        assert!(spec.prefix_hi(&prefix).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn extract_filtered() {
+        let spec = ScndIdxSpec {
+            filter: Some(ValuePredicate {
+                member_path: vec![MemberKey::from("created_at")],
+                test: PredicateTest::Range {
+                    lo: Some(Datum::Timestamp(100)),
+                    hi: None,
+                },
+            }),
+            ..spec_tenant_tags_ts()
+        };
+
+        let svs = spec.extract(&gen_pv(Some("t1"), &["a"], 100));
+        assert_eq!(svs.len(), 1);
+        assert!(spec.extract(&gen_pv(Some("t1"), &["a"], 99)).is_empty());
+
+        let (old_svs, new_svs) = spec.extract_delta(
+            Some(&gen_pv(Some("t1"), &["a"], 100)),
+            Some(&gen_pv(Some("t1"), &["a"], 99)),
+        );
+        assert_eq!(old_svs.len(), 1);
+        assert!(new_svs.is_empty());
+
+        let (old_svs, new_svs) = spec.extract_delta(
+            Some(&gen_pv(Some("t1"), &["a"], 99)),
+            Some(&gen_pv(Some("t1"), &["a"], 100)),
+        );
+        assert!(old_svs.is_empty());
+        assert_eq!(new_svs.len(), 1);
+    }
+
+    #[test]
+    fn check_unique() {
+        let pk_a = PKShared::from(Datum::I64(1));
+        let pk_b = PKShared::from(Datum::I64(2));
+        let sv = SVShared::Own(Arc::new(SubValue(str_dat("a"))));
+
+        let spec = Arc::new(spec_tenant_tags_ts());
+        assert!(spec.check_unique(&sv, &pk_a, &pk_b).is_ok());
+
+        let spec = Arc::new(ScndIdxSpec {
+            is_unique: true,
+            ..spec_tenant_tags_ts()
+        });
+        assert!(spec.check_unique(&sv, &pk_a, &pk_a).is_ok());
+        let violation = spec.check_unique(&sv, &pk_a, &pk_b).unwrap_err();
+        assert_eq!(violation.pk, pk_a);
+        assert_eq!(violation.other_pk, pk_b);
+    }
+
+    #[test]
+    fn project() {
+        let pv = gen_pv(Some("t1"), &["b", "a"], 100);
+
+        let mut spec = spec_tenant_tags_ts();
+        assert_eq!(spec.project(&pv), pv);
+
+        spec.projection = ScndIdxProjection::KeysOnly;
+        assert_eq!(*spec.project(&pv), Value(Datum::Tuple(vec![])));
+
+        spec.projection = ScndIdxProjection::Columns(vec![
+            SubValueSpec {
+                member_path: vec![MemberKey::from("created_at")],
+                datum_type: DatumType::Timestamp,
+            },
+            SubValueSpec {
+                member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+                datum_type: DatumType::Str,
+            },
+            SubValueSpec {
+                member_path: vec![MemberKey::from("absent")],
+                datum_type: DatumType::Str,
+            },
+        ]);
+        assert_eq!(
+            *spec.project(&pv),
+            Value(Datum::Tuple(vec![
+                Datum::Timestamp(100),
+                Datum::List(vec![str_dat("a"), str_dat("b")]),
+                Datum::Null,
+            ]))
+        );
+    }
+
+    #[test]
+    fn extract_ref() {
+        let filtered = ScndIdxSpec {
+            filter: Some(ValuePredicate {
+                member_path: vec![MemberKey::from("created_at")],
+                test: PredicateTest::Range {
+                    lo: Some(Datum::Timestamp(100)),
+                    hi: None,
+                },
+            }),
+            ..spec_tenant_tags_ts()
+        };
+        let projected = ScndIdxSpec {
+            projection: ScndIdxProjection::Columns(vec![SubValueSpec {
+                member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+                datum_type: DatumType::Str,
+            }]),
+            ..ScndIdxSpec::from(SubValueSpec {
+                member_path: vec![MemberKey::from("tenant")],
+                datum_type: DatumType::Str,
+            })
+        };
+        let pvs = [
+            gen_pv(Some("t1"), &["b", "a", "b"], 100),
+            gen_pv(Some("t1"), &["a"], 99),
+            gen_pv(None, &["a"], 100),
+            gen_pv(Some("t2"), &[], 100),
+        ];
+
+        for spec in [spec_tenant_tags_ts(), filtered, projected] {
+            for old_pv in pvs.iter() {
+                let old_pv_buf = old_pv.ser_solo().unwrap();
+                let old_pv_ref = DatumRef::deser_solo(&old_pv_buf).unwrap();
+
+                assert_eq!(spec.extract_ref(old_pv_ref), spec.extract(old_pv));
+                assert_eq!(spec.project_ref(old_pv_ref), spec.project(old_pv));
+
+                for new_pv in pvs.iter() {
+                    assert_eq!(
+                        spec.extract_delta_ref(Some(old_pv_ref), Some(new_pv)),
+                        spec.extract_delta(Some(old_pv), Some(new_pv)),
+                    );
+                }
+                assert_eq!(
+                    spec.extract_delta_ref(Some(old_pv_ref), None),
+                    spec.extract_delta(Some(old_pv), None),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ser_deser() -> Result<()> {
+        let keys_only = ScndIdxSpec {
+            projection: ScndIdxProjection::KeysOnly,
+            ..spec_tenant_tags_ts()
+        };
+        let unique = ScndIdxSpec {
+            is_unique: true,
+            ..spec_tenant_tags_ts()
+        };
+        let filtered = ScndIdxSpec {
+            filter: Some(ValuePredicate {
+                member_path: vec![MemberKey::from("status")],
+                test: PredicateTest::Eq(str_dat("active")),
+            }),
+            ..spec_tenant_tags_ts()
+        };
+        let projected = ScndIdxSpec {
+            is_unique: true,
+            filter: Some(ValuePredicate {
+                member_path: vec![],
+                test: PredicateTest::Type(DatumType::I64),
+            }),
+            columns: vec![SubValueSpec::whole(DatumType::I64)],
+            projection: ScndIdxProjection::Columns(vec![
+                SubValueSpec::whole(DatumType::I64),
+                SubValueSpec {
+                    member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+                    datum_type: DatumType::Str,
+                },
+            ]),
+        };
+        for spec in [
+            keys_only,
+            unique,
+            filtered,
+            projected,
+            spec_tenant_tags_ts(),
+            ScndIdxSpec::from(SubValueSpec {
+                member_path: vec![MemberKey::Idx(1), MemberKey::from("a\tb")],
+                datum_type: DatumType::Str,
+            }),
+        ] {
+            let buf = spec.ser_solo()?;
+            assert!(buf.contains(&b'\n') == false);
+            assert_eq!(ScndIdxSpec::deser_solo(&buf)?, spec);
+        }
+
+        /* A non-composite spec is compatible with SubValueSpec. */
+        let column = SubValueSpec {
+            member_path: vec![MemberKey::Idx(2)],
+            datum_type: DatumType::I64,
+        };
+        let buf = column.ser_solo()?;
+        assert_eq!(ScndIdxSpec::from(column).ser_solo()?, buf);
+
+        assert!(ScndIdxSpec::deser_solo(b"c1;1;\t").is_err());
+        assert!(ScndIdxSpec::deser_solo(b"k;").is_err());
+        assert!(ScndIdxSpec::deser_solo(b"u;").is_err());
+        assert!(ScndIdxSpec::deser_solo(b"c2;1;\t").is_err());
+
+        Ok(())
+    }
+}
@@ -2,7 +2,8 @@ use crate::serde::Datum;
 use crate::types::PVShared;
 use derive_more::{Deref, From};
 use owning_ref::OwningRef;
-use std::cmp::{Ord, PartialOrd};
+use std::borrow::Borrow;
+use std::cmp::{Ord, Ordering, PartialOrd};
 use std::ops::Deref;
 use std::sync::Arc;
 
@@ -36,3 +37,20 @@ impl PartialEq for SVShared {
     }
 }
 impl Eq for SVShared {}
+impl PartialOrd for SVShared {
+    fn partial_cmp(&self, other: &SVShared) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for SVShared {
+    fn cmp(&self, other: &SVShared) -> Ordering {
+        (self as &SubValue).cmp(other as &SubValue)
+    }
+}
+
+/* SVShared is comparable as a SubValue, eg by SVPKShared. */
+impl Borrow<SubValue> for SVShared {
+    fn borrow(&self) -> &SubValue {
+        self
+    }
+}
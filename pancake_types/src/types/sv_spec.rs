@@ -1,28 +1,34 @@
 use crate::io_utils;
-use crate::serde::{Datum, DatumType, DatumTypeInt};
-use crate::types::{SVShared, Value};
-use anyhow::Result;
+use crate::serde::{Datum, DatumRef, DatumType, DatumTypeInt};
+use crate::types::{PVShared, SVShared, SubValue, Value};
+use anyhow::{anyhow, Result};
 use owning_ref::OwningRef;
 use std::io::{BufRead, Cursor, Write};
 use std::str;
 use std::sync::Arc;
 
+#[cfg(test)]
 mod test;
 
-/// [`SubValueSpec`] specifies a contiguous sub-portion of a [`Value`].
+/// [`SubValueSpec`] specifies a contiguous sub-portion of a [`Value`],
+/// or, if the spec is multi-valued, zero or more such sub-portions.
 ///
-/// The spec is a DSL for locating this sub-portion,
-/// as well as an extractor of this sub-portion.
+/// The spec is a DSL for locating these sub-portions,
+/// as well as an extractor of these sub-portions.
 ///
 /// #### Specification
 ///
-/// `member_idxs`:
-/// - The empty `member_idxs` specifies the whole [`Value`].
-/// - Each `member_idx` specifies a member within a [`Datum::Tuple`].
-///   A series of `member_idx`s specifies members within nested Tuples.
+/// `member_path`:
+/// - The empty `member_path` specifies the whole [`Value`].
+/// - Each [`MemberKey::Idx`] specifies a member within a [`Datum::Tuple`] or a [`Datum::List`].
+/// - Each [`MemberKey::Name`] specifies a member within a [`Datum::Map`].
+/// - Each [`MemberKey::Each`] specifies every element within a [`Datum::List`].
+///   A spec containing it is multi-valued, and may be used as an inverted index.
+/// - A series of [`MemberKey`]s specifies members within nested Tuples, Lists and Maps.
 ///
 /// `datum_type`:
 /// - The `datum_type` equalling [`DatumType::Tuple`] specifies the whole (nested) Tuple.
+///   Ditto for [`DatumType::Map`].
 ///
 /// For example, given a tuple-typed [`Value`]
 ///
@@ -46,7 +52,7 @@ mod test;
 ///
 /// ```text
 /// SubValueSpec {
-///     member_idxs: vec![1, 0],
+///     member_path: vec![MemberKey::Idx(1), MemberKey::Idx(0)],
 ///     datum_type: DatumType::I64,
 /// }
 /// ```
@@ -55,48 +61,210 @@ mod test;
 ///
 /// ```text
 /// SubValueSpec {
-///     member_idxs: vec![1, 2],
+///     member_path: vec![MemberKey::Idx(1), MemberKey::Idx(2)],
 ///     datum_type: DatumType::Tuple,
 /// }
 /// ```
+///
+/// Given a map-typed [`Value`], representing `{"user": {"address": {"zip": "12345"}}}`,
+/// if you want to specify the zip code:
+///
+/// ```text
+/// SubValueSpec {
+///     member_path: vec![
+///         MemberKey::from("user"),
+///         MemberKey::from("address"),
+///         MemberKey::from("zip"),
+///     ],
+///     datum_type: DatumType::Str,
+/// }
+/// ```
+///
+/// Given a map-typed [`Value`], representing `{"tags": ["a", "b"]}`,
+/// if you want to specify every tag:
+///
+/// ```text
+/// SubValueSpec {
+///     member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+///     datum_type: DatumType::Str,
+/// }
+/// ```
 #[derive(PartialEq, Eq, Hash, Debug)]
 pub struct SubValueSpec {
-    pub member_idxs: Vec<u32>,
+    pub member_path: Vec<MemberKey>,
     pub datum_type: DatumType,
 }
 
+/// One step of [`SubValueSpec::member_path`].
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub enum MemberKey {
+    Idx(u32),
+    Name(String),
+    Each,
+}
+impl From<u32> for MemberKey {
+    fn from(idx: u32) -> Self {
+        Self::Idx(idx)
+    }
+}
+impl From<&str> for MemberKey {
+    fn from(name: &str) -> Self {
+        Self::Name(String::from(name))
+    }
+}
+
 /* Shorthand helper for a non-nested spec. */
 impl SubValueSpec {
     pub fn whole(datum_type: DatumType) -> Self {
         Self {
-            member_idxs: vec![],
+            member_path: vec![],
             datum_type,
         }
     }
+
+    pub fn is_multi_valued(&self) -> bool {
+        self.member_path.contains(&MemberKey::Each)
+    }
 }
 
 /* Extraction. */
 impl SubValueSpec {
-    pub fn extract(&self, pv: &Arc<Value>) -> Option<SVShared> {
-        let mut dat: &Datum = pv;
-        for member_idx in self.member_idxs.iter() {
-            let member_idx = *member_idx as usize;
-            match dat {
-                Datum::Tuple(members) if member_idx < members.len() => {
-                    dat = &members[member_idx];
+    /// @return The extracted sub-values, sorted and deduplicated.
+    ///     If `self` is not multi-valued, then there is at most one sub-value.
+    pub fn extract(&self, pv: &Arc<Value>) -> Vec<SVShared> {
+        let mut dats = vec![];
+        Self::walk(pv, &self.member_path, &mut dats);
+
+        let mut svs = dats
+            .into_iter()
+            .filter(|dat| DatumType::from(*dat) == self.datum_type)
+            .map(|dat| {
+                let dat = dat as *const _;
+                let dat = unsafe { &*dat };
+                let ownref = OwningRef::new(Arc::clone(pv)).map(|_| dat);
+                SVShared::Ref(ownref)
+            })
+            .collect::<Vec<_>>();
+        svs.sort_by(|a, b| (a as &SubValue).cmp(b));
+        svs.dedup();
+        svs
+    }
+
+    fn walk<'a>(dat: &'a Datum, member_path: &[MemberKey], out: &mut Vec<&'a Datum>) {
+        let (member_key, rest_path) = match member_path.split_first() {
+            None => {
+                out.push(dat);
+                return;
+            }
+            Some(pair) => pair,
+        };
+        match (dat, member_key) {
+            (Datum::Tuple(members) | Datum::List(members), MemberKey::Idx(member_idx)) => {
+                if let Some(member) = members.get(*member_idx as usize) {
+                    Self::walk(member, rest_path, out);
+                }
+            }
+            (Datum::Map(members), MemberKey::Name(member_name)) => {
+                if let Some(member) = members.get(member_name) {
+                    Self::walk(member, rest_path, out);
+                }
+            }
+            (Datum::List(members), MemberKey::Each) => {
+                for member in members.iter() {
+                    Self::walk(member, rest_path, out);
                 }
-                _ => return None,
             }
+            _ => (),
         }
+    }
+
+    /// The counterpart of [`Self::extract()`], over a view of a serialized [`Value`].
+    ///
+    /// @return The extracted sub-values, sorted and deduplicated.
+    pub fn extract_ref<'a>(&self, pv: DatumRef<'a>) -> Vec<DatumRef<'a>> {
+        let mut svs = vec![];
+        self.for_each_ref(pv, &mut |sv| svs.push(sv));
+        svs.sort();
+        svs.dedup();
+        svs
+    }
 
-        if DatumType::from(dat) == self.datum_type {
-            let dat = dat as *const _;
-            let dat = unsafe { &*dat };
-            let ownref = OwningRef::new(Arc::clone(pv)).map(|_| dat);
-            return Some(SVShared::Ref(ownref));
+    /// Visits each sub-value within a view of a serialized [`Value`], without allocating.
+    /// A multi-valued spec may visit a sub-value more than once, in the order of appearance.
+    pub fn for_each_ref<'a>(&self, pv: DatumRef<'a>, f: &mut impl FnMut(DatumRef<'a>)) {
+        Self::walk_ref(pv, &self.member_path, &mut |dat| {
+            if DatumType::from(&dat) == self.datum_type {
+                f(dat);
+            }
+        });
+    }
+
+    fn walk_ref<'a>(
+        dat: DatumRef<'a>,
+        member_path: &[MemberKey],
+        f: &mut impl FnMut(DatumRef<'a>),
+    ) {
+        let (member_key, rest_path) = match member_path.split_first() {
+            None => {
+                f(dat);
+                return;
+            }
+            Some(pair) => pair,
+        };
+        match (dat, member_key) {
+            (DatumRef::Tuple(members) | DatumRef::List(members), MemberKey::Idx(member_idx)) => {
+                if let Some(member) = members.get(*member_idx as usize) {
+                    Self::walk_ref(member, rest_path, f);
+                }
+            }
+            (DatumRef::Map(members), MemberKey::Name(member_name)) => {
+                if let Some(member) = members.get(member_name) {
+                    Self::walk_ref(member, rest_path, f);
+                }
+            }
+            (DatumRef::List(members), MemberKey::Each) => {
+                for member in members.iter() {
+                    Self::walk_ref(member, rest_path, f);
+                }
+            }
+            _ => (),
         }
+    }
 
-        None
+    /// Derives the changes to a secondary index,
+    /// caused by the value of one primary key changing from `old_pv` to `new_pv`.
+    ///
+    /// @return
+    ///     - The sub-values whose entries should be tombstoned.
+    ///     - The sub-values whose entries should be put, mapping to `new_pv`.
+    pub fn extract_delta(
+        &self,
+        old_pv: Option<&PVShared>,
+        new_pv: Option<&PVShared>,
+    ) -> (Vec<SVShared>, Vec<SVShared>) {
+        let old_svs = old_pv.map(|pv| self.extract(pv)).unwrap_or_default();
+        let new_svs = new_pv.map(|pv| self.extract(pv)).unwrap_or_default();
+        Self::diff_extracted(old_svs, new_svs, old_pv != new_pv)
+    }
+
+    /// @arg old_svs, new_svs Sorted and deduplicated.
+    pub(crate) fn diff_extracted(
+        old_svs: Vec<SVShared>,
+        new_svs: Vec<SVShared>,
+        is_pv_changed: bool,
+    ) -> (Vec<SVShared>, Vec<SVShared>) {
+        let tomb_svs = old_svs
+            .into_iter()
+            .filter(|old_sv| {
+                let found = new_svs.binary_search_by(|new_sv| (new_sv as &SubValue).cmp(old_sv));
+                found.is_err()
+            })
+            .collect::<Vec<_>>();
+
+        // If the PV changed, then every entry needs to be updated to the new PV.
+        let put_svs = if is_pv_changed { new_svs } else { vec![] };
+
+        (tomb_svs, put_svs)
     }
 }
 
@@ -107,10 +275,8 @@ impl SubValueSpec {
         let datum_type_int = DatumTypeInt::from(self.datum_type);
         write!(w, "{};", *datum_type_int)?;
 
-        /* member_idxs */
-        for member_idx in self.member_idxs.iter() {
-            write!(w, "{},", member_idx)?;
-        }
+        /* member_path */
+        Self::ser_member_path(w, &self.member_path)?;
 
         Ok(())
     }
@@ -120,26 +286,94 @@ impl SubValueSpec {
 
         /* datum_type */
         io_utils::read_until_then_trim(r, b';', &mut buf)?;
-        let datum_type_int = str::from_utf8(&buf)?.parse::<u8>()?;
+        Self::deser_after_datum_type(&buf, r)
+    }
+
+    /// @arg datum_type_buf The already-read `datum_type`, excluding its terminating `;`.
+    pub(crate) fn deser_after_datum_type<R: BufRead>(
+        datum_type_buf: &[u8],
+        r: &mut R,
+    ) -> Result<Self> {
+        /* datum_type */
+        let datum_type_int = str::from_utf8(datum_type_buf)?.parse::<u8>()?;
         let datum_type_int = DatumTypeInt::from(datum_type_int);
         let datum_type = DatumType::try_from(datum_type_int)?;
 
-        /* member_idxs */
-        let mut member_idxs = vec![];
+        /* member_path */
+        let member_path = Self::deser_member_path(r)?;
+
+        Ok(Self {
+            member_path,
+            datum_type,
+        })
+    }
+
+    /// Each [`MemberKey`] is terminated by `,`.
+    pub(crate) fn ser_member_path<W: Write>(w: &mut W, member_path: &[MemberKey]) -> Result<()> {
+        for member_key in member_path.iter() {
+            match member_key {
+                MemberKey::Idx(member_idx) => write!(w, "{},", member_idx)?,
+                MemberKey::Each => write!(w, "*,")?,
+                MemberKey::Name(member_name) => {
+                    w.write_all(b".")?;
+                    Self::ser_escaped_name(w, member_name)?;
+                    w.write_all(b",")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads until EOF.
+    pub(crate) fn deser_member_path<R: BufRead>(r: &mut R) -> Result<Vec<MemberKey>> {
+        let mut buf = vec![];
+        let mut member_path = vec![];
         loop {
             buf.clear();
             io_utils::read_until_then_trim(r, b',', &mut buf)?;
             if buf.is_empty() {
                 break;
             }
-            let member_idx = str::from_utf8(&buf)?.parse::<u32>()?;
-            member_idxs.push(member_idx);
+            let member_key = if buf == b"*" {
+                MemberKey::Each
+            } else if let Some(escaped_name) = buf.strip_prefix(b".") {
+                MemberKey::Name(Self::deser_escaped_name(escaped_name)?)
+            } else {
+                MemberKey::Idx(str::from_utf8(&buf)?.parse::<u32>()?)
+            };
+            member_path.push(member_key);
         }
+        Ok(member_path)
+    }
 
-        Ok(Self {
-            member_idxs,
-            datum_type,
-        })
+    /// Names are percent-escaped, so that they never contain the delimiters `,` and `\n`.
+    fn ser_escaped_name<W: Write>(w: &mut W, name: &str) -> Result<()> {
+        for byte in name.bytes() {
+            if byte == b'%' || byte == b',' || byte.is_ascii_control() {
+                write!(w, "%{:02X}", byte)?;
+            } else {
+                w.write_all(&[byte])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn deser_escaped_name(escaped: &[u8]) -> Result<String> {
+        let mut bytes = Vec::with_capacity(escaped.len());
+        let mut iter = escaped.iter();
+        while let Some(byte) = iter.next() {
+            if *byte == b'%' {
+                let hex = iter
+                    .as_slice()
+                    .get(0..2)
+                    .ok_or_else(|| anyhow!("Truncated escape sequence in member name."))?;
+                bytes.push(u8::from_str_radix(str::from_utf8(hex)?, 16)?);
+                iter.nth(1);
+            } else {
+                bytes.push(*byte);
+            }
+        }
+        Ok(String::from_utf8(bytes)?)
     }
 
     pub fn ser_solo(&self) -> Result<Vec<u8>> {
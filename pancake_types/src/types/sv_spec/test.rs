@@ -1,148 +1,358 @@
-#[cfg(test)]
-mod test {
-    use super::super::*;
+use super::*;
 
-    fn gen_spec_whole() -> SubValueSpec {
-        SubValueSpec::whole(DatumType::Str)
+fn gen_spec_whole() -> SubValueSpec {
+    SubValueSpec::whole(DatumType::Str)
+}
+fn gen_spec_partial_depth1() -> SubValueSpec {
+    SubValueSpec {
+        member_path: vec![MemberKey::Idx(1)],
+        datum_type: DatumType::Str,
     }
-    fn gen_spec_partial_depth1() -> SubValueSpec {
-        SubValueSpec {
-            member_idxs: vec![1],
-            datum_type: DatumType::Str,
-        }
+}
+fn gen_spec_map_path() -> SubValueSpec {
+    SubValueSpec {
+        member_path: vec![
+            MemberKey::from("user"),
+            MemberKey::Idx(1),
+            MemberKey::from("zip"),
+        ],
+        datum_type: DatumType::Str,
     }
-    fn gen_spec_partial_depth2() -> SubValueSpec {
-        SubValueSpec {
-            member_idxs: vec![2, 1],
-            datum_type: DatumType::Str,
-        }
+}
+fn gen_spec_partial_depth2() -> SubValueSpec {
+    SubValueSpec {
+        member_path: vec![MemberKey::Idx(2), MemberKey::Idx(1)],
+        datum_type: DatumType::Str,
     }
+}
+
+mod test_serde {
+    use super::*;
+    use std::io::Cursor;
 
-    mod test_serde {
-        use super::*;
-        use std::io::Cursor;
-
-        fn verify_serde(spec: SubValueSpec) -> Result<()> {
-            let mut buf = vec![];
-            spec.ser(&mut buf)?;
-            let deserialized = SubValueSpec::deser(&mut Cursor::new(&buf))?;
-            assert_eq!(spec, deserialized);
-            Ok(())
-        }
-
-        #[test]
-        fn test_serde() -> Result<()> {
-            verify_serde(gen_spec_whole())?;
-            verify_serde(gen_spec_partial_depth1())?;
-            verify_serde(gen_spec_partial_depth2())?;
-            Ok(())
-        }
+    fn verify_serde(spec: SubValueSpec) -> Result<()> {
+        let mut buf = vec![];
+        spec.ser(&mut buf)?;
+        let deserialized = SubValueSpec::deser(&mut Cursor::new(&buf))?;
+        assert_eq!(spec, deserialized);
+        Ok(())
     }
 
-    mod test_extract {
-        use super::*;
-        use crate::types::{PVShared, SubValue};
-
-        fn verify_extract(spec: SubValueSpec, pv: PVShared, exp_sv: Option<SubValue>) {
-            let pv = Arc::new(pv);
-            let act_sv = spec.extract(&pv);
-            match (exp_sv, act_sv) {
-                (None, None) => (),
-                (Some(exp_sv), Some(act_sv)) => {
-                    assert_eq!(&exp_sv, &act_sv as &SubValue);
-                }
-                etc => panic!("{etc:?}"),
-            }
-        }
-
-        #[test]
-        fn test_extract() {
-            verify_extract(
-                gen_spec_whole(),
-                PVShared::from(Datum::Str(String::from("asdf"))),
-                Some(SubValue(Datum::Str(String::from("asdf")))),
-            );
-            verify_extract(gen_spec_whole(), PVShared::from(Datum::I64(123)), None);
-
-            verify_extract(
-                gen_spec_partial_depth1(),
-                PVShared::from(Datum::Tuple(vec![
-                    Datum::I64(123),
-                    Datum::Str(String::from("asdf")),
-                    Datum::I64(123),
-                ])),
-                Some(SubValue(Datum::Str(String::from("asdf")))),
-            );
-            verify_extract(
-                gen_spec_partial_depth1(),
-                PVShared::from(Datum::Tuple(vec![
-                    Datum::I64(123),
-                    Datum::I64(123), // Not string.
-                    Datum::I64(123),
-                ])),
-                None,
-            );
-            verify_extract(
-                gen_spec_partial_depth1(),
-                PVShared::from(Datum::Tuple(vec![
-                    Datum::I64(123),
+    #[test]
+    fn test_serde() -> Result<()> {
+        verify_serde(gen_spec_whole())?;
+        verify_serde(gen_spec_partial_depth1())?;
+        verify_serde(gen_spec_partial_depth2())?;
+        verify_serde(gen_spec_map_path())?;
+        verify_serde(SubValueSpec {
+            member_path: vec![MemberKey::from("a,b%c\nd"), MemberKey::from("123")],
+            datum_type: DatumType::Map,
+        })?;
+        verify_serde(SubValueSpec {
+            member_path: vec![MemberKey::Idx(0)],
+            datum_type: DatumType::Decimal,
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_deser_legacy() -> Result<()> {
+        let spec = SubValueSpec::deser_solo(b"3;2,1,")?;
+        assert_eq!(gen_spec_partial_depth2(), spec);
+        Ok(())
+    }
+}
+
+mod test_extract {
+    use super::*;
+    use crate::serde::{DatumRef, Decimal};
+    use crate::types::{PVShared, SVShared, Ser, SubValue};
+    use std::collections::BTreeMap;
+
+    fn verify_extract(spec: SubValueSpec, pv: PVShared, exp_sv: Option<SubValue>) {
+        verify_extract_multi(spec, pv, exp_sv.into_iter().collect());
+    }
+
+    fn verify_extract_multi(spec: SubValueSpec, pv: PVShared, exp_svs: Vec<SubValue>) {
+        let pv = Arc::new(pv);
+        let act_svs = spec.extract(&pv);
+        let act_svs = act_svs
+            .iter()
+            .map(|act_sv| act_sv as &SubValue)
+            .collect::<Vec<_>>();
+        let exp_svs = exp_svs.iter().collect::<Vec<_>>();
+        assert_eq!(exp_svs, act_svs);
+
+        /* Extract from the serialized PV, too. */
+        let pv_buf = pv.ser_solo().unwrap();
+        let pv_ref = DatumRef::deser_solo(&pv_buf).unwrap();
+        let act_svs = spec
+            .extract_ref(pv_ref)
+            .iter()
+            .map(|act_sv| SubValue(act_sv.to_datum()))
+            .collect::<Vec<_>>();
+        let act_svs = act_svs.iter().collect::<Vec<_>>();
+        assert_eq!(exp_svs, act_svs);
+    }
+
+    #[test]
+    fn test_extract() {
+        verify_extract(
+            gen_spec_whole(),
+            PVShared::from(Datum::Str(String::from("asdf"))),
+            Some(SubValue(Datum::Str(String::from("asdf")))),
+        );
+        verify_extract(gen_spec_whole(), PVShared::from(Datum::I64(123)), None);
+
+        verify_extract(
+            gen_spec_partial_depth1(),
+            PVShared::from(Datum::Tuple(vec![
+                Datum::I64(123),
+                Datum::Str(String::from("asdf")),
+                Datum::I64(123),
+            ])),
+            Some(SubValue(Datum::Str(String::from("asdf")))),
+        );
+        verify_extract(
+            gen_spec_partial_depth1(),
+            PVShared::from(Datum::Tuple(vec![
+                Datum::I64(123),
+                Datum::I64(123), // Not string.
+                Datum::I64(123),
+            ])),
+            None,
+        );
+        verify_extract(
+            gen_spec_partial_depth1(),
+            PVShared::from(Datum::Tuple(vec![
+                Datum::I64(123),
+                // Missing member_idx 1.
+            ])),
+            None,
+        );
+        verify_extract(
+            gen_spec_partial_depth1(),
+            PVShared::from(Datum::Str(String::from("asdf"))), // Insufficient depth.
+            None,
+        );
+
+        verify_extract(
+            gen_spec_partial_depth2(),
+            PVShared::from(Datum::Tuple(vec![
+                Datum::I64(123),
+                Datum::Str(String::from("asdf")),
+                Datum::Tuple(vec![Datum::I64(456), Datum::Str(String::from("asdf2"))]),
+            ])),
+            Some(SubValue(Datum::Str(String::from("asdf2")))),
+        );
+        verify_extract(
+            gen_spec_partial_depth2(),
+            PVShared::from(Datum::Tuple(vec![
+                Datum::I64(123),
+                Datum::Str(String::from("asdf")),
+                Datum::Tuple(vec![
+                    Datum::I64(456),
+                    Datum::I64(456), // Not string.
+                ]),
+            ])),
+            None,
+        );
+        verify_extract(
+            gen_spec_partial_depth2(),
+            PVShared::from(Datum::Tuple(vec![
+                Datum::I64(123),
+                Datum::Str(String::from("asdf")),
+                Datum::Tuple(vec![
+                    Datum::I64(456),
                     // Missing member_idx 1.
-                ])),
-                None,
-            );
-            verify_extract(
-                gen_spec_partial_depth1(),
-                PVShared::from(Datum::Str(String::from("asdf"))), // Insufficient depth.
-                None,
-            );
-
-            verify_extract(
-                gen_spec_partial_depth2(),
-                PVShared::from(Datum::Tuple(vec![
-                    Datum::I64(123),
-                    Datum::Str(String::from("asdf")),
-                    Datum::Tuple(vec![Datum::I64(456), Datum::Str(String::from("asdf2"))]),
-                ])),
-                Some(SubValue(Datum::Str(String::from("asdf2")))),
-            );
-            verify_extract(
-                gen_spec_partial_depth2(),
-                PVShared::from(Datum::Tuple(vec![
-                    Datum::I64(123),
-                    Datum::Str(String::from("asdf")),
-                    Datum::Tuple(vec![
-                        Datum::I64(456),
-                        Datum::I64(456), // Not string.
-                    ]),
-                ])),
-                None,
-            );
-            verify_extract(
-                gen_spec_partial_depth2(),
-                PVShared::from(Datum::Tuple(vec![
-                    Datum::I64(123),
-                    Datum::Str(String::from("asdf")),
+                ]),
+            ])),
+            None,
+        );
+        verify_extract(
+            gen_spec_partial_depth2(),
+            PVShared::from(Datum::Tuple(vec![
+                Datum::I64(123),
+                Datum::Str(String::from("asdf")),
+                Datum::I64(123), // Insufficient depth.
+            ])),
+            None,
+        );
+        verify_extract(
+            gen_spec_partial_depth2(),
+            PVShared::from(Datum::Str(String::from("asdf"))), // Insufficient depth.
+            None,
+        );
+    }
+
+    #[test]
+    fn test_extract_map() {
+        let gen_pv = |zip: Datum| {
+            PVShared::from(Datum::Map(BTreeMap::from([
+                (String::from("name"), Datum::Str(String::from("asdf"))),
+                (
+                    String::from("user"),
                     Datum::Tuple(vec![
-                        Datum::I64(456),
-                        // Missing member_idx 1.
+                        Datum::I64(123),
+                        Datum::Map(BTreeMap::from([(String::from("zip"), zip)])),
                     ]),
-                ])),
-                None,
-            );
-            verify_extract(
-                gen_spec_partial_depth2(),
-                PVShared::from(Datum::Tuple(vec![
-                    Datum::I64(123),
-                    Datum::Str(String::from("asdf")),
-                    Datum::I64(123), // Insufficient depth.
-                ])),
-                None,
-            );
-            verify_extract(
-                gen_spec_partial_depth2(),
-                PVShared::from(Datum::Str(String::from("asdf"))), // Insufficient depth.
-                None,
-            );
-        }
+                ),
+            ])))
+        };
+
+        verify_extract(
+            gen_spec_map_path(),
+            gen_pv(Datum::Str(String::from("12345"))),
+            Some(SubValue(Datum::Str(String::from("12345")))),
+        );
+        verify_extract(gen_spec_map_path(), gen_pv(Datum::I64(12345)), None); // Not string.
+        verify_extract(
+            SubValueSpec {
+                member_path: vec![MemberKey::from("user"), MemberKey::from("1")],
+                datum_type: DatumType::Map,
+            },
+            gen_pv(Datum::Null),
+            None, // Name may not index a Tuple.
+        );
+        verify_extract(
+            SubValueSpec {
+                member_path: vec![MemberKey::from("nonexistent")],
+                datum_type: DatumType::Str,
+            },
+            gen_pv(Datum::Null),
+            None,
+        );
+        verify_extract(
+            SubValueSpec {
+                member_path: vec![MemberKey::Idx(0)],
+                datum_type: DatumType::Str,
+            },
+            gen_pv(Datum::Null),
+            None, // Idx may not index a Map.
+        );
+    }
+
+    #[test]
+    fn test_extract_list() {
+        let gen_pv = || {
+            PVShared::from(Datum::Map(BTreeMap::from([(
+                String::from("tags"),
+                Datum::List(vec![
+                    Datum::Str(String::from("b")),
+                    Datum::I64(123), // Not string.
+                    Datum::Str(String::from("a")),
+                    Datum::Str(String::from("b")), // Duplicate.
+                ]),
+            )])))
+        };
+        let str_sv = |s: &str| SubValue(Datum::Str(String::from(s)));
+
+        let spec_each = SubValueSpec {
+            member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+            datum_type: DatumType::Str,
+        };
+        assert!(spec_each.is_multi_valued());
+        verify_extract_multi(spec_each, gen_pv(), vec![str_sv("a"), str_sv("b")]);
+
+        verify_extract_multi(
+            SubValueSpec {
+                member_path: vec![MemberKey::Each],
+                datum_type: DatumType::Str,
+            },
+            gen_pv(),
+            vec![], // Each may not iterate a Map.
+        );
+        verify_extract_multi(
+            SubValueSpec {
+                member_path: vec![MemberKey::from("tags"), MemberKey::Each],
+                datum_type: DatumType::Str,
+            },
+            PVShared::from(Datum::Map(BTreeMap::from([(
+                String::from("tags"),
+                Datum::List(vec![]),
+            )]))),
+            vec![],
+        );
+
+        let spec_idx = SubValueSpec {
+            member_path: vec![MemberKey::from("tags"), MemberKey::Idx(2)],
+            datum_type: DatumType::Str,
+        };
+        assert!(spec_idx.is_multi_valued() == false);
+        verify_extract(spec_idx, gen_pv(), Some(str_sv("a")));
+    }
+
+    #[test]
+    fn test_extract_delta() {
+        let spec = SubValueSpec {
+            member_path: vec![MemberKey::Each],
+            datum_type: DatumType::I64,
+        };
+        let gen_pv = |ints: &[i64]| {
+            let dats = ints.iter().map(|i| Datum::I64(*i)).collect::<Vec<_>>();
+            Arc::new(PVShared::from(Datum::List(dats)))
+        };
+        let to_ints = |svs: Vec<SVShared>| {
+            svs.iter()
+                .map(|sv| match sv as &SubValue {
+                    SubValue(Datum::I64(i)) => *i,
+                    etc => panic!("{etc:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+        let verify =
+            |old: Option<&[i64]>, new: Option<&[i64]>, exp_tomb: &[i64], exp_put: &[i64]| {
+                let old_pv = old.map(gen_pv);
+                let new_pv = new.map(gen_pv);
+                let (tomb, put) = spec.extract_delta(old_pv.as_deref(), new_pv.as_deref());
+                assert_eq!(exp_tomb, to_ints(tomb));
+                assert_eq!(exp_put, to_ints(put));
+            };
+
+        verify(None, Some(&[2, 1]), &[], &[1, 2]);
+        verify(Some(&[1, 2]), None, &[1, 2], &[]);
+        verify(Some(&[1, 2, 3]), Some(&[3, 4, 2]), &[1], &[2, 3, 4]);
+        verify(Some(&[1, 2]), Some(&[1, 2]), &[], &[]);
+    }
+
+    #[test]
+    fn test_extract_scalars() {
+        let pv = || {
+            PVShared::from(Datum::Tuple(vec![
+                Datum::F64(1.5),
+                Datum::Bool(true),
+                Datum::Null,
+                Datum::Timestamp(123),
+                Datum::Decimal(Decimal::new(15, 1).unwrap()),
+            ]))
+        };
+        let spec = |member_idx: u32, datum_type: DatumType| SubValueSpec {
+            member_path: vec![MemberKey::Idx(member_idx)],
+            datum_type,
+        };
+
+        verify_extract(
+            spec(0, DatumType::F64),
+            pv(),
+            Some(SubValue(Datum::F64(1.5))),
+        );
+        verify_extract(spec(0, DatumType::Decimal), pv(), None);
+        verify_extract(
+            spec(1, DatumType::Bool),
+            pv(),
+            Some(SubValue(Datum::Bool(true))),
+        );
+        verify_extract(spec(2, DatumType::Null), pv(), Some(SubValue(Datum::Null)));
+        verify_extract(
+            spec(3, DatumType::Timestamp),
+            pv(),
+            Some(SubValue(Datum::Timestamp(123))),
+        );
+        verify_extract(spec(3, DatumType::I64), pv(), None);
+        verify_extract(
+            spec(4, DatumType::Decimal),
+            pv(),
+            Some(SubValue(Datum::Decimal(Decimal::new(150, 2).unwrap()))),
+        );
     }
 }
@@ -1,5 +1,7 @@
-use crate::serde::{Datum, OptDatum, ReadResult, WriteLen};
-use crate::types::{Deser, PKShared, PrimaryKey, SVShared, Ser, Serializable, SubValue};
+use crate::serde::{Datum, DatumRef, OptDatum, ReadResult, WriteLen};
+use crate::types::{
+    Deser, PKShared, PartialOrdSer, PrimaryKey, SVShared, Ser, Serializable, SubValue,
+};
 use anyhow::{anyhow, Result};
 use std::borrow::Borrow;
 use std::cmp::{Ord, Ordering, PartialOrd};
@@ -36,6 +38,19 @@ impl Deser for SVPKShared {
         let r_len = sv_r_len + pk_r_len;
         return Ok(ReadResult::Some(r_len, ()));
     }
+    fn read_ser<R: Read + Seek>(r: &mut R, buf: &mut Vec<u8>) -> Result<ReadResult<()>> {
+        let sv_r_len = match OptDatum::<Datum>::read_ser(r, buf)? {
+            ReadResult::EOF => return Ok(ReadResult::EOF),
+            ReadResult::Some(r_len, ()) => r_len,
+        };
+        let pk_r_len = match OptDatum::<Datum>::read_ser(r, buf)? {
+            ReadResult::EOF => return Err(anyhow!("SV found but PK not found.")),
+            ReadResult::Some(r_len, ()) => r_len,
+        };
+
+        let r_len = sv_r_len + pk_r_len;
+        return Ok(ReadResult::Some(r_len, ()));
+    }
     fn deser<R: Read + Seek>(r: &mut R) -> Result<ReadResult<Self>> {
         let (sv_r_len, sv_dat) = match Datum::deser(r)? {
             ReadResult::EOF => return Ok(ReadResult::EOF),
@@ -104,3 +119,34 @@ where
         }
     }
 }
+
+/* SVPKShared is comparable against the same type, while serialized. */
+impl PartialOrdSer<SVPKShared> for SVPKShared {
+    fn partial_cmp_ser(buf: &[u8], other: &SVPKShared) -> Result<Option<Ordering>> {
+        let (sv_r_len, sv) = match OptDatum::<DatumRef>::deser(buf)? {
+            ReadResult::Some(r_len, OptDatum::Some(sv)) => (r_len, sv),
+            _ => return Err(anyhow!("SV not found.")),
+        };
+        let pk = DatumRef::deser_solo(&buf[sv_r_len..])?;
+
+        let ord = sv
+            .cmp_datum(&other.sv)
+            .then_with(|| pk.cmp_datum(&other.pk));
+        Ok(Some(ord))
+    }
+}
+
+/* SVPKShared is comparable against {SubValue, &SubValue, etc}, while serialized. */
+impl<O> PartialOrdSer<O> for SVPKShared
+where
+    O: Borrow<SubValue>,
+{
+    /// Views the sub-value only. See [`SVPKShared::partial_cmp()`].
+    fn partial_cmp_ser(buf: &[u8], other: &O) -> Result<Option<Ordering>> {
+        let sv = DatumRef::deser_solo(buf)?;
+        match sv.cmp_datum(other.borrow()) {
+            Ordering::Equal => Ok(None),
+            ord => Ok(Some(ord)),
+        }
+    }
+}